@@ -0,0 +1,76 @@
+//! 10 万条历史下的写入、搜索和分页基准：cargo bench --bench history
+use std::sync::Arc;
+
+use clipboard_lib::bench::{generate, ClipboardItem, Crypto, Database, HistoryFilter};
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+
+const ITEMS: usize = 100_000;
+const PAGE_SIZE: usize = 50;
+
+fn setup() -> (Database, std::path::PathBuf) {
+    let dir = std::env::temp_dir().join(format!("clipboard-bench-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let crypto = Arc::new(Crypto::new(dir.join("key")));
+    let db = Database::new(dir.join("history.db"), crypto).unwrap();
+    db.insert_batch(&generate(ITEMS, 42)).unwrap();
+    (db, dir)
+}
+
+fn history(c: &mut Criterion) {
+    let (db, dir) = setup();
+    let mut next = 0u64;
+
+    c.bench_function("insert_item", |b| {
+        b.iter_batched(
+            || {
+                next += 1;
+                ClipboardItem {
+                    content: format!("bench insert {}", next),
+                    kind: "text".to_string(),
+                    timestamp: chrono::Local::now()
+                        .format("%Y-%m-%d %H:%M:%S%.3f")
+                        .to_string(),
+                    data_type: "text".to_string(),
+                    ..Default::default()
+                }
+            },
+            |item| db.insert_item(&item, usize::MAX).unwrap(),
+            BatchSize::SmallInput,
+        )
+    });
+
+    c.bench_function("search_like", |b| {
+        let filter = HistoryFilter {
+            query: Some("deploy review".into()),
+            ..Default::default()
+        };
+        b.iter(|| db.get_history(1, PAGE_SIZE, &filter).unwrap())
+    });
+
+    c.bench_function("search_regex", |b| {
+        let filter = HistoryFilter {
+            query: Some(r"#99\d{3}$".into()),
+            search_regex: true,
+            ..Default::default()
+        };
+        b.iter(|| db.get_history(1, PAGE_SIZE, &filter).unwrap())
+    });
+
+    c.bench_function("paginate_first_page", |b| {
+        let filter = HistoryFilter::default();
+        b.iter(|| db.get_history(1, PAGE_SIZE, &filter).unwrap())
+    });
+
+    c.bench_function("paginate_deep_page", |b| {
+        let last = ITEMS / PAGE_SIZE;
+        let filter = HistoryFilter::default();
+        b.iter(|| db.get_history(last, PAGE_SIZE, &filter).unwrap())
+    });
+
+    drop(db);
+    let _ = std::fs::remove_dir_all(&dir);
+}
+
+criterion_group!(benches, history);
+criterion_main!(benches);
@@ -0,0 +1,225 @@
+use tauri::Manager;
+
+use crate::models::{AccessibilityPrefs, ClipboardItem, ItemDescription};
+use crate::state::AppState;
+use crate::utils::{format_timestamp, resolve_language};
+
+// 描述中文本预览的最大字符数
+const PREVIEW_CHARS: usize = 80;
+
+/// 系统是否开启了减弱动态效果；无法判断时返回 None
+#[cfg(target_os = "macos")]
+fn os_reduced_motion() -> Option<bool> {
+    read_defaults_bool("reduceMotion")
+}
+
+/// 系统是否开启了高对比度；无法判断时返回 None
+#[cfg(target_os = "macos")]
+fn os_high_contrast() -> Option<bool> {
+    read_defaults_bool("increaseContrast")
+}
+
+#[cfg(target_os = "macos")]
+fn read_defaults_bool(key: &str) -> Option<bool> {
+    let output = std::process::Command::new("defaults")
+        .args(["read", "com.apple.universalaccess", key])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        // 用户从未改过这项设置时没有记录，即未开启
+        return Some(false);
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim() == "1")
+}
+
+#[cfg(target_os = "windows")]
+fn os_reduced_motion() -> Option<bool> {
+    use windows::Win32::Foundation::BOOL;
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETCLIENTAREAANIMATION, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    let mut animation = BOOL(1);
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            Some(&mut animation as *mut BOOL as *mut std::ffi::c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    }
+    .ok()?;
+    Some(!animation.as_bool())
+}
+
+#[cfg(target_os = "windows")]
+fn os_high_contrast() -> Option<bool> {
+    use windows::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        SystemParametersInfoW, SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS,
+    };
+
+    let mut contrast = HIGHCONTRASTW {
+        cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            contrast.cbSize,
+            Some(&mut contrast as *mut HIGHCONTRASTW as *mut std::ffi::c_void),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    }
+    .ok()?;
+    Some(contrast.dwFlags.contains(HCF_HIGHCONTRASTON))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn os_reduced_motion() -> Option<bool> {
+    gsettings("org.gnome.desktop.interface", "enable-animations").map(|enabled| !enabled)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn os_high_contrast() -> Option<bool> {
+    gsettings("org.gnome.desktop.a11y.interface", "high-contrast")
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn gsettings(schema: &str, key: &str) -> Option<bool> {
+    let output = std::process::Command::new("gsettings")
+        .args(["get", schema, key])
+        .output()
+        .ok()?;
+    match String::from_utf8_lossy(&output.stdout).trim() {
+        "true" => Some(true),
+        "false" => Some(false),
+        _ => None,
+    }
+}
+
+/// 配置为 system 时跟随系统，系统无法判断时视为关闭
+fn resolve(setting: &str, os: Option<bool>) -> bool {
+    match setting {
+        "on" => true,
+        "off" => false,
+        _ => os.unwrap_or(false),
+    }
+}
+
+/// 当前生效的减弱动态效果和高对比度设置
+pub fn prefs(app: &tauri::AppHandle) -> AccessibilityPrefs {
+    let (reduced_motion, high_contrast) = {
+        let state = app.state::<AppState>();
+        let config = state.config.lock().unwrap();
+        (config.reduced_motion.clone(), config.high_contrast.clone())
+    };
+    let os_reduced_motion = os_reduced_motion();
+    let os_high_contrast = os_high_contrast();
+    AccessibilityPrefs {
+        reduced_motion: resolve(&reduced_motion, os_reduced_motion),
+        high_contrast: resolve(&high_contrast, os_high_contrast),
+        os_reduced_motion,
+        os_high_contrast,
+    }
+}
+
+fn preview(content: &str) -> String {
+    let line = content.split_whitespace().collect::<Vec<_>>().join(" ");
+    let mut preview: String = line.chars().take(PREVIEW_CHARS).collect();
+    if line.chars().count() > PREVIEW_CHARS {
+        preview.push('…');
+    }
+    preview
+}
+
+/// 供读屏软件朗读的条目描述：类型、来源应用、时间和内容预览；敏感内容不朗读
+pub fn describe(item: &ClipboardItem, language: &str) -> String {
+    let zh = resolve_language(language) == "zh";
+    let file_count = || {
+        serde_json::from_str::<Vec<String>>(&item.content)
+            .map(|files| files.len())
+            .unwrap_or(0)
+    };
+    let kind = match (item.kind.as_str(), zh) {
+        ("image", false) => match (item.image_width, item.image_height) {
+            (Some(w), Some(h)) => format!("Image, {} by {}", w, h),
+            _ => "Image".to_string(),
+        },
+        ("image", true) => match (item.image_width, item.image_height) {
+            (Some(w), Some(h)) => format!("图片，{} × {}", w, h),
+            _ => "图片".to_string(),
+        },
+        ("file", false) => match file_count() {
+            1 => "1 file".to_string(),
+            n => format!("{} files", n),
+        },
+        ("file", true) => format!("{} 个文件", file_count()),
+        ("audio", false) => "Audio".to_string(),
+        ("audio", true) => "音频".to_string(),
+        (_, false) if item.data_type == "text" => "Text".to_string(),
+        (_, true) if item.data_type == "text" => "文本".to_string(),
+        (_, _) => item.data_type.clone(),
+    };
+
+    let mut parts = vec![kind];
+    if item.is_pinned {
+        parts.push(if zh { "已置顶" } else { "pinned" }.to_string());
+    }
+    if let Some(app) = item.source_app.as_deref().filter(|a| !a.is_empty()) {
+        parts.push(if zh {
+            format!("来自 {}", app)
+        } else {
+            format!("from {}", app)
+        });
+    }
+    parts.push(format_timestamp(item.timestamp_ms, "relative", language));
+
+    let text = if let Some(note) = item.note.as_deref().filter(|n| !n.trim().is_empty()) {
+        Some(preview(note))
+    } else if item.is_sensitive {
+        Some(
+            if zh {
+                "敏感内容已隐藏"
+            } else {
+                "sensitive content hidden"
+            }
+            .to_string(),
+        )
+    } else if item.kind == "text" {
+        Some(preview(&item.content))
+    } else {
+        None
+    };
+    let separator = if zh { "，" } else { ", " };
+    match text {
+        Some(text) => format!(
+            "{}{}{}",
+            parts.join(separator),
+            if zh { "：" } else { ": " },
+            text
+        ),
+        None => parts.join(separator),
+    }
+}
+
+/// 按 id 生成描述，找不到的条目跳过；演示模式下不包含来源应用
+pub fn describe_items(app: &tauri::AppHandle, ids: &[i64]) -> Vec<ItemDescription> {
+    let state = app.state::<AppState>();
+    let (language, presentation_mode) = {
+        let config = state.config.lock().unwrap();
+        (config.language.clone(), config.presentation_mode)
+    };
+    ids.iter()
+        .filter_map(|&id| {
+            let mut item = state.db.get_item(id).ok()?;
+            if presentation_mode {
+                item.source_app = None;
+            }
+            Some(ItemDescription {
+                id,
+                label: describe(&item, &language),
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,118 @@
+use crate::models::AppConfig;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+// 模型生成较长回复可能需要一段时间，但不能无限等待
+const REQUEST_TIMEOUT_SECS: u64 = 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AiAction {
+    Summarize,
+    Rewrite,
+    ExplainCode,
+}
+
+impl AiAction {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AiAction::Summarize => "AI Summary",
+            AiAction::Rewrite => "AI Rewrite",
+            AiAction::ExplainCode => "AI Code Explanation",
+        }
+    }
+
+    fn system_prompt(&self, style: Option<&str>) -> String {
+        match self {
+            AiAction::Summarize => "Summarize the user's text concisely. Reply in the same language as the text and return only the summary.".to_string(),
+            AiAction::Rewrite => format!(
+                "Rewrite the user's text in a {} style while keeping its meaning. Reply in the same language as the text and return only the rewritten text.",
+                style.unwrap_or("clearer")
+            ),
+            AiAction::ExplainCode => "Explain what the user's code does, step by step, in plain language. Mention any obvious bugs.".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ChatMessage<'a> {
+    role: &'a str,
+    content: &'a str,
+}
+
+#[derive(Serialize)]
+struct ChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<ChatMessage<'a>>,
+}
+
+#[derive(Deserialize)]
+struct ChatResponse {
+    choices: Vec<ChatChoice>,
+}
+
+#[derive(Deserialize)]
+struct ChatChoice {
+    message: ChatResponseMessage,
+}
+
+#[derive(Deserialize)]
+struct ChatResponseMessage {
+    content: String,
+}
+
+/// 调用用户配置的 OpenAI 兼容接口（/chat/completions）
+pub async fn run_action(
+    config: &AppConfig,
+    action: AiAction,
+    style: Option<&str>,
+    input: &str,
+) -> Result<String, String> {
+    if config.ai_endpoint.trim().is_empty() {
+        return Err("AI endpoint is not configured".to_string());
+    }
+
+    let url = format!(
+        "{}/chat/completions",
+        config.ai_endpoint.trim().trim_end_matches('/')
+    );
+    let system_prompt = action.system_prompt(style);
+    let body = ChatRequest {
+        model: &config.ai_model,
+        messages: vec![
+            ChatMessage {
+                role: "system",
+                content: &system_prompt,
+            },
+            ChatMessage {
+                role: "user",
+                content: input,
+            },
+        ],
+    };
+
+    let mut request = reqwest::Client::new()
+        .post(&url)
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .json(&body);
+    if !config.ai_api_key.is_empty() {
+        request = request.bearer_auth(&config.ai_api_key);
+    }
+
+    let response: ChatResponse = request
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    response
+        .choices
+        .into_iter()
+        .next()
+        .map(|c| c.message.content.trim().to_string())
+        .filter(|c| !c.is_empty())
+        .ok_or_else(|| "AI provider returned an empty response".to_string())
+}
@@ -0,0 +1,71 @@
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+
+use crate::models::ApiToken;
+use crate::share_server::{generate_token, token_matches};
+use crate::state::AppState;
+
+// 令牌前缀，便于在配置和日志中识别
+const TOKEN_PREFIX: &str = "clp_";
+
+/// 令牌授予的权限：write 允许粘贴、暂停等操作，sensitive 允许访问敏感条目
+#[derive(Debug, Clone, Copy)]
+pub struct Grant {
+    pub write: bool,
+    pub sensitive: bool,
+}
+
+impl Grant {
+    /// 各集成原有的专用 token 拥有完整权限
+    pub const FULL: Grant = Grant {
+        write: true,
+        sensitive: true,
+    };
+}
+
+/// 只保存令牌的 SHA-256，明文只在创建时返回一次
+pub fn hash(token: &str) -> String {
+    Sha256::digest(token.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// 生成新令牌，返回保存的记录和明文
+pub fn create(name: &str, scope: &str, no_sensitive: bool) -> Result<(ApiToken, String), String> {
+    if !matches!(scope, "read" | "write") {
+        return Err(format!("Unknown token scope: {}", scope));
+    }
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Token name is empty".to_string());
+    }
+    let token = format!("{}{}", TOKEN_PREFIX, generate_token());
+    let record = ApiToken {
+        id: generate_token()[..12].to_string(),
+        name: name.to_string(),
+        scope: scope.to_string(),
+        no_sensitive,
+        token_hash: hash(&token),
+        created_at: chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+    };
+    Ok((record, token))
+}
+
+/// 校验令牌，返回其权限；未知令牌返回 None
+pub fn authorize(app: &tauri::AppHandle, token: &str) -> Option<Grant> {
+    if !token.starts_with(TOKEN_PREFIX) {
+        return None;
+    }
+    let hash = hash(token);
+    let state = app.state::<AppState>();
+    let config = state.config.lock().unwrap();
+    config
+        .api_tokens
+        .iter()
+        .find(|t| token_matches(&hash, &t.token_hash))
+        .map(|t| Grant {
+            write: t.scope == "write",
+            sensitive: !t.no_sensitive,
+        })
+}
@@ -0,0 +1,203 @@
+use std::path::{Path, PathBuf};
+
+use clipboard_rs::{Clipboard, ClipboardContext};
+use tauri::Manager;
+
+use crate::state::AppState;
+
+// 剪贴板音频类型 -> 保存时的扩展名，按优先级排列
+const AUDIO_FORMATS: [(&str, &str); 14] = [
+    // macOS
+    ("com.microsoft.waveform-audio", "wav"),
+    ("public.aiff-audio", "aiff"),
+    ("public.aifc-audio", "aifc"),
+    ("public.mp3", "mp3"),
+    ("public.mpeg-4-audio", "m4a"),
+    ("com.apple.m4a-audio", "m4a"),
+    // Windows
+    ("CF_WAVE", "wav"),
+    ("WAVE", "wav"),
+    // Linux
+    ("audio/wav", "wav"),
+    ("audio/x-wav", "wav"),
+    ("audio/mpeg", "mp3"),
+    ("audio/ogg", "ogg"),
+    ("audio/flac", "flac"),
+    ("audio/mp4", "m4a"),
+];
+
+// 单个音频片段的大小上限
+const MAX_AUDIO_BYTES: usize = 32 * 1024 * 1024;
+
+/// 是否为音频格式，这些格式不再作为原始格式保存
+pub fn is_audio_format(format: &str) -> bool {
+    format.starts_with("audio/") || AUDIO_FORMATS.iter().any(|(f, _)| *f == format)
+}
+
+/// 读取剪贴板中的音频数据，返回 (格式名, 扩展名, 数据)
+pub fn read() -> Option<(String, &'static str, Vec<u8>)> {
+    let ctx = ClipboardContext::new().ok()?;
+    let formats = ctx.available_formats().unwrap_or_default();
+    AUDIO_FORMATS
+        .iter()
+        .filter(|(format, _)| formats.iter().any(|f| f == format))
+        .find_map(|(format, ext)| {
+            let data = ctx.get_buffer(format).ok()?;
+            if data.is_empty() || data.len() > MAX_AUDIO_BYTES {
+                log::info!(
+                    "Skipping audio clipboard format {} ({} bytes)",
+                    format,
+                    data.len()
+                );
+                return None;
+            }
+            Some((format.to_string(), *ext, data))
+        })
+}
+
+/// 写回剪贴板时使用的格式名
+pub fn clipboard_format(path: &str) -> Option<&'static str> {
+    let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+    // 只用当前平台的格式名：macOS 为 UTI，Windows 只支持 WAV，Linux 为 MIME 类型
+    let native = |format: &str| {
+        if cfg!(target_os = "macos") {
+            format.contains('.')
+        } else if cfg!(target_os = "windows") {
+            format == "WAVE"
+        } else {
+            format.starts_with("audio/")
+        }
+    };
+    AUDIO_FORMATS
+        .iter()
+        .find(|(format, e)| *e == ext && native(format))
+        .map(|(format, _)| *format)
+}
+
+pub fn audio_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("audio");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// 读取 WAV（PCM 8/16/24/32 位或 32 位浮点）的采样，多声道取平均，值域 -1.0..1.0
+fn wav_samples(bytes: &[u8]) -> Option<Vec<f32>> {
+    if bytes.len() < 12 || &bytes[0..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return None;
+    }
+    let u16_at = |i: usize| Some(u16::from_le_bytes(bytes.get(i..i + 2)?.try_into().ok()?));
+    let u32_at = |i: usize| Some(u32::from_le_bytes(bytes.get(i..i + 4)?.try_into().ok()?));
+
+    let mut format = None;
+    let mut data = None;
+    let mut pos = 12;
+    while pos + 8 <= bytes.len() {
+        let id = &bytes[pos..pos + 4];
+        let size = u32_at(pos + 4)? as usize;
+        let body = pos + 8;
+        let end = (body + size).min(bytes.len());
+        match id {
+            b"fmt " => format = Some((u16_at(body)?, u16_at(body + 2)?, u16_at(body + 14)?)),
+            b"data" => data = Some(&bytes[body..end]),
+            _ => {}
+        }
+        // 块按偶数字节对齐
+        pos = body + size + (size & 1);
+    }
+
+    let (audio_format, channels, bits) = format?;
+    let data = data?;
+    let channels = channels.max(1) as usize;
+    let width = (bits as usize).div_ceil(8);
+    if width == 0 {
+        return None;
+    }
+    let sample = |chunk: &[u8]| -> Option<f32> {
+        Some(match (audio_format, bits) {
+            (1, 8) => (chunk[0] as f32 - 128.0) / 128.0,
+            (1, 16) => i16::from_le_bytes([chunk[0], chunk[1]]) as f32 / 32768.0,
+            (1, 24) => {
+                (i32::from_le_bytes([0, chunk[0], chunk[1], chunk[2]]) >> 8) as f32 / 8388608.0
+            }
+            (1, 32) => i32::from_le_bytes(chunk[..4].try_into().ok()?) as f32 / 2147483648.0,
+            (3, 32) => f32::from_le_bytes(chunk[..4].try_into().ok()?),
+            _ => return None,
+        })
+    };
+    data.chunks_exact(width * channels)
+        .map(|frame| {
+            let sum: f32 = frame
+                .chunks_exact(width)
+                .map(&sample)
+                .sum::<Option<f32>>()?;
+            Some(sum / channels as f32)
+        })
+        .collect()
+}
+
+/// WAV 音频的波形缩略图（PNG data URL），宽 size、高 size / 4
+pub fn waveform_thumbnail(path: &str, size: u32) -> Result<String, String> {
+    let bytes = std::fs::read(path).map_err(|e| e.to_string())?;
+    let samples = wav_samples(&bytes).ok_or("Waveform is only available for WAV audio")?;
+    if samples.is_empty() {
+        return Err("Audio contains no samples".to_string());
+    }
+
+    let width = size.max(16);
+    let height = (size / 4).max(8);
+    let mid = height as f32 / 2.0;
+    let mut img = image::RgbaImage::new(width, height);
+    let per_column = samples.len().div_ceil(width as usize);
+    for (x, column) in samples.chunks(per_column).enumerate() {
+        let peak = column.iter().fold(0f32, |m, s| m.max(s.abs())).min(1.0);
+        let half = (peak * mid).max(0.5);
+        let (top, bottom) = ((mid - half) as u32, ((mid + half) as u32).min(height - 1));
+        for y in top..=bottom {
+            img.put_pixel(x as u32, y, image::Rgba([107, 114, 128, 255]));
+        }
+    }
+
+    let mut png = Vec::new();
+    image::DynamicImage::ImageRgba8(img)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(crate::utils::png_data_url(&png))
+}
+
+/// 播放音频文件；macOS 用 afplay 在后台播放，再次播放会停止上一段，其他平台交给默认播放器
+pub fn play(app: &tauri::AppHandle, path: &str) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let state = app.state::<AppState>();
+        let mut player = state.audio_player.lock().unwrap();
+        if let Some(mut child) = player.take() {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+        let child = std::process::Command::new("afplay")
+            .arg(path)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        *player = Some(child);
+        Ok(())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        use tauri_plugin_opener::OpenerExt;
+        app.opener()
+            .open_path(path, None::<&str>)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// 停止正在播放的音频
+pub fn stop(app: &tauri::AppHandle) {
+    if let Some(mut child) = app.state::<AppState>().audio_player.lock().unwrap().take() {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+}
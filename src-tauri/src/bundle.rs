@@ -0,0 +1,360 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::db::CollectionDeleteMode;
+use crate::models::{ClipboardItem, Collection, ItemFilter};
+use crate::state::AppState;
+use crate::utils::remove_pruned_images;
+
+const BUNDLE_FORMAT: &str = "clipbundle";
+const BUNDLE_VERSION: u32 = 1;
+
+/// manifest.json：描述整个片段包
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    format: String,
+    version: u32,
+    name: String,
+    exported_at: String,
+    item_count: usize,
+}
+
+/// items.json 中的一条记录，图片的 content 为包内 images/ 下的相对路径
+#[derive(Serialize, Deserialize)]
+struct BundleItem {
+    content: String,
+    kind: String,
+    data_type: String,
+    timestamp: String,
+    #[serde(default)]
+    source_app: Option<String>,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    html_content: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// 把收藏夹导出为 .clipbundle（zip：manifest.json、items.json、images/），敏感条目不会导出
+pub fn export_collection(app: &tauri::AppHandle, id: i64, path: &Path) -> Result<usize, String> {
+    let state = app.state::<AppState>();
+    let collection = state
+        .db
+        .get_collections()
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .find(|c| c.id == id)
+        .ok_or("Collection not found")?;
+    let items = state
+        .db
+        .get_collection_items(id)
+        .map_err(|e| e.to_string())?;
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut bundle_items = Vec::new();
+    for item in items.iter().filter(|i| !i.is_sensitive) {
+        let content = match item.kind.as_str() {
+            "image" => {
+                let source = Path::new(&item.content);
+                let Ok(bytes) = std::fs::read(source) else {
+                    log::warn!("Skipping missing image {:?} in bundle", source);
+                    continue;
+                };
+                let name = format!(
+                    "images/{}",
+                    source
+                        .file_name()
+                        .map(|n| n.to_string_lossy().to_string())
+                        .unwrap_or_else(|| format!("{}.png", bundle_items.len()))
+                );
+                zip.start_file(name.as_str(), options)
+                    .map_err(|e| e.to_string())?;
+                zip.write_all(&bytes).map_err(|e| e.to_string())?;
+                name
+            }
+            // 文件列表只在本机有意义
+            "file" => continue,
+            _ => item.content.clone(),
+        };
+        bundle_items.push(BundleItem {
+            content,
+            kind: item.kind.clone(),
+            data_type: item.data_type.clone(),
+            timestamp: item.timestamp.clone(),
+            source_app: item.source_app.clone(),
+            note: item.note.clone(),
+            html_content: item.html_content.clone(),
+            tags: item
+                .id
+                .and_then(|id| state.db.get_item_tags(id).ok())
+                .unwrap_or_default(),
+        });
+    }
+
+    let manifest = Manifest {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        name: collection.name,
+        exported_at: Local::now().to_rfc3339(),
+        item_count: bundle_items.len(),
+    };
+    zip.start_file("manifest.json", options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&serde_json::to_vec_pretty(&manifest).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    zip.start_file("items.json", options)
+        .map_err(|e| e.to_string())?;
+    zip.write_all(&serde_json::to_vec_pretty(&bundle_items).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(bundle_items.len())
+}
+
+fn read_entry(archive: &mut ZipArchive<File>, name: &str) -> Result<Vec<u8>, String> {
+    let mut entry = archive
+        .by_name(name)
+        .map_err(|_| format!("Bundle is missing {}", name))?;
+    let mut bytes = Vec::new();
+    entry.read_to_end(&mut bytes).map_err(|e| e.to_string())?;
+    Ok(bytes)
+}
+
+/// 导入 .clipbundle，条目放入以包名新建的收藏夹
+pub fn import_bundle(app: &tauri::AppHandle, path: &Path) -> Result<Collection, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+
+    let manifest: Manifest = serde_json::from_slice(&read_entry(&mut archive, "manifest.json")?)
+        .map_err(|e| e.to_string())?;
+    if manifest.format != BUNDLE_FORMAT || manifest.version > BUNDLE_VERSION {
+        return Err("Unsupported bundle format".to_string());
+    }
+    // 其他工具生成的包可能不是 UTF-8
+    let decoded = crate::encoding::decode(&read_entry(&mut archive, "items.json")?);
+    let items: Vec<BundleItem> = serde_json::from_str(&decoded.text).map_err(|e| e.to_string())?;
+
+    let state = app.state::<AppState>();
+    let images_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("images");
+    std::fs::create_dir_all(&images_dir).map_err(|e| e.to_string())?;
+    let collection = state
+        .db
+        .create_collection(manifest.name)
+        .map_err(|e| e.to_string())?;
+
+    // 中途失败时删除新建的收藏夹、新增的条目和已写入的图片，已有条目移出收藏夹
+    let mut imported = Imported::default();
+    if let Err(e) = import_items(
+        app,
+        &mut archive,
+        items,
+        &decoded,
+        &images_dir,
+        collection.id,
+        &mut imported,
+    ) {
+        if let Err(e) = state.db.delete_items(&imported.items) {
+            log::error!("Failed to remove partially imported items: {}", e);
+        }
+        if let Err(e) = state
+            .db
+            .delete_collection(collection.id, CollectionDeleteMode::Uncategorize)
+        {
+            log::error!("Failed to remove partially imported collection: {}", e);
+        }
+        for path in &imported.files {
+            let _ = std::fs::remove_file(path);
+        }
+        return Err(e);
+    }
+
+    Ok(collection)
+}
+
+/// 导入过程中新增的条目和写入的图片，供失败时回滚
+#[derive(Default)]
+struct Imported {
+    items: Vec<i64>,
+    files: Vec<PathBuf>,
+}
+
+/// 逐条导入包内条目，新增的条目和图片记在 imported 中
+fn import_items(
+    app: &tauri::AppHandle,
+    archive: &mut ZipArchive<File>,
+    items: Vec<BundleItem>,
+    decoded: &crate::encoding::Decoded,
+    images_dir: &Path,
+    collection_id: i64,
+    imported: &mut Imported,
+) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let max_size = state.config.lock().unwrap().max_history_size;
+
+    for (i, bundle_item) in items.into_iter().enumerate() {
+        let content = if bundle_item.kind == "image" {
+            // 只接受包内 images/ 下的文件，避免路径穿越
+            let Some(name) = bundle_item
+                .content
+                .strip_prefix("images/")
+                .and_then(|n| Path::new(n).file_name())
+                .map(|n| n.to_string_lossy().to_string())
+            else {
+                continue;
+            };
+            let bytes = read_entry(archive, &bundle_item.content)?;
+            let image_path = images_dir.join(format!(
+                "{}_{}_{}",
+                Local::now().timestamp_nanos_opt().unwrap_or(0),
+                i,
+                name
+            ));
+            std::fs::write(&image_path, bytes).map_err(|e| e.to_string())?;
+            imported.files.push(image_path.clone());
+            image_path.to_string_lossy().to_string()
+        } else if bundle_item.kind == "text" {
+            bundle_item.content
+        } else {
+            continue;
+        };
+
+        let item = ClipboardItem {
+            content,
+            kind: bundle_item.kind,
+            data_type: bundle_item.data_type,
+            timestamp: bundle_item.timestamp,
+            source_app: bundle_item.source_app,
+            note: bundle_item.note,
+            html_content: bundle_item.html_content,
+            collection_id: Some(collection_id),
+            ..Default::default()
+        };
+        // 已存在的相同内容只合并备注、标签和时间；已在其他收藏夹中的条目不移动
+        let merged = state
+            .db
+            .merge_item(&item, &bundle_item.tags, max_size)
+            .map_err(|e| e.to_string())?;
+        remove_pruned_images(&merged.pruned);
+        if !merged.merged {
+            imported.items.push(merged.id);
+            crate::encoding::record(&state.db, merged.id, decoded);
+        } else {
+            if item.kind == "image" {
+                let _ = std::fs::remove_file(&item.content);
+            }
+            if merged.collection_id.is_none() {
+                state
+                    .db
+                    .set_item_collection(merged.id, Some(collection_id))
+                    .map_err(|e| e.to_string())?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 图片压缩包 manifest.json 中的一条记录
+#[derive(Serialize)]
+struct ImageManifestItem {
+    file: String,
+    timestamp: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    source_app: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    note: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    height: Option<u32>,
+}
+
+/// 把选中的图片条目（原始文件）打包成 zip，附带记录时间和备注的 manifest.json；
+/// 传 ids 时按 ids 导出，否则按 filter 筛选。敏感条目和缺失的文件会被跳过，返回导出的图片数
+pub fn export_images_zip(
+    app: &tauri::AppHandle,
+    ids: Option<Vec<i64>>,
+    filter: Option<ItemFilter>,
+    path: &Path,
+) -> Result<usize, String> {
+    let state = app.state::<AppState>();
+    let items = match ids {
+        Some(ids) => ids
+            .into_iter()
+            .filter_map(|id| state.db.get_item(id).ok())
+            .collect(),
+        None => state
+            .db
+            .get_items_by_filter(&filter.unwrap_or_default())
+            .map_err(|e| e.to_string())?,
+    };
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    // 图片本身已压缩，直接存储
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Stored);
+
+    let mut manifest = Vec::new();
+    let mut names = std::collections::HashSet::new();
+    for item in items
+        .iter()
+        .filter(|i| i.kind == "image" && !i.is_sensitive)
+    {
+        let source = Path::new(&item.content);
+        let Ok(bytes) = std::fs::read(source) else {
+            log::warn!("Skipping missing image {:?} in export", source);
+            continue;
+        };
+        let mut name = source
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| format!("{}.png", manifest.len()));
+        // 不同目录下可能有同名文件
+        if !names.insert(name.clone()) {
+            name = format!("{}_{}", item.id.unwrap_or_default(), name);
+            names.insert(name.clone());
+        }
+        zip.start_file(name.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        zip.write_all(&bytes).map_err(|e| e.to_string())?;
+        manifest.push(ImageManifestItem {
+            file: name,
+            timestamp: item.timestamp.clone(),
+            source_app: item.source_app.clone(),
+            note: item.note.clone(),
+            width: item.image_width,
+            height: item.image_height,
+        });
+    }
+
+    zip.start_file(
+        "manifest.json",
+        options.compression_method(CompressionMethod::Deflated),
+    )
+    .map_err(|e| e.to_string())?;
+    let count = manifest.len();
+    let manifest_json = serde_json::json!({
+        "exported_at": Local::now().to_rfc3339(),
+        "image_count": count,
+        "images": manifest,
+    });
+    zip.write_all(&serde_json::to_vec_pretty(&manifest_json).map_err(|e| e.to_string())?)
+        .map_err(|e| e.to_string())?;
+    zip.finish().map_err(|e| e.to_string())?;
+
+    Ok(count)
+}
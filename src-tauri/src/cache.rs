@@ -0,0 +1,147 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::CacheStats;
+
+/// 缓存的内容：条目全文，或指定边长的缩略图
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CacheKey {
+    Content(i64),
+    Thumbnail(i64, u32),
+}
+
+impl CacheKey {
+    fn item_id(&self) -> i64 {
+        match self {
+            CacheKey::Content(id) | CacheKey::Thumbnail(id, _) => *id,
+        }
+    }
+}
+
+struct Entry {
+    value: String,
+    last_used: u64,
+}
+
+/// 按字节预算淘汰最久未使用条目的内存缓存，预算为 0 时不缓存
+pub struct ContentCache {
+    entries: HashMap<CacheKey, Entry>,
+    // 最近使用时间 -> key，最小的最先淘汰
+    order: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    bytes: usize,
+    budget: usize,
+    hits: u64,
+    misses: u64,
+    evictions: u64,
+}
+
+impl ContentCache {
+    pub fn new(budget_mb: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            bytes: 0,
+            budget: (budget_mb as usize).saturating_mul(1024 * 1024),
+            hits: 0,
+            misses: 0,
+            evictions: 0,
+        }
+    }
+
+    pub fn set_budget(&mut self, budget_mb: u64) {
+        self.budget = (budget_mb as usize).saturating_mul(1024 * 1024);
+        self.evict();
+    }
+
+    pub fn get(&mut self, key: CacheKey) -> Option<String> {
+        self.tick += 1;
+        let Some(entry) = self.entries.get_mut(&key) else {
+            self.misses += 1;
+            return None;
+        };
+        self.order.remove(&entry.last_used);
+        entry.last_used = self.tick;
+        self.order.insert(self.tick, key);
+        self.hits += 1;
+        Some(entry.value.clone())
+    }
+
+    /// 预热时判断是否已缓存，不计入命中统计
+    pub fn contains(&self, key: CacheKey) -> bool {
+        self.entries.contains_key(&key)
+    }
+
+    pub fn insert(&mut self, key: CacheKey, value: String) {
+        self.remove(key);
+        // 单条超过整个预算的内容不缓存
+        if value.len() > self.budget {
+            return;
+        }
+        self.tick += 1;
+        self.bytes += value.len();
+        self.order.insert(self.tick, key);
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                last_used: self.tick,
+            },
+        );
+        self.evict();
+    }
+
+    fn remove(&mut self, key: CacheKey) {
+        if let Some(entry) = self.entries.remove(&key) {
+            self.order.remove(&entry.last_used);
+            self.bytes -= entry.value.len();
+        }
+    }
+
+    /// 条目内容变化或被删除后，移除它的全文和缩略图
+    pub fn invalidate(&mut self, id: i64) {
+        let keys: Vec<CacheKey> = self
+            .entries
+            .keys()
+            .filter(|k| k.item_id() == id)
+            .copied()
+            .collect();
+        for key in keys {
+            self.remove(key);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+        self.bytes = 0;
+    }
+
+    fn evict(&mut self) {
+        self.shrink_to(self.budget);
+    }
+
+    /// 淘汰最久未使用的条目直到占用不超过 limit 字节，预算本身不变
+    pub fn shrink_to(&mut self, limit: usize) {
+        while self.bytes > limit {
+            let Some((_, key)) = self.order.pop_first() else {
+                break;
+            };
+            if let Some(entry) = self.entries.remove(&key) {
+                self.bytes -= entry.value.len();
+                self.evictions += 1;
+            }
+        }
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.len(),
+            bytes: self.bytes as u64,
+            budget_bytes: self.budget as u64,
+            hits: self.hits,
+            misses: self.misses,
+            evictions: self.evictions,
+        }
+    }
+}
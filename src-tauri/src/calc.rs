@@ -0,0 +1,423 @@
+use crate::models::SuggestedAction;
+use crate::state::AppState;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+use tauri::Manager;
+
+const RATES_URL: &str = "https://open.er-api.com/v6/latest/USD";
+// 汇率缓存有效期（秒）
+const RATES_TTL_SECS: i64 = 12 * 60 * 60;
+const MAX_EXPRESSION_LEN: usize = 200;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ExchangeRates {
+    pub base: String,
+    pub fetched_at: i64,
+    pub rates: HashMap<String, f64>,
+}
+
+impl ExchangeRates {
+    pub fn load<P: AsRef<Path>>(path: P) -> Option<Self> {
+        let content = fs::read_to_string(path).ok()?;
+        serde_json::from_str(&content).ok()
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), String> {
+        let json = serde_json::to_string(self).map_err(|e| e.to_string())?;
+        fs::write(path, json).map_err(|e| e.to_string())
+    }
+
+    pub fn is_stale(&self) -> bool {
+        chrono::Utc::now().timestamp() - self.fetched_at > RATES_TTL_SECS
+    }
+
+    fn convert(&self, amount: f64, from: &str, to: &str) -> Option<f64> {
+        let rate = |code: &str| {
+            if code == self.base {
+                Some(1.0)
+            } else {
+                self.rates.get(code).copied()
+            }
+        };
+        Some(amount / rate(from)? * rate(to)?)
+    }
+}
+
+pub fn rates_cache_path(app: &tauri::AppHandle) -> PathBuf {
+    let state = app.state::<AppState>();
+    state
+        .config_path
+        .parent()
+        .map(|dir| dir.join("exchange_rates.json"))
+        .unwrap_or_else(|| PathBuf::from("exchange_rates.json"))
+}
+
+async fn fetch_rates() -> Result<ExchangeRates, String> {
+    #[derive(Deserialize)]
+    struct RatesResponse {
+        base_code: String,
+        rates: HashMap<String, f64>,
+    }
+
+    let response: RatesResponse = reqwest::get(RATES_URL)
+        .await
+        .map_err(|e| e.to_string())?
+        .error_for_status()
+        .map_err(|e| e.to_string())?
+        .json()
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(ExchangeRates {
+        base: response.base_code,
+        fetched_at: chrono::Utc::now().timestamp(),
+        rates: response.rates,
+    })
+}
+
+/// 启动时读取汇率缓存文件，不访问网络
+pub fn load_cached_rates(app: &tauri::AppHandle) {
+    if let Some(cached) = ExchangeRates::load(rates_cache_path(app)) {
+        let state = app.state::<AppState>();
+        if let Ok(mut rates) = state.exchange_rates.lock() {
+            *rates = Some(cached);
+        };
+    }
+}
+
+/// 在后台拉取汇率并写入缓存文件，同一时间只有一次请求
+fn refresh_rates(app: &tauri::AppHandle) {
+    static FETCHING: AtomicBool = AtomicBool::new(false);
+    if FETCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        match fetch_rates().await {
+            Ok(fresh) => {
+                if let Err(e) = fresh.save(rates_cache_path(&app)) {
+                    log::warn!("Failed to cache exchange rates: {}", e);
+                }
+                let state = app.state::<AppState>();
+                if let Ok(mut rates) = state.exchange_rates.lock() {
+                    *rates = Some(fresh);
+                };
+                log::info!("Exchange rates refreshed");
+            }
+            Err(e) => log::warn!("Failed to fetch exchange rates: {}", e),
+        }
+        FETCHING.store(false, Ordering::SeqCst);
+    });
+}
+
+/// 按配置为复制的文本生成计算建议。汇率只在遇到金额且缓存缺失或过期时才拉取，
+/// 拉取完成后复制的金额才有换算结果
+pub fn suggestions(app: &tauri::AppHandle, text: &str) -> Vec<SuggestedAction> {
+    let state = app.state::<AppState>();
+    let (enabled, target_currency) = {
+        let config = state.config.lock().unwrap();
+        (config.calc_suggestions, config.target_currency.clone())
+    };
+    if !enabled {
+        return Vec::new();
+    }
+    let rates = state.exchange_rates.lock().unwrap();
+    if currency_amount(text.trim()).is_some() && rates.as_ref().is_none_or(|r| r.is_stale()) {
+        refresh_rates(app);
+    }
+    suggest_actions(text, rates.as_ref(), &target_currency)
+}
+
+struct Parser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    operators: usize,
+}
+
+impl Parser<'_> {
+    fn skip_ws(&mut self) {
+        while matches!(self.chars.peek(), Some(c) if c.is_whitespace()) {
+            self.chars.next();
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.chars.peek().copied()
+    }
+
+    fn expr(&mut self) -> Option<f64> {
+        let mut value = self.term()?;
+        while let Some(op) = self.peek() {
+            match op {
+                '+' => {
+                    self.chars.next();
+                    value += self.term()?;
+                }
+                '-' | '−' => {
+                    self.chars.next();
+                    value -= self.term()?;
+                }
+                _ => break,
+            }
+            self.operators += 1;
+        }
+        Some(value)
+    }
+
+    fn term(&mut self) -> Option<f64> {
+        let mut value = self.power()?;
+        while let Some(op) = self.peek() {
+            match op {
+                '*' | 'x' | 'X' | '×' => {
+                    self.chars.next();
+                    value *= self.power()?;
+                }
+                '/' | '÷' => {
+                    self.chars.next();
+                    let rhs = self.power()?;
+                    if rhs == 0.0 {
+                        return None;
+                    }
+                    value /= rhs;
+                }
+                '%' => {
+                    self.chars.next();
+                    let rhs = self.power()?;
+                    if rhs == 0.0 {
+                        return None;
+                    }
+                    value %= rhs;
+                }
+                _ => break,
+            }
+            self.operators += 1;
+        }
+        Some(value)
+    }
+
+    fn power(&mut self) -> Option<f64> {
+        let base = self.unary()?;
+        if self.peek() == Some('^') {
+            self.chars.next();
+            self.operators += 1;
+            // Right associative
+            let exp = self.power()?;
+            return Some(base.powf(exp));
+        }
+        Some(base)
+    }
+
+    fn unary(&mut self) -> Option<f64> {
+        match self.peek()? {
+            '-' | '−' => {
+                self.chars.next();
+                Some(-self.unary()?)
+            }
+            '+' => {
+                self.chars.next();
+                self.unary()
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Option<f64> {
+        if self.peek()? == '(' {
+            self.chars.next();
+            let value = self.expr()?;
+            if self.peek()? != ')' {
+                return None;
+            }
+            self.chars.next();
+            return Some(value);
+        }
+
+        let mut number = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_ascii_digit() || c == '.' {
+                number.push(c);
+                self.chars.next();
+            } else if c == ',' {
+                // Thousands separator
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        number.parse().ok()
+    }
+}
+
+/// 计算简单的四则运算表达式，至少需要一个运算符才视为表达式
+pub fn evaluate_expression(input: &str) -> Option<f64> {
+    let trimmed = input.trim().trim_end_matches('=').trim_end();
+    if trimmed.is_empty() || trimmed.len() > MAX_EXPRESSION_LEN {
+        return None;
+    }
+    if !trimmed
+        .chars()
+        .all(|c| c.is_ascii_digit() || c.is_whitespace() || "+-−*/÷×xX^%().,".contains(c))
+    {
+        return None;
+    }
+
+    // 0x10 是十六进制数，不是 0 乘以 10
+    static HEX: OnceLock<Regex> = OnceLock::new();
+    let hex_regex = HEX.get_or_init(|| Regex::new(r"(?:^|[^\d.,])0[xX]").unwrap());
+    if hex_regex.is_match(trimmed) {
+        return None;
+    }
+
+    // Dates and phone numbers look like subtraction chains
+    static DATE: OnceLock<Regex> = OnceLock::new();
+    let date_regex = DATE.get_or_init(|| Regex::new(r"^\d{1,4}[-/.]\d{1,2}[-/.]\d{1,4}$").unwrap());
+    let minus_count = trimmed.matches(['-', '−']).count();
+    let other_ops = trimmed
+        .matches(['+', '*', '/', '÷', '×', 'x', 'X', '^', '%'])
+        .count();
+    if date_regex.is_match(trimmed) || (minus_count >= 2 && other_ops == 0) {
+        return None;
+    }
+
+    let mut parser = Parser {
+        chars: trimmed.chars().peekable(),
+        operators: 0,
+    };
+    let value = parser.expr()?;
+    if parser.peek().is_some() || parser.operators == 0 || !value.is_finite() {
+        return None;
+    }
+    Some(value)
+}
+
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let formatted = format!("{:.6}", value);
+    formatted
+        .trim_end_matches('0')
+        .trim_end_matches('.')
+        .to_string()
+}
+
+fn convert_unit(value: f64, unit: &str) -> Option<(f64, &'static str)> {
+    let converted = match unit {
+        "km" => (value * 0.621_371, "mi"),
+        "mi" | "mile" | "miles" => (value * 1.609_344, "km"),
+        "m" => (value * 3.280_84, "ft"),
+        "ft" | "feet" => (value * 0.3048, "m"),
+        "cm" => (value / 2.54, "in"),
+        "in" | "inch" | "inches" => (value * 2.54, "cm"),
+        "kg" => (value * 2.204_623, "lb"),
+        "lb" | "lbs" => (value * 0.453_592, "kg"),
+        "g" => (value * 0.035_274, "oz"),
+        "oz" => (value * 28.349_523, "g"),
+        "l" => (value * 0.264_172, "gal"),
+        "gal" => (value * 3.785_412, "L"),
+        "°c" | "c" => (value * 9.0 / 5.0 + 32.0, "°F"),
+        "°f" | "f" => ((value - 32.0) * 5.0 / 9.0, "°C"),
+        _ => return None,
+    };
+    Some(converted)
+}
+
+fn currency_code(symbol: &str) -> Option<&'static str> {
+    match symbol {
+        "$" => Some("USD"),
+        "€" => Some("EUR"),
+        "£" => Some("GBP"),
+        "¥" | "￥" => Some("CNY"),
+        "₩" => Some("KRW"),
+        "₹" => Some("INR"),
+        _ => None,
+    }
+}
+
+/// 文本是否为金额（$12.50、100 EUR），返回去掉千位分隔符的数值和币种代码
+fn currency_amount(text: &str) -> Option<(String, String)> {
+    static SYMBOL: OnceLock<Regex> = OnceLock::new();
+    static CODE: OnceLock<Regex> = OnceLock::new();
+    let symbol_regex =
+        SYMBOL.get_or_init(|| Regex::new(r"^([$€£¥￥₩₹])\s*(\d[\d,]*(?:\.\d+)?)$").unwrap());
+    let code_regex =
+        CODE.get_or_init(|| Regex::new(r"(?i)^(\d[\d,]*(?:\.\d+)?)\s*([a-z]{3})$").unwrap());
+    if let Some(caps) = symbol_regex.captures(text) {
+        currency_code(&caps[1]).map(|code| (caps[2].replace(',', ""), code.to_string()))
+    } else {
+        code_regex
+            .captures(text)
+            .map(|caps| (caps[1].replace(',', ""), caps[2].to_uppercase()))
+    }
+}
+
+/// 为复制的文本生成计算结果建议（表达式、单位换算、汇率换算）
+pub fn suggest_actions(
+    text: &str,
+    rates: Option<&ExchangeRates>,
+    target_currency: &str,
+) -> Vec<SuggestedAction> {
+    let mut actions = Vec::new();
+    let trimmed = text.trim();
+    if trimmed.is_empty() || trimmed.len() > MAX_EXPRESSION_LEN {
+        return actions;
+    }
+
+    if let Some(value) = evaluate_expression(trimmed) {
+        let result = format_number(value);
+        actions.push(SuggestedAction {
+            kind: "calc".to_string(),
+            label: format!("= {}", result),
+            value: result,
+        });
+        return actions;
+    }
+
+    static UNIT: OnceLock<Regex> = OnceLock::new();
+    let unit_regex = UNIT.get_or_init(|| {
+        Regex::new(
+            r"(?i)^(-?\d+(?:\.\d+)?)\s*(km|miles|mile|mi|m|feet|ft|cm|inches|inch|in|kg|lbs|lb|g|oz|l|gal|°c|°f|c|f)$",
+        )
+        .unwrap()
+    });
+    if let Some(caps) = unit_regex.captures(trimmed) {
+        let value: f64 = caps[1].parse().unwrap_or(0.0);
+        if let Some((converted, unit)) = convert_unit(value, &caps[2].to_lowercase()) {
+            let result = format!(
+                "{} {}",
+                format_number((converted * 100.0).round() / 100.0),
+                unit
+            );
+            actions.push(SuggestedAction {
+                kind: "unit".to_string(),
+                label: result.clone(),
+                value: result,
+            });
+        }
+        return actions;
+    }
+
+    let Some(rates) = rates else {
+        return actions;
+    };
+    if let Some((amount, from)) = currency_amount(trimmed) {
+        let target = target_currency.to_uppercase();
+        if let (Ok(amount), true) = (amount.parse::<f64>(), from != target) {
+            if let Some(converted) = rates.convert(amount, &from, &target) {
+                let result = format!("{:.2} {}", converted, target);
+                actions.push(SuggestedAction {
+                    kind: "currency".to_string(),
+                    label: format!("≈ {}", result),
+                    value: format!("{:.2}", converted),
+                });
+            }
+        }
+    }
+
+    actions
+}
@@ -0,0 +1,378 @@
+use crate::models::SuggestedAction;
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use regex::{Captures, Regex};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+use tauri::Manager;
+
+// 过长的文本一般不是一条日程，不做识别
+const MAX_DETECT_LENGTH: usize = 500;
+// 生成的 .ics 文件保留一天，日历应用打开时就已导入
+const EVENT_FILE_MAX_AGE: std::time::Duration = std::time::Duration::from_secs(24 * 60 * 60);
+
+const MONTH_NAMES: &str = "january|jan|february|feb|march|mar|april|apr|may|june|jun|july|jul|august|aug|september|sept|sep|october|oct|november|nov|december|dec";
+
+/// 从文本中识别出的日期时间，没有时间时为全天
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedDate {
+    pub start: NaiveDateTime,
+    pub all_day: bool,
+}
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+fn num(caps: &Captures, i: usize) -> Option<u32> {
+    caps.get(i).and_then(|m| m.as_str().parse().ok())
+}
+
+fn month_number(name: &str) -> Option<u32> {
+    MONTHS
+        .iter()
+        .position(|m| name.starts_with(m))
+        .map(|i| i as u32 + 1)
+}
+
+/// 没写年份的日期取今天或之后最近的一次
+fn upcoming(today: NaiveDate, month: u32, day: u32) -> Option<NaiveDate> {
+    let date = NaiveDate::from_ymd_opt(today.year(), month, day)?;
+    if date < today {
+        NaiveDate::from_ymd_opt(today.year() + 1, month, day)
+    } else {
+        Some(date)
+    }
+}
+
+/// 今天之后（不含今天）最近的某个星期几
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> NaiveDate {
+    let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+    today + Duration::days(if ahead == 0 { 7 } else { ahead as i64 })
+}
+
+/// 识别文本中的日期；today / 明天这类相对说法在普通句子里很常见，返回的 bool 表示必须同时有时间才算日程
+fn find_date(text: &str, today: NaiveDate) -> Option<(NaiveDate, bool)> {
+    static ISO: OnceLock<Regex> = OnceLock::new();
+    static CHINESE: OnceLock<Regex> = OnceLock::new();
+    static US: OnceLock<Regex> = OnceLock::new();
+    static MONTH_DAY: OnceLock<Regex> = OnceLock::new();
+    static DAY_MONTH: OnceLock<Regex> = OnceLock::new();
+    static RELATIVE: OnceLock<Regex> = OnceLock::new();
+    static IN_DAYS: OnceLock<Regex> = OnceLock::new();
+    static WEEKDAY: OnceLock<Regex> = OnceLock::new();
+    static CHINESE_WEEKDAY: OnceLock<Regex> = OnceLock::new();
+
+    // 2026-10-20、2026/10/20
+    let iso = ISO.get_or_init(|| Regex::new(r"\b(\d{4})[-/.](\d{1,2})[-/.](\d{1,2})\b").unwrap());
+    if let Some(c) = iso.captures(text) {
+        let date = NaiveDate::from_ymd_opt(c[1].parse().ok()?, num(&c, 2)?, num(&c, 3)?)?;
+        return Some((date, false));
+    }
+    // 2026年10月20日、10月20号
+    let chinese =
+        CHINESE.get_or_init(|| Regex::new(r"(?:(\d{4})年)?(\d{1,2})月(\d{1,2})[日号]").unwrap());
+    if let Some(c) = chinese.captures(text) {
+        let date = match c.get(1) {
+            Some(year) => {
+                NaiveDate::from_ymd_opt(year.as_str().parse().ok()?, num(&c, 2)?, num(&c, 3)?)
+            }
+            None => upcoming(today, num(&c, 2)?, num(&c, 3)?),
+        };
+        return Some((date?, false));
+    }
+    // 10/20/2026（月/日/年）
+    let us = US.get_or_init(|| Regex::new(r"\b(\d{1,2})/(\d{1,2})/(\d{4})\b").unwrap());
+    if let Some(c) = us.captures(text) {
+        let date = NaiveDate::from_ymd_opt(c[3].parse().ok()?, num(&c, 1)?, num(&c, 2)?)?;
+        return Some((date, false));
+    }
+    // Oct 20, 2026 / October 20
+    let month_day = MONTH_DAY.get_or_init(|| {
+        Regex::new(&format!(
+            r"\b({})\.?\s+(\d{{1,2}})(?:st|nd|rd|th)?\b(?:,?\s+(\d{{4}})\b)?",
+            MONTH_NAMES
+        ))
+        .unwrap()
+    });
+    if let Some(c) = month_day.captures(text) {
+        let month = month_number(&c[1])?;
+        let date = match num(&c, 3) {
+            Some(year) => NaiveDate::from_ymd_opt(year as i32, month, num(&c, 2)?),
+            None => upcoming(today, month, num(&c, 2)?),
+        };
+        return Some((date?, false));
+    }
+    // 20 October 2026 / 20th Oct
+    let day_month = DAY_MONTH.get_or_init(|| {
+        Regex::new(&format!(
+            r"\b(\d{{1,2}})(?:st|nd|rd|th)?\s+({})\b\.?(?:,?\s+(\d{{4}})\b)?",
+            MONTH_NAMES
+        ))
+        .unwrap()
+    });
+    if let Some(c) = day_month.captures(text) {
+        let month = month_number(&c[2])?;
+        let date = match num(&c, 3) {
+            Some(year) => NaiveDate::from_ymd_opt(year as i32, month, num(&c, 1)?),
+            None => upcoming(today, month, num(&c, 1)?),
+        };
+        return Some((date?, false));
+    }
+    // today / tomorrow / 明天
+    let relative = RELATIVE.get_or_init(|| {
+        Regex::new(r"\b(day after tomorrow|tomorrow|today|tonight)\b|(今天|今晚|明天|明晚|后天)")
+            .unwrap()
+    });
+    if let Some(c) = relative.captures(text) {
+        let days = match c.get(1).or(c.get(2)).map(|m| m.as_str())? {
+            "day after tomorrow" | "后天" => 2,
+            "tomorrow" | "明天" | "明晚" => 1,
+            _ => 0,
+        };
+        return Some((today + Duration::days(days), true));
+    }
+    // in 3 days / in 2 weeks
+    let in_days =
+        IN_DAYS.get_or_init(|| Regex::new(r"\bin (\d{1,3}) (day|days|week|weeks)\b").unwrap());
+    if let Some(c) = in_days.captures(text) {
+        let n = num(&c, 1)? as i64;
+        let days = if c[2].starts_with("week") { n * 7 } else { n };
+        return Some((today + Duration::days(days), true));
+    }
+    // next Tuesday / this friday / monday：都取之后最近的那一天
+    let weekday = WEEKDAY.get_or_init(|| {
+        Regex::new(
+            r"\b(?:(?:next|this|on)\s+)?(monday|tuesday|wednesday|thursday|friday|saturday|sunday)\b",
+        )
+        .unwrap()
+    });
+    if let Some(c) = weekday.captures(text) {
+        let (_, weekday) = WEEKDAYS.iter().find(|(name, _)| *name == &c[1])?;
+        return Some((next_weekday(today, *weekday), false));
+    }
+    // 周二、下周二、星期五、下礼拜天
+    let chinese_weekday = CHINESE_WEEKDAY
+        .get_or_init(|| Regex::new(r"(下?)(?:周|星期|礼拜)([一二三四五六日天])").unwrap());
+    if let Some(c) = chinese_weekday.captures(text) {
+        let offset = "一二三四五六日".find(&c[2]).map(|i| i / 3).unwrap_or(6) as i64;
+        let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+        let date = monday + Duration::days(offset);
+        let date = if !c[1].is_empty() || date < today {
+            date + Duration::days(7)
+        } else {
+            date
+        };
+        return Some((date, false));
+    }
+    None
+}
+
+fn find_time(text: &str) -> Option<NaiveTime> {
+    static NOON: OnceLock<Regex> = OnceLock::new();
+    static AM_PM: OnceLock<Regex> = OnceLock::new();
+    static CHINESE: OnceLock<Regex> = OnceLock::new();
+    static CLOCK: OnceLock<Regex> = OnceLock::new();
+
+    let noon = NOON.get_or_init(|| Regex::new(r"\b(noon|midnight)\b").unwrap());
+    if let Some(c) = noon.captures(text) {
+        return NaiveTime::from_hms_opt(if &c[1] == "noon" { 12 } else { 0 }, 0, 0);
+    }
+    // 3pm、3:30 p.m.；am/pm 后必须是词边界，避免 "3 amps" 这类误判
+    let am_pm = AM_PM.get_or_init(|| {
+        Regex::new(r"\b(\d{1,2})(?::([0-5]\d))?\s*(am\b|pm\b|a\.m\.|p\.m\.)").unwrap()
+    });
+    if let Some(c) = am_pm.captures(text) {
+        let hour = num(&c, 1)?;
+        if !(1..=12).contains(&hour) {
+            return None;
+        }
+        let hour = match (hour, c[3].starts_with('p')) {
+            (12, false) => 0,
+            (12, true) => 12,
+            (h, true) => h + 12,
+            (h, false) => h,
+        };
+        return NaiveTime::from_hms_opt(hour, num(&c, 2).unwrap_or(0), 0);
+    }
+    // 下午3点、3点半、15:30
+    let chinese = CHINESE.get_or_init(|| {
+        Regex::new(r"(上午|早上|中午|下午|晚上)?\s*(\d{1,2})[点时](?:(\d{1,2})分?|(半))?").unwrap()
+    });
+    if let Some(c) = chinese.captures(text) {
+        let mut hour = num(&c, 2)?;
+        if matches!(c.get(1).map(|m| m.as_str()), Some("下午" | "晚上")) && hour < 12 {
+            hour += 12;
+        }
+        let minute = if c.get(4).is_some() {
+            30
+        } else {
+            num(&c, 3).unwrap_or(0)
+        };
+        return NaiveTime::from_hms_opt(hour, minute, 0);
+    }
+    let clock = CLOCK.get_or_init(|| Regex::new(r"\b([01]?\d|2[0-3]):([0-5]\d)\b").unwrap());
+    if let Some(c) = clock.captures(text) {
+        return NaiveTime::from_hms_opt(num(&c, 1)?, num(&c, 2)?, 0);
+    }
+    None
+}
+
+/// 识别文本中的日期和时间（如 "next Tuesday 3pm"、"明天下午3点"），必须包含日期
+pub fn parse(text: &str, now: NaiveDateTime) -> Option<ParsedDate> {
+    if text.len() > MAX_DETECT_LENGTH {
+        return None;
+    }
+    let lower = text.to_lowercase();
+    let (date, needs_time) = find_date(&lower, now.date())?;
+    Some(match find_time(&lower) {
+        Some(time) => ParsedDate {
+            start: date.and_time(time),
+            all_day: false,
+        },
+        None if needs_time => return None,
+        None => ParsedDate {
+            start: date.and_hms_opt(0, 0, 0)?,
+            all_day: true,
+        },
+    })
+}
+
+/// 保存在条目建议操作中的日期时间：全天为 YYYY-MM-DD，否则为 YYYY-MM-DDTHH:MM:SS
+pub fn to_value(parsed: &ParsedDate) -> String {
+    if parsed.all_day {
+        parsed.start.format("%Y-%m-%d").to_string()
+    } else {
+        parsed.start.format("%Y-%m-%dT%H:%M:%S").to_string()
+    }
+}
+
+/// to_value 的逆操作
+pub fn from_value(value: &str) -> Option<ParsedDate> {
+    if let Ok(start) = NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S") {
+        return Some(ParsedDate {
+            start,
+            all_day: false,
+        });
+    }
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d").ok()?;
+    Some(ParsedDate {
+        start: date.and_hms_opt(0, 0, 0)?,
+        all_day: true,
+    })
+}
+
+/// 转义 ICS 文本字段
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+}
+
+/// 按 RFC 5545 把超过 75 字节的行折行
+fn ics_fold(line: &str) -> String {
+    let mut folded = String::new();
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            width = 1;
+        }
+        folded.push(c);
+        width += c.len_utf8();
+    }
+    folded
+}
+
+/// 生成单个事件的 ICS 内容：定时事件默认一小时，使用本地浮动时间
+pub fn build_ics(uid: &str, summary: &str, description: &str, parsed: &ParsedDate) -> String {
+    let (start, end) = if parsed.all_day {
+        (
+            format!("DTSTART;VALUE=DATE:{}", parsed.start.format("%Y%m%d")),
+            format!(
+                "DTEND;VALUE=DATE:{}",
+                (parsed.start + Duration::days(1)).format("%Y%m%d")
+            ),
+        )
+    } else {
+        (
+            format!("DTSTART:{}", parsed.start.format("%Y%m%dT%H%M%S")),
+            format!(
+                "DTEND:{}",
+                (parsed.start + Duration::hours(1)).format("%Y%m%dT%H%M%S")
+            ),
+        )
+    };
+    let lines = [
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//clipboard//EN".to_string(),
+        "BEGIN:VEVENT".to_string(),
+        format!("UID:{}", uid),
+        format!("DTSTAMP:{}", chrono::Utc::now().format("%Y%m%dT%H%M%SZ")),
+        start,
+        end,
+        format!("SUMMARY:{}", ics_escape(summary)),
+        format!("DESCRIPTION:{}", ics_escape(description)),
+        "END:VEVENT".to_string(),
+        "END:VCALENDAR".to_string(),
+    ];
+    let mut ics: String = lines
+        .iter()
+        .map(|line| ics_fold(line))
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    ics.push_str("\r\n");
+    ics
+}
+
+/// 生成的 .ics 文件所在目录，顺带删除超过 EVENT_FILE_MAX_AGE 的旧文件
+pub fn events_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join("events");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    for entry in std::fs::read_dir(&dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+    {
+        let stale = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .is_some_and(|age| age > EVENT_FILE_MAX_AGE);
+        if stale {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+    Ok(dir)
+}
+
+/// 文本中包含日期时作为建议操作附带在条目上，value 为识别出的日期时间
+pub fn suggestion(text: &str, now: NaiveDateTime) -> Option<SuggestedAction> {
+    let parsed = parse(text, now)?;
+    let label = if parsed.all_day {
+        parsed.start.format("%Y-%m-%d").to_string()
+    } else {
+        parsed.start.format("%Y-%m-%d %H:%M").to_string()
+    };
+    Some(SuggestedAction {
+        kind: "event".to_string(),
+        label,
+        value: to_value(&parsed),
+    })
+}
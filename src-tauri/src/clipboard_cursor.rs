@@ -0,0 +1,105 @@
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+use crate::models::ClipboardItem;
+use crate::state::AppState;
+use crate::utils::write_to_clipboard;
+
+/// 剪贴板在历史中后退（更早）或前进（更新）
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    Back,
+    Forward,
+}
+
+/// 按配置重新注册后退 / 前进快捷键，留空的不注册，不抢占其他快捷键
+pub fn sync_shortcuts(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let (main, back, forward) = {
+        let config = state.config.lock().unwrap();
+        (
+            config.shortcut.clone(),
+            config.clipboard_back_shortcut.clone(),
+            config.clipboard_forward_shortcut.clone(),
+        )
+    };
+    let main = crate::shortcut::parse(&main).ok();
+
+    let manager = app.global_shortcut();
+    let Ok(mut registered) = state.clipboard_cursor_shortcuts.lock() else {
+        return;
+    };
+    for (shortcut, _) in registered.drain(..) {
+        let _ = manager.unregister(shortcut);
+    }
+
+    for (accelerator, direction) in [(back, Direction::Back), (forward, Direction::Forward)] {
+        if accelerator.trim().is_empty() {
+            continue;
+        }
+        let shortcut = match crate::shortcut::parse(&accelerator) {
+            Ok(shortcut) => shortcut,
+            Err(e) => {
+                log::warn!("Invalid clipboard history shortcut {}: {}", accelerator, e);
+                continue;
+            }
+        };
+        if Some(shortcut) == main
+            || registered.iter().any(|(s, _)| *s == shortcut)
+            || crate::quick_paste::item_for_shortcut(app, &shortcut).is_some()
+        {
+            log::warn!("Clipboard history shortcut {} already in use", accelerator);
+            continue;
+        }
+        match manager.register(shortcut) {
+            Ok(()) => registered.push((shortcut, direction)),
+            Err(e) => log::warn!(
+                "Failed to register clipboard history shortcut {}: {}",
+                accelerator,
+                e
+            ),
+        }
+    }
+}
+
+/// 快捷键对应的方向
+pub fn direction_for_shortcut(app: &tauri::AppHandle, shortcut: &Shortcut) -> Option<Direction> {
+    let state = app.state::<AppState>();
+    let registered = state.clipboard_cursor_shortcuts.lock().ok()?;
+    registered
+        .iter()
+        .find(|(s, _)| s == shortcut)
+        .map(|(_, direction)| *direction)
+}
+
+/// 把剪贴板换成历史中前一条 / 后一条记录，不打开任何窗口；
+/// 返回写入剪贴板的条目，已经到头时返回 None
+pub fn step(app: &tauri::AppHandle, direction: Direction) -> Result<Option<ClipboardItem>, String> {
+    let state = app.state::<AppState>();
+    let mut cursor = state.clipboard_cursor.lock().unwrap();
+    let offset = match direction {
+        Direction::Back => *cursor + 1,
+        Direction::Forward if *cursor == 0 => return Ok(None),
+        Direction::Forward => *cursor - 1,
+    };
+    let Some(id) = state
+        .db
+        .get_id_at_offset(offset)
+        .map_err(|e| e.to_string())?
+    else {
+        return Ok(None);
+    };
+    let item = state.db.get_item(id).map_err(|e| e.to_string())?;
+    // 自己写入的内容带有标记，监听线程不会把它当作新的复制
+    write_to_clipboard(app, &item)?;
+    *cursor = offset;
+    log::info!("Clipboard moved to history item {} ({} back)", id, offset);
+    Ok(Some(item))
+}
+
+/// 复制或粘贴了内容后回到最新一条
+pub fn reset(app: &tauri::AppHandle) {
+    if let Ok(mut cursor) = app.state::<AppState>().clipboard_cursor.lock() {
+        *cursor = 0;
+    }
+}
@@ -0,0 +1,361 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+use crate::models::{ColorSwatch, SuggestedAction};
+
+// 支持互相转换的颜色写法
+pub const COLOR_FORMATS: [&str; 4] = ["hex", "rgb", "hsl", "oklch"];
+
+/// sRGB 颜色，r/g/b 为 0..=255，alpha 为 0..=1
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f64,
+    pub g: f64,
+    pub b: f64,
+    pub a: f64,
+}
+
+/// 解析数值，支持百分比（相对 scale）
+fn number(value: &str, scale: f64) -> Option<f64> {
+    match value.strip_suffix('%') {
+        Some(percent) => percent
+            .trim()
+            .parse::<f64>()
+            .ok()
+            .map(|v| v / 100.0 * scale),
+        None => value.trim().parse::<f64>().ok(),
+    }
+}
+
+/// 解析角度，支持 deg / turn / rad 单位
+fn hue(value: &str) -> Option<f64> {
+    let value = value.trim();
+    if let Some(v) = value.strip_suffix("deg") {
+        v.parse().ok()
+    } else if let Some(v) = value.strip_suffix("turn") {
+        v.parse::<f64>().ok().map(|v| v * 360.0)
+    } else if let Some(v) = value.strip_suffix("rad") {
+        v.parse::<f64>().ok().map(|v| v.to_degrees())
+    } else {
+        value.parse().ok()
+    }
+}
+
+/// short 为 true 时接受 3/4 位简写，否则只接受 6/8 位
+fn parse_hex(hex: &str, short: bool) -> Option<Color> {
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let digits: Vec<u8> = match hex.len() {
+        3 | 4 if short => hex
+            .chars()
+            .map(|c| u8::from_str_radix(&c.to_string().repeat(2), 16).ok())
+            .collect::<Option<_>>()?,
+        6 | 8 => (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).ok())
+            .collect::<Option<_>>()?,
+        _ => return None,
+    };
+    Some(Color {
+        r: digits[0] as f64,
+        g: digits[1] as f64,
+        b: digits[2] as f64,
+        a: digits.get(3).map(|a| *a as f64 / 255.0).unwrap_or(1.0),
+    })
+}
+
+fn hsl_to_rgb(h: f64, s: f64, l: f64) -> (f64, f64, f64) {
+    let h = h.rem_euclid(360.0);
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = l - c / 2.0;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    ((r + m) * 255.0, (g + m) * 255.0, (b + m) * 255.0)
+}
+
+fn rgb_to_hsl(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let (r, g, b) = (r / 255.0, g / 255.0, b / 255.0);
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let l = (max + min) / 2.0;
+    let d = max - min;
+    if d == 0.0 {
+        return (0.0, 0.0, l);
+    }
+    let s = d / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == r {
+        60.0 * ((g - b) / d).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / d + 2.0)
+    } else {
+        60.0 * ((r - g) / d + 4.0)
+    };
+    (h, s, l)
+}
+
+fn to_linear(c: f64) -> f64 {
+    let c = c / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f64) -> f64 {
+    let c = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    c * 255.0
+}
+
+/// sRGB -> OKLCH（L 为 0..=1，hue 为角度）
+fn rgb_to_oklch(r: f64, g: f64, b: f64) -> (f64, f64, f64) {
+    let (r, g, b) = (to_linear(r), to_linear(g), to_linear(b));
+    let l = (0.4122214708 * r + 0.5363325363 * g + 0.0514459929 * b).cbrt();
+    let m = (0.2119034982 * r + 0.6806995451 * g + 0.1073969566 * b).cbrt();
+    let s = (0.0883024619 * r + 0.2817188376 * g + 0.6299787005 * b).cbrt();
+    let lightness = 0.2104542553 * l + 0.7936177850 * m - 0.0040720468 * s;
+    let a = 1.9779984951 * l - 2.4285922050 * m + 0.4505937099 * s;
+    let b = 0.0259040371 * l + 0.7827717662 * m - 0.8086757660 * s;
+    let chroma = (a * a + b * b).sqrt();
+    let hue = if chroma < 1e-4 {
+        0.0
+    } else {
+        b.atan2(a).to_degrees().rem_euclid(360.0)
+    };
+    (lightness, chroma, hue)
+}
+
+fn oklch_to_rgb(lightness: f64, chroma: f64, hue: f64) -> (f64, f64, f64) {
+    let (a, b) = (
+        chroma * hue.to_radians().cos(),
+        chroma * hue.to_radians().sin(),
+    );
+    let l = (lightness + 0.3963377774 * a + 0.2158037573 * b).powi(3);
+    let m = (lightness - 0.1055613458 * a - 0.0638541728 * b).powi(3);
+    let s = (lightness - 0.0894841775 * a - 1.2914855480 * b).powi(3);
+    (
+        from_linear(4.0767416621 * l - 3.3077115913 * m + 0.2309699292 * s),
+        from_linear(-1.2684380046 * l + 2.6097574011 * m - 0.3413193965 * s),
+        from_linear(-0.0041960863 * l - 0.7034186147 * m + 1.7076147010 * s),
+    )
+}
+
+/// 解析 #hex、rgb()/rgba()、hsl()/hsla()、oklch() 写法的颜色。
+/// #abc、#1234 这类简写与 issue 编号和短单词难以区分，只在以 ; 结尾的 CSS 写法中接受
+pub fn parse(text: &str) -> Option<Color> {
+    static FUNCTION: OnceLock<Regex> = OnceLock::new();
+
+    let text = text.trim().to_lowercase();
+    if let Some(hex) = text.strip_prefix('#') {
+        return match hex.strip_suffix(';') {
+            Some(hex) => parse_hex(hex.trim_end(), true),
+            None => parse_hex(hex, false),
+        };
+    }
+
+    let function_regex =
+        FUNCTION.get_or_init(|| Regex::new(r"^(rgba?|hsla?|oklch)\(\s*([^)]*)\)$").unwrap());
+    let caps = function_regex.captures(&text)?;
+    // 同时支持逗号分隔和空格分隔（alpha 用 / 分隔）的写法
+    let args = caps[2].replace(['/', ','], " ");
+    let args: Vec<&str> = args.split_whitespace().collect();
+    if args.len() != 3 && args.len() != 4 {
+        return None;
+    }
+    let a = match args.get(3) {
+        Some(alpha) => number(alpha, 1.0)?.clamp(0.0, 1.0),
+        None => 1.0,
+    };
+    // hsl 的饱和度和亮度可以写成百分比或 0..=100
+    let percent = |v: &str| {
+        number(v, 1.0).map(|x| {
+            if v.ends_with('%') {
+                x.clamp(0.0, 1.0)
+            } else {
+                (x / 100.0).clamp(0.0, 1.0)
+            }
+        })
+    };
+    let (r, g, b) = match &caps[1] {
+        "rgb" | "rgba" => (
+            number(args[0], 255.0)?,
+            number(args[1], 255.0)?,
+            number(args[2], 255.0)?,
+        ),
+        "hsl" | "hsla" => hsl_to_rgb(hue(args[0])?, percent(args[1])?, percent(args[2])?),
+        _ => oklch_to_rgb(number(args[0], 1.0)?, number(args[1], 0.4)?, hue(args[2])?),
+    };
+    Some(Color {
+        r: r.clamp(0.0, 255.0),
+        g: g.clamp(0.0, 255.0),
+        b: b.clamp(0.0, 255.0),
+        a,
+    })
+}
+
+pub fn is_color(text: &str) -> bool {
+    parse(text).is_some()
+}
+
+fn trim_float(value: f64, decimals: usize) -> String {
+    let formatted = format!("{:.*}", decimals, value);
+    if formatted.contains('.') {
+        formatted
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string()
+    } else {
+        formatted
+    }
+}
+
+/// 按指定写法输出颜色
+pub fn format(color: &Color, format: &str) -> Result<String, String> {
+    let (r, g, b) = (color.r.round(), color.g.round(), color.b.round());
+    let opaque = color.a >= 1.0;
+    let alpha = trim_float(color.a, 3);
+    Ok(match format {
+        "hex" if opaque => format!("#{:02x}{:02x}{:02x}", r as u8, g as u8, b as u8),
+        "hex" => format!(
+            "#{:02x}{:02x}{:02x}{:02x}",
+            r as u8,
+            g as u8,
+            b as u8,
+            (color.a * 255.0).round() as u8
+        ),
+        "rgb" if opaque => format!("rgb({}, {}, {})", r, g, b),
+        "rgb" => format!("rgba({}, {}, {}, {})", r, g, b, alpha),
+        "hsl" => {
+            let (h, s, l) = rgb_to_hsl(color.r, color.g, color.b);
+            let (h, s, l) = (h.round() % 360.0, (s * 100.0).round(), (l * 100.0).round());
+            if opaque {
+                format!("hsl({}, {}%, {}%)", h, s, l)
+            } else {
+                format!("hsla({}, {}%, {}%, {})", h, s, l, alpha)
+            }
+        }
+        "oklch" => {
+            let (l, c, h) = rgb_to_oklch(color.r, color.g, color.b);
+            let body = format!(
+                "{}% {} {}",
+                trim_float(l * 100.0, 2),
+                trim_float(c, 4),
+                trim_float(h, 2)
+            );
+            if opaque {
+                format!("oklch({})", body)
+            } else {
+                format!("oklch({} / {})", body, alpha)
+            }
+        }
+        _ => return Err(format!("Unknown color format: {}", format)),
+    })
+}
+
+/// 颜色条目的其他写法，作为建议操作展示
+pub fn conversions(text: &str) -> Vec<SuggestedAction> {
+    let Some(color) = parse(text) else {
+        return Vec::new();
+    };
+    let original = text.trim().to_lowercase();
+    COLOR_FORMATS
+        .iter()
+        .filter_map(|f| format(&color, f).ok())
+        .filter(|converted| *converted != original)
+        .map(|converted| SuggestedAction {
+            kind: "color".to_string(),
+            label: converted.clone(),
+            value: converted,
+        })
+        .collect()
+}
+
+/// 用 k-means 提取图片的 n 个主色，按占比从高到低排列
+pub fn extract_palette(img: &image::DynamicImage, n: usize) -> Vec<ColorSwatch> {
+    let n = n.clamp(1, 16);
+    // 缩小后取样足够准确，用最近邻避免插值出新颜色
+    let pixels: Vec<[f64; 3]> = img
+        .resize(96, 96, image::imageops::FilterType::Nearest)
+        .to_rgba8()
+        .pixels()
+        .filter(|p| p[3] >= 128)
+        .map(|p| [p[0] as f64, p[1] as f64, p[2] as f64])
+        .collect();
+    if pixels.is_empty() {
+        return Vec::new();
+    }
+
+    // 按亮度排序后等距取初始中心，结果稳定可复现
+    let mut sorted = pixels.clone();
+    sorted.sort_by(|a, b| {
+        let luma = |p: &[f64; 3]| 0.299 * p[0] + 0.587 * p[1] + 0.114 * p[2];
+        luma(a).total_cmp(&luma(b))
+    });
+    let mut centers: Vec<[f64; 3]> = (0..n)
+        .map(|i| sorted[(i * 2 + 1) * sorted.len() / (n * 2)])
+        .collect();
+    centers.dedup();
+
+    let distance = |a: &[f64; 3], b: &[f64; 3]| {
+        (a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)
+    };
+    let mut counts = vec![0usize; centers.len()];
+    for _ in 0..12 {
+        let mut sums = vec![[0f64; 3]; centers.len()];
+        counts = vec![0; centers.len()];
+        for p in &pixels {
+            let nearest = (0..centers.len())
+                .min_by(|&i, &j| distance(p, &centers[i]).total_cmp(&distance(p, &centers[j])))
+                .unwrap_or(0);
+            for c in 0..3 {
+                sums[nearest][c] += p[c];
+            }
+            counts[nearest] += 1;
+        }
+        let mut moved = false;
+        for (i, center) in centers.iter_mut().enumerate() {
+            if counts[i] == 0 {
+                continue;
+            }
+            let next = sums[i].map(|s| s / counts[i] as f64);
+            moved |= distance(center, &next) > 0.25;
+            *center = next;
+        }
+        if !moved {
+            break;
+        }
+    }
+
+    let mut swatches: Vec<ColorSwatch> = centers
+        .iter()
+        .zip(&counts)
+        .filter(|(_, count)| **count > 0)
+        .map(|(center, count)| {
+            let color = Color {
+                r: center[0],
+                g: center[1],
+                b: center[2],
+                a: 1.0,
+            };
+            ColorSwatch {
+                hex: format(&color, "hex").unwrap_or_default(),
+                rgb: format(&color, "rgb").unwrap_or_default(),
+                share: *count as f64 / pixels.len() as f64,
+            }
+        })
+        .collect();
+    swatches.sort_by(|a, b| b.share.total_cmp(&a.share));
+    swatches
+}
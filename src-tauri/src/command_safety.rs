@@ -0,0 +1,67 @@
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 危险命令的模式和对应的提示
+fn patterns() -> &'static [(Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+    PATTERNS.get_or_init(|| {
+        [
+            (r"\brm\s+(-\w+\s+)*-\w*(rf|fr)\w*\b", "rm -rf"),
+            (
+                r"\brm\s+(-\w+\s+)*--(recursive|no-preserve-root)\b",
+                "rm -rf",
+            ),
+            (
+                r"\b(curl|wget)\b[^|\n]*\|\s*(sudo\s+)?(ba|z|da|k|fi)?sh\b",
+                "pipe download to shell",
+            ),
+            (r"\bchmod\s+(-\w+\s+)*0?777\b", "chmod 777"),
+            (r"\bchown\s+-\w*R\w*\s+\S+\s+/(\s|$)", "chown -R /"),
+            (r"\bmkfs(\.\w+)?\b", "mkfs"),
+            (r"\bdd\s+.*\bof=/dev/", "dd to device"),
+            (r">\s*/dev/(sd|nvme|disk)\w*", "write to device"),
+            (r":\(\)\s*\{\s*:\s*\|\s*:\s*&\s*\}\s*;\s*:", "fork bomb"),
+            (r"\bgit\s+push\s+.*(--force\b|-f\b)", "git push --force"),
+            (
+                r"(?i)\bRemove-Item\b.*-Recurse\b.*-Force\b",
+                "Remove-Item -Recurse -Force",
+            ),
+            (r"(?i)\bformat\s+[a-z]:", "format drive"),
+        ]
+        .into_iter()
+        .filter_map(|(pattern, reason)| Regex::new(pattern).ok().map(|re| (re, reason)))
+        .collect()
+    })
+}
+
+/// 文本中命中的危险命令模式，按出现顺序去重
+pub fn dangerous_command_reasons(text: &str) -> Vec<&'static str> {
+    // 长文本通常不是要直接执行的命令
+    if text.len() > 4096 {
+        return Vec::new();
+    }
+    let mut reasons = Vec::new();
+    for (re, reason) in patterns() {
+        if re.is_match(text) && !reasons.contains(reason) {
+            reasons.push(*reason);
+        }
+    }
+    if !reasons.is_empty() && text.contains("sudo ") {
+        reasons.push("sudo");
+    }
+    reasons
+}
+
+pub fn is_dangerous_command(text: &str) -> bool {
+    !dangerous_command_reasons(text).is_empty()
+}
+
+/// 目标应用是否是终端：应用名或可执行文件路径包含配置的任一名称（不区分大小写）
+pub fn is_terminal_app(terminal_apps: &[String], app_name: &str, app_path: &str) -> bool {
+    let app_name = app_name.to_lowercase();
+    let app_path = app_path.to_lowercase();
+    terminal_apps.iter().any(|t| {
+        let t = t.trim().to_lowercase();
+        !t.is_empty() && (app_name.contains(&t) || app_path.contains(&t))
+    })
+}
@@ -1,38 +1,92 @@
 use chrono::Local;
 use std::fs;
 use tauri::{Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
 use tauri_plugin_global_shortcut::GlobalShortcutExt;
+use tauri_plugin_opener::OpenerExt;
 
-use crate::models::{AppConfig, CaptureResult, ClipboardItem, Collection};
-use crate::ocr::recognize_text;
+use crate::ai::{self, AiAction};
+use crate::command_safety::{dangerous_command_reasons, is_terminal_app};
+use crate::db::CollectionDeleteMode;
+use crate::error::AppError;
+use crate::jobs::JobPriority;
+use crate::models::{
+    AccessibilityPrefs, ActivityCalendar, AppConfig, CacheCleanup, CacheStats, CaptureResult,
+    CleanupCandidate, ClearResult, ClipboardItem, Collection, CollectionCount, ColorSwatch,
+    CustomClassifier, DangerousPaste, ExtractedEntity, FinalizedCapture, GitSyncResult,
+    HistoryFilter, ItemDescription, ItemFilter, ItemOccurrence, ItemVersion, JobInfo, MemoryStats,
+    OcrResult, PaletteAction, PasteRule, PixelGrid, PluginInfo, PowerState, QueryStats,
+    RoutingRule, ScriptInfo, SettingsImportResult, SimilarItem, StorageUsage, TextNormalization,
+    TextStats, TypeCount, UsageStats, Webhook, WindowRect,
+};
+use crate::ocr::{self, recognize_text, OcrOptions};
+use crate::pdf::{write_image_pdf, PdfPage};
 use crate::state::AppState;
 use crate::tray::{update_pause_menu_item, update_tray_menu};
-use crate::utils::{classify_content, write_to_clipboard};
+use crate::utils::{
+    apply_paste_rules, cached_item_content, cached_thumbnail, classify_with_custom, open_item_urls,
+    prewarm_cache, remove_pruned_images, resolve_existing_path, write_to_clipboard,
+    write_to_clipboard_with, THUMBNAIL_SIZE,
+};
+
+// 隐藏自身窗口后等待合成器刷新的时间
+const CAPTURE_SETTLE_MS: u64 = 200;
+
+// 条目元数据的键长度和值大小上限
+const MAX_META_KEY_CHARS: usize = 64;
+const MAX_META_VALUE_BYTES: usize = 64 * 1024;
+
+// generate_test_data 单次生成的条目上限
+const MAX_TEST_DATA_ITEMS: usize = 1_000_000;
 
 #[tauri::command]
 pub async fn start_capture(
     app: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     log::info!("Starting screen capture...");
+    crate::usage::record(&app, crate::usage::SCREENSHOT);
 
     // Ensure cache directory exists
-    let cache_dir = app
-        .path()
-        .app_cache_dir()
-        .map_err(|e| e.to_string())?
-        .join("screenshots");
+    let cache_dir = crate::screenshot::screenshot_cache_dir(&app)?;
     if !cache_dir.exists() {
-        fs::create_dir_all(&cache_dir).map_err(|e| e.to_string())?;
+        fs::create_dir_all(&cache_dir)?;
+    }
+
+    // Remember the frontmost app for the filename template
+    if let Ok(active) = active_win_pos_rs::get_active_window() {
+        if let Ok(mut source_app) = state.capture_source_app.lock() {
+            *source_app = Some(active.app_name);
+        }
+    }
+
+    // 1. Hide our own visible windows and wait for the compositor, so they never end up in the capture
+    let hidden_windows: Vec<_> = app
+        .webview_windows()
+        .into_values()
+        .filter(|w| !w.label().starts_with("screenshot_") && w.is_visible().unwrap_or(false))
+        .collect();
+    for window in &hidden_windows {
+        let _ = window.hide();
+    }
+    if !hidden_windows.is_empty() {
+        tokio::time::sleep(std::time::Duration::from_millis(CAPTURE_SETTLE_MS)).await;
     }
 
-    // 1. Capture screens FIRST (before showing window to avoid capturing our own UI)
+    // 2. Capture screens before showing the screenshot windows
     let captures = tauri::async_runtime::spawn_blocking(move || {
         crate::screenshot::capture_all_screens(cache_dir)
     })
     .await
-    .map_err(|e| e.to_string())?
-    .map_err(|e| e)?;
+    .map_err(|e| e.to_string());
+
+    for window in &hidden_windows {
+        let _ = window.show();
+    }
+    let (captures, frames): (Vec<_>, Vec<_>) = captures??.into_iter().unzip();
+    if let Ok(mut stored) = state.capture_frames.lock() {
+        *stored = captures.iter().map(|c| c.id).zip(frames).collect();
+    }
 
     log::info!("Capture complete: {} screens", captures.len());
 
@@ -41,9 +95,9 @@ pub async fn start_capture(
         *c = Some(captures.clone());
     }
 
-    // 2. Multi-window: Create a window for EACH screen
+    // 3. Multi-window: Create a window for EACH screen
     if captures.is_empty() {
-        return Err("No screens captured".to_string());
+        return Err("No screens captured".into());
     }
 
     for cap in &captures {
@@ -130,33 +184,130 @@ pub async fn start_capture(
     Ok(())
 }
 
+// 延时截图的最长等待时间（秒）
+const MAX_CAPTURE_DELAY_SECS: u64 = 30;
+// paste_and_run 切换焦点、粘贴和按回车之间的等待时间
+const PASTE_AND_RUN_DELAY_MS: u64 = 150;
+// 复制文件内容时读取的总大小上限
+const MAX_FILE_CONTENTS_BYTES: u64 = 16 * 1024 * 1024;
+const COUNTDOWN_WINDOW_LABEL: &str = "capture_countdown";
+
+/// 倒计时结束后再截图，便于截取按下快捷键就会关闭的菜单和提示框
+#[tauri::command]
+pub async fn start_capture_delayed(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    seconds: u64,
+) -> Result<(), AppError> {
+    let seconds = seconds.min(MAX_CAPTURE_DELAY_SECS);
+    log::info!("Starting delayed capture in {}s", seconds);
+
+    if seconds > 0 {
+        // Small unfocused window so open menus are not dismissed
+        let url = format!("index.html?countdown={}", seconds);
+        let countdown = tauri::WebviewWindowBuilder::new(
+            &app,
+            COUNTDOWN_WINDOW_LABEL,
+            tauri::WebviewUrl::App(url.into()),
+        )
+        .title("Countdown")
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .focused(false)
+        .inner_size(140.0, 140.0)
+        .center()
+        .build()
+        .map_err(|e| e.to_string())?;
+
+        for remaining in (1..=seconds).rev() {
+            let _ = app.emit("capture-countdown", remaining);
+            tokio::time::sleep(std::time::Duration::from_secs(1)).await;
+        }
+
+        let _ = countdown.close();
+        // Give the compositor a moment to remove the countdown window
+        tokio::time::sleep(std::time::Duration::from_millis(150)).await;
+    }
+
+    start_capture(app, state).await
+}
+
 #[tauri::command]
-pub fn get_capture_data(state: tauri::State<AppState>) -> Result<Vec<CaptureResult>, String> {
+pub fn get_capture_data(state: tauri::State<AppState>) -> Result<Vec<CaptureResult>, AppError> {
     if let Ok(captures) = state.current_captures.lock() {
         if let Some(c) = &*captures {
             return Ok(c.clone());
         }
     }
-    Err("No capture data available".to_string())
+    Err(AppError::NotFound("No capture data available".to_string()))
+}
+
+/// 放大镜：返回截图中 (x, y) 周围的像素（截图像素坐标）
+#[tauri::command]
+pub fn get_pixels_at(
+    state: tauri::State<AppState>,
+    screen_id: u32,
+    x: i32,
+    y: i32,
+    radius: Option<u32>,
+) -> Result<PixelGrid, AppError> {
+    let frames = state.capture_frames.lock().unwrap();
+    let frame = frames
+        .get(&screen_id)
+        .ok_or_else(|| AppError::NotFound(format!("No capture for screen {}", screen_id)))?;
+    Ok(frame.pixels_at(x, y, radius.unwrap_or(8).clamp(1, 32)))
+}
+
+/// 截图时屏幕上的窗口区域（从前到后），用于选区吸附
+#[tauri::command]
+pub fn get_window_rects(
+    state: tauri::State<AppState>,
+    screen_id: u32,
+) -> Result<Vec<WindowRect>, AppError> {
+    let frames = state.capture_frames.lock().unwrap();
+    frames
+        .get(&screen_id)
+        .map(|frame| frame.windows.clone())
+        .ok_or_else(|| AppError::NotFound(format!("No capture for screen {}", screen_id)))
 }
 
 #[tauri::command]
-pub async fn close_capture(app: tauri::AppHandle) -> Result<(), String> {
+pub async fn close_capture(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+) -> Result<(), AppError> {
     log::info!("Closing all screenshot windows");
+    if let Ok(mut frames) = state.capture_frames.lock() {
+        frames.clear();
+    }
     for (label, window) in app.webview_windows() {
         if label.starts_with("screenshot_") {
             let _ = window.close();
         }
     }
+
+    // Capture session finished, apply the cache policy in the background
+    let handle = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::screenshot::cleanup_capture_cache(&handle, false);
+    });
     Ok(())
 }
 
+/// 清空临时截图缓存并按策略清理已保存的截图，返回释放的空间
 #[tauri::command]
-pub async fn save_captured_image(
-    app: tauri::AppHandle,
-    base64_data: String,
-) -> Result<String, String> {
-    // 1. Decode base64
+pub async fn clear_capture_cache(app: tauri::AppHandle) -> Result<CacheCleanup, AppError> {
+    tauri::async_runtime::spawn_blocking(move || {
+        crate::screenshot::cleanup_capture_cache(&app, true)
+    })
+    .await
+    .map_err(|e| AppError::Other(e.to_string()))
+}
+
+/// 解码前端传来的 base64 图片
+fn decode_capture(base64_data: &str) -> Result<image::DynamicImage, String> {
     // remove data:image/png;base64, prefix if present
     let base64_clean = base64_data
         .split(",")
@@ -167,29 +318,240 @@ pub async fn save_captured_image(
     let data = general_purpose::STANDARD
         .decode(base64_clean)
         .map_err(|e| e.to_string())?;
+    image::load_from_memory(&data).map_err(|e| e.to_string())
+}
 
-    // 2. Generate path
-    let app_data_dir = app.path().app_data_dir().map_err(|e| e.to_string())?;
+/// 把多张截图写成多页 PDF
+fn capture_pdf(
+    images: &[image::DynamicImage],
+    quality: u8,
+    now: &chrono::DateTime<Local>,
+) -> Result<Vec<u8>, String> {
+    let pages = images
+        .iter()
+        .map(|img| {
+            Ok(PdfPage {
+                width: img.width(),
+                height: img.height(),
+                jpeg: crate::screenshot::encode_capture(img, "jpeg", quality, Vec::new())?,
+            })
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(write_image_pdf(&pages, &crate::pdf::pdf_date(now)))
+}
 
-    // Ensure "images" subdirectory exists (optional, or just put in root)
-    // We can't easily rely on existing logic but let's check lib.rs for where we store images.
-    // Usually images are stored as files. Let's put them in `captures` folder.
-    let captures_dir = app_data_dir.join("captures");
+/// 按配置的目录和文件名模板生成新截图路径，已存在时递增 {seq} 或追加序号
+fn new_capture_path(
+    app: &tauri::AppHandle,
+    now: &chrono::DateTime<Local>,
+    extension: &str,
+) -> Result<std::path::PathBuf, AppError> {
+    let state = app.state::<AppState>();
+    let (template, save_dir) = {
+        let config = state.config.lock().unwrap();
+        (
+            config.capture_filename_template.clone(),
+            config.capture_save_dir.clone(),
+        )
+    };
+    let captures_dir = if save_dir.trim().is_empty() {
+        crate::screenshot::captures_dir(app)?
+    } else {
+        std::path::PathBuf::from(save_dir.trim())
+    };
     if !captures_dir.exists() {
-        fs::create_dir_all(&captures_dir).map_err(|e| e.to_string())?;
+        fs::create_dir_all(&captures_dir)?;
+    }
+
+    let app_name = state.capture_source_app.lock().unwrap().clone();
+    let clipboard = if template.contains("{clipboard}") {
+        app.clipboard().read_text().ok()
+    } else {
+        None
+    };
+
+    let has_seq = template.contains("{seq}");
+    for seq in 1..10000 {
+        let mut name = crate::screenshot::render_filename_template(
+            &template,
+            now,
+            app_name.as_deref(),
+            clipboard.as_deref(),
+            seq,
+        );
+        if !has_seq && seq > 1 {
+            name = format!("{}_{}", name, seq);
+        }
+        let path = captures_dir.join(format!("{}.{}", name, extension));
+        if !path.exists() {
+            return Ok(path);
+        }
+    }
+    Err(AppError::Other(
+        "Could not find a free capture filename".to_string(),
+    ))
+}
+
+/// 保存截图，format 为 png（默认）/ jpeg / webp / pdf，quality 用于 JPEG、WebP 和 PDF
+#[tauri::command]
+pub async fn save_captured_image(
+    app: tauri::AppHandle,
+    base64_data: String,
+    format: Option<String>,
+    quality: Option<u8>,
+) -> Result<String, AppError> {
+    let format = format.unwrap_or_else(|| "png".to_string()).to_lowercase();
+    let quality = quality.unwrap_or(90);
+    let now = Local::now();
+
+    let img = decode_capture(&base64_data)?;
+    let (data, extension) = match format.as_str() {
+        "pdf" => (capture_pdf(&[img], quality, &now)?, "pdf"),
+        "jpeg" | "jpg" => (
+            crate::screenshot::encode_capture(
+                &img,
+                "jpeg",
+                quality,
+                crate::screenshot::exif_metadata(&now),
+            )?,
+            "jpg",
+        ),
+        other => (
+            crate::screenshot::encode_capture(
+                &img,
+                other,
+                quality,
+                crate::screenshot::exif_metadata(&now),
+            )?,
+            other,
+        ),
+    };
+
+    let path = new_capture_path(&app, &now, extension)?;
+    fs::write(&path, data)?;
+
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 确认截图后一次完成：保存图片、写入剪贴板、加入历史和收藏，并可在后台做 OCR
+#[tauri::command]
+pub async fn finalize_capture(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    base64_data: String,
+    to_clipboard: bool,
+    to_history: bool,
+    collection_id: Option<i64>,
+    ocr: Option<bool>,
+) -> Result<FinalizedCapture, AppError> {
+    let now = Local::now();
+    let img = decode_capture(&base64_data)?;
+
+    // History images live next to the ones captured by the monitor
+    let path = if to_history {
+        let images_dir = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| e.to_string())?
+            .join("images");
+        fs::create_dir_all(&images_dir)?;
+        images_dir.join(format!("{}.png", now.timestamp_nanos_opt().unwrap_or(0)))
+    } else {
+        new_capture_path(&app, &now, "png")?
+    };
+    img.save(&path)?;
+    let path = path.to_string_lossy().to_string();
+
+    let mut item = ClipboardItem {
+        id: None,
+        content: path.clone(),
+        kind: "image".to_string(),
+        timestamp: now.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        source_app: Some("Screenshot".to_string()),
+        data_type: "image".to_string(),
+        collection_id: if to_history { collection_id } else { None },
+        ..Default::default()
+    };
+
+    if to_history {
+        let max_size = state.config.lock().unwrap().max_history_size;
+        let (id, pruned_items) = state.db.insert_item(&item, max_size)?;
+        remove_pruned_images(&pruned_items);
+        item.id = Some(id);
+    }
+
+    // Marks the image as app-initiated so the monitor does not record it again
+    if to_clipboard {
+        write_to_clipboard(&app, &item)?;
+    }
+
+    let Some(id) = item.id else {
+        return Ok(FinalizedCapture { path, item: None });
+    };
+
+    let history = state
+        .db
+        .get_history(1, 20, &HistoryFilter::default())
+        .unwrap_or_default();
+    if let Err(e) = update_tray_menu(&app, &history) {
+        log::error!("Failed to update tray menu: {}", e);
+    }
+    crate::utils::emit_clipboard_update(&app);
+
+    if ocr.unwrap_or(false) {
+        let options = ocr_options(&state, None);
+        let image_path = path.clone();
+        state
+            .jobs
+            .schedule(&app, "ocr", JobPriority::Normal, move |ctx| {
+                let handle = ctx.app();
+                let text = tauri::async_runtime::block_on(recognize_text(&image_path, &options))
+                    .map_err(|e| format!("OCR failed for capture {}: {}", id, e))?;
+                let state = handle.state::<AppState>();
+                state
+                    .db
+                    .set_ocr_text(id, &text)
+                    .map_err(|e| format!("Failed to store OCR text for capture {}: {}", id, e))?;
+                crate::utils::emit_clipboard_update(handle);
+                Ok(())
+            });
     }
 
-    let filename = format!("capture_{}.png", Local::now().format("%Y%m%d_%H%M%S_%f"));
-    let path = captures_dir.join(filename);
+    log::info!("Capture finalized as history item {}", id);
+    let item = state.db.get_item(id)?;
+    Ok(FinalizedCapture {
+        path,
+        item: Some(item),
+    })
+}
+
+/// 把同一次截图会话中的多个选区/屏幕保存为一个多页 PDF
+#[tauri::command]
+pub async fn save_captures_as_pdf(
+    app: tauri::AppHandle,
+    images: Vec<String>,
+    quality: Option<u8>,
+) -> Result<String, AppError> {
+    if images.is_empty() {
+        return Err(AppError::InvalidInput("No captures to export".to_string()));
+    }
+    let now = Local::now();
+    let decoded = images
+        .iter()
+        .map(|data| decode_capture(data))
+        .collect::<Result<Vec<_>, String>>()?;
+    let data = capture_pdf(&decoded, quality.unwrap_or(90), &now)?;
 
-    // 3. Write
-    fs::write(&path, data).map_err(|e| e.to_string())?;
+    let path = new_capture_path(&app, &now, "pdf")?;
+    fs::write(&path, data)?;
+    log::info!("Saved {} captures as PDF: {:?}", decoded.len(), path);
 
     Ok(path.to_string_lossy().to_string())
 }
 
 #[tauri::command]
 pub fn get_history(
+    app: tauri::AppHandle,
     state: tauri::State<AppState>,
     page: usize,
     page_size: usize,
@@ -197,24 +559,103 @@ pub fn get_history(
     search_regex: Option<bool>,
     search_case_sensitive: Option<bool>,
     collection_id: Option<i64>,
-) -> Vec<ClipboardItem> {
+    data_type: Option<String>,
+    from: Option<i64>,
+    to: Option<i64>,
+    date_preset: Option<String>,
+    device: Option<String>,
+) -> Result<Vec<ClipboardItem>, AppError> {
     log::info!(
         "get_history query: {:?}, regex: {:?}, case: {:?}",
         query,
         search_regex,
         search_case_sensitive
     );
-    state
-        .db
-        .get_history(
-            page,
-            page_size,
-            query,
-            search_regex.unwrap_or(false),
-            search_case_sensitive.unwrap_or(false),
-            collection_id,
-        )
-        .unwrap_or_default()
+    let search_regex = search_regex.unwrap_or(false);
+    let search_case_sensitive = search_case_sensitive.unwrap_or(false);
+    // 预设优先于 from / to
+    let (from, to) = match date_preset.as_deref().filter(|p| !p.is_empty()) {
+        Some(preset) => {
+            let (start, end) = crate::utils::date_preset_range(preset, Local::now())?;
+            (Some(start), Some(end))
+        }
+        None => (from, to),
+    };
+    // 本机的设备名对应没有来源设备的条目
+    let origin_device = device
+        .filter(|d| !d.is_empty())
+        .map(|d| (d != crate::utils::device_name(&state.config.lock().unwrap())).then_some(d));
+    let filter = HistoryFilter {
+        query,
+        search_regex,
+        search_case_sensitive,
+        collection_id,
+        data_type,
+        from,
+        to,
+        origin_device,
+    };
+    // 无效的正则表达式在 SQLite 中只会报出笼统的错误，先校验以便界面提示
+    if let Some(query) = filter.query.as_deref().filter(|_| filter.search_regex) {
+        regex::Regex::new(query)
+            .map_err(|e| AppError::InvalidInput(format!("Invalid search pattern: {}", e)))?;
+    }
+    let items = state.db.get_history(page, page_size, &filter)?;
+    let items = crate::utils::present_items(&state, items);
+
+    // 后台预热本页和下一页的全文与缩略图，快速滚动时不必逐条读库、解码图片；
+    // 只保留最新一页的预热任务
+    if state.config.lock().unwrap().content_cache_mb > 0 && !items.is_empty() {
+        let current = items.clone();
+        state.jobs.cancel_kind(&app, "prewarm");
+        state
+            .jobs
+            .schedule(&app, "prewarm", JobPriority::Low, move |ctx| {
+                let app = ctx.app();
+                let next = app
+                    .state::<AppState>()
+                    .db
+                    .get_history(page + 1, page_size, &filter)
+                    .unwrap_or_default();
+                prewarm_cache(app, current.into_iter().chain(next), || ctx.is_cancelled());
+                Ok(())
+            });
+    }
+    Ok(items)
+}
+
+/// 危险命令将要粘贴到终端时，发出 dangerous-paste-confirm 事件并返回需要确认的错误
+pub fn confirm_dangerous_paste(
+    app: &tauri::AppHandle,
+    id: Option<i64>,
+    content: &str,
+    target_app: Option<String>,
+    target_app_path: &str,
+) -> Result<(), AppError> {
+    let reasons = dangerous_command_reasons(content);
+    if reasons.is_empty() {
+        return Ok(());
+    }
+    let to_terminal = is_terminal_app(
+        &app.state::<AppState>().config.lock().unwrap().terminal_apps,
+        target_app.as_deref().unwrap_or(""),
+        target_app_path,
+    );
+    if !to_terminal {
+        return Ok(());
+    }
+    let _ = app.emit(
+        "dangerous-paste-confirm",
+        DangerousPaste {
+            id,
+            content: content.to_string(),
+            target_app,
+            reasons: reasons.iter().map(|r| r.to_string()).collect(),
+        },
+    );
+    Err(AppError::ConfirmationRequired(
+        "Dangerous command requires confirmation".to_string(),
+    ))
 }
 
 #[tauri::command]
@@ -224,15 +665,10 @@ pub fn set_clipboard_item(
     kind: String,
     id: Option<i64>,
     html_content: Option<String>,
+    confirmed: Option<bool>,
     state: tauri::State<AppState>,
-) -> Result<(), String> {
-    // Mark this content as set by the app to avoid duplication in monitor
-    // Do this BEFORE writing to clipboard to avoid race condition
-    if let Ok(mut last_change) = state.last_app_change.lock() {
-        *last_change = Some(content.clone());
-    }
-
-    let data_type = classify_content(&content);
+) -> Result<(), AppError> {
+    let data_type = classify_with_custom(&content, &crate::plugins::classifiers(&app));
 
     let item = ClipboardItem {
         id,
@@ -246,49 +682,71 @@ pub fn set_clipboard_item(
         collection_id: None,
         note: None,
         html_content: html_content.clone(),
+        ..Default::default()
     };
 
-    // Write to clipboard
-    if let Err(e) = write_to_clipboard(&app, &item) {
+    // 按目标应用的粘贴规则调整写入的内容，历史中保留原文
+    let mut paste_item = item.clone();
+    let mut include_raw_formats = true;
+    if kind == "text" {
+        let target_app = state.last_active_app.lock().unwrap().clone();
+        let target_path = state.last_active_app_path.lock().unwrap().clone();
+        let target_app_path = target_path.as_deref().unwrap_or("");
+        include_raw_formats = apply_paste_rules(
+            &app,
+            &mut paste_item,
+            target_app.as_deref().unwrap_or(""),
+            target_app_path,
+        );
+
+        // 危险命令粘贴到终端前，先让前端确认后带 confirmed 重新调用
+        if !confirmed.unwrap_or(false) {
+            confirm_dangerous_paste(&app, id, &paste_item.content, target_app, target_app_path)?;
+        }
+    }
+
+    // Write to clipboard (stamped, so the monitor skips it)
+    if let Err(e) = write_to_clipboard_with(&app, &paste_item, include_raw_formats) {
         log::error!("Failed to write to clipboard: {}", e);
-        return Err(e);
+        return Err(e.into());
     }
 
     // Update DB
     if let Some(id) = id {
         if let Err(e) = state.db.update_timestamp(id) {
             log::error!("Failed to update timestamp: {}", e);
-            return Err(e.to_string());
+            return Err(e.into());
+        }
+        let target_app = state.last_active_app.lock().unwrap().clone();
+        if let Err(e) = state.db.record_paste(id, target_app.as_deref()) {
+            log::error!("Failed to record paste: {}", e);
         }
+        crate::usage::record(&app, crate::usage::PASTE);
+        crate::webhook::notify_item(
+            &app,
+            "paste",
+            id,
+            serde_json::json!({ "target_app": target_app }),
+        );
     } else {
         let max_size = state.config.lock().unwrap().max_history_size;
         match state.db.insert_item(&item, max_size) {
-            Ok(pruned_items) => {
-                // Delete pruned images
-                for pruned in pruned_items {
-                    if pruned.kind == "image" {
-                        let path = std::path::Path::new(&pruned.content);
-                        if path.exists() {
-                            if let Err(e) = fs::remove_file(path) {
-                                log::error!("Failed to delete pruned image file: {}", e);
-                            } else {
-                                log::info!("Deleted pruned image file: {:?}", path);
-                            }
-                        }
-                    }
-                }
+            Ok((_, pruned_items)) => {
+                remove_pruned_images(&pruned_items);
             }
             Err(e) => {
                 log::error!("Failed to insert item into DB: {}", e);
-                return Err(e.to_string());
+                return Err(e.into());
             }
         }
     }
+    // 选中的条目成为最新一条
+    crate::clipboard_cursor::reset(&app);
 
     // Update Tray
     let history = state
         .db
-        .get_history(1, 20, None, false, false, None)
+        .get_history(1, 20, &HistoryFilter::default())
         .unwrap_or_default();
     if let Err(e) = update_tray_menu(&app, &history) {
         log::error!("Failed to update tray menu: {}", e);
@@ -298,15 +756,112 @@ pub fn set_clipboard_item(
     Ok(())
 }
 
+/// 把文本条目作为命令粘贴到之前的前台终端，确认后再按回车执行。
+/// 未确认时发出 paste-and-run-confirm 事件，前端确认后带 confirmed 重新调用；
+/// 命中危险命令模式时只粘贴不执行；没有括号粘贴的终端会在粘贴时直接执行前面的行，
+/// 因此拒绝多行命令。返回是否已按回车
+#[tauri::command]
+pub async fn paste_and_run(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: i64,
+    confirmed: Option<bool>,
+) -> Result<bool, AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Only text items can be run as commands".to_string(),
+        ));
+    }
+    let content = state.db.get_item_content(id)?;
+    let command = content.trim_end_matches(['\r', '\n']).to_string();
+    if command.trim().is_empty() {
+        return Err(AppError::InvalidInput("Command is empty".to_string()));
+    }
+    if command.contains('\n') {
+        return Err(AppError::InvalidInput(
+            "Multi-line commands cannot be pasted and run".to_string(),
+        ));
+    }
+
+    let target_app = state.last_active_app.lock().unwrap().clone();
+    let target_path = state.last_active_app_path.lock().unwrap().clone();
+    if !is_terminal_app(
+        &state.config.lock().unwrap().terminal_apps,
+        target_app.as_deref().unwrap_or(""),
+        target_path.as_deref().unwrap_or(""),
+    ) {
+        return Err(AppError::InvalidInput(
+            "The target app is not a terminal".to_string(),
+        ));
+    }
+
+    let reasons: Vec<String> = dangerous_command_reasons(&command)
+        .iter()
+        .map(|r| r.to_string())
+        .collect();
+    if !confirmed.unwrap_or(false) {
+        let _ = app.emit(
+            "paste-and-run-confirm",
+            DangerousPaste {
+                id: Some(id),
+                content: command,
+                target_app,
+                reasons,
+            },
+        );
+        return Err(AppError::ConfirmationRequired(
+            "Command requires confirmation".to_string(),
+        ));
+    }
+
+    // 把焦点还给终端后再粘贴
+    crate::popup::hide(&app, "paste", true);
+    tokio::time::sleep(std::time::Duration::from_millis(PASTE_AND_RUN_DELAY_MS)).await;
+    // 只写入纯文本，不带 HTML 和原始格式
+    write_to_clipboard(
+        &app,
+        &ClipboardItem {
+            id: None,
+            content: command,
+            html_content: None,
+            ..item
+        },
+    )?;
+    crate::keystroke::press(crate::keystroke::Key::TerminalPaste)?;
+    let run = reasons.is_empty();
+    if run {
+        tokio::time::sleep(std::time::Duration::from_millis(PASTE_AND_RUN_DELAY_MS)).await;
+        crate::keystroke::press(crate::keystroke::Key::Enter)?;
+    } else {
+        log::warn!("Pasted command without running it: {}", reasons.join(", "));
+    }
+
+    if let Err(e) = state.db.record_paste(id, target_app.as_deref()) {
+        log::error!("Failed to record paste: {}", e);
+    }
+    crate::usage::record(&app, crate::usage::PASTE);
+    crate::webhook::notify_item(
+        &app,
+        "paste",
+        id,
+        serde_json::json!({ "target_app": target_app, "run": run }),
+    );
+    Ok(run)
+}
+
 #[tauri::command]
 pub fn delete_item(
     app: tauri::AppHandle,
     index: usize,
     state: tauri::State<AppState>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     match state.db.delete_item(index) {
         Ok(Some(item)) => {
-            if item.kind == "image" {
+            if let Some(id) = item.id {
+                state.content_cache.lock().unwrap().invalidate(id);
+            }
+            if item.kind == "image" || item.kind == "audio" {
                 let path = std::path::Path::new(&item.content);
                 if path.exists() {
                     if let Err(e) = fs::remove_file(path) {
@@ -322,26 +877,29 @@ pub fn delete_item(
         }
         Err(e) => {
             log::error!("Failed to delete item from DB: {}", e);
-            return Err(e.to_string());
+            return Err(e.into());
         }
     }
 
     // Update Tray
     let history = state
         .db
-        .get_history(1, 20, None, false, false, None)
+        .get_history(1, 20, &HistoryFilter::default())
         .unwrap_or_default();
     if let Err(e) = update_tray_menu(&app, &history) {
         log::error!("Failed to update tray menu after delete: {}", e);
     }
+    crate::quick_paste::sync(&app);
     log::info!("Deleted item at index {}", index);
     Ok(())
 }
 
 #[tauri::command]
-pub fn toggle_sensitive(state: tauri::State<AppState>, index: usize) -> Result<bool, String> {
+pub fn toggle_sensitive(state: tauri::State<AppState>, index: usize) -> Result<bool, AppError> {
     match state.db.toggle_sensitive(index) {
         Ok(new_state) => {
+            // 只知道列表位置，直接清空缓存，避免变为敏感的内容留在内存中
+            state.content_cache.lock().unwrap().clear();
             log::info!(
                 "Toggled sensitive state for item {} to {}",
                 index,
@@ -351,63 +909,228 @@ pub fn toggle_sensitive(state: tauri::State<AppState>, index: usize) -> Result<b
         }
         Err(e) => {
             log::error!("Failed to toggle sensitive state: {}", e);
-            Err(e.to_string())
+            Err(e.into())
         }
     }
 }
 
 #[tauri::command]
-pub fn toggle_pin(state: tauri::State<AppState>, index: usize) -> Result<bool, String> {
+pub fn toggle_pin(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    index: usize,
+) -> Result<bool, AppError> {
     match state.db.toggle_pin(index) {
         Ok(new_state) => {
             log::info!("Toggled pin state for item {} to {}", index, new_state);
+            crate::quick_paste::sync(&app);
             Ok(new_state)
         }
         Err(e) => {
             log::error!("Failed to toggle pin state: {}", e);
-            Err(e.to_string())
+            Err(e.into())
+        }
+    }
+}
+
+/// 锁定 / 解锁条目，锁定后不能编辑、删除，也不会被清理
+#[tauri::command]
+pub fn toggle_lock(state: tauri::State<AppState>, id: i64) -> Result<bool, AppError> {
+    match state.db.toggle_lock(id) {
+        Ok(new_state) => {
+            log::info!("Toggled lock state for item {} to {}", id, new_state);
+            Ok(new_state)
+        }
+        Err(e) => {
+            log::error!("Failed to toggle lock state: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+/// 设置条目的一项自定义元数据（任意 JSON），value 为空时删除
+#[tauri::command]
+pub fn set_item_meta(
+    state: tauri::State<AppState>,
+    id: i64,
+    key: String,
+    value: Option<serde_json::Value>,
+) -> Result<(), AppError> {
+    let key = key.trim();
+    if key.is_empty() || key.chars().count() > MAX_META_KEY_CHARS {
+        return Err(AppError::InvalidInput(format!(
+            "Metadata key must be 1-{} characters",
+            MAX_META_KEY_CHARS
+        )));
+    }
+    let value = value.filter(|v| !v.is_null());
+    if value
+        .as_ref()
+        .is_some_and(|v| v.to_string().len() > MAX_META_VALUE_BYTES)
+    {
+        return Err(AppError::InvalidInput(
+            "Metadata value is too large".to_string(),
+        ));
+    }
+    // 确认条目存在，避免留下孤立的元数据
+    state.db.get_item(id)?;
+    Ok(state.db.set_item_meta(id, key, value.as_ref())?)
+}
+
+/// 条目的全部自定义元数据
+#[tauri::command]
+pub fn get_item_meta(
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<serde_json::Map<String, serde_json::Value>, AppError> {
+    Ok(state.db.get_item_meta(id)?)
+}
+
+/// 设置置顶条目在 Quick Paste 中的快捷键，传空值清除
+#[tauri::command]
+pub fn set_item_accelerator(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+    accelerator: Option<String>,
+) -> Result<(), AppError> {
+    let accelerator = accelerator
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty());
+    let accelerator = accelerator
+        .map(|a| crate::shortcut::normalize(&a))
+        .transpose()?;
+    if let Some(accelerator) = &accelerator {
+        let main = crate::shortcut::parse(&state.config.lock().unwrap().shortcut).ok();
+        if main == Some(crate::shortcut::parse(accelerator)?) {
+            return Err(AppError::InvalidInput(
+                "Shortcut is already used to open the popup".to_string(),
+            ));
         }
     }
+    state.db.set_item_accelerator(id, accelerator.as_deref())?;
+    crate::quick_paste::sync(&app);
+    Ok(())
 }
 
 #[tauri::command]
 pub fn update_clipboard_item_content(
+    app: tauri::AppHandle,
     state: tauri::State<AppState>,
     id: i64,
     content: String,
     data_type: String,
     note: Option<String>,
     html_content: Option<String>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
+    let suggested_actions = crate::utils::suggested_actions(&app, &content, &data_type);
+
     match state
         .db
         .update_content(id, content, data_type, note, html_content)
     {
         Ok(_) => {
             log::info!("Updated item content for id {}", id);
+            state.content_cache.lock().unwrap().invalidate(id);
+            if let Err(e) = state.db.set_suggested_actions(id, &suggested_actions) {
+                log::error!("Failed to update suggested actions: {}", e);
+            }
             Ok(())
         }
         Err(e) => {
             log::error!("Failed to update item content: {}", e);
-            Err(e.to_string())
+            Err(e.into())
         }
     }
 }
 
+/// 相同内容每次被复制的时间和来源应用
+#[tauri::command]
+pub fn get_item_occurrences(
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<Vec<ItemOccurrence>, AppError> {
+    let occurrences = state.db.get_item_occurrences(id)?;
+    if !state.config.lock().unwrap().presentation_mode {
+        return Ok(occurrences);
+    }
+    Ok(occurrences
+        .into_iter()
+        .map(|occurrence| ItemOccurrence {
+            source_app: None,
+            ..occurrence
+        })
+        .collect())
+}
+
+/// 条目来自的设备：来源设备和同步时合并进来的设备
+#[tauri::command]
+pub fn get_item_devices(state: tauri::State<AppState>, id: i64) -> Result<Vec<String>, AppError> {
+    Ok(state.db.get_item_devices(id)?)
+}
+
+/// 条目编辑前的历史版本，新版本在前
+#[tauri::command]
+pub fn get_item_versions(
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<Vec<ItemVersion>, AppError> {
+    Ok(state.db.get_item_versions(id)?)
+}
+
+/// 把条目恢复为某个历史版本；当前内容会先记录为新版本，恢复操作本身也可撤销
+#[tauri::command]
+pub fn restore_version(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+    version: i64,
+) -> Result<ClipboardItem, AppError> {
+    let target = state
+        .db
+        .get_item_versions(id)?
+        .into_iter()
+        .find(|v| v.version == version)
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Version {} not found for item {}", version, id))
+        })?;
+    update_clipboard_item_content(
+        app.clone(),
+        state.clone(),
+        id,
+        target.content,
+        target.data_type,
+        target.note,
+        target.html_content,
+    )?;
+    log::info!("Restored item {} to version {}", id, version);
+    crate::utils::emit_clipboard_update(&app);
+    Ok(crate::utils::present_item(&state, state.db.get_item(id)?))
+}
+
+/// 清空历史，可按时间、类型、来源应用筛选；不传 filter 时按配置清空全部
 #[tauri::command]
-pub fn clear_history(app: tauri::AppHandle, state: tauri::State<AppState>) -> Result<(), String> {
-    let (clear_pinned, clear_collected) = {
+pub fn clear_history(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    filter: Option<ItemFilter>,
+) -> Result<ClearResult, AppError> {
+    let mut filter = filter.unwrap_or_default();
+    {
         let config = state.config.lock().unwrap();
-        (
-            config.clear_pinned_on_clear,
-            config.clear_collected_on_clear,
-        )
-    };
+        filter
+            .only_unpinned
+            .get_or_insert(!config.clear_pinned_on_clear);
+        filter
+            .only_uncollected
+            .get_or_insert(!config.clear_collected_on_clear);
+    }
 
-    match state.db.clear_history(clear_pinned, clear_collected) {
+    let deleted = match state.db.clear_history(&filter) {
         Ok(items) => {
-            for item in items {
-                if item.kind == "image" {
+            state.content_cache.lock().unwrap().clear();
+            for item in &items {
+                if item.kind == "image" || item.kind == "audio" {
                     let path = std::path::Path::new(&item.content);
                     if path.exists() {
                         if let Err(e) = fs::remove_file(path) {
@@ -416,52 +1139,404 @@ pub fn clear_history(app: tauri::AppHandle, state: tauri::State<AppState>) -> Re
                     }
                 }
             }
+            items.len()
         }
         Err(e) => {
             log::error!("Failed to clear history: {}", e);
-            return Err(e.to_string());
+            return Err(e.into());
         }
-    }
+    };
+    let remaining = state.db.count_history()?;
+    log::info!(
+        "Cleared {} items from history, {} remaining",
+        deleted,
+        remaining
+    );
 
     // Update Tray
-    let _ = update_tray_menu(&app, &[]);
-    Ok(())
+    let history = state
+        .db
+        .get_history(1, 20, &HistoryFilter::default())
+        .unwrap_or_default();
+    let _ = update_tray_menu(&app, &history);
+    crate::quick_paste::sync(&app);
+    crate::webhook::notify(
+        &app,
+        "clear",
+        None,
+        serde_json::json!({ "deleted": deleted, "remaining": remaining }),
+    );
+    Ok(ClearResult { deleted, remaining })
 }
 
+/// 清理向导的候选条目，默认 30 天前的前 100 条
 #[tauri::command]
-pub fn get_config(state: tauri::State<AppState>) -> AppConfig {
-    let config = state.config.lock().unwrap();
-    config.clone()
+pub fn get_cleanup_candidates(
+    state: tauri::State<AppState>,
+    min_age_days: Option<u64>,
+    limit: Option<usize>,
+) -> Result<Vec<CleanupCandidate>, AppError> {
+    let min_age_days = min_age_days.unwrap_or(30);
+    let limit = limit.unwrap_or(100).clamp(1, 1000);
+    Ok(state.db.get_cleanup_candidates(min_age_days, limit)?)
 }
 
+/// 一次删除清理向导中选中的条目，锁定的条目会被跳过
 #[tauri::command]
-pub fn save_config(
+pub fn apply_cleanup(
     app: tauri::AppHandle,
-    shortcut: String,
-    max_history_size: usize,
-    language: String,
-    theme: String,
-    sensitive_apps: Vec<String>,
-    compact_mode: bool,
-    clear_pinned_on_clear: bool,
-    clear_collected_on_clear: bool,
     state: tauri::State<AppState>,
-) -> Result<(), String> {
-    let old_shortcut = {
-        let config = state.config.lock().unwrap();
-        config.shortcut.clone()
-    };
-
-    let new_config = AppConfig {
-        shortcut: shortcut.clone(),
+    ids: Vec<i64>,
+) -> Result<ClearResult, AppError> {
+    let items = state.db.delete_items(&ids)?;
+    {
+        let mut cache = state.content_cache.lock().unwrap();
+        for id in items.iter().filter_map(|item| item.id) {
+            cache.invalidate(id);
+        }
+    }
+    remove_pruned_images(&items);
+    let deleted = items.len();
+    let remaining = state.db.count_history()?;
+    log::info!(
+        "Cleanup removed {} of {} selected items, {} remaining",
+        deleted,
+        ids.len(),
+        remaining
+    );
+
+    let history = state
+        .db
+        .get_history(1, 20, &HistoryFilter::default())
+        .unwrap_or_default();
+    let _ = update_tray_menu(&app, &history);
+    crate::quick_paste::sync(&app);
+    crate::webhook::notify(
+        &app,
+        "clear",
+        None,
+        serde_json::json!({ "deleted": deleted, "remaining": remaining }),
+    );
+    Ok(ClearResult { deleted, remaining })
+}
+
+#[tauri::command]
+pub fn get_config(state: tauri::State<AppState>) -> AppConfig {
+    state.config.lock().unwrap().redacted()
+}
+
+#[tauri::command]
+pub fn save_config(
+    app: tauri::AppHandle,
+    shortcut: String,
+    max_history_size: usize,
+    language: String,
+    theme: String,
+    sensitive_apps: Vec<String>,
+    compact_mode: bool,
+    clear_pinned_on_clear: bool,
+    clear_collected_on_clear: bool,
+    calc_suggestions: Option<bool>,
+    target_currency: Option<String>,
+    ai_endpoint: Option<String>,
+    ai_api_key: Option<String>,
+    ai_model: Option<String>,
+    monitor_find_pasteboard: Option<bool>,
+    sync_find_pasteboard: Option<bool>,
+    restore_clipboard_on_startup: Option<bool>,
+    ocr_engine: Option<String>,
+    ocr_languages: Option<Vec<String>>,
+    capture_cache_max_age_days: Option<u64>,
+    capture_cache_max_size_mb: Option<u64>,
+    capture_filename_template: Option<String>,
+    capture_save_dir: Option<String>,
+    watch_folders: Option<Vec<String>>,
+    share_server_enabled: Option<bool>,
+    share_server_port: Option<u16>,
+    streamdeck_enabled: Option<bool>,
+    streamdeck_port: Option<u16>,
+    record_universal_clipboard: Option<bool>,
+    storage_quota_mb: Option<u64>,
+    storage_warning_percent: Option<u8>,
+    git_sync_repo: Option<String>,
+    git_sync_collection_id: Option<Option<i64>>,
+    git_sync_interval_minutes: Option<u64>,
+    capture_raw_formats: Option<bool>,
+    paste_rules: Option<Vec<PasteRule>>,
+    capture_normalization: Option<TextNormalization>,
+    paste_normalization: Option<TextNormalization>,
+    terminal_apps: Option<Vec<String>>,
+    min_text_length: Option<usize>,
+    max_text_length_kb: Option<usize>,
+    truncate_long_text: Option<bool>,
+    dedup_ignore_whitespace: Option<bool>,
+    content_cache_mb: Option<u64>,
+    memory_budget_mb: Option<u64>,
+    background_workers: Option<usize>,
+    popup_scaling: Option<String>,
+    popup_width: Option<u32>,
+    popup_height: Option<u32>,
+    default_country_code: Option<String>,
+    custom_classifiers: Option<Vec<CustomClassifier>>,
+    routing_rules: Option<Vec<RoutingRule>>,
+    webhooks: Option<Vec<Webhook>>,
+    scripts_enabled: Option<bool>,
+    script_http_permissions: Option<Vec<String>>,
+    mqtt_enabled: Option<bool>,
+    mqtt_host: Option<String>,
+    mqtt_port: Option<u16>,
+    mqtt_username: Option<String>,
+    mqtt_password: Option<String>,
+    mqtt_topic: Option<String>,
+    mqtt_allowlist: Option<Vec<String>>,
+    mqtt_shared_secret: Option<String>,
+    mqtt_tls: Option<bool>,
+    mqtt_publish_previews: Option<bool>,
+    capture_debounce_ms: Option<u64>,
+    update_event_coalesce_ms: Option<u64>,
+    power_saving_mode: Option<String>,
+    power_saving_idle_minutes: Option<u64>,
+    reduced_motion: Option<String>,
+    high_contrast: Option<String>,
+    popup_trigger_mode: Option<String>,
+    tray_middle_click_action: Option<String>,
+    tray_double_click_action: Option<String>,
+    usage_tracking: Option<bool>,
+    device_name: Option<String>,
+    clipboard_back_shortcut: Option<String>,
+    clipboard_forward_shortcut: Option<String>,
+    cycle_paste_shortcut: Option<String>,
+    cycle_paste_window_ms: Option<u64>,
+    state: tauri::State<AppState>,
+) -> Result<(), AppError> {
+    let shortcut = crate::shortcut::normalize(&shortcut).map_err(AppError::InvalidInput)?;
+    let clipboard_back_shortcut = clipboard_back_shortcut
+        .map(|s| crate::shortcut::normalize_optional(&s))
+        .transpose()
+        .map_err(AppError::InvalidInput)?;
+    let clipboard_forward_shortcut = clipboard_forward_shortcut
+        .map(|s| crate::shortcut::normalize_optional(&s))
+        .transpose()
+        .map_err(AppError::InvalidInput)?;
+    let cycle_paste_shortcut = cycle_paste_shortcut
+        .map(|s| crate::shortcut::normalize_optional(&s))
+        .transpose()
+        .map_err(AppError::InvalidInput)?;
+
+    let old_config = {
+        let config = state.config.lock().unwrap();
+        config.clone()
+    };
+
+    let new_config = AppConfig {
+        shortcut,
         max_history_size,
-        language: language.clone(),
-        theme: theme.clone(),
+        language,
+        theme,
         sensitive_apps,
         compact_mode,
         clear_pinned_on_clear,
         clear_collected_on_clear,
+        calc_suggestions: calc_suggestions.unwrap_or(old_config.calc_suggestions),
+        target_currency: target_currency.unwrap_or(old_config.target_currency),
+        ai_endpoint: ai_endpoint.unwrap_or(old_config.ai_endpoint),
+        ai_api_key: ai_api_key.unwrap_or(old_config.ai_api_key),
+        ai_model: ai_model.unwrap_or(old_config.ai_model),
+        monitor_find_pasteboard: monitor_find_pasteboard
+            .unwrap_or(old_config.monitor_find_pasteboard),
+        sync_find_pasteboard: sync_find_pasteboard.unwrap_or(old_config.sync_find_pasteboard),
+        restore_clipboard_on_startup: restore_clipboard_on_startup
+            .unwrap_or(old_config.restore_clipboard_on_startup),
+        ocr_engine: ocr_engine.unwrap_or(old_config.ocr_engine),
+        ocr_languages: ocr_languages.unwrap_or(old_config.ocr_languages),
+        capture_cache_max_age_days: capture_cache_max_age_days
+            .unwrap_or(old_config.capture_cache_max_age_days),
+        capture_cache_max_size_mb: capture_cache_max_size_mb
+            .unwrap_or(old_config.capture_cache_max_size_mb),
+        capture_filename_template: capture_filename_template
+            .unwrap_or(old_config.capture_filename_template),
+        capture_save_dir: capture_save_dir.unwrap_or(old_config.capture_save_dir),
+        watch_folders: watch_folders.unwrap_or(old_config.watch_folders),
+        share_server_enabled: share_server_enabled.unwrap_or(old_config.share_server_enabled),
+        share_server_port: share_server_port.unwrap_or(old_config.share_server_port),
+        share_token: old_config.share_token,
+        streamdeck_enabled: streamdeck_enabled.unwrap_or(old_config.streamdeck_enabled),
+        streamdeck_port: streamdeck_port.unwrap_or(old_config.streamdeck_port),
+        streamdeck_token: old_config.streamdeck_token,
+        api_tokens: old_config.api_tokens,
+        presentation_mode: old_config.presentation_mode,
+        record_universal_clipboard: record_universal_clipboard
+            .unwrap_or(old_config.record_universal_clipboard),
+        storage_quota_mb: storage_quota_mb.unwrap_or(old_config.storage_quota_mb),
+        storage_warning_percent: storage_warning_percent
+            .unwrap_or(old_config.storage_warning_percent),
+        git_sync_repo: git_sync_repo.unwrap_or(old_config.git_sync_repo),
+        git_sync_collection_id: git_sync_collection_id.unwrap_or(old_config.git_sync_collection_id),
+        git_sync_interval_minutes: git_sync_interval_minutes
+            .unwrap_or(old_config.git_sync_interval_minutes),
+        capture_raw_formats: capture_raw_formats.unwrap_or(old_config.capture_raw_formats),
+        paste_rules: paste_rules.unwrap_or(old_config.paste_rules),
+        capture_normalization: capture_normalization.unwrap_or(old_config.capture_normalization),
+        paste_normalization: paste_normalization.unwrap_or(old_config.paste_normalization),
+        terminal_apps: terminal_apps.unwrap_or(old_config.terminal_apps),
+        min_text_length: min_text_length.unwrap_or(old_config.min_text_length),
+        max_text_length_kb: max_text_length_kb.unwrap_or(old_config.max_text_length_kb),
+        truncate_long_text: truncate_long_text.unwrap_or(old_config.truncate_long_text),
+        dedup_ignore_whitespace: dedup_ignore_whitespace
+            .unwrap_or(old_config.dedup_ignore_whitespace),
+        content_cache_mb: content_cache_mb.unwrap_or(old_config.content_cache_mb),
+        memory_budget_mb: memory_budget_mb.unwrap_or(old_config.memory_budget_mb),
+        background_workers: background_workers.unwrap_or(old_config.background_workers),
+        popup_scaling: popup_scaling.unwrap_or(old_config.popup_scaling),
+        popup_width: popup_width.unwrap_or(old_config.popup_width),
+        popup_height: popup_height.unwrap_or(old_config.popup_height),
+        default_country_code: default_country_code.unwrap_or(old_config.default_country_code),
+        custom_classifiers: custom_classifiers.unwrap_or(old_config.custom_classifiers),
+        routing_rules: routing_rules.unwrap_or(old_config.routing_rules),
+        webhooks: webhooks.unwrap_or(old_config.webhooks),
+        scripts_enabled: scripts_enabled.unwrap_or(old_config.scripts_enabled),
+        script_http_permissions: script_http_permissions
+            .unwrap_or(old_config.script_http_permissions),
+        enabled_plugins: old_config.enabled_plugins,
+        mqtt_enabled: mqtt_enabled.unwrap_or(old_config.mqtt_enabled),
+        mqtt_host: mqtt_host.unwrap_or(old_config.mqtt_host.clone()),
+        mqtt_port: mqtt_port.unwrap_or(old_config.mqtt_port),
+        mqtt_username: mqtt_username.unwrap_or(old_config.mqtt_username.clone()),
+        mqtt_password: mqtt_password.unwrap_or(old_config.mqtt_password.clone()),
+        mqtt_topic: mqtt_topic.unwrap_or(old_config.mqtt_topic.clone()),
+        mqtt_allowlist: mqtt_allowlist.unwrap_or(old_config.mqtt_allowlist),
+        mqtt_shared_secret: mqtt_shared_secret.unwrap_or(old_config.mqtt_shared_secret.clone()),
+        mqtt_tls: mqtt_tls.unwrap_or(old_config.mqtt_tls),
+        mqtt_publish_previews: mqtt_publish_previews.unwrap_or(old_config.mqtt_publish_previews),
+        capture_debounce_ms: capture_debounce_ms.unwrap_or(old_config.capture_debounce_ms),
+        update_event_coalesce_ms: update_event_coalesce_ms
+            .unwrap_or(old_config.update_event_coalesce_ms),
+        power_saving_mode: power_saving_mode.unwrap_or(old_config.power_saving_mode.clone()),
+        reduced_motion: reduced_motion.unwrap_or(old_config.reduced_motion.clone()),
+        high_contrast: high_contrast.unwrap_or(old_config.high_contrast.clone()),
+        popup_trigger_mode: popup_trigger_mode.unwrap_or(old_config.popup_trigger_mode.clone()),
+        tray_middle_click_action: tray_middle_click_action
+            .unwrap_or(old_config.tray_middle_click_action.clone()),
+        tray_double_click_action: tray_double_click_action
+            .unwrap_or(old_config.tray_double_click_action.clone()),
+        power_saving_idle_minutes: power_saving_idle_minutes
+            .unwrap_or(old_config.power_saving_idle_minutes),
+        usage_tracking: usage_tracking.unwrap_or(old_config.usage_tracking),
+        device_name: device_name.unwrap_or(old_config.device_name.clone()),
+        clipboard_back_shortcut: clipboard_back_shortcut
+            .unwrap_or(old_config.clipboard_back_shortcut.clone()),
+        clipboard_forward_shortcut: clipboard_forward_shortcut
+            .unwrap_or(old_config.clipboard_forward_shortcut.clone()),
+        cycle_paste_shortcut: cycle_paste_shortcut
+            .unwrap_or(old_config.cycle_paste_shortcut.clone()),
+        cycle_paste_window_ms: cycle_paste_window_ms.unwrap_or(old_config.cycle_paste_window_ms),
     };
+    validate_config(&new_config)?;
+    // 新设置 Git 同步仓库时检查 git 是否可用，已有的配置不因此无法保存
+    if !new_config.git_sync_repo.trim().is_empty()
+        && new_config.git_sync_repo != state.config.lock().unwrap().git_sync_repo
+    {
+        crate::git_sync::check_git()?;
+    }
+    Ok(apply_config(&app, &state, new_config)?)
+}
+
+/// 校验配置中的枚举值和正则
+pub fn validate_config(config: &AppConfig) -> Result<(), AppError> {
+    let mode = &config.power_saving_mode;
+    if !matches!(mode.as_str(), "auto" | "always" | "off") {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown power saving mode: {}",
+            mode
+        )));
+    }
+    if !(100..=10_000).contains(&config.cycle_paste_window_ms) {
+        return Err(AppError::InvalidInput(
+            "Cycle paste window must be between 100 and 10000 ms".to_string(),
+        ));
+    }
+    let mode = &config.popup_trigger_mode;
+    if !matches!(mode.as_str(), "toggle" | "hold") {
+        return Err(AppError::InvalidInput(format!(
+            "Unknown popup trigger mode: {}",
+            mode
+        )));
+    }
+    for action in [
+        &config.tray_middle_click_action,
+        &config.tray_double_click_action,
+    ] {
+        if !matches!(action.as_str(), "none" | "paste_latest") && !crate::palette::is_action(action)
+        {
+            return Err(AppError::InvalidInput(format!(
+                "Unknown tray action: {}",
+                action
+            )));
+        }
+    }
+    for setting in [&config.reduced_motion, &config.high_contrast] {
+        if !matches!(setting.as_str(), "system" | "on" | "off") {
+            return Err(AppError::InvalidInput(format!(
+                "Unknown accessibility setting: {}",
+                setting
+            )));
+        }
+    }
+    crate::git_sync::validate_repo(&config.git_sync_repo).map_err(AppError::InvalidInput)?;
+    for classifier in &config.custom_classifiers {
+        regex::Regex::new(&classifier.pattern).map_err(|e| {
+            AppError::InvalidInput(format!(
+                "Invalid pattern for classifier {}: {}",
+                classifier.name, e
+            ))
+        })?;
+    }
+    for rule in &config.routing_rules {
+        if let Some(pattern) = &rule.pattern {
+            regex::Regex::new(pattern).map_err(|e| {
+                AppError::InvalidInput(format!("Invalid pattern for rule {}: {}", rule.name, e))
+            })?;
+        }
+    }
+    Ok(())
+}
+
+/// 保存并应用新配置：写入配置文件，按变化重启相关服务、重新注册快捷键
+pub fn apply_config(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    mut new_config: AppConfig,
+) -> Result<(), String> {
+    let old_config = state.config.lock().unwrap().clone();
+    let old_shortcut = old_config.shortcut.clone();
+    let old_watch_folders = old_config.watch_folders.clone();
+    let old_share_server = (
+        old_config.share_server_enabled,
+        old_config.share_server_port,
+    );
+    let old_streamdeck = (old_config.streamdeck_enabled, old_config.streamdeck_port);
+    let shortcut = new_config.shortcut.clone();
+    let history_shortcuts_changed = (
+        &new_config.shortcut,
+        &new_config.clipboard_back_shortcut,
+        &new_config.clipboard_forward_shortcut,
+        &new_config.cycle_paste_shortcut,
+    ) != (
+        &old_config.shortcut,
+        &old_config.clipboard_back_shortcut,
+        &old_config.clipboard_forward_shortcut,
+        &old_config.cycle_paste_shortcut,
+    );
+
+    // 首次开启共享时生成访问 token
+    if new_config.share_server_enabled && new_config.share_token.is_empty() {
+        new_config.share_token = crate::share_server::generate_token();
+    }
+    if new_config.streamdeck_enabled && new_config.streamdeck_token.is_empty() {
+        new_config.streamdeck_token = crate::share_server::generate_token();
+    }
+
+    new_config.seal_secrets(&state.crypto);
 
     // Save to file
     if let Ok(json) = serde_json::to_string_pretty(&new_config) {
@@ -471,20 +1546,91 @@ pub fn save_config(
         }
     }
 
+    let watch_folders_changed = new_config.watch_folders != old_watch_folders;
+    let share_server_changed = (
+        new_config.share_server_enabled,
+        new_config.share_server_port,
+    ) != old_share_server;
+    let streamdeck_changed =
+        (new_config.streamdeck_enabled, new_config.streamdeck_port) != old_streamdeck;
+    let mqtt_changed = (
+        new_config.mqtt_enabled,
+        &new_config.mqtt_host,
+        new_config.mqtt_port,
+        new_config.mqtt_tls,
+        &new_config.mqtt_username,
+        &new_config.mqtt_topic,
+        &new_config.device_name,
+    ) != (
+        old_config.mqtt_enabled,
+        &old_config.mqtt_host,
+        old_config.mqtt_port,
+        old_config.mqtt_tls,
+        &old_config.mqtt_username,
+        &old_config.mqtt_topic,
+        &old_config.device_name,
+    ) || state.crypto.open_secret(&new_config.mqtt_password)
+        != state.crypto.open_secret(&old_config.mqtt_password);
+    let find_pasteboard_changed =
+        new_config.monitor_find_pasteboard != old_config.monitor_find_pasteboard;
+    let power_changed = (
+        &new_config.power_saving_mode,
+        new_config.power_saving_idle_minutes,
+    ) != (
+        &old_config.power_saving_mode,
+        old_config.power_saving_idle_minutes,
+    );
+
     // Update state
     {
         let mut config = state.config.lock().unwrap();
         *config = new_config;
     }
 
+    state
+        .db
+        .set_dedup_ignore_whitespace(state.config.lock().unwrap().dedup_ignore_whitespace);
+    if let Ok(mut cache) = state.content_cache.lock() {
+        cache.set_budget(state.config.lock().unwrap().content_cache_mb);
+    }
+    crate::memory::enforce(app);
+    state
+        .jobs
+        .set_max_workers(app, state.config.lock().unwrap().background_workers);
+
+    if watch_folders_changed {
+        crate::folder_watch::restart(app);
+    }
+    if share_server_changed {
+        crate::share_server::restart(app);
+    }
+    if mqtt_changed {
+        crate::mqtt::restart(app);
+    }
+    if streamdeck_changed {
+        crate::streamdeck::restart(app);
+    }
+    if power_changed {
+        crate::power::refresh(app);
+    }
+    if find_pasteboard_changed {
+        crate::find_pasteboard::restart(app);
+    }
+
     // Update shortcut if changed
     if shortcut != old_shortcut {
         let shortcut_manager = app.global_shortcut();
         let _ = shortcut_manager.unregister(old_shortcut.as_str());
+        // 让出与新快捷键冲突的 Quick Paste 快捷键
+        crate::quick_paste::sync(app);
         if let Err(e) = shortcut_manager.register(shortcut.as_str()) {
             log::error!("Failed to register new shortcut: {}", e);
         }
     }
+    if history_shortcuts_changed {
+        crate::clipboard_cursor::sync_shortcuts(app);
+        crate::cycle_paste::sync_shortcut(app);
+    }
 
     // Emit event
     let _ = app.emit("config-updated", ());
@@ -506,27 +1652,422 @@ pub fn get_paused(state: tauri::State<AppState>) -> bool {
     *is_paused
 }
 
+/// 不打开窗口，把剪贴板换成历史中更早的一条；已是最早一条时返回 None
+#[tauri::command]
+pub fn clipboard_back(app: tauri::AppHandle) -> Result<Option<ClipboardItem>, AppError> {
+    let item = crate::clipboard_cursor::step(&app, crate::clipboard_cursor::Direction::Back)?;
+    Ok(item.map(|item| crate::utils::present_item(&app.state::<AppState>(), item)))
+}
+
+/// 把剪贴板换回历史中更新的一条；已回到最新一条时返回 None
+#[tauri::command]
+pub fn clipboard_forward(app: tauri::AppHandle) -> Result<Option<ClipboardItem>, AppError> {
+    let item = crate::clipboard_cursor::step(&app, crate::clipboard_cursor::Direction::Forward)?;
+    Ok(item.map(|item| crate::utils::present_item(&app.state::<AppState>(), item)))
+}
+
+/// 固定弹出窗口，失去焦点时保持显示
+#[tauri::command]
+pub fn set_popup_pinned(app: tauri::AppHandle, pinned: bool, state: tauri::State<AppState>) {
+    *state.popup_pinned.lock().unwrap() = pinned;
+    let _ = app.emit("popup-pinned-changed", pinned);
+}
+
+#[tauri::command]
+pub fn get_popup_pinned(state: tauri::State<AppState>) -> bool {
+    *state.popup_pinned.lock().unwrap()
+}
+
+/// 隐藏弹出窗口（如按下 Esc），并把焦点还给之前的应用
+#[tauri::command]
+pub fn hide_popup(app: tauri::AppHandle, reason: Option<String>) {
+    crate::popup::hide(&app, reason.as_deref().unwrap_or("escape"), true);
+}
+
+/// 局域网共享页面的访问地址，未开启时返回错误
+#[tauri::command]
+pub fn get_share_url(state: tauri::State<AppState>) -> Result<String, AppError> {
+    let config = state.config.lock().unwrap();
+    if !config.share_server_enabled || config.share_token.is_empty() {
+        return Err("Share server is disabled".into());
+    }
+    Ok(crate::share_server::share_url(
+        config.share_server_port,
+        &config.share_token,
+    ))
+}
+
+/// 重新生成共享 token，旧链接立即失效
+#[tauri::command]
+pub fn regenerate_share_token(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<String, AppError> {
+    let config = {
+        let mut config = state.config.lock().unwrap();
+        config.share_token = crate::share_server::generate_token();
+        config.clone()
+    };
+    let json = serde_json::to_string_pretty(&config)?;
+    fs::write(&state.config_path, json)?;
+    let _ = app.emit("config-updated", ());
+    Ok(crate::share_server::share_url(
+        config.share_server_port,
+        &config.share_token,
+    ))
+}
+
+/// 重新生成 Stream Deck token，插件需要更新配置
+#[tauri::command]
+pub fn regenerate_streamdeck_token(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<String, AppError> {
+    let config = {
+        let mut config = state.config.lock().unwrap();
+        config.streamdeck_token = crate::share_server::generate_token();
+        config.clone()
+    };
+    let json = serde_json::to_string_pretty(&config)?;
+    fs::write(&state.config_path, json)?;
+    let _ = app.emit("config-updated", ());
+    Ok(config.streamdeck_token)
+}
+
+/// 开关演示模式，保存到配置并通知各窗口
+#[tauri::command]
+pub fn set_presentation_mode(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    enabled: bool,
+) -> Result<(), AppError> {
+    let config = {
+        let mut config = state.config.lock().unwrap();
+        config.presentation_mode = enabled;
+        config.clone()
+    };
+    let json = serde_json::to_string_pretty(&config)?;
+    fs::write(&state.config_path, json)?;
+    log::info!("Presentation mode {}", if enabled { "on" } else { "off" });
+    // 跳转列表和 Dock 菜单随演示模式隐藏或恢复最近条目
+    let history = state
+        .db
+        .get_history(1, 20, &HistoryFilter::default())
+        .unwrap_or_default();
+    if let Err(e) = update_tray_menu(&app, &history) {
+        log::error!("Failed to update tray: {}", e);
+    }
+    let _ = app.emit("presentation-mode-changed", enabled);
+    let _ = app.emit("config-updated", ());
+    Ok(())
+}
+
+/// 当前生效的减弱动态效果和高对比度设置，以及系统设置
+#[tauri::command]
+pub fn get_accessibility_prefs(app: tauri::AppHandle) -> AccessibilityPrefs {
+    crate::accessibility::prefs(&app)
+}
+
+/// 条目的读屏描述（类型、来源应用、时间和预览），按界面语言生成
+#[tauri::command]
+pub fn get_item_descriptions(app: tauri::AppHandle, ids: Vec<i64>) -> Vec<ItemDescription> {
+    crate::accessibility::describe_items(&app, &ids)
+}
+
+/// 是否处于按住快捷键预览中，弹出窗口据此把带修饰键的方向键当作导航
+#[tauri::command]
+pub fn get_quick_peek_active(app: tauri::AppHandle) -> bool {
+    crate::popup::is_quick_peek_active(&app)
+}
+
+/// 取消按住预览，松开快捷键时不粘贴
+#[tauri::command]
+pub fn cancel_quick_peek(app: tauri::AppHandle) {
+    crate::popup::cancel_quick_peek(&app);
+}
+
+/// 录制下一个按下的组合键，返回与键盘布局无关的加速键（如 CommandOrControl+Shift+KeyV）
+#[tauri::command]
+pub async fn capture_shortcut(app: tauri::AppHandle) -> Result<String, AppError> {
+    Ok(crate::shortcut::capture(&app).await?)
+}
+
+/// 录制期间设置窗口转发的按键，code 为 KeyboardEvent.code
+#[tauri::command]
+pub fn record_shortcut_key(
+    app: tauri::AppHandle,
+    code: String,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+) -> Result<(), AppError> {
+    crate::shortcut::record_key(&app, &code, ctrl, alt, shift, meta).map_err(AppError::InvalidInput)
+}
+
+/// 当前电源、空闲状态以及是否处于省电模式
+#[tauri::command]
+pub fn get_power_state(app: tauri::AppHandle) -> PowerState {
+    crate::power::refresh(&app)
+}
+
+/// 创建 API 令牌（scope 为 read 或 write），明文只返回这一次
+#[tauri::command]
+pub fn create_api_token(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    name: String,
+    scope: String,
+    no_sensitive: Option<bool>,
+) -> Result<String, AppError> {
+    let (record, token) = crate::api_tokens::create(&name, &scope, no_sensitive.unwrap_or(true))?;
+    log::info!("Created {} API token {:?}", record.scope, record.name);
+    let config = {
+        let mut config = state.config.lock().unwrap();
+        config.api_tokens.push(record);
+        config.clone()
+    };
+    let json = serde_json::to_string_pretty(&config)?;
+    fs::write(&state.config_path, json)?;
+    let _ = app.emit("config-updated", ());
+    Ok(token)
+}
+
+/// 吊销 API 令牌，使用它的集成立即失去访问权限
+#[tauri::command]
+pub fn revoke_api_token(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: String,
+) -> Result<(), AppError> {
+    let config = {
+        let mut config = state.config.lock().unwrap();
+        let before = config.api_tokens.len();
+        config.api_tokens.retain(|t| t.id != id);
+        if config.api_tokens.len() == before {
+            return Err(AppError::NotFound(format!("API token not found: {}", id)));
+        }
+        config.clone()
+    };
+    let json = serde_json::to_string_pretty(&config)?;
+    fs::write(&state.config_path, json)?;
+    log::info!("Revoked API token {}", id);
+    let _ = app.emit("config-updated", ());
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_item_content(app: tauri::AppHandle, id: i64) -> Result<String, AppError> {
+    Ok(cached_item_content(&app, id)?)
+}
+
+/// 图片条目的 PNG 缩略图（data URL），默认边长 256
+#[tauri::command]
+pub fn get_thumbnail(
+    app: tauri::AppHandle,
+    id: i64,
+    size: Option<u32>,
+) -> Result<String, AppError> {
+    let size = size.unwrap_or(THUMBNAIL_SIZE).clamp(32, 1024);
+    Ok(cached_thumbnail(&app, id, size)?)
+}
+
+#[tauri::command]
+pub fn cache_stats(state: tauri::State<AppState>) -> CacheStats {
+    state.content_cache.lock().unwrap().stats()
+}
+
+/// 开发用：写入 n 条生成的文本条目，用于在大量历史下检查搜索和分页性能；发布版本不可用
+#[tauri::command]
+pub fn generate_test_data(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    n: usize,
+) -> Result<usize, AppError> {
+    if !cfg!(debug_assertions) {
+        return Err(AppError::Unsupported(
+            "Test data can only be generated in development builds".to_string(),
+        ));
+    }
+    if n > MAX_TEST_DATA_ITEMS {
+        return Err(AppError::InvalidInput(format!(
+            "At most {} items can be generated",
+            MAX_TEST_DATA_ITEMS
+        )));
+    }
+    let items = crate::test_data::generate(n, rand::random());
+    let start = std::time::Instant::now();
+    let count = state.db.insert_batch(&items)?;
+    log::info!("Generated {} test items in {:?}", count, start.elapsed());
+    crate::utils::emit_clipboard_update(&app);
+    Ok(count)
+}
+
+/// 数据库操作的调用次数和耗时，用于排查搜索卡顿
+#[tauri::command]
+pub fn get_db_stats(state: tauri::State<AppState>) -> Vec<QueryStats> {
+    state.db.query_stats()
+}
+
+/// 内存占用诊断：进程常驻内存以及缓存、截图、粘贴栈各自的占用
 #[tauri::command]
-pub fn get_item_content(state: tauri::State<AppState>, id: i64) -> Result<String, String> {
-    state.db.get_item_content(id).map_err(|e| e.to_string())
+pub fn get_memory_stats(app: tauri::AppHandle) -> MemoryStats {
+    crate::memory::enforce(&app)
 }
 
 #[tauri::command]
 pub fn create_collection(
     state: tauri::State<AppState>,
     name: String,
-) -> Result<Collection, String> {
-    state.db.create_collection(name).map_err(|e| e.to_string())
+) -> Result<Collection, AppError> {
+    Ok(state.db.create_collection(name)?)
+}
+
+/// 各收藏夹的条目数，一次查询得到全部
+#[tauri::command]
+pub fn get_collection_counts(
+    state: tauri::State<AppState>,
+) -> Result<Vec<CollectionCount>, AppError> {
+    Ok(state.db.get_collection_counts()?)
+}
+
+/// 各数据类型的条目数
+#[tauri::command]
+pub fn get_type_counts(state: tauri::State<AppState>) -> Result<Vec<TypeCount>, AppError> {
+    Ok(state.db.get_type_counts()?)
 }
 
 #[tauri::command]
-pub fn get_collections(state: tauri::State<AppState>) -> Result<Vec<Collection>, String> {
-    state.db.get_collections().map_err(|e| e.to_string())
+pub fn get_collections(state: tauri::State<AppState>) -> Result<Vec<Collection>, AppError> {
+    Ok(state.db.get_collections()?)
 }
 
+/// 设置收藏夹免于清理：其中的条目不会被数量上限清理，也不会被清空历史删除
 #[tauri::command]
-pub fn delete_collection(state: tauri::State<AppState>, id: i64) -> Result<(), String> {
-    state.db.delete_collection(id).map_err(|e| e.to_string())
+pub fn set_collection_retention_exempt(
+    state: tauri::State<AppState>,
+    id: i64,
+    exempt: bool,
+) -> Result<(), AppError> {
+    state.db.set_collection_retention_exempt(id, exempt)?;
+    log::info!("Collection {} retention exempt: {}", id, exempt);
+    Ok(())
+}
+
+/// 删除收藏夹；mode 决定其中的条目移出收藏夹（默认）、一起删除（delete_items）或移到 target_id（move）
+#[tauri::command]
+pub fn delete_collection(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+    mode: Option<String>,
+    target_id: Option<i64>,
+) -> Result<usize, AppError> {
+    let mode = CollectionDeleteMode::parse(mode.as_deref(), target_id)?;
+    let deleted = state.db.delete_collection(id, mode)?;
+    remove_pruned_images(&deleted);
+    if let Ok(mut cache) = state.content_cache.lock() {
+        for id in deleted.iter().filter_map(|item| item.id) {
+            cache.invalidate(id);
+        }
+    }
+    if deleted.iter().any(|item| item.is_pinned) {
+        crate::quick_paste::sync(&app);
+    }
+    log::info!(
+        "Deleted collection {} ({:?}), removed {} items",
+        id,
+        mode,
+        deleted.len()
+    );
+    crate::utils::emit_clipboard_update(&app);
+    Ok(deleted.len())
+}
+
+/// 导出收藏夹为 .clipbundle，返回导出的条目数
+#[tauri::command]
+pub fn export_collection(app: tauri::AppHandle, id: i64, path: String) -> Result<usize, AppError> {
+    Ok(crate::bundle::export_collection(
+        &app,
+        id,
+        std::path::Path::new(&path),
+    )?)
+}
+
+/// 把选中的图片（按 ids 或筛选条件）打包为 zip，返回导出的图片数
+#[tauri::command]
+pub fn export_images_zip(
+    app: tauri::AppHandle,
+    ids: Option<Vec<i64>>,
+    filter: Option<ItemFilter>,
+    path: String,
+) -> Result<usize, AppError> {
+    Ok(crate::bundle::export_images_zip(
+        &app,
+        ids,
+        filter,
+        std::path::Path::new(&path),
+    )?)
+}
+
+/// 用 macOS Quick Look 面板预览条目，文本先写入临时文件
+#[tauri::command]
+pub fn quicklook_item(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    Ok(crate::quicklook::quicklook_item(&app, id)?)
+}
+
+/// 打印文本或图片条目（先生成临时 PDF，再调起系统打印）
+#[tauri::command]
+pub async fn print_item(app: tauri::AppHandle, id: i64) -> Result<(), AppError> {
+    Ok(
+        tauri::async_runtime::spawn_blocking(move || crate::print::print_item(&app, id))
+            .await
+            .map_err(|e| e.to_string())??,
+    )
+}
+
+#[tauri::command]
+pub fn import_bundle(app: tauri::AppHandle, path: String) -> Result<Collection, AppError> {
+    let collection = crate::bundle::import_bundle(&app, std::path::Path::new(&path))?;
+    crate::utils::emit_clipboard_update(&app);
+    Ok(collection)
+}
+
+/// 导出设置（配置、规则、快捷键和收藏夹，不含条目）为 JSON 文件
+#[tauri::command]
+pub fn export_settings(app: tauri::AppHandle, path: String) -> Result<(), AppError> {
+    Ok(crate::settings::export_settings(
+        &app,
+        std::path::Path::new(&path),
+    )?)
+}
+
+/// 从 JSON 文件导入设置，替换当前配置；allow_network 为 true 时才导入会向外发送数据的设置
+#[tauri::command]
+pub fn import_settings(
+    app: tauri::AppHandle,
+    path: String,
+    allow_network: Option<bool>,
+) -> Result<SettingsImportResult, AppError> {
+    let result = crate::settings::import_settings(
+        &app,
+        std::path::Path::new(&path),
+        allow_network.unwrap_or(false),
+    )?;
+    if result.collections_created > 0 {
+        crate::utils::emit_clipboard_update(&app);
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub async fn git_sync_now(app: tauri::AppHandle) -> Result<GitSyncResult, AppError> {
+    crate::git_sync::check_git()?;
+    Ok(
+        tauri::async_runtime::spawn_blocking(move || crate::git_sync::run(&app))
+            .await
+            .map_err(|e| e.to_string())??,
+    )
 }
 
 #[tauri::command]
@@ -534,11 +2075,24 @@ pub fn set_item_collection(
     state: tauri::State<AppState>,
     item_id: i64,
     collection_id: Option<i64>,
-) -> Result<(), String> {
-    state
-        .db
-        .set_item_collection(item_id, collection_id)
-        .map_err(|e| e.to_string())
+) -> Result<(), AppError> {
+    Ok(state.db.set_item_collection(item_id, collection_id)?)
+}
+
+/// 向 Webhook 发送一条 test 事件，返回 HTTP 状态码
+#[tauri::command]
+pub async fn test_webhook(webhook: Webhook) -> Result<u16, AppError> {
+    Ok(crate::webhook::test(webhook).await?)
+}
+
+/// 用示例文本（及来源应用）测试自动归类规则是否匹配
+#[tauri::command]
+pub fn test_rule(
+    rule: RoutingRule,
+    sample: String,
+    source_app: Option<String>,
+) -> Result<bool, AppError> {
+    crate::rules::matches(&rule, &sample, source_app.as_deref()).map_err(AppError::InvalidInput)
 }
 
 #[tauri::command]
@@ -548,25 +2102,955 @@ pub fn get_history_count(state: tauri::State<AppState>) -> usize {
 
 #[tauri::command]
 pub fn set_paste_stack(
+    app: tauri::AppHandle,
     state: tauri::State<AppState>,
     items: Vec<ClipboardItem>,
-) -> Result<(), String> {
+) -> Result<(), AppError> {
     let mut stack = state.paste_stack.lock().map_err(|e| e.to_string())?;
     *stack = items;
+    crate::paste_stack::save(&app, &stack);
     Ok(())
 }
 
+/// 尚未粘贴的粘贴栈条目，按粘贴顺序
 #[tauri::command]
-pub async fn ocr_image(image_path: String) -> Result<String, String> {
-    log::info!("Starting OCR for image: {}", image_path);
-    match recognize_text(&image_path).await {
-        Ok(text) => {
-            log::info!("OCR successful, text length: {}", text.len());
-            Ok(text)
-        }
-        Err(e) => {
-            log::error!("OCR failed: {}", e);
-            Err(e)
-        }
-    }
+pub fn get_paste_stack(state: tauri::State<AppState>) -> Result<Vec<ClipboardItem>, AppError> {
+    let stack = state.paste_stack.lock().map_err(|e| e.to_string())?.clone();
+    Ok(crate::utils::present_items(&state, stack))
+}
+
+#[tauri::command]
+pub fn clear_paste_stack(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+) -> Result<(), AppError> {
+    let mut stack = state.paste_stack.lock().map_err(|e| e.to_string())?;
+    stack.clear();
+    crate::paste_stack::save(&app, &stack);
+    Ok(())
+}
+
+/// 读取配置中的 OCR 选项，language 非空时覆盖配置的识别语言（可用逗号分隔多个）
+fn ocr_options(state: &AppState, language: Option<String>) -> OcrOptions {
+    let config = state.config.lock().unwrap();
+    let languages = match language.filter(|l| !l.trim().is_empty()) {
+        Some(language) => language
+            .split(',')
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty())
+            .collect(),
+        None => config.ocr_languages.clone(),
+    };
+    OcrOptions {
+        engine: config.ocr_engine.clone(),
+        languages,
+    }
+}
+
+#[tauri::command]
+pub async fn ocr_image(
+    state: tauri::State<'_, AppState>,
+    image_path: String,
+    language: Option<String>,
+) -> Result<String, AppError> {
+    log::info!("Starting OCR for image: {}", image_path);
+    let options = ocr_options(&state, language);
+    match recognize_text(&image_path, &options).await {
+        Ok(text) => {
+            log::info!("OCR successful, text length: {}", text.len());
+            Ok(text)
+        }
+        Err(e) => {
+            log::error!("OCR failed: {}", e);
+            Err(e.into())
+        }
+    }
+}
+
+/// 返回识别结果的行/词位置和置信度，供截图查看器逐行复制
+#[tauri::command]
+pub async fn ocr_image_layout(
+    state: tauri::State<'_, AppState>,
+    image_path: String,
+    language: Option<String>,
+) -> Result<OcrResult, AppError> {
+    let options = ocr_options(&state, language);
+    Ok(ocr::recognize(&image_path, &options).await?)
+}
+
+#[tauri::command]
+pub async fn list_ocr_languages(
+    state: tauri::State<'_, AppState>,
+    engine: Option<String>,
+) -> Result<Vec<String>, AppError> {
+    let mut options = ocr_options(&state, None);
+    if let Some(engine) = engine {
+        options.engine = engine;
+    }
+    Ok(ocr::list_languages(&options).await?)
+}
+
+/// 为尚未识别过的历史图片补做 OCR，返回排队的数量
+#[tauri::command]
+pub fn ocr_backfill(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    limit: Option<usize>,
+) -> Result<usize, AppError> {
+    if state.jobs.is_active("ocr-backfill") {
+        return Err("OCR backfill is already running".into());
+    }
+
+    let items = state
+        .db
+        .get_images_without_ocr(limit.unwrap_or(500), ocr::MAX_BACKFILL_ATTEMPTS)?;
+    let total = items.len();
+    log::info!("Starting OCR backfill for {} images", total);
+    state
+        .jobs
+        .schedule(&app, "ocr-backfill", JobPriority::Low, move |ctx| {
+            ocr::run_backfill(ctx, items)
+        });
+    Ok(total)
+}
+
+#[tauri::command]
+pub fn cancel_ocr_backfill(app: tauri::AppHandle, state: tauri::State<AppState>) {
+    state.jobs.cancel_kind(&app, "ocr-backfill");
+}
+
+/// 排队和运行中的后台任务
+#[tauri::command]
+pub fn list_jobs(state: tauri::State<AppState>) -> Vec<JobInfo> {
+    state.jobs.list()
+}
+
+#[tauri::command]
+pub fn cancel_job(app: tauri::AppHandle, state: tauri::State<AppState>, id: u64) -> bool {
+    state.jobs.cancel(&app, id)
+}
+
+/// 取出条目对应的文件系统路径（路径文本、图片文件或文件列表中的第一个）
+fn item_fs_path(state: &AppState, id: i64) -> Result<String, AppError> {
+    let item = state.db.get_item(id)?;
+    let path = match item.kind.as_str() {
+        "image" | "audio" => Some(item.content),
+        "file" => serde_json::from_str::<Vec<String>>(&item.content)
+            .ok()
+            .and_then(|files| files.into_iter().next()),
+        _ => resolve_existing_path(&item.content),
+    };
+    path.ok_or_else(|| {
+        AppError::NotFound(format!("Item {} does not point to an existing path", id))
+    })
+}
+
+#[tauri::command]
+pub fn open_item_path(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<(), AppError> {
+    let path = item_fs_path(&state, id)?;
+    log::info!("Opening path for item {}: {}", id, path);
+    app.opener()
+        .open_path(path, None::<&str>)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+#[tauri::command]
+pub fn reveal_in_finder(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<(), AppError> {
+    let path = item_fs_path(&state, id)?;
+    log::info!("Revealing path for item {}: {}", id, path);
+    app.opener()
+        .reveal_item_in_dir(path)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+/// 文件条目中的路径列表
+fn file_item_paths(state: &AppState, id: i64) -> Result<Vec<String>, AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "file" {
+        return Err(AppError::InvalidInput(format!(
+            "Item {} is not a file item",
+            id
+        )));
+    }
+    Ok(serde_json::from_str(&item.content)?)
+}
+
+/// 把文本写入剪贴板，不影响条目本身
+fn write_plain_text(app: &tauri::AppHandle, text: String) -> Result<(), String> {
+    let item = ClipboardItem {
+        content: text,
+        kind: "text".to_string(),
+        data_type: "text".to_string(),
+        ..Default::default()
+    };
+    write_to_clipboard(app, &item)
+}
+
+/// 把文件条目的路径作为文本复制，多个文件每行一个
+#[tauri::command]
+pub fn copy_file_paths(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<(), AppError> {
+    let paths = file_item_paths(&state, id)?;
+    Ok(write_plain_text(&app, paths.join("\n"))?)
+}
+
+/// 复制文件条目中文本文件的内容（非 UTF-8 编码自动转换），多个文件按顺序以空行分隔；二进制文件报错
+#[tauri::command]
+pub fn copy_file_contents(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<(), AppError> {
+    let paths = file_item_paths(&state, id)?;
+    let mut total = 0;
+    let mut contents = Vec::new();
+    for path in &paths {
+        let metadata = fs::metadata(path).map_err(|e| format!("{}: {}", path, e))?;
+        if metadata.is_dir() {
+            return Err(AppError::InvalidInput(format!("{} is a directory", path)));
+        }
+        total += metadata.len();
+        if total > MAX_FILE_CONTENTS_BYTES {
+            return Err(AppError::InvalidInput(
+                "Files are too large to copy as text".to_string(),
+            ));
+        }
+        let bytes = fs::read(path).map_err(|e| format!("{}: {}", path, e))?;
+        let decoded = crate::encoding::decode(&bytes);
+        if decoded.text.contains('\0') {
+            return Err(AppError::InvalidInput(format!(
+                "{} is not a text file",
+                path
+            )));
+        }
+        if decoded.lossy {
+            log::warn!("{} decoded as {} with replacements", path, decoded.encoding);
+        }
+        contents.push(decoded.text);
+    }
+    Ok(write_plain_text(&app, contents.join("\n\n"))?)
+}
+
+/// 以文件对象复制，粘贴到文件管理器时得到文件本身
+#[tauri::command]
+pub fn copy_file_object(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<(), AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "file" {
+        return Err(AppError::InvalidInput(format!(
+            "Item {} is not a file item",
+            id
+        )));
+    }
+    Ok(write_to_clipboard(&app, &item)?)
+}
+
+#[tauri::command]
+pub fn open_item_url(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+    open_all: Option<bool>,
+) -> Result<usize, AppError> {
+    let item = state.db.get_item(id)?;
+    Ok(open_item_urls(&app, &item, open_all.unwrap_or(false))?)
+}
+
+#[tauri::command]
+pub fn extract_entities(
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<Vec<ExtractedEntity>, AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Entities can only be extracted from text items".to_string(),
+        ));
+    }
+    Ok(crate::utils::extract_entities(&item.content))
+}
+
+#[tauri::command]
+pub fn count_stats(
+    state: tauri::State<AppState>,
+    id: i64,
+    include_tokens: Option<bool>,
+) -> Result<TextStats, AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Statistics are only available for text items".to_string(),
+        ));
+    }
+    Ok(crate::utils::text_stats(
+        &item.content,
+        include_tokens.unwrap_or(true),
+    ))
+}
+
+/// 对文本条目执行 AI 操作，结果作为关联条目保存
+async fn run_ai_item_action(
+    app: &tauri::AppHandle,
+    state: &AppState,
+    id: i64,
+    action: AiAction,
+    style: Option<&str>,
+) -> Result<ClipboardItem, AppError> {
+    let item = state.db.get_item(id)?;
+    if item.is_sensitive {
+        return Err(AppError::InvalidInput(
+            "Sensitive items are never sent to AI providers".to_string(),
+        ));
+    }
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "AI actions are only available for text items".to_string(),
+        ));
+    }
+
+    let mut config = state.config.lock().unwrap().clone();
+    config.ai_api_key = state.crypto.open_secret(&config.ai_api_key);
+    let result = ai::run_action(&config, action, style, &item.content).await?;
+
+    let derived = ClipboardItem {
+        id: None,
+        data_type: classify_with_custom(&result, &crate::plugins::classifiers(app)),
+        content: result,
+        kind: "text".to_string(),
+        timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        source_app: Some("AI".to_string()),
+        note: Some(action.label().to_string()),
+        parent_id: Some(id),
+        ..Default::default()
+    };
+
+    let (new_id, pruned_items) = state.db.insert_item(&derived, config.max_history_size)?;
+    remove_pruned_images(&pruned_items);
+    let mut meta = vec![
+        ("ai_action", serde_json::json!(action.label())),
+        ("ai_model", serde_json::json!(config.ai_model)),
+    ];
+    if let Some(style) = style {
+        meta.push(("ai_style", serde_json::json!(style)));
+    }
+    for (key, value) in meta {
+        if let Err(e) = state.db.set_item_meta(new_id, key, Some(&value)) {
+            log::error!("Failed to set {} metadata on item {}: {}", key, new_id, e);
+        }
+    }
+    crate::utils::emit_clipboard_update(app);
+    log::info!("{} stored as item {} (from {})", action.label(), new_id, id);
+
+    Ok(state.db.get_item(new_id)?)
+}
+
+/// scripts 目录中的脚本及其钩子和编译错误
+#[tauri::command]
+pub fn list_scripts(app: tauri::AppHandle) -> Vec<ScriptInfo> {
+    crate::scripting::list(&app)
+}
+
+/// 在文件管理器中打开脚本目录
+#[tauri::command]
+pub fn open_scripts_dir(app: tauri::AppHandle) -> Result<(), AppError> {
+    let dir = crate::scripting::scripts_dir(&app)?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+/// 用脚本的 transform(text) 转换文本条目，结果作为关联条目保存
+#[tauri::command]
+pub async fn run_script_transform(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: i64,
+    script: String,
+) -> Result<ClipboardItem, AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Scripts can only transform text items".to_string(),
+        ));
+    }
+
+    let handle = app.clone();
+    let name = script.clone();
+    let content = item.content.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        let script = crate::scripting::get(&handle, &name)?;
+        crate::scripting::run_transform(&script, &content)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let config = state.config.lock().unwrap().clone();
+    let derived = ClipboardItem {
+        data_type: classify_with_custom(&result, &crate::plugins::classifiers(&app)),
+        content: result,
+        kind: "text".to_string(),
+        timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        source_app: Some("Script".to_string()),
+        note: Some(script.clone()),
+        parent_id: Some(id),
+        is_sensitive: item.is_sensitive,
+        ..Default::default()
+    };
+
+    let (new_id, pruned_items) = state.db.insert_item(&derived, config.max_history_size)?;
+    remove_pruned_images(&pruned_items);
+    crate::utils::emit_clipboard_update(&app);
+    log::info!("Script {} stored as item {} (from {})", script, new_id, id);
+
+    Ok(state.db.get_item(new_id)?)
+}
+
+/// plugins 目录中的插件及其启用状态
+#[tauri::command]
+pub fn list_plugins(app: tauri::AppHandle) -> Vec<PluginInfo> {
+    crate::plugins::reload(&app);
+    crate::plugins::list(&app)
+}
+
+/// 启用或停用插件并重新加载插件分类器
+#[tauri::command]
+pub fn set_plugin_enabled(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: String,
+    enabled: bool,
+) -> Result<(), AppError> {
+    if enabled && !crate::plugins::list(&app).iter().any(|p| p.id == id) {
+        return Err(AppError::NotFound(format!("Plugin not found: {}", id)));
+    }
+    let config = {
+        let mut config = state.config.lock().unwrap();
+        config.enabled_plugins.retain(|p| *p != id);
+        if enabled {
+            config.enabled_plugins.push(id.clone());
+        }
+        config.clone()
+    };
+    let json = serde_json::to_string_pretty(&config)?;
+    fs::write(&state.config_path, json)?;
+    crate::plugins::reload(&app);
+    log::info!(
+        "Plugin {} {}",
+        id,
+        if enabled { "enabled" } else { "disabled" }
+    );
+    let _ = app.emit("config-updated", ());
+    Ok(())
+}
+
+/// 在文件管理器中打开插件目录
+#[tauri::command]
+pub fn open_plugins_dir(app: tauri::AppHandle) -> Result<(), AppError> {
+    let dir = crate::plugins::plugins_dir(&app)?;
+    app.opener()
+        .open_path(dir.to_string_lossy(), None::<&str>)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+/// 对文本条目执行插件提供的操作，结果作为关联条目保存
+#[tauri::command]
+pub async fn run_plugin_action(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: i64,
+    plugin: String,
+    action: String,
+) -> Result<ClipboardItem, AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Plugin actions are only available for text items".to_string(),
+        ));
+    }
+    let (dir, manifest) = crate::plugins::get(&app, &plugin)?;
+    let plugin_action = manifest
+        .actions
+        .iter()
+        .find(|a| a.id == action)
+        .cloned()
+        .ok_or_else(|| AppError::NotFound(format!("Plugin {} has no action {}", plugin, action)))?;
+    if !plugin_action.data_types.is_empty() && !plugin_action.data_types.contains(&item.data_type) {
+        return Err(AppError::InvalidInput(format!(
+            "{} is not available for {} items",
+            plugin_action.label, item.data_type
+        )));
+    }
+
+    let id_for_script = plugin.clone();
+    let http = crate::plugins::http_granted(&app, &plugin);
+    let content = item.content.clone();
+    let function = plugin_action.function.clone();
+    let result = tauri::async_runtime::spawn_blocking(move || {
+        crate::plugins::runtime(&dir, &id_for_script, &manifest, http)?.call(&function, &content)
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let max_history_size = state.config.lock().unwrap().max_history_size;
+    let derived = ClipboardItem {
+        data_type: classify_with_custom(&result, &crate::plugins::classifiers(&app)),
+        content: result,
+        kind: "text".to_string(),
+        timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        source_app: Some("Plugin".to_string()),
+        note: Some(plugin_action.label.clone()),
+        parent_id: Some(id),
+        is_sensitive: item.is_sensitive,
+        ..Default::default()
+    };
+
+    let (new_id, pruned_items) = state.db.insert_item(&derived, max_history_size)?;
+    remove_pruned_images(&pruned_items);
+    for (key, value) in [("plugin", &plugin), ("plugin_action", &action)] {
+        if let Err(e) = state
+            .db
+            .set_item_meta(new_id, key, Some(&serde_json::json!(value)))
+        {
+            log::error!("Failed to set {} metadata on item {}: {}", key, new_id, e);
+        }
+    }
+    crate::utils::emit_clipboard_update(&app);
+    log::info!(
+        "Plugin action {}/{} stored as item {} (from {})",
+        plugin,
+        action,
+        new_id,
+        id
+    );
+
+    Ok(state.db.get_item(new_id)?)
+}
+
+/// 用插件提供的分享目标打开文本条目
+#[tauri::command]
+pub fn share_to_plugin_target(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+    plugin: String,
+    target: String,
+) -> Result<(), AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Only text items can be shared".to_string(),
+        ));
+    }
+    let (_, manifest) = crate::plugins::get(&app, &plugin)?;
+    let target = manifest
+        .share_targets
+        .iter()
+        .find(|t| t.id == target)
+        .ok_or_else(|| {
+            AppError::NotFound(format!("Plugin {} has no share target {}", plugin, target))
+        })?;
+    let url = crate::plugins::share_url(&target.url, &item.content);
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+#[tauri::command]
+pub async fn summarize_item(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<ClipboardItem, AppError> {
+    run_ai_item_action(&app, &state, id, AiAction::Summarize, None).await
+}
+
+#[tauri::command]
+pub async fn rewrite_item(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: i64,
+    style: Option<String>,
+) -> Result<ClipboardItem, AppError> {
+    run_ai_item_action(&app, &state, id, AiAction::Rewrite, style.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn explain_code(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<ClipboardItem, AppError> {
+    run_ai_item_action(&app, &state, id, AiAction::ExplainCode, None).await
+}
+
+/// 把颜色条目转换为 hex / rgb / hsl / oklch 写法
+#[tauri::command]
+pub fn convert_color(
+    state: tauri::State<AppState>,
+    id: i64,
+    format: String,
+) -> Result<String, AppError> {
+    let item = state.db.get_item(id)?;
+    let color = crate::color::parse(&item.content)
+        .ok_or_else(|| AppError::InvalidInput("Item is not a color".to_string()))?;
+    crate::color::format(&color, &format).map_err(AppError::InvalidInput)
+}
+
+/// 用系统拨号应用（tel: 链接）拨打电话条目
+#[tauri::command]
+pub fn call_item(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<(), AppError> {
+    let item = state.db.get_item(id)?;
+    if item.data_type != "phone" {
+        return Err(AppError::InvalidInput(
+            "Item is not a phone number".to_string(),
+        ));
+    }
+    let country_code = state.config.lock().unwrap().default_country_code.clone();
+    let url = crate::contact::tel_url(&item.content, &country_code);
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+/// 在地图中打开地址条目
+#[tauri::command]
+pub fn map_item(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<(), AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Only text items can be opened in maps".to_string(),
+        ));
+    }
+    let url = crate::contact::maps_url(&item.content)?;
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+/// 用默认邮件客户端写邮件：邮箱条目作为收件人；其他文本条目（或 as_body 为 true 时）作为正文，
+/// 可另外指定收件人 to
+#[tauri::command]
+pub fn compose_email(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+    as_body: Option<bool>,
+    to: Option<String>,
+) -> Result<(), AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Only text items can be sent by email".to_string(),
+        ));
+    }
+    let url = if item.data_type == "email" && !as_body.unwrap_or(false) {
+        crate::contact::mailto_url(item.content.trim(), None, None)
+    } else {
+        let content = state.db.get_item_content(id)?;
+        crate::contact::mailto_url(
+            to.as_deref().unwrap_or(""),
+            item.note.as_deref(),
+            Some(&content),
+        )
+    };
+    app.opener()
+        .open_url(url, None::<&str>)
+        .map_err(|e| AppError::Other(e.to_string()))
+}
+
+/// 电话条目的 E.164 形式（+国家码+号码），用于复制
+#[tauri::command]
+pub fn normalize_phone(state: tauri::State<AppState>, id: i64) -> Result<String, AppError> {
+    let item = state.db.get_item(id)?;
+    let country_code = state.config.lock().unwrap().default_country_code.clone();
+    crate::contact::to_e164(&item.content, &country_code)
+        .ok_or_else(|| AppError::InvalidInput("Item is not a valid phone number".to_string()))
+}
+
+/// 把表格条目（制表符分隔、Markdown 或 CSV）转换为 csv / markdown / json
+#[tauri::command]
+pub fn convert_table(
+    state: tauri::State<AppState>,
+    id: i64,
+    format: String,
+) -> Result<String, AppError> {
+    let content = state.db.get_item_content(id)?;
+    crate::table::convert(&content, &format).map_err(AppError::InvalidInput)
+}
+
+/// 用条目中识别出的日期时间生成 .ics 文件并用默认日历应用打开，返回文件路径。
+/// 识别结果作为 "event" 建议操作保存在条目上
+#[tauri::command]
+pub fn create_event(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<String, AppError> {
+    let mut item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::InvalidInput(
+            "Events can only be created from text items".to_string(),
+        ));
+    }
+    let saved = item
+        .suggested_actions
+        .iter()
+        .find(|a| a.kind == "event")
+        .and_then(|a| crate::calendar::from_value(&a.value));
+    let parsed = match saved {
+        Some(parsed) => parsed,
+        None => {
+            let action = crate::calendar::suggestion(&item.content, Local::now().naive_local())
+                .ok_or_else(|| AppError::NotFound("No date found in item".to_string()))?;
+            let parsed = crate::calendar::from_value(&action.value)
+                .ok_or_else(|| AppError::NotFound("No date found in item".to_string()))?;
+            item.suggested_actions.push(action);
+            state
+                .db
+                .set_suggested_actions(id, &item.suggested_actions)?;
+            parsed
+        }
+    };
+
+    let content = state.db.get_item_content(id)?;
+    let summary = item.note.clone().unwrap_or_else(|| {
+        content
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or_default()
+            .trim()
+            .chars()
+            .take(80)
+            .collect()
+    });
+    let uid = format!(
+        "item-{}-{}@{}",
+        id,
+        item.timestamp_ms,
+        app.config().identifier
+    );
+    let ics = crate::calendar::build_ics(&uid, &summary, &content, &parsed);
+
+    let path = crate::calendar::events_dir(&app)?.join(format!("item-{}.ics", id));
+    fs::write(&path, ics)?;
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|e| AppError::Other(e.to_string()))?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 计算图片条目的 n 个主色，每个颜色可以作为颜色条目粘贴
+#[tauri::command]
+pub async fn extract_palette(
+    state: tauri::State<'_, AppState>,
+    image_id: i64,
+    n: Option<usize>,
+) -> Result<Vec<ColorSwatch>, AppError> {
+    let item = state.db.get_item(image_id)?;
+    if item.kind != "image" {
+        return Err(AppError::InvalidInput(
+            "Palette can only be extracted from image items".to_string(),
+        ));
+    }
+    let palette = tauri::async_runtime::spawn_blocking(move || {
+        let img = image::open(&item.content).map_err(|e| e.to_string())?;
+        Ok::<_, String>(crate::color::extract_palette(&img, n.unwrap_or(5)))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    Ok(palette)
+}
+
+/// 播放音频条目
+#[tauri::command]
+pub fn play_item(
+    app: tauri::AppHandle,
+    state: tauri::State<AppState>,
+    id: i64,
+) -> Result<(), AppError> {
+    let item = state.db.get_item(id)?;
+    if item.kind != "audio" {
+        return Err(AppError::InvalidInput(
+            "Only audio items can be played".to_string(),
+        ));
+    }
+    Ok(crate::audio::play(&app, &item.content)?)
+}
+
+#[tauri::command]
+pub fn stop_audio(app: tauri::AppHandle) {
+    crate::audio::stop(&app);
+}
+
+/// 提取文件条目指向的 PDF 中的文本，作为关联的文本条目保存以便搜索和引用
+#[tauri::command]
+pub async fn extract_pdf_text(
+    app: tauri::AppHandle,
+    state: tauri::State<'_, AppState>,
+    id: i64,
+) -> Result<ClipboardItem, AppError> {
+    let path = std::path::PathBuf::from(item_fs_path(&state, id)?);
+    let is_pdf = path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+    if !is_pdf {
+        return Err(AppError::InvalidInput(
+            "Item does not point to a PDF file".to_string(),
+        ));
+    }
+
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let text = tauri::async_runtime::spawn_blocking(move || crate::pdf::extract_text(&path))
+        .await
+        .map_err(|e| e.to_string())??;
+
+    let derived = ClipboardItem {
+        data_type: classify_with_custom(&text, &crate::plugins::classifiers(&app)),
+        content: text,
+        kind: "text".to_string(),
+        timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        source_app: Some("PDF".to_string()),
+        note: Some(file_name),
+        parent_id: Some(id),
+        ..Default::default()
+    };
+
+    let max_size = state.config.lock().unwrap().max_history_size;
+    let (new_id, pruned_items) = state.db.insert_item(&derived, max_size)?;
+    remove_pruned_images(&pruned_items);
+    crate::utils::emit_clipboard_update(&app);
+    log::info!("Extracted PDF text from item {} as item {}", id, new_id);
+
+    Ok(state.db.get_item(new_id)?)
+}
+
+#[tauri::command]
+pub fn find_similar(
+    state: tauri::State<AppState>,
+    id: i64,
+    threshold: Option<f64>,
+) -> Result<Vec<SimilarItem>, AppError> {
+    let threshold = threshold.unwrap_or(0.9).clamp(0.0, 1.0);
+    Ok(state.db.find_similar(id, threshold)?)
+}
+
+#[tauri::command]
+pub fn get_activity_calendar(
+    state: tauri::State<AppState>,
+    year: Option<i32>,
+) -> Result<ActivityCalendar, AppError> {
+    use chrono::Datelike;
+    let year = year.unwrap_or_else(|| Local::now().year());
+    Ok(state.db.get_activity_calendar(year)?)
+}
+
+/// 可用于筛选的设备：本机在前，其后是同步来的条目中出现过的设备
+#[tauri::command]
+pub fn get_devices(state: tauri::State<AppState>) -> Result<Vec<String>, AppError> {
+    let local = crate::utils::device_name(&state.config.lock().unwrap());
+    let mut devices = vec![local.clone()];
+    devices.extend(
+        state
+            .db
+            .get_origin_devices()?
+            .into_iter()
+            .filter(|d| *d != local),
+    );
+    Ok(devices)
+}
+
+/// 本地使用统计，默认最近 30 天
+#[tauri::command]
+pub fn get_usage_stats(app: tauri::AppHandle, days: Option<u32>) -> Result<UsageStats, AppError> {
+    let days = days.unwrap_or(30).clamp(1, 366);
+    Ok(crate::usage::stats(&app, days)?)
+}
+
+#[tauri::command]
+pub fn clear_usage_stats(state: tauri::State<AppState>) -> Result<usize, AppError> {
+    Ok(state.db.clear_usage_counts()?)
+}
+
+/// 把条目加密后通过 MQTT 发送到白名单中的设备，对方收到后直接写入剪贴板
+#[tauri::command]
+pub fn send_to_device(app: tauri::AppHandle, id: i64, device: String) -> Result<String, AppError> {
+    crate::mqtt::send_to_device(&app, id, &device)
+}
+
+#[tauri::command]
+pub fn get_storage_usage(app: tauri::AppHandle) -> Result<StorageUsage, AppError> {
+    Ok(crate::utils::storage_usage(
+        &app,
+        std::time::Duration::ZERO,
+    )?)
+}
+
+#[tauri::command]
+pub fn get_suggestions(
+    state: tauri::State<AppState>,
+    context_app: Option<String>,
+    limit: Option<usize>,
+) -> Result<Vec<ClipboardItem>, AppError> {
+    let context_app = context_app.or_else(|| state.last_active_app.lock().unwrap().clone());
+    let Some(app_name) = context_app else {
+        return Ok(Vec::new());
+    };
+    let items = state.db.get_suggestions(&app_name, limit.unwrap_or(5))?;
+    Ok(crate::utils::present_items(&state, items))
+}
+
+#[tauri::command]
+pub fn format_timestamp(
+    state: tauri::State<AppState>,
+    id: i64,
+    style: Option<String>,
+) -> Result<String, AppError> {
+    let item = state.db.get_item(id)?;
+    let language = state.config.lock().unwrap().language.clone();
+    Ok(crate::utils::format_timestamp(
+        item.timestamp_ms,
+        style.as_deref().unwrap_or("relative"),
+        &language,
+    ))
+}
+
+/// 命令面板：按查询模糊排序的可执行操作
+#[tauri::command]
+pub fn palette_actions(query: String) -> Vec<PaletteAction> {
+    crate::palette::search(&query)
+}
+
+/// 执行命令面板中的操作，转换类操作默认作用于最近一条文本
+#[tauri::command]
+pub async fn run_action(
+    app: tauri::AppHandle,
+    id: String,
+    item_id: Option<i64>,
+) -> Result<(), AppError> {
+    Ok(crate::palette::run(&app, &id, item_id).await?)
 }
@@ -0,0 +1,176 @@
+use crate::models::SuggestedAction;
+use regex::Regex;
+use std::sync::OnceLock;
+
+/// 整段文本是否为电话号码：北美格式，或以 + 开头的国际号码
+pub fn is_phone(text: &str) -> bool {
+    static PHONE: OnceLock<Regex> = OnceLock::new();
+    static INTERNATIONAL: OnceLock<Regex> = OnceLock::new();
+    let text = text.trim();
+    let phone = PHONE
+        .get_or_init(|| Regex::new(r"^(\+\d{1,3}[- ]?)?\(?\d{3}\)?[- ]?\d{3}[- ]?\d{4}$").unwrap());
+    let international =
+        INTERNATIONAL.get_or_init(|| Regex::new(r"^\+\d[\d ().-]{6,20}\d$").unwrap());
+    if phone.is_match(text) {
+        return true;
+    }
+    let digits = text.chars().filter(|c| c.is_ascii_digit()).count();
+    international.is_match(text) && (8..=15).contains(&digits)
+}
+
+/// 规范化为 E.164（+国家码+号码）。没有国家码时去掉本地长途前缀 0 并补上 default_country_code
+pub fn to_e164(text: &str, default_country_code: &str) -> Option<String> {
+    let text = text.trim();
+    let digits: String = text.chars().filter(|c| c.is_ascii_digit()).collect();
+    let number = if text.starts_with('+') {
+        digits
+    } else if let Some(rest) = digits.strip_prefix("00") {
+        rest.to_string()
+    } else {
+        let country: String = default_country_code
+            .chars()
+            .filter(|c| c.is_ascii_digit())
+            .collect();
+        if country.is_empty() {
+            return None;
+        }
+        // 北美号码本身可能已带 1
+        if country == "1" && digits.len() == 11 && digits.starts_with('1') {
+            digits
+        } else {
+            format!("{}{}", country, digits.trim_start_matches('0'))
+        }
+    };
+    (8..=15)
+        .contains(&number.len())
+        .then(|| format!("+{}", number))
+}
+
+/// 整段文本是否像一个街道地址：门牌号 + 街道类型，或中文的 路/街/道 + 号
+pub fn is_address(text: &str) -> bool {
+    static STREET: OnceLock<Regex> = OnceLock::new();
+    static CHINESE: OnceLock<Regex> = OnceLock::new();
+    let text = text.trim();
+    if text.is_empty() || text.len() > 200 || text.lines().count() > 4 || text.contains("://") {
+        return false;
+    }
+    let street = STREET.get_or_init(|| {
+        Regex::new(
+            r"(?i)\b\d{1,6}[a-z]?\s+(?:[\w.'-]+\s+){1,5}(?:street|st|avenue|ave|road|rd|boulevard|blvd|lane|ln|drive|dr|court|ct|way|place|pl|square|sq|terrace|highway|hwy|parkway|pkwy)\b\.?",
+        )
+        .unwrap()
+    });
+    let chinese = CHINESE.get_or_init(|| {
+        Regex::new(r"(?:省|市|区|县|镇).{0,30}?(?:路|街|道|巷|弄)\s*\d+\s*号").unwrap()
+    });
+    street.is_match(text) || chinese.is_match(text)
+}
+
+/// 在地图中搜索地址的链接：macOS 使用系统地图，其他平台使用 Google 地图
+pub fn maps_url(address: &str) -> Result<String, String> {
+    let query = address.split_whitespace().collect::<Vec<_>>().join(" ");
+    let url = if cfg!(target_os = "macos") {
+        reqwest::Url::parse_with_params("https://maps.apple.com/", &[("q", query)])
+    } else {
+        reqwest::Url::parse_with_params(
+            "https://www.google.com/maps/search/",
+            &[("api", "1".to_string()), ("query", query)],
+        )
+    };
+    url.map(|u| u.to_string()).map_err(|e| e.to_string())
+}
+
+/// 电话号码的 tel: 链接，优先使用 E.164 形式
+pub fn tel_url(phone: &str, default_country_code: &str) -> String {
+    let number = to_e164(phone, default_country_code).unwrap_or_else(|| {
+        phone
+            .chars()
+            .filter(|c| c.is_ascii_digit() || *c == '+')
+            .collect()
+    });
+    format!("tel:{}", number)
+}
+
+// mailto 链接过长时部分邮件客户端会打不开，正文超出部分截断
+const MAX_MAILTO_BODY: usize = 1800;
+
+/// 百分号编码，只保留 RFC 3986 的非保留字符（空格编码为 %20 而不是 +），
+/// 可用于 mailto（RFC 6068）和 URL 模板
+pub fn percent_encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// 构造 mailto: 链接，收件人、主题、正文均可为空
+pub fn mailto_url(to: &str, subject: Option<&str>, body: Option<&str>) -> String {
+    let to = to
+        .split([',', ';'])
+        .map(|addr| percent_encode(addr.trim()).replace("%40", "@"))
+        .filter(|addr| !addr.is_empty())
+        .collect::<Vec<_>>()
+        .join(",");
+    let mut params = Vec::new();
+    if let Some(subject) = subject.filter(|s| !s.is_empty()) {
+        params.push(format!("subject={}", percent_encode(subject)));
+    }
+    if let Some(body) = body.filter(|b| !b.is_empty()) {
+        let body: String = match body.char_indices().nth(MAX_MAILTO_BODY) {
+            Some((end, _)) => format!("{}…", &body[..end]),
+            None => body.to_string(),
+        };
+        // 换行统一为 CRLF
+        let body = body.replace("\r\n", "\n").replace('\n', "\r\n");
+        params.push(format!("body={}", percent_encode(&body)));
+    }
+    if params.is_empty() {
+        format!("mailto:{}", to)
+    } else {
+        format!("mailto:{}?{}", to, params.join("&"))
+    }
+}
+
+/// 电话、地址和邮箱条目的快捷操作：拨打、复制 E.164、在地图中打开、写邮件
+pub fn suggestions(
+    text: &str,
+    data_type: &str,
+    default_country_code: &str,
+) -> Vec<SuggestedAction> {
+    match data_type {
+        "phone" => {
+            let mut actions = vec![SuggestedAction {
+                kind: "call".to_string(),
+                label: "Call".to_string(),
+                value: tel_url(text, default_country_code),
+            }];
+            if let Some(e164) = to_e164(text, default_country_code) {
+                actions.push(SuggestedAction {
+                    kind: "e164".to_string(),
+                    label: e164.clone(),
+                    value: e164,
+                });
+            }
+            actions
+        }
+        "address" => maps_url(text)
+            .map(|url| {
+                vec![SuggestedAction {
+                    kind: "map".to_string(),
+                    label: "Open in Maps".to_string(),
+                    value: url,
+                }]
+            })
+            .unwrap_or_default(),
+        "email" => vec![SuggestedAction {
+            kind: "email".to_string(),
+            label: "Compose Email".to_string(),
+            value: mailto_url(text.trim(), None, None),
+        }],
+        _ => Vec::new(),
+    }
+}
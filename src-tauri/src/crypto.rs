@@ -3,9 +3,14 @@ use aes_gcm::{
     aead::{Aead, AeadCore, KeyInit, OsRng},
     Aes256Gcm, Key, Nonce,
 };
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
 use std::fs;
 use std::path::Path;
 
+// 配置文件中加密保存的密钥带此前缀，没有前缀的是旧版本留下的明文
+const SECRET_PREFIX: &str = "enc:";
+
 pub struct Crypto {
     cipher: Aes256Gcm,
 }
@@ -27,6 +32,23 @@ impl Crypto {
         }
     }
 
+    /// 用 HKDF-SHA256 从共享密钥派生加密密钥；salt 每条消息随机生成，info 区分用途和通信双方
+    pub fn derive(secret: &[u8], salt: &[u8], info: &[u8]) -> Self {
+        let mut extract =
+            <Hmac<Sha256> as Mac>::new_from_slice(salt).expect("HMAC accepts keys of any size");
+        extract.update(secret);
+        let prk = extract.finalize().into_bytes();
+
+        let mut expand =
+            <Hmac<Sha256> as Mac>::new_from_slice(&prk).expect("HMAC accepts keys of any size");
+        expand.update(info);
+        expand.update(&[1]);
+        let key = expand.finalize().into_bytes();
+        Self {
+            cipher: Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_slice())),
+        }
+    }
+
     pub fn encrypt(&self, plaintext: &str) -> Result<String, String> {
         let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
         let ciphertext = self
@@ -62,4 +84,29 @@ impl Crypto {
 
         String::from_utf8(plaintext_bytes).map_err(|e| e.to_string())
     }
+
+    /// 加密要写入配置文件的密钥；空值和已加密的值原样返回
+    pub fn seal_secret(&self, value: &str) -> String {
+        if value.is_empty() || value.starts_with(SECRET_PREFIX) {
+            return value.to_string();
+        }
+        match self.encrypt(value) {
+            Ok(encrypted) => format!("{}{}", SECRET_PREFIX, encrypted),
+            Err(e) => {
+                log::error!("Failed to encrypt secret: {}", e);
+                String::new()
+            }
+        }
+    }
+
+    /// 取出配置中的密钥明文；旧版本的明文值原样返回，无法解密时返回空
+    pub fn open_secret(&self, value: &str) -> String {
+        let Some(encrypted) = value.strip_prefix(SECRET_PREFIX) else {
+            return value.to_string();
+        };
+        self.decrypt(encrypted).unwrap_or_else(|e| {
+            log::error!("Failed to decrypt secret: {}", e);
+            String::new()
+        })
+    }
 }
@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+use crate::command_safety::is_terminal_app;
+use crate::keystroke::{self, Key};
+use crate::state::AppState;
+use crate::utils::{apply_paste_rules, write_to_clipboard_with};
+
+// 写入剪贴板 / 撤销后等待前台应用处理完，再发送下一个按键
+const KEY_DELAY: Duration = Duration::from_millis(50);
+
+// 同一时间只执行一次循环粘贴
+static PASTE_LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// 按配置重新注册循环粘贴快捷键，留空不注册，不抢占其他快捷键
+pub fn sync_shortcut(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let (main, accelerator) = {
+        let config = state.config.lock().unwrap();
+        (config.shortcut.clone(), config.cycle_paste_shortcut.clone())
+    };
+
+    let manager = app.global_shortcut();
+    let Ok(mut registered) = state.cycle_paste_shortcut.lock() else {
+        return;
+    };
+    if let Some(shortcut) = registered.take() {
+        let _ = manager.unregister(shortcut);
+    }
+    if accelerator.trim().is_empty() {
+        return;
+    }
+
+    let shortcut = match crate::shortcut::parse(&accelerator) {
+        Ok(shortcut) => shortcut,
+        Err(e) => {
+            log::warn!("Invalid cycle paste shortcut {}: {}", accelerator, e);
+            return;
+        }
+    };
+    if crate::shortcut::parse(&main).ok() == Some(shortcut)
+        || crate::quick_paste::item_for_shortcut(app, &shortcut).is_some()
+        || crate::clipboard_cursor::direction_for_shortcut(app, &shortcut).is_some()
+    {
+        log::warn!("Cycle paste shortcut {} already in use", accelerator);
+        return;
+    }
+    match manager.register(shortcut) {
+        Ok(()) => *registered = Some(shortcut),
+        Err(e) => log::warn!(
+            "Failed to register cycle paste shortcut {}: {}",
+            accelerator,
+            e
+        ),
+    }
+}
+
+pub fn is_shortcut(app: &tauri::AppHandle, shortcut: &Shortcut) -> bool {
+    let state = app.state::<AppState>();
+    let registered = state.cycle_paste_shortcut.lock().unwrap();
+    registered.as_ref() == Some(shortcut)
+}
+
+/// 按一次粘贴最新一条；在时间窗口内再按，撤销刚才的粘贴并换成更早的一条，
+/// 到最早一条后回到最新一条。连续按下时依次执行，各次的撤销、写入和粘贴按键不会交错
+pub fn trigger(app: &tauri::AppHandle) {
+    let pressed_at = Instant::now();
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let _guard = PASTE_LOCK.lock().await;
+        let state = app.state::<AppState>();
+        let window = Duration::from_millis(state.config.lock().unwrap().cycle_paste_window_ms);
+        let (offset, replace) = {
+            let mut last = state.cycle_paste.lock().unwrap();
+            let step = match *last {
+                Some((offset, at)) if pressed_at.saturating_duration_since(at) <= window => {
+                    (offset + 1, true)
+                }
+                _ => (0, false),
+            };
+            *last = Some((step.0, pressed_at));
+            step
+        };
+        if let Err(e) = paste_at(&app, offset, replace).await {
+            log::error!("Cycle paste failed: {}", e);
+            state.cycle_paste.lock().unwrap().take();
+        }
+    });
+}
+
+async fn paste_at(app: &tauri::AppHandle, offset: usize, replace: bool) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    // 终端里 Ctrl+Z 会挂起前台任务，Ctrl+V 也不是粘贴，无法撤销上一次粘贴
+    let target = active_win_pos_rs::get_active_window().ok();
+    let target_app = target.as_ref().map(|active| active.app_name.clone());
+    let target_path = target
+        .as_ref()
+        .map(|active| active.process_path.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let in_terminal = is_terminal_app(
+        &state.config.lock().unwrap().terminal_apps,
+        target_app.as_deref().unwrap_or(""),
+        &target_path,
+    );
+    if replace && in_terminal {
+        return Err("Cycle paste cannot replace a paste in a terminal".to_string());
+    }
+
+    let (offset, id) = match state
+        .db
+        .get_id_at_offset(offset)
+        .map_err(|e| e.to_string())?
+    {
+        Some(id) => (offset, id),
+        None => {
+            let id = state
+                .db
+                .get_id_at_offset(0)
+                .map_err(|e| e.to_string())?
+                .ok_or("History is empty")?;
+            if let Some((last, _)) = state.cycle_paste.lock().unwrap().as_mut() {
+                *last = 0;
+            }
+            (0, id)
+        }
+    };
+    let mut item = state.db.get_item(id).map_err(|e| e.to_string())?;
+
+    // 和其他粘贴方式一样应用粘贴规则，危险命令粘贴到终端前需要确认
+    let include_raw_formats = apply_paste_rules(
+        app,
+        &mut item,
+        target_app.as_deref().unwrap_or(""),
+        &target_path,
+    );
+    if item.kind == "text" {
+        crate::commands::confirm_dangerous_paste(
+            app,
+            item.id,
+            &item.content,
+            target_app,
+            &target_path,
+        )
+        .map_err(String::from)?;
+    }
+
+    if replace {
+        keystroke::press(Key::Undo)?;
+        tokio::time::sleep(KEY_DELAY).await;
+    }
+    // 自己写入的内容带有标记，监听线程不会把它当作新的复制
+    write_to_clipboard_with(app, &item, include_raw_formats)?;
+    tokio::time::sleep(KEY_DELAY).await;
+    keystroke::press(if in_terminal {
+        Key::TerminalPaste
+    } else {
+        Key::Paste
+    })?;
+
+    // 剪贴板停留在这一条，后退 / 前进从这里继续
+    *state.clipboard_cursor.lock().unwrap() = offset;
+    if !replace {
+        crate::usage::record(app, crate::usage::PASTE);
+    }
+    Ok(())
+}
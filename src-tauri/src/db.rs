@@ -1,19 +1,230 @@
+use crate::command_safety::is_dangerous_command;
 use crate::crypto::Crypto;
-use crate::models::{ClipboardItem, Collection};
+use crate::models::{
+    ActivityCalendar, CleanupCandidate, ClipboardItem, Collection, CollectionCount, DayCount,
+    HistoryFilter, ItemFilter, ItemMetrics, ItemOccurrence, ItemVersion, QueryStats, SimilarItem,
+    SuggestedAction, TypeCount, TypeUsage, UsageCount,
+};
+use crate::similarity;
 use chrono::Local;
 use regex::Regex;
 use rusqlite::{functions::FunctionFlags, params, Connection, OptionalExtension, Result};
+use std::collections::HashMap;
+use std::ops::{Deref, DerefMut};
 use std::path::Path;
-use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+/// 把 "YYYY-MM-DD HH:MM:SS[.fff]" 形式的本地时间转换为毫秒时间戳
+fn timestamp_millis(timestamp: &str) -> i64 {
+    chrono::NaiveDateTime::parse_from_str(timestamp, "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .and_then(|naive| naive.and_local_timezone(Local).earliest())
+        .map(|dt| dt.timestamp_millis())
+        .unwrap_or_else(|| Local::now().timestamp_millis())
+}
+
+// 每个条目最多保留的复制记录数，只保留最新的
+const MAX_OCCURRENCES_PER_ITEM: usize = 100;
+
+// 粘贴记录只用于推荐，保留的天数
+const PASTE_EVENT_RETENTION_DAYS: i64 = 90;
+
+const ITEM_COLUMNS: &str = "id, content, kind, timestamp, is_sensitive, is_pinned, source_app, data_type, collection_id, note, html_content, byte_size, char_count, line_count, image_width, image_height, suggested_actions, parent_id, paste_count, timestamp_ms, ocr_text, is_dangerous, is_truncated, accelerator, is_locked, copy_count, origin_device";
+
+// 不属于免清理收藏夹的条目（collection_id 为 NULL 时 NOT IN 的结果也是 NULL，需要单独判断）
+const NOT_RETENTION_EXEMPT: &str = "(collection_id IS NULL OR collection_id NOT IN (SELECT id FROM collections WHERE exempt_from_retention = 1))";
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+/// merge_item 的结果：merged 为 true 时合并到了已有条目
+pub struct MergedItem {
+    pub id: i64,
+    pub merged: bool,
+    // 合并时为已有条目所在的收藏夹，调用方据此决定是否移动它
+    pub collection_id: Option<i64>,
+    pub pruned: Vec<ClipboardItem>,
+}
+
+type MergeTarget = (i64, i64, Option<String>, Option<String>, bool, Option<i64>);
+
+/// 合并两条备注，相同或已包含时不重复
+fn merge_notes(existing: Option<String>, imported: Option<String>) -> Option<String> {
+    let imported = imported.filter(|n| !n.trim().is_empty());
+    match (existing.filter(|n| !n.trim().is_empty()), imported) {
+        (Some(existing), Some(imported)) if !existing.contains(imported.trim()) => {
+            Some(format!("{}\n\n{}", existing, imported))
+        }
+        (Some(existing), _) => Some(existing),
+        (None, imported) => imported,
+    }
+}
+
+// 条目元数据中保存标签列表（JSON 字符串数组）的 key，标签不影响内置的 data_type 分类
+pub const TAGS_META_KEY: &str = "tags";
+
+/// 把标签并入条目已有的标签列表，已有的标签不重复添加
+fn add_item_tags(conn: &Connection, item_id: i64, tags: &[String]) -> Result<()> {
+    if tags.is_empty() {
+        return Ok(());
+    }
+    let existing: Option<String> = conn
+        .query_row(
+            "SELECT value FROM item_metadata WHERE item_id = ?1 AND key = ?2",
+            params![item_id, TAGS_META_KEY],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let mut merged: Vec<String> = existing
+        .and_then(|value| serde_json::from_str(&value).ok())
+        .unwrap_or_default();
+    let before = merged.len();
+    for tag in tags {
+        if !merged.contains(tag) {
+            merged.push(tag.clone());
+        }
+    }
+    if merged.len() == before {
+        return Ok(());
+    }
+    conn.execute(
+        "INSERT INTO item_metadata (item_id, key, value) VALUES (?1, ?2, ?3)
+         ON CONFLICT (item_id, key) DO UPDATE SET value = excluded.value",
+        params![
+            item_id,
+            TAGS_META_KEY,
+            serde_json::to_string(&merged).unwrap_or_default()
+        ],
+    )?;
+    Ok(())
+}
+
+/// 对锁定条目执行编辑 / 删除时返回的错误
+fn locked_error() -> rusqlite::Error {
+    rusqlite::Error::UserFunctionError(Box::new(crate::error::ItemLocked))
+}
+
+// 只读连接数量；WAL 模式下读取不会被写入阻塞
+const READ_POOL_SIZE: usize = 4;
+const STATEMENT_CACHE_CAPACITY: usize = 64;
+// 超过这个耗时的数据库操作记录警告日志
+const SLOW_QUERY: Duration = Duration::from_millis(200);
+// 每个条目保留的历史版本数
+const MAX_ITEM_VERSIONS: i64 = 50;
+
+#[derive(Default)]
+struct Timing {
+    count: u64,
+    total: Duration,
+    max: Duration,
+}
+
+/// 持有连接期间计时，释放时把耗时计入对应操作的统计
+struct TimedConn<'a> {
+    conn: MutexGuard<'a, Connection>,
+    name: &'static str,
+    start: Instant,
+    timings: &'a Mutex<HashMap<&'static str, Timing>>,
+}
+
+impl Deref for TimedConn<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        &self.conn
+    }
+}
+
+impl DerefMut for TimedConn<'_> {
+    fn deref_mut(&mut self) -> &mut Connection {
+        &mut self.conn
+    }
+}
+
+impl Drop for TimedConn<'_> {
+    fn drop(&mut self) {
+        let elapsed = self.start.elapsed();
+        if elapsed > SLOW_QUERY {
+            log::warn!("Slow database operation {}: {:?}", self.name, elapsed);
+        }
+        if let Ok(mut timings) = self.timings.lock() {
+            let timing = timings.entry(self.name).or_default();
+            timing.count += 1;
+            timing.total += elapsed;
+            timing.max = timing.max.max(elapsed);
+        }
+    }
+}
+
+/// 打开连接并做通用设置：WAL、忙等待、语句缓存和 REGEXP 函数
+fn open_connection(path: &Path) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE_CAPACITY);
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+
+    // Add REGEXP function
+    conn.create_scalar_function(
+        "REGEXP",
+        2,
+        FunctionFlags::SQLITE_DETERMINISTIC,
+        move |ctx| {
+            let regex_s = ctx.get::<String>(0)?;
+            // Handle nullable text column (like 'note')
+            let text = ctx.get::<Option<String>>(1)?.unwrap_or_default();
+
+            let regex = Regex::new(&regex_s).map_err(|e| {
+                log::error!("Invalid regex '{}': {}", regex_s, e);
+                rusqlite::Error::UserFunctionError(Box::new(e))
+            })?;
+
+            Ok(regex.is_match(&text))
+        },
+    )?;
+    Ok(conn)
+}
+
+/// 删除收藏夹时如何处理其中的条目
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollectionDeleteMode {
+    // 移出收藏夹，归入未分类
+    Uncategorize,
+    DeleteItems,
+    MoveTo(i64),
+}
+
+impl CollectionDeleteMode {
+    /// mode 为 uncategorize / delete_items / move，move 需要 target_id
+    pub fn parse(mode: Option<&str>, target_id: Option<i64>) -> std::result::Result<Self, String> {
+        match mode.unwrap_or("uncategorize") {
+            "uncategorize" => Ok(Self::Uncategorize),
+            "delete_items" => Ok(Self::DeleteItems),
+            "move" => target_id
+                .map(Self::MoveTo)
+                .ok_or_else(|| "Missing target collection".to_string()),
+            other => Err(format!("Unknown collection delete mode: {}", other)),
+        }
+    }
+}
 
 pub struct Database {
+    // 唯一的写连接，也用于需要读到自己刚写入内容的操作
     conn: Mutex<Connection>,
+    // 只读连接池，搜索和列表查询不必等待写入
+    readers: Vec<Mutex<Connection>>,
+    next_reader: AtomicUsize,
+    timings: Mutex<HashMap<&'static str, Timing>>,
     crypto: Arc<Crypto>,
+    // 去重时把只有空白或换行符不同的文本视为同一条
+    dedup_ignore_whitespace: AtomicBool,
 }
 
 impl Database {
     pub fn new<P: AsRef<Path>>(path: P, crypto: Arc<Crypto>) -> Result<Self> {
-        let mut conn = Connection::open(path)?;
+        let path = path.as_ref();
+        let mut conn = open_connection(path)?;
 
         let tx = conn.transaction()?;
         let version: i32 = tx.query_row("PRAGMA user_version", [], |row| row.get(0))?;
@@ -85,337 +296,937 @@ impl Database {
             tx.execute("PRAGMA user_version = 6", [])?;
         }
 
-        tx.commit()?;
-
-        // Add REGEXP function
-        conn.create_scalar_function(
-            "REGEXP",
-            2,
-            FunctionFlags::SQLITE_DETERMINISTIC,
-            move |ctx| {
-                let regex_s = ctx.get::<String>(0)?;
-                // Handle nullable text column (like 'note')
-                let text = ctx.get::<Option<String>>(1)?.unwrap_or_default();
-
-                // log::info!("REGEXP called: pattern='{}', text='{}'", regex_s, text);
-
-                let regex = Regex::new(&regex_s).map_err(|e| {
-                    log::error!("Invalid regex '{}': {}", regex_s, e);
-                    rusqlite::Error::UserFunctionError(Box::new(e))
-                })?;
-
-                Ok(regex.is_match(&text))
-            },
-        )?;
-
-        Ok(Self {
-            conn: Mutex::new(conn),
-            crypto,
-        })
-    }
-
-    pub fn get_history(
-        &self,
-        page: usize,
-        page_size: usize,
-        query: Option<String>,
-        search_regex: bool,
-        search_case_sensitive: bool,
-        collection_id: Option<i64>,
-    ) -> Result<Vec<ClipboardItem>> {
-        let conn = self.conn.lock().unwrap();
-        let offset = (page - 1) * page_size;
-
-        let mut sql = String::from("SELECT id, content, kind, timestamp, is_sensitive, is_pinned, source_app, data_type, collection_id, note, html_content FROM history WHERE 1=1");
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+        if version < 7 {
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN byte_size INTEGER NOT NULL DEFAULT 0",
+                [],
+            );
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN char_count INTEGER NOT NULL DEFAULT 0",
+                [],
+            );
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN line_count INTEGER NOT NULL DEFAULT 0",
+                [],
+            );
+            let _ = tx.execute("ALTER TABLE history ADD COLUMN image_width INTEGER", []);
+            let _ = tx.execute("ALTER TABLE history ADD COLUMN image_height INTEGER", []);
+            // Backfill plain text rows; encrypted and image rows are measured on next write
+            tx.execute(
+                "UPDATE history SET byte_size = LENGTH(CAST(content AS BLOB)), char_count = LENGTH(content), line_count = MAX(1, LENGTH(content) - LENGTH(REPLACE(content, char(10), '')) + (SUBSTR(content, -1) <> char(10))) WHERE kind = 'text' AND is_sensitive = 0",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 7", [])?;
+        }
 
-        if let Some(q) = query {
-            if !q.is_empty() {
-                if search_regex {
-                    sql.push_str(" AND (content REGEXP ? OR note REGEXP ?)");
-                    // If case insensitive, we prepend (?i) flag to the regex string.
-                    // This flag works in Rust regex crate which we used in create_scalar_function.
-                    let final_query = if search_case_sensitive {
-                        q.clone()
-                    } else {
-                        format!("(?i){}", q)
-                    };
-                    params.push(Box::new(final_query.clone()));
-                    params.push(Box::new(final_query));
-                } else {
-                    if search_case_sensitive {
-                        // SQLite LIKE is case-insensitive by default for ASCII characters.
-                        // To make it case-sensitive, we can use GLOB which is case-sensitive (and uses * instead of %),
-                        // OR we can use the `PRAGMA case_sensitive_like = ON` command (but that is connection wide),
-                        // OR we can simply use the binary comparison for exact match or INSTR for substrings,
-                        // BUT for pattern matching usually `GLOB` is the way.
-                        // However, GLOB uses * and ?, not %. So we need to reformat the pattern or just use INSTR > 0 for simpler specific contains check.
-                        // Since `q` here is a substring (like %q%), INSTR(content, q) > 0 does the job and is case-sensitive by default (if not explicitly COLLATE NOCASE).
-                        // Wait, sqlite INSTR IS case-sensitive by default? No, it depends on collation?
-                        // Actually, GLOB is the standard way for case-sensitive pattern matching in SQLite.
-                        // wildcard: * matches any sequence, ? matches any single char.
+        if version < 8 {
+            let _ = tx.execute("ALTER TABLE history ADD COLUMN suggested_actions TEXT", []);
+            tx.execute("PRAGMA user_version = 8", [])?;
+        }
 
-                        sql.push_str(" AND (content GLOB ? OR note GLOB ?)");
-                        let pattern = format!("*{}*", q); // Using * for GLOB
-                        params.push(Box::new(pattern.clone()));
-                        params.push(Box::new(pattern));
-                    } else {
-                        sql.push_str(" AND (content LIKE ? OR note LIKE ?)");
-                        let pattern = format!("%{}%", q);
-                        params.push(Box::new(pattern.clone()));
-                        params.push(Box::new(pattern));
-                    }
-                }
-            }
+        if version < 9 {
+            // Items derived from another item (e.g. AI summaries) point back to it
+            let _ = tx.execute("ALTER TABLE history ADD COLUMN parent_id INTEGER", []);
+            tx.execute("PRAGMA user_version = 9", [])?;
         }
 
-        if let Some(cid) = collection_id {
-            sql.push_str(" AND collection_id = ?");
-            params.push(Box::new(cid));
+        if version < 10 {
+            // SimHash for text / dHash for images, filled lazily for older rows
+            let _ = tx.execute("ALTER TABLE history ADD COLUMN fingerprint INTEGER", []);
+            tx.execute("PRAGMA user_version = 10", [])?;
         }
 
-        sql.push_str(" ORDER BY is_pinned DESC, timestamp DESC LIMIT ? OFFSET ?");
-        params.push(Box::new(page_size));
-        params.push(Box::new(offset));
+        if version < 11 {
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN paste_count INTEGER NOT NULL DEFAULT 0",
+                [],
+            );
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS paste_events (
+                    item_id INTEGER NOT NULL,
+                    target_app TEXT,
+                    pasted_at TEXT NOT NULL
+                )",
+                [],
+            )?;
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_paste_events_app ON paste_events (target_app, item_id)",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 11", [])?;
+        }
 
-        let mut stmt = conn.prepare(&sql)?;
+        if version < 12 {
+            // Second-resolution strings reorder items copied within the same second,
+            // so sort by epoch millis and keep the string for display only.
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN timestamp_ms INTEGER NOT NULL DEFAULT 0",
+                [],
+            );
+            tx.execute(
+                "UPDATE history SET timestamp_ms = CAST(ROUND((julianday(timestamp, 'utc') - 2440587.5) * 86400000) AS INTEGER)",
+                [],
+            )?;
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_history_timestamp_ms ON history (is_pinned, timestamp_ms)",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 12", [])?;
+        }
 
-        // Convert params to references for query_map
-        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+        if version < 13 {
+            // OCR text of image items, searchable alongside content and note
+            let _ = tx.execute("ALTER TABLE history ADD COLUMN ocr_text TEXT", []);
+            tx.execute("PRAGMA user_version = 13", [])?;
+        }
 
-        let rows = stmt.query_map(params_refs.as_slice(), |row| {
-            let id: i64 = row.get(0)?;
-            let content: String = row.get(1)?;
-            let kind: String = row.get(2)?;
-            let timestamp: String = row.get(3)?;
-            let is_sensitive: bool = row.get(4)?;
-            let is_pinned: bool = row.get(5)?;
-            let source_app: Option<String> = row.get(6)?;
-            let data_type: String = row.get(7)?;
-            let collection_id: Option<i64> = row.get(8)?;
-            let note: Option<String> = row.get(9)?;
-            let html_content: Option<String> = row.get(10)?;
-
-            let final_content = if is_sensitive && kind == "text" {
-                self.crypto.decrypt(&content).unwrap_or(content)
-            } else {
-                content
-            };
+        if version < 14 {
+            // App-specific clipboard formats stored alongside an item, restored on paste
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS raw_formats (
+                    item_id INTEGER NOT NULL,
+                    format TEXT NOT NULL,
+                    data BLOB NOT NULL,
+                    PRIMARY KEY (item_id, format)
+                )",
+                [],
+            )?;
+            tx.execute(
+                "CREATE TRIGGER IF NOT EXISTS history_delete_raw_formats AFTER DELETE ON history
+                 BEGIN DELETE FROM raw_formats WHERE item_id = OLD.id; END",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 14", [])?;
+        }
 
-            let final_html = if let Some(html) = html_content {
-                if is_sensitive {
-                    Some(self.crypto.decrypt(&html).unwrap_or(html))
-                } else {
-                    Some(html)
-                }
-            } else {
-                None
+        if version < 15 {
+            // Text that looks like a risky shell command, confirmed before pasting into terminals
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN is_dangerous BOOLEAN NOT NULL DEFAULT 0",
+                [],
+            );
+            let dangerous: Vec<i64> = {
+                let mut stmt = tx.prepare(
+                    "SELECT id, content FROM history WHERE kind = 'text' AND is_sensitive = 0",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?;
+                rows.filter_map(|row| row.ok())
+                    .filter(|(_, content)| is_dangerous_command(content))
+                    .map(|(id, _)| id)
+                    .collect()
             };
-
-            Ok(ClipboardItem {
-                id: Some(id),
-                content: final_content,
-                kind,
-                timestamp,
-                is_sensitive,
-                is_pinned,
-                source_app,
-                data_type,
-                collection_id,
-                note,
-                html_content: final_html,
-            })
-        })?;
-
-        let mut items = Vec::new();
-        for row in rows {
-            items.push(row?);
+            for id in dangerous {
+                tx.execute(
+                    "UPDATE history SET is_dangerous = 1 WHERE id = ?1",
+                    params![id],
+                )?;
+            }
+            tx.execute("PRAGMA user_version = 15", [])?;
         }
-        Ok(items)
-    }
-
-    pub fn insert_item(&self, item: &ClipboardItem, max_size: usize) -> Result<Vec<ClipboardItem>> {
-        let conn = self.conn.lock().unwrap();
-        let mut pruned_items = Vec::new();
 
-        let content_to_store = if item.is_sensitive && item.kind == "text" {
-            self.crypto
-                .encrypt(&item.content)
-                .unwrap_or(item.content.clone())
-        } else {
-            item.content.clone()
-        };
+        if version < 16 {
+            // Text cut to the configured maximum length before being recorded
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN is_truncated BOOLEAN NOT NULL DEFAULT 0",
+                [],
+            );
+            tx.execute("PRAGMA user_version = 16", [])?;
+        }
 
-        let html_to_store = if let Some(html) = &item.html_content {
-            if item.is_sensitive {
-                Some(self.crypto.encrypt(html).unwrap_or(html.clone()))
-            } else {
-                Some(html.clone())
+        if version < 17 {
+            // Hash of text ignoring surrounding whitespace and line endings, for loose dedup
+            let _ = tx.execute("ALTER TABLE history ADD COLUMN normalized_hash INTEGER", []);
+            let hashes: Vec<(i64, i64)> = {
+                let mut stmt = tx.prepare(
+                    "SELECT id, content FROM history WHERE kind = 'text' AND is_sensitive = 0",
+                )?;
+                let rows = stmt.query_map([], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })?;
+                rows.filter_map(|row| row.ok())
+                    .map(|(id, content)| (id, similarity::whitespace_hash(&content) as i64))
+                    .collect()
+            };
+            for (id, hash) in hashes {
+                tx.execute(
+                    "UPDATE history SET normalized_hash = ?1 WHERE id = ?2",
+                    params![hash, id],
+                )?;
             }
-        } else {
-            None
-        };
-
-        // Deduplicate: Update timestamp, source_app and html_content if exists
-        let updated_count = conn.execute(
-            "UPDATE history SET timestamp = ?1, source_app = ?2, html_content = ?3 WHERE content = ?4 AND kind = ?5",
-            params![item.timestamp, item.source_app, html_to_store, content_to_store, item.kind],
-        )?;
-
-        if updated_count == 0 {
-            // Insert new item
-            conn.execute(
-                "INSERT INTO history (content, kind, timestamp, is_sensitive, is_pinned, source_app, data_type, collection_id, note, html_content) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
-                params![
-                    content_to_store,
-                    item.kind,
-                    item.timestamp,
-                    item.is_sensitive,
-                    item.is_pinned,
-                    item.source_app,
-                    item.data_type,
-                    item.collection_id,
-                    item.note,
-                    html_to_store
-                ],
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_history_normalized_hash ON history (normalized_hash)",
+                [],
             )?;
+            tx.execute("PRAGMA user_version = 17", [])?;
         }
 
-        // Prune if exceeding max_size
-        let count: usize = conn.query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))?;
-        if count > max_size {
-            let delete_count = count - max_size;
-
-            // Fetch items to be deleted first (oldest timestamp, NOT pinned)
-            let mut stmt = conn.prepare(&format!(
-                "SELECT content, kind, timestamp, is_sensitive, is_pinned, source_app, data_type, collection_id, note, html_content FROM history WHERE is_pinned = 0 ORDER BY timestamp ASC LIMIT {}",
-                delete_count
-            ))?;
-
-            let rows = stmt.query_map([], |row| {
-                let content: String = row.get(0)?;
-                let kind: String = row.get(1)?;
-                let timestamp: String = row.get(2)?;
-                let is_sensitive: bool = row.get(3)?;
-                let is_pinned: bool = row.get(4)?;
-                let source_app: Option<String> = row.get(5)?;
-                let data_type: String = row.get(6)?;
-                let collection_id: Option<i64> = row.get(7)?;
-                let note: Option<String> = row.get(8)?;
-                let html_content: Option<String> = row.get(9)?;
-
-                let final_content = if is_sensitive && kind == "text" {
-                    self.crypto.decrypt(&content).unwrap_or(content)
-                } else {
-                    content
-                };
-
-                let final_html = if let Some(html) = html_content {
-                    if is_sensitive {
-                        Some(self.crypto.decrypt(&html).unwrap_or(html))
-                    } else {
-                        Some(html)
-                    }
-                } else {
-                    None
-                };
-
-                Ok(ClipboardItem {
-                    id: None,
-                    content: final_content,
-                    kind,
-                    timestamp,
-                    is_sensitive,
-                    is_pinned,
-                    source_app,
-                    data_type,
-                    collection_id,
-                    note,
-                    html_content: final_html,
-                })
-            })?;
+        if version < 18 {
+            // Global shortcut of a pinned item, listed in the tray Quick Paste submenu
+            let _ = tx.execute("ALTER TABLE history ADD COLUMN accelerator TEXT", []);
+            tx.execute("PRAGMA user_version = 18", [])?;
+        }
 
-            for row in rows {
-                if let Ok(item) = row {
-                    pruned_items.push(item);
-                }
-            }
+        if version < 19 {
+            // Locked items can't be edited, deleted or pruned until unlocked
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN is_locked BOOLEAN NOT NULL DEFAULT 0",
+                [],
+            );
+            tx.execute("PRAGMA user_version = 19", [])?;
+        }
 
-            // Delete them
-            conn.execute(
-                &format!(
-                    "DELETE FROM history WHERE id IN (SELECT id FROM history WHERE is_pinned = 0 AND collection_id IS NULL ORDER BY timestamp ASC LIMIT {})",
-                    delete_count
-                ),
+        if version < 20 {
+            // Arbitrary key/value metadata attached by plugins, rules and AI actions
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS item_metadata (
+                    item_id INTEGER NOT NULL,
+                    key TEXT NOT NULL,
+                    value TEXT NOT NULL,
+                    PRIMARY KEY (item_id, key)
+                )",
+                [],
+            )?;
+            tx.execute(
+                "CREATE TRIGGER IF NOT EXISTS history_delete_item_metadata AFTER DELETE ON history
+                 BEGIN DELETE FROM item_metadata WHERE item_id = OLD.id; END",
                 [],
             )?;
+            tx.execute("PRAGMA user_version = 20", [])?;
         }
 
-        Ok(pruned_items)
-    }
-
-    pub fn delete_item(&self, index: usize) -> Result<Option<ClipboardItem>> {
-        // Index is from the frontend, which sees the list in DESC order (latest first).
-        // So index 0 is the latest item (highest ID).
-        // We need to find the ID of the item at that offset.
-        let conn = self.conn.lock().unwrap();
+        if version < 21 {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS item_versions (
+                    item_id INTEGER NOT NULL,
+                    version INTEGER NOT NULL,
+                    content TEXT NOT NULL,
+                    data_type TEXT NOT NULL,
+                    note TEXT,
+                    html_content TEXT,
+                    created_at TEXT NOT NULL,
+                    PRIMARY KEY (item_id, version)
+                )",
+                [],
+            )?;
+            tx.execute(
+                "CREATE TRIGGER IF NOT EXISTS history_delete_item_versions AFTER DELETE ON history
+                 BEGIN DELETE FROM item_versions WHERE item_id = OLD.id; END",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 21", [])?;
+        }
 
-        // Get the ID and details of the item at the specified offset
-        let item: Option<(i64, ClipboardItem)> = conn
-            .query_row(
-                "SELECT id, content, kind, timestamp, is_sensitive, is_pinned, source_app, data_type, collection_id, note, html_content FROM history ORDER BY is_pinned DESC, timestamp DESC LIMIT 1 OFFSET ?1",
-                params![index],
-                |row| {
-                    let id: i64 = row.get(0)?;
-                    let content: String = row.get(1)?;
-                    let kind: String = row.get(2)?;
-                    let timestamp: String = row.get(3)?;
-                    let is_sensitive: bool = row.get(4)?;
-                    let is_pinned: bool = row.get(5)?;
-                    let source_app: Option<String> = row.get(6)?;
-                    let data_type: String = row.get(7)?;
-                    let collection_id: Option<i64> = row.get(8)?;
-                    let note: Option<String> = row.get(9)?;
-                    let html_content: Option<String> = row.get(10)?;
-
-                    let final_content = if is_sensitive && kind == "text" {
-                        self.crypto.decrypt(&content).unwrap_or(content)
+        if version < 22 {
+            let _ = tx.execute(
+                "ALTER TABLE history ADD COLUMN copy_count INTEGER NOT NULL DEFAULT 1",
+                [],
+            );
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS item_occurrences (
+                    item_id INTEGER NOT NULL,
+                    source_app TEXT,
+                    copied_at TEXT NOT NULL,
+                    copied_at_ms INTEGER NOT NULL
+                )",
+                [],
+            )?;
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_item_occurrences_item ON item_occurrences (item_id, copied_at_ms)",
+                [],
+            )?;
+            // 已有条目只知道最近一次复制
+            tx.execute(
+                "INSERT INTO item_occurrences (item_id, source_app, copied_at, copied_at_ms)
+                 SELECT id, source_app, timestamp, timestamp_ms FROM history",
+                [],
+            )?;
+            tx.execute(
+                "CREATE TRIGGER IF NOT EXISTS history_delete_item_occurrences AFTER DELETE ON history
+                 BEGIN DELETE FROM item_occurrences WHERE item_id = OLD.id; END",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 22", [])?;
+        }
+
+        if version < 23 {
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS paste_stack (
+                    position INTEGER PRIMARY KEY,
+                    is_sensitive BOOLEAN NOT NULL DEFAULT 0,
+                    item TEXT NOT NULL
+                )",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 23", [])?;
+        }
+
+        if version < 24 {
+            // 不参与清理的收藏夹（如"参考资料"永不过期）
+            tx.execute(
+                "ALTER TABLE collections ADD COLUMN exempt_from_retention BOOLEAN NOT NULL DEFAULT 0",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 24", [])?;
+        }
+
+        if version < 25 {
+            // 本地使用统计：按天累计各功能的使用次数，不记录内容
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS usage_counts (
+                    day TEXT NOT NULL,
+                    event TEXT NOT NULL,
+                    count INTEGER NOT NULL DEFAULT 0,
+                    PRIMARY KEY (day, event)
+                )",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 25", [])?;
+        }
+
+        if version < 26 {
+            // 同步来的条目记录来源设备，本机记录的条目为 NULL
+            tx.execute("ALTER TABLE history ADD COLUMN origin_device TEXT", [])?;
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_history_origin_device ON history (origin_device)",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 26", [])?;
+        }
+
+        if version < 27 {
+            // 导入 / 同步时合并到已有条目的其他来源设备
+            tx.execute(
+                "CREATE TABLE IF NOT EXISTS item_origins (
+                    item_id INTEGER NOT NULL,
+                    device TEXT NOT NULL,
+                    PRIMARY KEY (item_id, device)
+                )",
+                [],
+            )?;
+            tx.execute(
+                "CREATE TRIGGER IF NOT EXISTS history_delete_item_origins AFTER DELETE ON history
+                 BEGIN DELETE FROM item_origins WHERE item_id = OLD.id; END",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 27", [])?;
+        }
+
+        if version < 28 {
+            // 文本指纹改用稳定的哈希，旧值作废，查找相似条目时重新计算
+            tx.execute(
+                "UPDATE history SET fingerprint = NULL WHERE kind = 'text'",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 28", [])?;
+        }
+
+        if version < 29 {
+            // OCR 失败的次数；失败时 ocr_text 保持 NULL，超过次数后补做 OCR 不再重试
+            tx.execute(
+                "ALTER TABLE history ADD COLUMN ocr_attempts INTEGER NOT NULL DEFAULT 0",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 29", [])?;
+        }
+
+        if version < 30 {
+            // 与 ItemMetrics 保持一致：末尾换行不算新的一行，空文本记为 1 行
+            tx.execute(
+                "UPDATE history SET line_count = MAX(1, LENGTH(content) - LENGTH(REPLACE(content, char(10), '')) + (SUBSTR(content, -1) <> char(10))) WHERE kind = 'text' AND is_sensitive = 0",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 30", [])?;
+        }
+
+        if version < 31 {
+            // 粘贴记录随条目一起删除，并清理已删除条目留下的记录
+            tx.execute(
+                "DELETE FROM paste_events WHERE item_id NOT IN (SELECT id FROM history)",
+                [],
+            )?;
+            tx.execute(
+                "CREATE TRIGGER IF NOT EXISTS history_delete_paste_events AFTER DELETE ON history
+                 BEGIN DELETE FROM paste_events WHERE item_id = OLD.id; END",
+                [],
+            )?;
+            tx.execute(
+                "CREATE INDEX IF NOT EXISTS idx_paste_events_time ON paste_events (pasted_at)",
+                [],
+            )?;
+            tx.execute("PRAGMA user_version = 31", [])?;
+        }
+
+        tx.commit()?;
+
+        let readers = (0..READ_POOL_SIZE)
+            .map(|_| open_connection(path).map(Mutex::new))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self {
+            conn: Mutex::new(conn),
+            readers,
+            next_reader: AtomicUsize::new(0),
+            timings: Mutex::new(HashMap::new()),
+            crypto,
+            dedup_ignore_whitespace: AtomicBool::new(false),
+        })
+    }
+
+    fn timed<'a>(&'a self, conn: MutexGuard<'a, Connection>, name: &'static str) -> TimedConn<'a> {
+        TimedConn {
+            conn,
+            name,
+            start: Instant::now(),
+            timings: &self.timings,
+        }
+    }
+
+    /// 写连接
+    fn write(&self, name: &'static str) -> TimedConn<'_> {
+        self.timed(self.conn.lock().unwrap(), name)
+    }
+
+    /// 从连接池取一个空闲的只读连接，都在使用时轮流等待
+    fn read(&self, name: &'static str) -> TimedConn<'_> {
+        let start = self.next_reader.fetch_add(1, Ordering::Relaxed);
+        let conn = (0..self.readers.len())
+            .find_map(|i| {
+                self.readers[(start + i) % self.readers.len()]
+                    .try_lock()
+                    .ok()
+            })
+            .unwrap_or_else(|| self.readers[start % self.readers.len()].lock().unwrap());
+        self.timed(conn, name)
+    }
+
+    /// 各数据库操作的调用次数和耗时，按总耗时降序
+    pub fn query_stats(&self) -> Vec<QueryStats> {
+        let timings = self.timings.lock().unwrap();
+        let mut stats: Vec<QueryStats> = timings
+            .iter()
+            .map(|(name, timing)| QueryStats {
+                name: name.to_string(),
+                count: timing.count,
+                total_ms: timing.total.as_secs_f64() * 1000.0,
+                max_ms: timing.max.as_secs_f64() * 1000.0,
+            })
+            .collect();
+        stats.sort_by(|a, b| b.total_ms.total_cmp(&a.total_ms));
+        stats
+    }
+
+    pub fn set_dedup_ignore_whitespace(&self, enabled: bool) {
+        self.dedup_ignore_whitespace
+            .store(enabled, Ordering::Relaxed);
+    }
+
+    fn row_to_item(&self, row: &rusqlite::Row) -> Result<ClipboardItem> {
+        let id: i64 = row.get(0)?;
+        let content: String = row.get(1)?;
+        let kind: String = row.get(2)?;
+        let is_sensitive: bool = row.get(4)?;
+        let html_content: Option<String> = row.get(10)?;
+        let suggested_actions: Option<String> = row.get(16)?;
+
+        let final_content = if is_sensitive && kind == "text" {
+            self.crypto.decrypt(&content).unwrap_or(content)
+        } else {
+            content
+        };
+
+        let final_html = if let Some(html) = html_content {
+            if is_sensitive {
+                Some(self.crypto.decrypt(&html).unwrap_or(html))
+            } else {
+                Some(html)
+            }
+        } else {
+            None
+        };
+
+        Ok(ClipboardItem {
+            id: Some(id),
+            content: final_content,
+            kind,
+            timestamp: row.get(3)?,
+            is_sensitive,
+            is_pinned: row.get(5)?,
+            source_app: row.get(6)?,
+            data_type: row.get(7)?,
+            collection_id: row.get(8)?,
+            note: row.get(9)?,
+            html_content: final_html,
+            byte_size: row.get(11)?,
+            char_count: row.get(12)?,
+            line_count: row.get(13)?,
+            image_width: row.get(14)?,
+            image_height: row.get(15)?,
+            suggested_actions: suggested_actions
+                .and_then(|json| serde_json::from_str(&json).ok())
+                .unwrap_or_default(),
+            parent_id: row.get(17)?,
+            paste_count: row.get(18)?,
+            timestamp_ms: row.get(19)?,
+            ocr_text: row.get(20)?,
+            is_dangerous: row.get(21)?,
+            is_truncated: row.get(22)?,
+            accelerator: row.get(23)?,
+            is_locked: row.get(24)?,
+            copy_count: row.get(25)?,
+            origin_device: row.get(26)?,
+        })
+    }
+
+    pub fn get_history(
+        &self,
+        page: usize,
+        page_size: usize,
+        filter: &HistoryFilter,
+    ) -> Result<Vec<ClipboardItem>> {
+        let conn = self.read("get_history");
+        let offset = (page - 1) * page_size;
+
+        let mut sql = format!("SELECT {} FROM history WHERE 1=1", ITEM_COLUMNS);
+        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
+
+        if let Some(q) = &filter.query {
+            if !q.is_empty() {
+                if filter.search_regex {
+                    sql.push_str(" AND (content REGEXP ? OR note REGEXP ? OR ocr_text REGEXP ?)");
+                    // If case insensitive, we prepend (?i) flag to the regex string.
+                    // This flag works in Rust regex crate which we used in create_scalar_function.
+                    let final_query = if filter.search_case_sensitive {
+                        q.clone()
                     } else {
-                        content
+                        format!("(?i){}", q)
                     };
+                    params.push(Box::new(final_query.clone()));
+                    params.push(Box::new(final_query.clone()));
+                    params.push(Box::new(final_query));
+                } else {
+                    if filter.search_case_sensitive {
+                        // SQLite LIKE is case-insensitive by default for ASCII characters.
+                        // To make it case-sensitive, we can use GLOB which is case-sensitive (and uses * instead of %),
+                        // OR we can use the `PRAGMA case_sensitive_like = ON` command (but that is connection wide),
+                        // OR we can simply use the binary comparison for exact match or INSTR for substrings,
+                        // BUT for pattern matching usually `GLOB` is the way.
+                        // However, GLOB uses * and ?, not %. So we need to reformat the pattern or just use INSTR > 0 for simpler specific contains check.
+                        // Since `q` here is a substring (like %q%), INSTR(content, q) > 0 does the job and is case-sensitive by default (if not explicitly COLLATE NOCASE).
+                        // Wait, sqlite INSTR IS case-sensitive by default? No, it depends on collation?
+                        // Actually, GLOB is the standard way for case-sensitive pattern matching in SQLite.
+                        // wildcard: * matches any sequence, ? matches any single char.
 
-                     let final_html = if let Some(html) = html_content {
-                         if is_sensitive {
-                             Some(self.crypto.decrypt(&html).unwrap_or(html))
-                        } else {
-                             Some(html)
-                        }
+                        sql.push_str(" AND (content GLOB ? OR note GLOB ? OR ocr_text GLOB ?)");
+                        let pattern = format!("*{}*", q); // Using * for GLOB
+                        params.push(Box::new(pattern.clone()));
+                        params.push(Box::new(pattern.clone()));
+                        params.push(Box::new(pattern));
                     } else {
-                        None
-                    };
+                        sql.push_str(" AND (content LIKE ? OR note LIKE ? OR ocr_text LIKE ?)");
+                        let pattern = format!("%{}%", q);
+                        params.push(Box::new(pattern.clone()));
+                        params.push(Box::new(pattern.clone()));
+                        params.push(Box::new(pattern));
+                    }
+                }
+            }
+        }
 
-                    Ok((
-                        id,
-                        ClipboardItem {
-                            id: Some(id),
-                            content: final_content,
-                            kind,
-                            timestamp,
-                            is_sensitive,
-                            is_pinned,
-                            source_app,
-                            data_type,
-                            collection_id,
-                            note,
-                            html_content: final_html,
-                        },
-                    ))
+        if let Some(cid) = filter.collection_id {
+            sql.push_str(" AND collection_id = ?");
+            params.push(Box::new(cid));
+        }
+
+        if let Some(data_type) = &filter.data_type {
+            sql.push_str(" AND data_type = ?");
+            params.push(Box::new(data_type.clone()));
+        }
+
+        // 毫秒时间戳，from 含、to 不含
+        if let Some(from) = filter.from {
+            sql.push_str(" AND timestamp_ms >= ?");
+            params.push(Box::new(from));
+        }
+        if let Some(to) = filter.to {
+            sql.push_str(" AND timestamp_ms < ?");
+            params.push(Box::new(to));
+        }
+
+        // Some(None) 为本机记录的条目
+        match &filter.origin_device {
+            Some(Some(device)) => {
+                sql.push_str(
+                    " AND (origin_device = ? OR id IN (SELECT item_id FROM item_origins WHERE device = ?))",
+                );
+                params.push(Box::new(device.clone()));
+                params.push(Box::new(device.clone()));
+            }
+            Some(None) => sql.push_str(" AND origin_device IS NULL"),
+            None => {}
+        }
+
+        sql.push_str(" ORDER BY is_pinned DESC, timestamp_ms DESC, id DESC LIMIT ? OFFSET ?");
+        params.push(Box::new(page_size));
+        params.push(Box::new(offset));
+
+        let mut stmt = conn.prepare_cached(&sql)?;
+
+        // Convert params to references for query_map
+        let params_refs: Vec<&dyn rusqlite::ToSql> = params.iter().map(|p| p.as_ref()).collect();
+
+        let rows = stmt.query_map(params_refs.as_slice(), |row| self.row_to_item(row))?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            items.push(row?);
+        }
+        Ok(items)
+    }
+
+    /// 写入条目（内容相同则更新时间戳），返回条目 id 以及因超出上限被清理的条目
+    pub fn insert_item(
+        &self,
+        item: &ClipboardItem,
+        max_size: usize,
+    ) -> Result<(i64, Vec<ClipboardItem>)> {
+        // 图片指纹需要解码图片，在取得写连接之前计算
+        let fingerprint = if item.is_sensitive {
+            None
+        } else {
+            similarity::fingerprint(&item.kind, &item.content).map(|f| f as i64)
+        };
+        let conn = self.write("insert_item");
+        let mut pruned_items = Vec::new();
+
+        let content_to_store = if item.is_sensitive && item.kind == "text" {
+            self.crypto
+                .encrypt(&item.content)
+                .unwrap_or(item.content.clone())
+        } else {
+            item.content.clone()
+        };
+
+        let html_to_store = if let Some(html) = &item.html_content {
+            if item.is_sensitive {
+                Some(self.crypto.encrypt(html).unwrap_or(html.clone()))
+            } else {
+                Some(html.clone())
+            }
+        } else {
+            None
+        };
+
+        let timestamp_ms = if item.timestamp_ms > 0 {
+            item.timestamp_ms
+        } else {
+            timestamp_millis(&item.timestamp)
+        };
+
+        let normalized_hash = if item.kind == "text" && !item.is_sensitive {
+            Some(similarity::whitespace_hash(&item.content) as i64)
+        } else {
+            None
+        };
+
+        // Deduplicate: exact content first, then (optionally) text differing only in whitespace
+        let mut existing_id: Option<i64> = conn
+            .query_row(
+                "SELECT id FROM history WHERE content = ?1 AND kind = ?2",
+                params![content_to_store, item.kind],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if existing_id.is_none() && self.dedup_ignore_whitespace.load(Ordering::Relaxed) {
+            if let Some(hash) = normalized_hash {
+                existing_id = conn
+                    .query_row(
+                        "SELECT id FROM history WHERE kind = 'text' AND is_sensitive = 0 AND normalized_hash = ?1 ORDER BY timestamp_ms DESC LIMIT 1",
+                        params![hash],
+                        |row| row.get(0),
+                    )
+                    .optional()?;
+            }
+        }
+
+        // Update timestamp, source_app and html_content if exists
+        if let Some(id) = existing_id {
+            conn.execute(
+                "UPDATE history SET timestamp = ?1, timestamp_ms = ?2, source_app = ?3, html_content = ?4, copy_count = copy_count + 1 WHERE id = ?5",
+                params![
+                    item.timestamp,
+                    timestamp_ms,
+                    item.source_app,
+                    html_to_store,
+                    id
+                ],
+            )?;
+        }
+
+        let item_id = if let Some(id) = existing_id {
+            id
+        } else {
+            let metrics = ItemMetrics::measure(&item.kind, &item.content);
+            let suggested_actions = if item.suggested_actions.is_empty() {
+                None
+            } else {
+                serde_json::to_string(&item.suggested_actions).ok()
+            };
+
+            // Insert new item
+            conn.execute(
+                "INSERT INTO history (content, kind, timestamp, is_sensitive, is_pinned, source_app, data_type, collection_id, note, html_content, byte_size, char_count, line_count, image_width, image_height, suggested_actions, parent_id, fingerprint, timestamp_ms, is_dangerous, is_truncated, normalized_hash, origin_device) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22, ?23)",
+                params![
+                    content_to_store,
+                    item.kind,
+                    item.timestamp,
+                    item.is_sensitive,
+                    item.is_pinned,
+                    item.source_app,
+                    item.data_type,
+                    item.collection_id,
+                    item.note,
+                    html_to_store,
+                    metrics.byte_size,
+                    metrics.char_count,
+                    metrics.line_count,
+                    metrics.image_width,
+                    metrics.image_height,
+                    suggested_actions,
+                    item.parent_id,
+                    fingerprint,
+                    timestamp_ms,
+                    item.kind == "text" && is_dangerous_command(&item.content),
+                    item.is_truncated,
+                    normalized_hash,
+                    item.origin_device
+                ],
+            )?;
+            conn.last_insert_rowid()
+        };
+
+        // 每次复制都记录一次，重复复制同一内容时可以看到时间线
+        conn.execute(
+            "INSERT INTO item_occurrences (item_id, source_app, copied_at, copied_at_ms) VALUES (?1, ?2, ?3, ?4)",
+            params![item_id, item.source_app, item.timestamp, timestamp_ms],
+        )?;
+        conn.execute(
+            "DELETE FROM item_occurrences WHERE item_id = ?1 AND rowid NOT IN (
+                SELECT rowid FROM item_occurrences WHERE item_id = ?1 ORDER BY copied_at_ms DESC LIMIT ?2
+             )",
+            params![item_id, MAX_OCCURRENCES_PER_ITEM],
+        )?;
+
+        // Prune if exceeding max_size
+        let count: usize = conn.query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))?;
+        if count > max_size {
+            let delete_count = count - max_size;
+
+            // Fetch items to be deleted first (oldest timestamp, NOT pinned, NOT collected);
+            // 收藏的条目（包括免清理收藏夹）不参与数量上限清理
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT {} FROM history WHERE is_pinned = 0 AND collection_id IS NULL AND is_locked = 0 ORDER BY timestamp_ms ASC, id ASC LIMIT {}",
+                ITEM_COLUMNS, delete_count
+            ))?;
+
+            let rows = stmt.query_map([], |row| self.row_to_item(row))?;
+
+            for row in rows {
+                if let Ok(item) = row {
+                    pruned_items.push(item);
+                }
+            }
+
+            // Delete them
+            conn.execute(
+                &format!(
+                    "DELETE FROM history WHERE id IN (SELECT id FROM history WHERE is_pinned = 0 AND collection_id IS NULL AND is_locked = 0 ORDER BY timestamp_ms ASC, id ASC LIMIT {})",
+                    delete_count
+                ),
+                [],
+            )?;
+        }
+
+        Ok((item_id, pruned_items))
+    }
+
+    /// 导入 / 同步时写入条目：内容相同（文本按忽略空白的哈希，图片按指纹和大小）的已有条目
+    /// 只合并，保留较早的时间、合并备注和标签并记录来源设备，不会更新时间或覆盖备注
+    pub fn merge_item(
+        &self,
+        item: &ClipboardItem,
+        tags: &[String],
+        max_size: usize,
+    ) -> Result<MergedItem> {
+        // 图片指纹需要解码图片，在取得写连接之前计算
+        let image_fingerprint = if item.kind == "image" {
+            similarity::fingerprint("image", &item.content)
+        } else {
+            None
+        };
+        {
+            let mut conn = self.write("merge_item");
+            // id、时间、备注、来源设备、是否锁定、收藏夹
+            let found = |row: &rusqlite::Row<'_>| -> Result<MergeTarget> {
+                Ok((
+                    row.get(0)?,
+                    row.get(1)?,
+                    row.get(2)?,
+                    row.get(3)?,
+                    row.get(4)?,
+                    row.get(5)?,
+                ))
+            };
+            let existing = match item.kind.as_str() {
+                "text" if !item.is_sensitive => conn
+                    .query_row(
+                        "SELECT id, timestamp_ms, note, origin_device, is_locked, collection_id FROM history
+                         WHERE kind = 'text' AND is_sensitive = 0 AND normalized_hash = ?1
+                         ORDER BY timestamp_ms ASC LIMIT 1",
+                        params![similarity::whitespace_hash(&item.content) as i64],
+                        found,
+                    )
+                    .optional()?,
+                "image" => match image_fingerprint {
+                    Some(fingerprint) => conn
+                        .query_row(
+                            "SELECT id, timestamp_ms, note, origin_device, is_locked, collection_id FROM history
+                             WHERE kind = 'image' AND fingerprint = ?1 AND byte_size = ?2
+                             ORDER BY timestamp_ms ASC LIMIT 1",
+                            params![
+                                fingerprint as i64,
+                                ItemMetrics::measure("image", &item.content).byte_size
+                            ],
+                            found,
+                        )
+                        .optional()?,
+                    None => None,
                 },
+                _ => None,
+            };
+
+            if let Some((id, timestamp_ms, note, origin_device, is_locked, collection_id)) =
+                existing
+            {
+                let tx = conn.transaction()?;
+                // 锁定的条目不修改内容，只记录来源设备
+                if !is_locked {
+                    let imported_ms = if item.timestamp_ms > 0 {
+                        item.timestamp_ms
+                    } else {
+                        timestamp_millis(&item.timestamp)
+                    };
+                    if imported_ms < timestamp_ms {
+                        tx.execute(
+                            "UPDATE history SET timestamp = ?1, timestamp_ms = ?2 WHERE id = ?3",
+                            params![item.timestamp, imported_ms, id],
+                        )?;
+                    }
+                    tx.execute(
+                        "UPDATE history SET note = ?1, html_content = COALESCE(html_content, ?2) WHERE id = ?3",
+                        params![merge_notes(note, item.note.clone()), item.html_content, id],
+                    )?;
+                    add_item_tags(&tx, id, tags)?;
+                }
+                if let Some(device) = &item.origin_device {
+                    if origin_device.as_ref() != Some(device) {
+                        tx.execute(
+                            "INSERT OR IGNORE INTO item_origins (item_id, device) VALUES (?1, ?2)",
+                            params![id, device],
+                        )?;
+                    }
+                }
+                tx.commit()?;
+                return Ok(MergedItem {
+                    id,
+                    merged: true,
+                    collection_id,
+                    pruned: Vec::new(),
+                });
+            }
+        }
+
+        let (id, pruned) = self.insert_item(item, max_size)?;
+        add_item_tags(&self.write("merge_item"), id, tags)?;
+        Ok(MergedItem {
+            id,
+            merged: false,
+            collection_id: item.collection_id,
+            pruned,
+        })
+    }
+
+    /// 条目的标签列表
+    pub fn get_item_tags(&self, item_id: i64) -> Result<Vec<String>> {
+        let conn = self.read("get_item_tags");
+        let value: Option<String> = conn
+            .query_row(
+                "SELECT value FROM item_metadata WHERE item_id = ?1 AND key = ?2",
+                params![item_id, TAGS_META_KEY],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(value
+            .and_then(|value| serde_json::from_str(&value).ok())
+            .unwrap_or_default())
+    }
+
+    /// 在一个事务中批量写入条目，不做去重和裁剪，用于生成测试数据；返回写入条数
+    pub fn insert_batch(&self, items: &[ClipboardItem]) -> Result<usize> {
+        let mut conn = self.write("insert_batch");
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO history (content, kind, timestamp, timestamp_ms, is_sensitive, is_pinned, source_app, data_type, byte_size, char_count, line_count, fingerprint, is_dangerous, normalized_hash) VALUES (?1, ?2, ?3, ?4, 0, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+            )?;
+            for item in items {
+                let metrics = ItemMetrics::measure(&item.kind, &item.content);
+                let is_text = item.kind == "text";
+                stmt.execute(params![
+                    item.content,
+                    item.kind,
+                    item.timestamp,
+                    timestamp_millis(&item.timestamp),
+                    item.is_pinned,
+                    item.source_app,
+                    item.data_type,
+                    metrics.byte_size,
+                    metrics.char_count,
+                    metrics.line_count,
+                    similarity::fingerprint(&item.kind, &item.content).map(|f| f as i64),
+                    is_text && is_dangerous_command(&item.content),
+                    is_text.then(|| similarity::whitespace_hash(&item.content) as i64),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(items.len())
+    }
+
+    pub fn delete_item(&self, index: usize) -> Result<Option<ClipboardItem>> {
+        // Index is from the frontend, which sees the list in DESC order (latest first).
+        // So index 0 is the latest item (highest ID).
+        // We need to find the ID of the item at that offset.
+        let conn = self.write("delete_item");
+
+        // Get the ID and details of the item at the specified offset
+        let item: Option<ClipboardItem> = conn
+            .query_row(
+                &format!(
+                    "SELECT {} FROM history ORDER BY is_pinned DESC, timestamp_ms DESC, id DESC LIMIT 1 OFFSET ?1",
+                    ITEM_COLUMNS
+                ),
+                params![index],
+                |row| self.row_to_item(row),
             )
             .optional()?;
 
-        if let Some((id, item)) = item {
-            conn.execute("DELETE FROM history WHERE id = ?1", params![id])?;
+        if let Some(item) = item {
+            if item.is_locked {
+                return Err(locked_error());
+            }
+            conn.execute("DELETE FROM history WHERE id = ?1", params![item.id])?;
             Ok(Some(item))
         } else {
             Ok(None)
@@ -423,12 +1234,13 @@ impl Database {
     }
 
     pub fn toggle_sensitive(&self, index: usize) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.write("toggle_sensitive");
+        let tx = conn.transaction()?;
 
         // Get item at index
-        let item: Option<(i64, String, bool, String)> = conn
+        let item: Option<(i64, String, bool, String)> = tx
             .query_row(
-                "SELECT id, content, is_sensitive, kind FROM history ORDER BY is_pinned DESC, timestamp DESC LIMIT 1 OFFSET ?1",
+                "SELECT id, content, is_sensitive, kind FROM history ORDER BY is_pinned DESC, timestamp_ms DESC, id DESC LIMIT 1 OFFSET ?1",
                 params![index],
                 |row| {
                     Ok((
@@ -455,10 +1267,16 @@ impl Database {
                 content
             };
 
-            conn.execute(
+            tx.execute(
                 "UPDATE history SET is_sensitive = ?1, content = ?2 WHERE id = ?3",
                 params![new_state, new_content, id],
             )?;
+            // 原始格式是明文，标记为敏感后删除
+            if new_state {
+                tx.execute("DELETE FROM raw_formats WHERE item_id = ?1", params![id])?;
+            }
+            self.reseal_versions(&tx, id, &kind, new_state)?;
+            tx.commit()?;
             Ok(new_state)
         } else {
             Err(rusqlite::Error::QueryReturnedNoRows)
@@ -466,12 +1284,12 @@ impl Database {
     }
 
     pub fn toggle_pin(&self, index: usize) -> Result<bool> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write("toggle_pin");
 
         // Get item at index
         let item: Option<(i64, bool)> = conn
             .query_row(
-                "SELECT id, is_pinned FROM history ORDER BY is_pinned DESC, timestamp DESC LIMIT 1 OFFSET ?1",
+                "SELECT id, is_pinned FROM history ORDER BY is_pinned DESC, timestamp_ms DESC, id DESC LIMIT 1 OFFSET ?1",
                 params![index],
                 |row| Ok((row.get(0)?, row.get(1)?)),
             )
@@ -489,6 +1307,146 @@ impl Database {
         }
     }
 
+    /// 切换条目的锁定状态，返回新的状态
+    pub fn toggle_lock(&self, id: i64) -> Result<bool> {
+        let conn = self.write("toggle_lock");
+        conn.execute(
+            "UPDATE history SET is_locked = NOT is_locked WHERE id = ?1",
+            params![id],
+        )?;
+        conn.query_row(
+            "SELECT is_locked FROM history WHERE id = ?1",
+            params![id],
+            |row| row.get(0),
+        )
+    }
+
+    /// 托盘 Quick Paste 中列出的置顶条目
+    pub fn get_quick_paste_items(&self, limit: usize) -> Result<Vec<ClipboardItem>> {
+        let conn = self.read("get_quick_paste_items");
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM history WHERE is_pinned = 1 ORDER BY timestamp_ms DESC, id DESC LIMIT ?1",
+            ITEM_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![limit], |row| self.row_to_item(row))?;
+        rows.collect()
+    }
+
+    /// 设置条目的快捷键，同一快捷键只保留在一个条目上
+    pub fn set_item_accelerator(&self, id: i64, accelerator: Option<&str>) -> Result<()> {
+        let conn = self.write("set_item_accelerator");
+        if let Some(accelerator) = accelerator {
+            conn.execute(
+                "UPDATE history SET accelerator = NULL WHERE accelerator = ?1 AND id != ?2",
+                params![accelerator, id],
+            )?;
+        }
+        conn.execute(
+            "UPDATE history SET accelerator = ?1 WHERE id = ?2",
+            params![accelerator, id],
+        )?;
+        Ok(())
+    }
+
+    /// 把条目当前的内容连同它的时间戳存为新版本（敏感内容保持加密），只保留最近的若干个版本
+    fn record_version(tx: &rusqlite::Transaction, id: i64) -> Result<()> {
+        tx.execute(
+            "INSERT INTO item_versions (item_id, version, content, data_type, note, html_content, created_at)
+             SELECT id, COALESCE((SELECT MAX(version) FROM item_versions WHERE item_id = ?1), 0) + 1,
+                    content, data_type, note, html_content, timestamp
+             FROM history WHERE id = ?1",
+            params![id],
+        )?;
+        tx.execute(
+            "DELETE FROM item_versions WHERE item_id = ?1 AND version <= (SELECT MAX(version) FROM item_versions WHERE item_id = ?1) - ?2",
+            params![id, MAX_ITEM_VERSIONS],
+        )?;
+        Ok(())
+    }
+
+    /// 条目的敏感标记变化后，按新状态加密或解密它的历史版本，不留下明文副本
+    fn reseal_versions(
+        &self,
+        tx: &rusqlite::Transaction,
+        id: i64,
+        kind: &str,
+        sensitive: bool,
+    ) -> Result<()> {
+        let convert = |value: String| {
+            if sensitive {
+                self.crypto.encrypt(&value).unwrap_or(value)
+            } else {
+                self.crypto.decrypt(&value).unwrap_or(value)
+            }
+        };
+        let versions = {
+            let mut stmt = tx.prepare(
+                "SELECT version, content, html_content FROM item_versions WHERE item_id = ?1",
+            )?;
+            let rows = stmt.query_map(params![id], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                ))
+            })?;
+            rows.collect::<Result<Vec<_>>>()?
+        };
+        for (version, content, html_content) in versions {
+            let content = if kind == "text" {
+                convert(content)
+            } else {
+                content
+            };
+            tx.execute(
+                "UPDATE item_versions SET content = ?1, html_content = ?2 WHERE item_id = ?3 AND version = ?4",
+                params![content, html_content.map(&convert), id, version],
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 条目的历史版本，新版本在前
+    pub fn get_item_versions(&self, id: i64) -> Result<Vec<ItemVersion>> {
+        let conn = self.read("get_item_versions");
+        let (is_sensitive, kind): (bool, String) = conn.query_row(
+            "SELECT is_sensitive, kind FROM history WHERE id = ?1",
+            params![id],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )?;
+        let decrypt = |value: String| {
+            if is_sensitive {
+                self.crypto.decrypt(&value).unwrap_or(value)
+            } else {
+                value
+            }
+        };
+        let mut stmt = conn.prepare_cached(
+            "SELECT version, content, data_type, note, html_content, created_at FROM item_versions WHERE item_id = ?1 ORDER BY version DESC",
+        )?;
+        let rows = stmt.query_map(params![id], |row| {
+            Ok(ItemVersion {
+                version: row.get(0)?,
+                content: row.get(1)?,
+                data_type: row.get(2)?,
+                note: row.get(3)?,
+                html_content: row.get(4)?,
+                created_at: row.get(5)?,
+            })
+        })?;
+        let mut versions = Vec::new();
+        for row in rows {
+            let mut version = row?;
+            if kind == "text" {
+                version.content = decrypt(version.content);
+            }
+            version.html_content = version.html_content.map(decrypt);
+            versions.push(version);
+        }
+        Ok(versions)
+    }
+
+    /// 修改条目内容；修改前的内容记录为一个历史版本
     pub fn update_content(
         &self,
         id: i64,
@@ -497,15 +1455,34 @@ impl Database {
         new_note: Option<String>,
         new_html_content: Option<String>,
     ) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let mut conn = self.write("update_content");
+        let tx = conn.transaction()?;
 
         // Fetch is_sensitive and kind to encrypt if needed
-        let (is_sensitive, kind): (bool, String) = conn.query_row(
-            "SELECT is_sensitive, kind FROM history WHERE id = ?1",
+        let (is_sensitive, kind, is_locked): (bool, String, bool) = tx.query_row(
+            "SELECT is_sensitive, kind, is_locked FROM history WHERE id = ?1",
             params![id],
-            |row| Ok((row.get(0)?, row.get(1)?)),
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
         )?;
+        if is_locked {
+            return Err(locked_error());
+        }
+
+        let now = Local::now();
+        Self::record_version(&tx, id)?;
+        let metrics = ItemMetrics::measure(&kind, &new_content);
+        let fingerprint = if is_sensitive {
+            None
+        } else {
+            similarity::fingerprint(&kind, &new_content).map(|f| f as i64)
+        };
 
+        let is_dangerous = kind == "text" && is_dangerous_command(&new_content);
+        let normalized_hash = if kind == "text" && !is_sensitive {
+            Some(similarity::whitespace_hash(&new_content) as i64)
+        } else {
+            None
+        };
         let final_content = if is_sensitive && kind == "text" {
             self.crypto.encrypt(&new_content).unwrap_or(new_content)
         } else {
@@ -522,110 +1499,242 @@ impl Database {
             None
         };
 
+        tx.execute(
+            "UPDATE history SET content = ?1, data_type = ?2, timestamp = ?3, timestamp_ms = ?4, note = ?5, html_content = ?6, byte_size = ?7, char_count = ?8, line_count = ?9, fingerprint = ?10, is_dangerous = ?11, normalized_hash = ?12 WHERE id = ?13",
+            params![
+                final_content,
+                new_data_type,
+                now.format("%Y-%m-%d %H:%M:%S").to_string(),
+                now.timestamp_millis(),
+                new_note,
+                final_html_content,
+                metrics.byte_size,
+                metrics.char_count,
+                metrics.line_count,
+                fingerprint,
+                is_dangerous,
+                normalized_hash,
+                id
+            ],
+        )?;
+        tx.commit()
+    }
+
+    pub fn set_suggested_actions(&self, id: i64, actions: &[SuggestedAction]) -> Result<()> {
+        let conn = self.write("set_suggested_actions");
+        let json = if actions.is_empty() {
+            None
+        } else {
+            serde_json::to_string(actions).ok()
+        };
+        conn.execute(
+            "UPDATE history SET suggested_actions = ?1 WHERE id = ?2",
+            params![json, id],
+        )?;
+        Ok(())
+    }
+
+    /// 尚未做过 OCR、且失败次数少于 max_attempts 的图片条目，按时间从新到旧返回 (id, 图片路径)
+    pub fn get_images_without_ocr(
+        &self,
+        limit: usize,
+        max_attempts: u32,
+    ) -> Result<Vec<(i64, String)>> {
+        let conn = self.read("get_images_without_ocr");
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, content FROM history WHERE kind = 'image' AND ocr_text IS NULL AND ocr_attempts < ?2 ORDER BY timestamp_ms DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit, max_attempts], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })?;
+        rows.collect()
+    }
+
+    /// 记录一次 OCR 失败，ocr_text 保持 NULL 以便之后重试
+    pub fn record_ocr_failure(&self, id: i64) -> Result<()> {
+        let conn = self.write("record_ocr_failure");
         conn.execute(
-            "UPDATE history SET content = ?1, data_type = ?2, timestamp = ?3, note = ?4, html_content = ?5 WHERE id = ?6",
+            "UPDATE history SET ocr_attempts = ocr_attempts + 1 WHERE id = ?1",
+            params![id],
+        )?;
+        Ok(())
+    }
+
+    /// 按编码完成的图片文件重新计算体积、尺寸和指纹，用于后台编码的占位条目；条目已不存在时返回 false
+    pub fn refresh_image_metrics(&self, id: i64, path: &str) -> Result<bool> {
+        // 指纹需要解码图片，在取得写连接之前计算
+        let metrics = ItemMetrics::measure("image", path);
+        let fingerprint = similarity::fingerprint("image", path).map(|f| f as i64);
+        let conn = self.write("refresh_image_metrics");
+        let updated = conn.execute(
+            "UPDATE history SET byte_size = ?1, image_width = ?2, image_height = ?3, fingerprint = ?4 WHERE id = ?5 AND content = ?6",
             params![
-                final_content,
-                new_data_type,
-                Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
-                new_note,
-                final_html_content,
-                id
+                metrics.byte_size,
+                metrics.image_width,
+                metrics.image_height,
+                fingerprint,
+                id,
+                path
             ],
         )?;
+        Ok(updated > 0)
+    }
 
+    pub fn set_ocr_text(&self, id: i64, text: &str) -> Result<()> {
+        let conn = self.write("set_ocr_text");
+        conn.execute(
+            "UPDATE history SET ocr_text = ?1 WHERE id = ?2",
+            params![text, id],
+        )?;
         Ok(())
     }
 
-    pub fn clear_history(
-        &self,
-        clear_pinned_on_clear: bool,
-        clear_collected_on_clear: bool,
-    ) -> Result<Vec<ClipboardItem>> {
-        let conn = self.conn.lock().unwrap();
-
-        // 构建 WHERE 条件
+    /// 把筛选条件转成 WHERE 条件和对应的参数
+    fn filter_conditions(filter: &ItemFilter) -> (Vec<String>, Vec<rusqlite::types::Value>) {
         let mut conditions = Vec::new();
-        if !clear_pinned_on_clear {
-            conditions.push("is_pinned = 0");
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        if filter.only_unpinned.unwrap_or(false) {
+            conditions.push("is_pinned = 0".to_string());
         }
-        if !clear_collected_on_clear {
-            conditions.push("collection_id IS NULL");
+        if filter.only_uncollected.unwrap_or(false) {
+            conditions.push("collection_id IS NULL".to_string());
         }
-        // 如果都为 true，则不加条件，全部清除
-        let where_clause = if conditions.is_empty() {
-            String::from("")
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
+        if let Some(days) = filter.older_than_days {
+            let cutoff = Local::now().timestamp_millis() - days as i64 * DAY_MS;
+            values.push(cutoff.into());
+            conditions.push(format!("timestamp_ms < ?{}", values.len()));
+        }
+        if let Some(data_type) = &filter.data_type {
+            values.push(data_type.clone().into());
+            conditions.push(format!("data_type = ?{}", values.len()));
+        }
+        if let Some(source_app) = &filter.source_app {
+            values.push(source_app.clone().into());
+            conditions.push(format!("source_app = ?{}", values.len()));
+        }
+        (conditions, values)
+    }
 
-        // 查询所有将要被删除的项
-        let select_sql = format!(
-            "SELECT id, content, kind, timestamp, is_sensitive, is_pinned, source_app, data_type, collection_id, note, html_content FROM history {}",
-            where_clause
-        );
-        let mut stmt = conn.prepare(&select_sql)?;
-        let rows = stmt.query_map([], |row| {
-            let id: i64 = row.get(0)?;
-            let content: String = row.get(1)?;
-            let kind: String = row.get(2)?;
-            let timestamp: String = row.get(3)?;
-            let is_sensitive: bool = row.get(4)?;
-            let is_pinned: bool = row.get(5)?;
-            let source_app: Option<String> = row.get(6)?;
-            let data_type: String = row.get(7)?;
-            let collection_id: Option<i64> = row.get(8)?;
-            let note: Option<String> = row.get(9)?;
-            let html_content: Option<String> = row.get(10)?;
-
-            let final_content = if is_sensitive && kind == "text" {
-                self.crypto.decrypt(&content).unwrap_or(content)
-            } else {
-                content
-            };
+    /// 按筛选条件删除条目，返回被删除的条目（用于清理图片文件）
+    pub fn clear_history(&self, filter: &ItemFilter) -> Result<Vec<ClipboardItem>> {
+        let conn = self.write("clear_history");
 
-            let final_html = if let Some(html) = html_content {
-                if is_sensitive {
-                    Some(self.crypto.decrypt(&html).unwrap_or(html))
-                } else {
-                    Some(html)
-                }
-            } else {
-                None
-            };
+        // 锁定的条目和免清理收藏夹中的条目始终保留
+        let (mut conditions, values) = Self::filter_conditions(filter);
+        conditions.push("is_locked = 0".to_string());
+        conditions.push(NOT_RETENTION_EXEMPT.to_string());
 
-            Ok(ClipboardItem {
-                id: Some(id),
-                content: final_content,
-                kind,
-                timestamp,
-                is_sensitive,
-                is_pinned,
-                source_app,
-                data_type,
-                collection_id,
-                note,
-                html_content: final_html,
-            })
+        // 一条语句删除并返回被删除的项
+        let sql = format!(
+            "DELETE FROM history WHERE {} RETURNING {}",
+            conditions.join(" AND "),
+            ITEM_COLUMNS
+        );
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+            self.row_to_item(row)
         })?;
 
         let mut items = Vec::new();
         for row in rows {
             items.push(row?);
         }
+        Ok(items)
+    }
+
+    /// 清理候选：早于 min_age_days、从未粘贴、未置顶 / 锁定 / 收藏的条目，
+    /// 按占用空间与存放天数的乘积降序
+    pub fn get_cleanup_candidates(
+        &self,
+        min_age_days: u64,
+        limit: usize,
+    ) -> Result<Vec<CleanupCandidate>> {
+        let conn = self.read("get_cleanup_candidates");
+        let now = Local::now().timestamp_millis();
+        let cutoff = now - min_age_days as i64 * DAY_MS;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM history
+             WHERE paste_count = 0 AND is_pinned = 0 AND is_locked = 0 AND collection_id IS NULL
+               AND timestamp_ms < ?1
+             ORDER BY byte_size * (?2 - timestamp_ms) DESC, id ASC LIMIT ?3",
+            ITEM_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![cutoff, now, limit], |row| self.row_to_item(row))?;
+
+        let mut candidates = Vec::new();
+        for row in rows {
+            let item = row?;
+            let age_days = (now - item.timestamp_ms).max(0) as f64 / DAY_MS as f64;
+            candidates.push(CleanupCandidate {
+                reclaimable_bytes: item.byte_size,
+                age_days: age_days as u64,
+                score: item.byte_size as f64 / 1024.0 * age_days,
+                item,
+            });
+        }
+        Ok(candidates)
+    }
+
+    /// 删除指定的条目（跳过锁定的），返回被删除的条目（用于清理图片文件）
+    pub fn delete_items(&self, ids: &[i64]) -> Result<Vec<ClipboardItem>> {
+        let mut conn = self.write("delete_items");
+        let tx = conn.transaction()?;
+        let mut deleted = Vec::new();
+        {
+            let mut stmt = tx.prepare_cached(&format!(
+                "DELETE FROM history WHERE id = ?1 AND is_locked = 0 RETURNING {}",
+                ITEM_COLUMNS
+            ))?;
+            for id in ids {
+                if let Some(item) = stmt
+                    .query_row(params![id], |row| self.row_to_item(row))
+                    .optional()?
+                {
+                    deleted.push(item);
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(deleted)
+    }
 
-        // 删除这些项
-        let delete_sql = if where_clause.is_empty() {
-            String::from("DELETE FROM history")
+    /// 按筛选条件列出条目，按时间从旧到新
+    pub fn get_items_by_filter(&self, filter: &ItemFilter) -> Result<Vec<ClipboardItem>> {
+        let conn = self.read("get_items_by_filter");
+        let (conditions, values) = Self::filter_conditions(filter);
+        let where_clause = if conditions.is_empty() {
+            String::new()
         } else {
-            format!("DELETE FROM history {}", where_clause)
+            format!("WHERE {}", conditions.join(" AND "))
         };
-        conn.execute(&delete_sql, [])?;
+        let sql = format!(
+            "SELECT {} FROM history {} ORDER BY timestamp_ms ASC, id ASC",
+            ITEM_COLUMNS, where_clause
+        );
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(values), |row| {
+            self.row_to_item(row)
+        })?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            items.push(row?);
+        }
         Ok(items)
     }
 
+    /// 是否有历史条目引用该内容（如图片文件路径）
+    pub fn is_content_referenced(&self, content: &str) -> Result<bool> {
+        let conn = self.read("is_content_referenced");
+        conn.query_row(
+            "SELECT EXISTS(SELECT 1 FROM history WHERE content = ?1)",
+            params![content],
+            |row| row.get(0),
+        )
+    }
+
     pub fn get_item_content(&self, id: i64) -> Result<String> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.read("get_item_content");
         let (content, is_sensitive, kind): (String, bool, String) = conn.query_row(
             "SELECT content, is_sensitive, kind FROM history WHERE id = ?1",
             params![id],
@@ -639,24 +1748,410 @@ impl Database {
         }
     }
 
+    pub fn get_item(&self, id: i64) -> Result<ClipboardItem> {
+        let conn = self.read("get_item");
+        conn.query_row(
+            &format!("SELECT {} FROM history WHERE id = ?1", ITEM_COLUMNS),
+            params![id],
+            |row| self.row_to_item(row),
+        )
+    }
+
+    /// 查找与指定条目相近的文本/图片（相似度不低于 threshold）
+    pub fn find_similar(&self, id: i64, threshold: f64) -> Result<Vec<SimilarItem>> {
+        // 先读出条目和已保存的指纹；缺少指纹的图片需要解码，放到释放读连接之后计算
+        let (target, candidates) = {
+            let conn = self.read("find_similar");
+            let target: (ClipboardItem, Option<i64>) = conn.query_row(
+                &format!(
+                    "SELECT {}, fingerprint FROM history WHERE id = ?1",
+                    ITEM_COLUMNS
+                ),
+                params![id],
+                |row| Ok((self.row_to_item(row)?, row.get("fingerprint")?)),
+            )?;
+            let mut stmt = conn.prepare_cached(&format!(
+                "SELECT {}, fingerprint FROM history WHERE kind = ?1 AND id != ?2 AND is_sensitive = 0",
+                ITEM_COLUMNS
+            ))?;
+            let candidates = stmt
+                .query_map(params![target.0.kind, id], |row| {
+                    Ok((
+                        self.row_to_item(row)?,
+                        row.get::<_, Option<i64>>("fingerprint")?,
+                    ))
+                })?
+                .collect::<Result<Vec<_>>>()?;
+            (target, candidates)
+        };
+
+        let (target, stored) = target;
+        let mut backfill = Vec::new();
+        let target_fp = match stored {
+            Some(fp) => fp as u64,
+            None => match similarity::fingerprint(&target.kind, &target.content) {
+                Some(fp) => {
+                    if !target.is_sensitive {
+                        backfill.push((id, fp as i64));
+                    }
+                    fp
+                }
+                None => return Ok(Vec::new()),
+            },
+        };
+
+        let mut similar = Vec::new();
+        for (item, stored) in candidates {
+            let fp = match stored {
+                Some(fp) => fp as u64,
+                None => match similarity::fingerprint(&item.kind, &item.content) {
+                    Some(fp) => {
+                        backfill.extend(item.id.map(|id| (id, fp as i64)));
+                        fp
+                    }
+                    None => continue,
+                },
+            };
+            let score = similarity::similarity(target_fp, fp);
+            if score >= threshold {
+                similar.push(SimilarItem {
+                    item,
+                    similarity: score,
+                });
+            }
+        }
+
+        if !backfill.is_empty() {
+            let conn = self.write("find_similar");
+            for (item_id, fp) in backfill {
+                conn.execute(
+                    "UPDATE history SET fingerprint = ?1 WHERE id = ?2",
+                    params![fp, item_id],
+                )?;
+            }
+        }
+
+        similar.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+        Ok(similar)
+    }
+
+    pub fn get_activity_calendar(&self, year: i32) -> Result<ActivityCalendar> {
+        let conn = self.read("get_activity_calendar");
+        let year_str = format!("{:04}", year);
+
+        let mut stmt = conn.prepare_cached(
+            "SELECT substr(timestamp, 1, 10) AS day, COUNT(*) FROM history WHERE substr(timestamp, 1, 4) = ?1 GROUP BY day ORDER BY day",
+        )?;
+        let rows = stmt.query_map(params![year_str], |row| {
+            Ok(DayCount {
+                date: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?;
+        let mut days = Vec::new();
+        for row in rows {
+            days.push(row?);
+        }
+
+        let mut hours = vec![0usize; 24];
+        let mut stmt = conn.prepare_cached(
+            "SELECT CAST(substr(timestamp, 12, 2) AS INTEGER) AS hour, COUNT(*) FROM history WHERE substr(timestamp, 1, 4) = ?1 GROUP BY hour",
+        )?;
+        let rows = stmt.query_map(params![year_str], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, usize>(1)?))
+        })?;
+        for row in rows {
+            let (hour, count) = row?;
+            if (0..24).contains(&hour) {
+                hours[hour as usize] = count;
+            }
+        }
+
+        Ok(ActivityCalendar {
+            year,
+            total: days.iter().map(|d| d.count).sum(),
+            days,
+            hours,
+        })
+    }
+
+    /// 按复制时间从新到旧第 offset 条非敏感记录的 id（不考虑置顶）
+    pub fn get_id_at_offset(&self, offset: usize) -> Result<Option<i64>> {
+        let conn = self.read("get_id_at_offset");
+        conn.query_row(
+            "SELECT id FROM history WHERE is_sensitive = 0 ORDER BY timestamp_ms DESC, id DESC LIMIT 1 OFFSET ?1",
+            params![offset],
+            |row| row.get(0),
+        )
+        .optional()
+    }
+
+    pub fn get_latest_restorable_item(&self) -> Result<Option<ClipboardItem>> {
+        let conn = self.read("get_latest_restorable_item");
+        conn.query_row(
+            &format!(
+                "SELECT {} FROM history WHERE is_sensitive = 0 ORDER BY timestamp_ms DESC, id DESC LIMIT 1",
+                ITEM_COLUMNS
+            ),
+            [],
+            |row| self.row_to_item(row),
+        )
+        .optional()
+    }
+
+    /// 数据库文件占用的字节数
+    pub fn database_size(&self) -> Result<u64> {
+        let conn = self.read("database_size");
+        conn.query_row(
+            "SELECT page_count * page_size FROM pragma_page_count(), pragma_page_size()",
+            [],
+            |row| row.get(0),
+        )
+    }
+
+    /// 每个收藏夹中的条目数（包括空收藏夹），一次查询
+    pub fn get_collection_counts(&self) -> Result<Vec<CollectionCount>> {
+        let conn = self.read("get_collection_counts");
+        let mut stmt = conn.prepare_cached(
+            "SELECT c.id, c.name, COUNT(h.id) FROM collections c
+             LEFT JOIN history h ON h.collection_id = c.id
+             GROUP BY c.id ORDER BY c.created_at DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(CollectionCount {
+                collection_id: row.get(0)?,
+                name: row.get(1)?,
+                count: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 条目的来源设备和合并进来的其他设备，本机记录的条目不含本机
+    pub fn get_item_devices(&self, id: i64) -> Result<Vec<String>> {
+        let conn = self.read("get_item_devices");
+        let mut stmt = conn.prepare_cached(
+            "SELECT origin_device FROM history WHERE id = ?1 AND origin_device IS NOT NULL
+             UNION SELECT device FROM item_origins WHERE item_id = ?1 ORDER BY 1",
+        )?;
+        let rows = stmt.query_map(params![id], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// 同步来的条目中出现过的设备名称
+    pub fn get_origin_devices(&self) -> Result<Vec<String>> {
+        let conn = self.read("get_origin_devices");
+        let mut stmt = conn.prepare_cached(
+            "SELECT origin_device FROM history WHERE origin_device IS NOT NULL
+             UNION SELECT device FROM item_origins ORDER BY 1",
+        )?;
+        let rows = stmt.query_map([], |row| row.get(0))?;
+        rows.collect()
+    }
+
+    /// 每种数据类型的条目数，按数量降序
+    pub fn get_type_counts(&self) -> Result<Vec<TypeCount>> {
+        let conn = self.read("get_type_counts");
+        let mut stmt = conn.prepare_cached(
+            "SELECT data_type, COUNT(*) FROM history GROUP BY data_type ORDER BY 2 DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TypeCount {
+                data_type: row.get(0)?,
+                count: row.get(1)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn usage_by_type(&self) -> Result<Vec<TypeUsage>> {
+        let conn = self.read("usage_by_type");
+        let mut stmt = conn.prepare_cached(
+            "SELECT data_type, COUNT(*), COALESCE(SUM(byte_size), 0) FROM history GROUP BY data_type ORDER BY 3 DESC",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok(TypeUsage {
+                data_type: row.get(0)?,
+                count: row.get(1)?,
+                bytes: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 等待进行中的写入完成，并把缓存的脏页写回磁盘
+    pub fn flush(&self) -> Result<()> {
+        let conn = self.write("flush");
+        conn.cache_flush()?;
+        // 把 WAL 中的内容写回主库，退出后只留下一个数据库文件
+        conn.query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))
+    }
+
     pub fn count_history(&self) -> Result<usize> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.read("count_history");
         let count: usize = conn.query_row("SELECT COUNT(*) FROM history", [], |row| row.get(0))?;
         Ok(count)
     }
 
     pub fn update_timestamp(&self, id: i64) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write("update_timestamp");
+        let now = chrono::Local::now();
+        conn.execute(
+            "UPDATE history SET timestamp = ?1, timestamp_ms = ?2 WHERE id = ?3",
+            params![
+                now.format("%Y-%m-%d %H:%M:%S").to_string(),
+                now.timestamp_millis(),
+                id
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// 条目每次被复制的时间和来源应用，新的在前
+    pub fn get_item_occurrences(&self, id: i64) -> Result<Vec<ItemOccurrence>> {
+        let conn = self.read("get_item_occurrences");
+        let mut stmt = conn.prepare_cached(
+            "SELECT copied_at, source_app FROM item_occurrences WHERE item_id = ?1 ORDER BY copied_at_ms DESC",
+        )?;
+        let rows = stmt.query_map(params![id], |row| {
+            Ok(ItemOccurrence {
+                copied_at: row.get(0)?,
+                source_app: row.get(1)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    /// 整体替换粘贴栈；条目以 JSON 保存，敏感条目整段加密
+    pub fn save_paste_stack(&self, items: &[ClipboardItem]) -> Result<()> {
+        let mut conn = self.write("save_paste_stack");
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM paste_stack", [])?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO paste_stack (position, is_sensitive, item) VALUES (?1, ?2, ?3)",
+            )?;
+            for (position, item) in items.iter().enumerate() {
+                let json = serde_json::to_string(item)
+                    .map_err(|e| rusqlite::Error::ToSqlConversionFailure(Box::new(e)))?;
+                let json = if item.is_sensitive {
+                    self.crypto.encrypt(&json).unwrap_or(json)
+                } else {
+                    json
+                };
+                stmt.execute(params![position as i64, item.is_sensitive, json])?;
+            }
+        }
+        tx.commit()
+    }
+
+    /// 粘贴栈中的条目，按粘贴顺序；无法解析的条目跳过
+    pub fn get_paste_stack(&self) -> Result<Vec<ClipboardItem>> {
+        let conn = self.read("get_paste_stack");
+        let mut stmt =
+            conn.prepare_cached("SELECT is_sensitive, item FROM paste_stack ORDER BY position")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, bool>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut items = Vec::new();
+        for row in rows {
+            let (is_sensitive, json) = row?;
+            let json = if is_sensitive {
+                self.crypto.decrypt(&json).unwrap_or(json)
+            } else {
+                json
+            };
+            match serde_json::from_str(&json) {
+                Ok(item) => items.push(item),
+                Err(e) => log::error!("Skipped unreadable paste stack item: {}", e),
+            }
+        }
+        Ok(items)
+    }
+
+    /// 记录一次从历史中粘贴条目的操作，用于按目标应用推荐
+    pub fn record_paste(&self, id: i64, target_app: Option<&str>) -> Result<()> {
+        let conn = self.write("record_paste");
         let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         conn.execute(
-            "UPDATE history SET timestamp = ?1 WHERE id = ?2",
-            params![timestamp, id],
+            "UPDATE history SET paste_count = paste_count + 1 WHERE id = ?1",
+            params![id],
+        )?;
+        conn.execute(
+            "INSERT INTO paste_events (item_id, target_app, pasted_at) VALUES (?1, ?2, ?3)",
+            params![id, target_app, timestamp],
+        )?;
+        let cutoff = (chrono::Local::now() - chrono::Duration::days(PASTE_EVENT_RETENTION_DAYS))
+            .format("%Y-%m-%d %H:%M:%S")
+            .to_string();
+        conn.execute(
+            "DELETE FROM paste_events WHERE pasted_at < ?1",
+            params![cutoff],
+        )?;
+        Ok(())
+    }
+
+    /// 当天该功能的使用次数加一
+    pub fn record_usage(&self, event: &str) -> Result<()> {
+        let conn = self.write("record_usage");
+        let day = Local::now().format("%Y-%m-%d").to_string();
+        conn.execute(
+            "INSERT INTO usage_counts (day, event, count) VALUES (?1, ?2, 1)
+             ON CONFLICT (day, event) DO UPDATE SET count = count + 1",
+            params![day, event],
         )?;
         Ok(())
     }
 
+    /// 最近 days 天（含今天）每天每项功能的使用次数，按日期排序
+    pub fn get_usage_counts(&self, days: u32) -> Result<Vec<UsageCount>> {
+        let conn = self.read("get_usage_counts");
+        let since = (Local::now() - chrono::Duration::days(days.saturating_sub(1) as i64))
+            .format("%Y-%m-%d")
+            .to_string();
+        let mut stmt = conn.prepare_cached(
+            "SELECT day, event, count FROM usage_counts WHERE day >= ?1 ORDER BY day, event",
+        )?;
+        let rows = stmt.query_map(params![since], |row| {
+            Ok(UsageCount {
+                date: row.get(0)?,
+                event: row.get(1)?,
+                count: row.get(2)?,
+            })
+        })?;
+        rows.collect()
+    }
+
+    pub fn clear_usage_counts(&self) -> Result<usize> {
+        let conn = self.write("clear_usage_counts");
+        conn.execute("DELETE FROM usage_counts", [])
+    }
+
+    /// 根据目标应用的历史粘贴记录与来源应用，返回最常用的条目
+    pub fn get_suggestions(&self, context_app: &str, limit: usize) -> Result<Vec<ClipboardItem>> {
+        let conn = self.read("get_suggestions");
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM history h
+             LEFT JOIN (
+                 SELECT item_id, COUNT(*) AS app_pastes FROM paste_events WHERE target_app = ?1 GROUP BY item_id
+             ) p ON p.item_id = h.id
+             WHERE h.is_sensitive = 0
+               AND (p.app_pastes > 0 OR (h.source_app = ?1 AND h.paste_count > 0))
+             ORDER BY COALESCE(p.app_pastes, 0) * 3 + h.paste_count DESC, h.timestamp_ms DESC
+             LIMIT ?2",
+            ITEM_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![context_app, limit], |row| self.row_to_item(row))?;
+
+        let mut items = Vec::new();
+        for row in rows {
+            items.push(row?);
+        }
+        Ok(items)
+    }
+
     pub fn create_collection(&self, name: String) -> Result<Collection> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write("create_collection");
         let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
         conn.execute(
             "INSERT INTO collections (name, created_at) VALUES (?1, ?2)",
@@ -667,18 +2162,34 @@ impl Database {
             id,
             name,
             created_at: timestamp,
+            exempt_from_retention: false,
         })
     }
 
+    /// 设置收藏夹是否免于自动清理和清空历史
+    pub fn set_collection_retention_exempt(&self, id: i64, exempt: bool) -> Result<()> {
+        let conn = self.write("set_collection_retention_exempt");
+        let updated = conn.execute(
+            "UPDATE collections SET exempt_from_retention = ?1 WHERE id = ?2",
+            params![exempt, id],
+        )?;
+        if updated == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+        Ok(())
+    }
+
     pub fn get_collections(&self) -> Result<Vec<Collection>> {
-        let conn = self.conn.lock().unwrap();
-        let mut stmt =
-            conn.prepare("SELECT id, name, created_at FROM collections ORDER BY created_at DESC")?;
+        let conn = self.read("get_collections");
+        let mut stmt = conn.prepare_cached(
+            "SELECT id, name, created_at, exempt_from_retention FROM collections ORDER BY created_at DESC",
+        )?;
         let rows = stmt.query_map([], |row| {
             Ok(Collection {
                 id: row.get(0)?,
                 name: row.get(1)?,
                 created_at: row.get(2)?,
+                exempt_from_retention: row.get(3)?,
             })
         })?;
 
@@ -689,20 +2200,166 @@ impl Database {
         Ok(collections)
     }
 
-    pub fn delete_collection(&self, id: i64) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
-        // First, remove items from this collection (set collection_id to NULL)
-        conn.execute(
+    /// 在一个事务中删除收藏夹并按 mode 处理其中的条目，返回被删除的条目（用于清理图片文件）；
+    /// 删除条目时锁定的条目改为移出收藏夹
+    pub fn delete_collection(
+        &self,
+        id: i64,
+        mode: CollectionDeleteMode,
+    ) -> Result<Vec<ClipboardItem>> {
+        let mut conn = self.write("delete_collection");
+        let tx = conn.transaction()?;
+        let mut deleted = Vec::new();
+        match mode {
+            CollectionDeleteMode::Uncategorize => {}
+            CollectionDeleteMode::DeleteItems => {
+                let mut stmt = tx.prepare_cached(&format!(
+                    "DELETE FROM history WHERE collection_id = ?1 AND is_locked = 0 RETURNING {}",
+                    ITEM_COLUMNS
+                ))?;
+                let rows = stmt.query_map(params![id], |row| self.row_to_item(row))?;
+                for row in rows {
+                    deleted.push(row?);
+                }
+            }
+            CollectionDeleteMode::MoveTo(target) => {
+                let exists: bool = tx.query_row(
+                    "SELECT EXISTS (SELECT 1 FROM collections WHERE id = ?1)",
+                    params![target],
+                    |row| row.get(0),
+                )?;
+                if target == id || !exists {
+                    return Err(rusqlite::Error::UserFunctionError(
+                        format!("Invalid target collection: {}", target).into(),
+                    ));
+                }
+                tx.execute(
+                    "UPDATE history SET collection_id = ?1 WHERE collection_id = ?2",
+                    params![target, id],
+                )?;
+            }
+        }
+        tx.execute(
             "UPDATE history SET collection_id = NULL WHERE collection_id = ?1",
             params![id],
         )?;
-        // Then delete the collection
-        conn.execute("DELETE FROM collections WHERE id = ?1", params![id])?;
+        tx.execute("DELETE FROM collections WHERE id = ?1", params![id])?;
+        tx.commit()?;
+        Ok(deleted)
+    }
+
+    /// 保存条目的原始剪贴板格式；原始数据无法加密，敏感条目不保存
+    pub fn set_raw_formats(&self, item_id: i64, formats: &[(String, Vec<u8>)]) -> Result<()> {
+        let mut conn = self.write("set_raw_formats");
+        let tx = conn.transaction()?;
+        tx.execute(
+            "DELETE FROM raw_formats WHERE item_id = ?1",
+            params![item_id],
+        )?;
+        let is_sensitive: bool = tx
+            .query_row(
+                "SELECT is_sensitive FROM history WHERE id = ?1",
+                params![item_id],
+                |row| row.get(0),
+            )
+            .optional()?
+            .unwrap_or(true);
+        if is_sensitive {
+            return tx.commit();
+        }
+        for (format, data) in formats {
+            tx.execute(
+                "INSERT INTO raw_formats (item_id, format, data) VALUES (?1, ?2, ?3)",
+                params![item_id, format, data],
+            )?;
+        }
+        tx.commit()
+    }
+
+    pub fn get_raw_formats(&self, item_id: i64) -> Result<Vec<(String, Vec<u8>)>> {
+        let conn = self.read("get_raw_formats");
+        let mut stmt = conn
+            .prepare("SELECT format, data FROM raw_formats WHERE item_id = ?1 ORDER BY format")?;
+        let rows = stmt.query_map(params![item_id], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        rows.collect()
+    }
+
+    /// 设置条目的一项元数据（JSON），value 为 None 时删除该项
+    pub fn set_item_meta(
+        &self,
+        item_id: i64,
+        key: &str,
+        value: Option<&serde_json::Value>,
+    ) -> Result<()> {
+        let conn = self.write("set_item_meta");
+        match value {
+            Some(value) => conn.execute(
+                "INSERT INTO item_metadata (item_id, key, value) VALUES (?1, ?2, ?3)
+                 ON CONFLICT (item_id, key) DO UPDATE SET value = excluded.value",
+                params![item_id, key, value.to_string()],
+            )?,
+            None => conn.execute(
+                "DELETE FROM item_metadata WHERE item_id = ?1 AND key = ?2",
+                params![item_id, key],
+            )?,
+        };
         Ok(())
     }
 
+    /// 条目的全部元数据，按 key 排序
+    pub fn get_item_meta(
+        &self,
+        item_id: i64,
+    ) -> Result<serde_json::Map<String, serde_json::Value>> {
+        let conn = self.read("get_item_meta");
+        let mut stmt = conn.prepare_cached(
+            "SELECT key, value FROM item_metadata WHERE item_id = ?1 ORDER BY key",
+        )?;
+        let rows = stmt.query_map(params![item_id], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut meta = serde_json::Map::new();
+        for row in rows {
+            let (key, value) = row?;
+            let value = serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value));
+            meta.insert(key, value);
+        }
+        Ok(meta)
+    }
+
+    pub fn get_collection_items(&self, collection_id: i64) -> Result<Vec<ClipboardItem>> {
+        let conn = self.read("get_collection_items");
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT {} FROM history WHERE collection_id = ?1 ORDER BY timestamp_ms DESC, id DESC",
+            ITEM_COLUMNS
+        ))?;
+        let rows = stmt.query_map(params![collection_id], |row| self.row_to_item(row))?;
+        rows.collect()
+    }
+
+    /// 按自动归类规则设置收藏夹、标签和置顶，None / false 的部分保持不变；
+    /// 标签加入元数据中的标签列表，不覆盖 data_type
+    pub fn route_item(
+        &self,
+        id: i64,
+        collection_id: Option<i64>,
+        tag: Option<&str>,
+        pin: bool,
+    ) -> Result<()> {
+        let mut conn = self.write("route_item");
+        let tx = conn.transaction()?;
+        tx.execute(
+            "UPDATE history SET collection_id = COALESCE(?1, collection_id), is_pinned = (is_pinned OR ?2) WHERE id = ?3",
+            params![collection_id, pin, id],
+        )?;
+        if let Some(tag) = tag {
+            add_item_tags(&tx, id, &[tag.to_string()])?;
+        }
+        tx.commit()
+    }
+
     pub fn set_item_collection(&self, item_id: i64, collection_id: Option<i64>) -> Result<()> {
-        let conn = self.conn.lock().unwrap();
+        let conn = self.write("set_item_collection");
         conn.execute(
             "UPDATE history SET collection_id = ?1 WHERE id = ?2",
             params![collection_id, item_id],
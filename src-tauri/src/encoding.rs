@@ -0,0 +1,56 @@
+use encoding_rs::{Encoding, UTF_8};
+
+use crate::db::Database;
+
+/// 字节按检测到的编码转为 UTF-8 的结果；lossy 表示有无法转换的字节被替换
+pub struct Decoded {
+    pub text: String,
+    pub encoding: &'static str,
+    pub lossy: bool,
+}
+
+/// 检测文本编码（BOM 优先，其次 UTF-8，再由 chardetng 猜测 GBK、Shift-JIS、Latin-1 等）并转为 UTF-8
+pub fn decode(bytes: &[u8]) -> Decoded {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, lossy) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return Decoded {
+            text: text.into_owned(),
+            encoding: encoding.name(),
+            lossy,
+        };
+    }
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Decoded {
+            text: text.to_string(),
+            encoding: UTF_8.name(),
+            lossy: false,
+        };
+    }
+    let mut detector = chardetng::EncodingDetector::new();
+    detector.feed(bytes, true);
+    let encoding = detector.guess(None, false);
+    let (text, lossy) = encoding.decode_without_bom_handling(bytes);
+    Decoded {
+        text: text.into_owned(),
+        encoding: encoding.name(),
+        lossy,
+    }
+}
+
+/// 非 UTF-8 来源的条目在元数据 source_encoding 中记录原编码和是否有损
+pub fn record(db: &Database, id: i64, decoded: &Decoded) {
+    if decoded.encoding == UTF_8.name() && !decoded.lossy {
+        return;
+    }
+    if decoded.lossy {
+        log::warn!(
+            "Item {} was transcoded from {} with replacement characters",
+            id,
+            decoded.encoding
+        );
+    }
+    let value = serde_json::json!({ "encoding": decoded.encoding, "lossy": decoded.lossy });
+    if let Err(e) = db.set_item_meta(id, "source_encoding", Some(&value)) {
+        log::error!("Failed to record source encoding of item {}: {}", id, e);
+    }
+}
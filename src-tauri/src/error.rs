@@ -0,0 +1,182 @@
+use std::fmt;
+
+use rusqlite::ErrorCode;
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+
+/// 命令返回给前端的错误，序列化为 { code, kind, message, details }：
+/// code 是具体的错误码，kind 是错误类别，details 是底层错误信息
+#[derive(Debug)]
+pub enum AppError {
+    // 条目、收藏夹、文件等不存在
+    NotFound(String),
+    // 文件系统权限或系统权限（辅助功能、屏幕录制等）不足
+    PermissionDenied(String),
+    // 数据库被其他连接占用
+    DatabaseLocked(rusqlite::Error),
+    Database(rusqlite::Error),
+    Io(std::io::Error),
+    // 参数或配置不合法
+    InvalidInput(String),
+    // 条目已锁定，需先解锁
+    ItemLocked,
+    // 当前平台或环境不支持
+    Unsupported(String),
+    // 需要用户在前端确认后带 confirmed 重新调用
+    ConfirmationRequired(String),
+    Other(String),
+}
+
+/// 锁定条目被编辑 / 删除时数据库层返回的错误
+#[derive(Debug)]
+pub struct ItemLocked;
+
+impl fmt::Display for ItemLocked {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Item is locked")
+    }
+}
+
+impl std::error::Error for ItemLocked {}
+
+impl AppError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_) => "not_found",
+            AppError::PermissionDenied(_) => "permission_denied",
+            AppError::DatabaseLocked(_) => "database_locked",
+            AppError::Database(_) => "database_error",
+            AppError::Io(_) => "io_error",
+            AppError::InvalidInput(_) => "invalid_input",
+            AppError::ItemLocked => "item_locked",
+            AppError::Unsupported(_) => "unsupported",
+            AppError::ConfirmationRequired(_) => "confirmation_required",
+            AppError::Other(_) => "internal",
+        }
+    }
+
+    /// 错误类别：用户可以处理的（user）、需要授权的（permission）、存储相关的（storage）
+    /// 和其他（internal）
+    pub fn kind(&self) -> &'static str {
+        match self {
+            AppError::NotFound(_)
+            | AppError::InvalidInput(_)
+            | AppError::ItemLocked
+            | AppError::Unsupported(_)
+            | AppError::ConfirmationRequired(_) => "user",
+            AppError::PermissionDenied(_) => "permission",
+            AppError::DatabaseLocked(_) | AppError::Database(_) | AppError::Io(_) => "storage",
+            AppError::Other(_) => "internal",
+        }
+    }
+
+    fn details(&self) -> Option<String> {
+        match self {
+            AppError::DatabaseLocked(e) | AppError::Database(e) => match e {
+                rusqlite::Error::SqliteFailure(err, _) => {
+                    Some(format!("SQLite error {}: {}", err.extended_code, e))
+                }
+                _ => Some(e.to_string()),
+            },
+            AppError::Io(e) => Some(match e.raw_os_error() {
+                Some(code) => format!("{:?} (os error {})", e.kind(), code),
+                None => format!("{:?}", e.kind()),
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AppError::NotFound(message)
+            | AppError::PermissionDenied(message)
+            | AppError::InvalidInput(message)
+            | AppError::Unsupported(message)
+            | AppError::ConfirmationRequired(message)
+            | AppError::Other(message) => f.write_str(message),
+            AppError::DatabaseLocked(_) => f.write_str("Database is busy, please try again"),
+            AppError::Database(e) => write!(f, "Database error: {}", e),
+            AppError::Io(e) => e.fmt(f),
+            AppError::ItemLocked => ItemLocked.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("details", &self.details())?;
+        state.end()
+    }
+}
+
+impl From<rusqlite::Error> for AppError {
+    fn from(e: rusqlite::Error) -> Self {
+        match &e {
+            rusqlite::Error::QueryReturnedNoRows => AppError::NotFound("Not found".to_string()),
+            rusqlite::Error::UserFunctionError(inner) if inner.is::<ItemLocked>() => {
+                AppError::ItemLocked
+            }
+            rusqlite::Error::SqliteFailure(err, _) => match err.code {
+                ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked => AppError::DatabaseLocked(e),
+                ErrorCode::PermissionDenied | ErrorCode::ReadOnly => {
+                    AppError::PermissionDenied(e.to_string())
+                }
+                _ => AppError::Database(e),
+            },
+            _ => AppError::Database(e),
+        }
+    }
+}
+
+impl From<std::io::Error> for AppError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            std::io::ErrorKind::NotFound => AppError::NotFound(e.to_string()),
+            std::io::ErrorKind::PermissionDenied => AppError::PermissionDenied(e.to_string()),
+            _ => AppError::Io(e),
+        }
+    }
+}
+
+impl From<image::ImageError> for AppError {
+    fn from(e: image::ImageError) -> Self {
+        match e {
+            image::ImageError::IoError(e) => e.into(),
+            e => AppError::Other(e.to_string()),
+        }
+    }
+}
+
+impl From<serde_json::Error> for AppError {
+    fn from(e: serde_json::Error) -> Self {
+        AppError::InvalidInput(e.to_string())
+    }
+}
+
+// 内部函数仍返回 String 错误，在命令中用 ? 转换
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Other(message)
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Other(message.to_string())
+    }
+}
+
+// 其他模块直接调用命令函数时仍按 String 处理
+impl From<AppError> for String {
+    fn from(e: AppError) -> Self {
+        e.to_string()
+    }
+}
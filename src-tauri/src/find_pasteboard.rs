@@ -0,0 +1,202 @@
+#![allow(deprecated)]
+#![allow(unexpected_cfgs)]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use chrono::Local;
+use tauri::Manager;
+
+use crate::models::ClipboardItem;
+use crate::state::AppState;
+use crate::utils::{classify_with_custom, remove_pruned_images};
+
+#[cfg(target_os = "macos")]
+use cocoa::base::{id, nil};
+#[cfg(target_os = "macos")]
+use cocoa::foundation::{NSAutoreleasePool, NSString};
+#[cfg(target_os = "macos")]
+use objc::{class, msg_send, sel, sel_impl};
+
+#[cfg(target_os = "macos")]
+const FIND_PASTEBOARD_NAME: &str = "Apple CFPasteboard find";
+#[cfg(target_os = "macos")]
+const PLAIN_TEXT_TYPE: &str = "public.utf8-plain-text";
+
+#[cfg(target_os = "macos")]
+unsafe fn find_pasteboard() -> id {
+    let name = NSString::alloc(nil).init_str(FIND_PASTEBOARD_NAME);
+    msg_send![class!(NSPasteboard), pasteboardWithName: name]
+}
+
+#[cfg(target_os = "macos")]
+pub fn change_count() -> i64 {
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+        let pasteboard = find_pasteboard();
+        msg_send![pasteboard, changeCount]
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub fn read_text() -> Option<String> {
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+        let pasteboard = find_pasteboard();
+        let ty = NSString::alloc(nil).init_str(PLAIN_TEXT_TYPE);
+        let string: id = msg_send![pasteboard, stringForType: ty];
+        if string == nil {
+            return None;
+        }
+        let text = std::ffi::CStr::from_ptr(string.UTF8String())
+            .to_string_lossy()
+            .to_string();
+        Some(text)
+    }
+}
+
+/// 写入查找剪贴板，返回写入后的 changeCount
+#[cfg(target_os = "macos")]
+pub fn write_text(text: &str) -> Option<i64> {
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+        let pasteboard = find_pasteboard();
+        let ty = NSString::alloc(nil).init_str(PLAIN_TEXT_TYPE);
+        let value = NSString::alloc(nil).init_str(text);
+        let _: i64 = msg_send![pasteboard, clearContents];
+        let ok: bool = msg_send![pasteboard, setString: value forType: ty];
+        if ok {
+            let count: i64 = msg_send![pasteboard, changeCount];
+            Some(count)
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn change_count() -> i64 {
+    0
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn read_text() -> Option<String> {
+    None
+}
+
+#[cfg(not(target_os = "macos"))]
+pub fn write_text(_text: &str) -> Option<i64> {
+    None
+}
+
+/// 把最新复制的文本同步到查找剪贴板（配置开启时）
+pub fn propagate(app: &tauri::AppHandle, text: &str) {
+    let state = app.state::<AppState>();
+    if !state.config.lock().unwrap().sync_find_pasteboard {
+        return;
+    }
+    if let Some(count) = write_text(text) {
+        if let Ok(mut last_write) = state.last_find_pasteboard_write.lock() {
+            *last_write = Some(count);
+        }
+    }
+}
+
+// 轮询线程是否在运行
+static WATCHING: AtomicBool = AtomicBool::new(false);
+
+/// 开启 monitor_find_pasteboard 时启动轮询线程；关闭后线程在下一次轮询时自行退出
+pub fn restart(app: &tauri::AppHandle) {
+    if !cfg!(target_os = "macos") || !is_enabled(app) {
+        return;
+    }
+    if WATCHING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || watch(app));
+}
+
+fn is_enabled(app: &tauri::AppHandle) -> bool {
+    app.state::<AppState>()
+        .config
+        .lock()
+        .unwrap()
+        .monitor_find_pasteboard
+}
+
+/// 轮询查找剪贴板（macOS 没有它的变更通知），按主剪贴板的规则把新的查找词记录到历史
+fn watch(app: tauri::AppHandle) {
+    let mut last_count = change_count();
+    loop {
+        std::thread::sleep(crate::power::poll_interval(
+            &app,
+            std::time::Duration::from_secs(1),
+        ));
+
+        if !is_enabled(&app) {
+            WATCHING.store(false, Ordering::SeqCst);
+            // 退出前刚好又被开启时，由本线程继续轮询
+            if !is_enabled(&app) || WATCHING.swap(true, Ordering::SeqCst) {
+                return;
+            }
+        }
+
+        let state = app.state::<AppState>();
+        let count = change_count();
+        if count == last_count {
+            continue;
+        }
+        last_count = count;
+
+        let self_write = state
+            .last_find_pasteboard_write
+            .lock()
+            .map(|w| *w == Some(count))
+            .unwrap_or(false);
+        if self_write || crate::utils::is_capture_suspended(&state) {
+            continue;
+        }
+
+        // 与主剪贴板一样，忽略敏感应用中的查找词
+        let source_app = active_win_pos_rs::get_active_window()
+            .ok()
+            .map(|w| w.app_name);
+        let Some(text) = read_text().filter(|t| !t.trim().is_empty()) else {
+            continue;
+        };
+        let (max_size, limited) = {
+            let config = state.config.lock().unwrap();
+            if let Some(name) = source_app.as_deref() {
+                if crate::utils::is_sensitive_app(&config, name) {
+                    log::info!(
+                        "Ignored find pasteboard change from sensitive app: {}",
+                        name
+                    );
+                    continue;
+                }
+            }
+            (config.max_history_size, config.limit_text_length(text))
+        };
+        let Some((text, is_truncated)) = limited else {
+            continue;
+        };
+        let item = ClipboardItem {
+            id: None,
+            data_type: classify_with_custom(&text, &crate::plugins::classifiers(&app)),
+            content: text,
+            kind: "text".to_string(),
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            source_app: Some("Find Pasteboard".to_string()),
+            is_truncated,
+            ..Default::default()
+        };
+        match state.db.insert_item(&item, max_size) {
+            Ok((_, pruned_items)) => {
+                remove_pruned_images(&pruned_items);
+                log::info!("New find pasteboard text captured");
+                crate::utils::emit_clipboard_update(&app);
+            }
+            Err(e) => log::error!("Failed to insert find pasteboard item: {}", e),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::Local;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use tauri::Manager;
+
+use crate::models::{ClipboardItem, HistoryFilter};
+use crate::state::AppState;
+use crate::tray::update_tray_menu;
+use crate::utils::{check_storage_quota, remove_pruned_images};
+
+const IMAGE_EXTENSIONS: [&str; 8] = ["png", "jpg", "jpeg", "gif", "webp", "bmp", "tiff", "heic"];
+// 浏览器下载中 / 编辑器临时文件，完成后会重命名为正式文件
+const PARTIAL_EXTENSIONS: [&str; 6] = ["crdownload", "part", "download", "tmp", "partial", "swp"];
+
+/// 展开 ~/ 开头的目录
+fn expand_dir(dir: &str) -> Option<PathBuf> {
+    let dir = dir.trim();
+    if dir.is_empty() {
+        return None;
+    }
+    let path = match dir.strip_prefix("~/") {
+        Some(rest) => home::home_dir()?.join(rest),
+        None if dir == "~" => home::home_dir()?,
+        None => PathBuf::from(dir),
+    };
+    path.is_dir().then_some(path)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+fn should_ignore(path: &Path) -> bool {
+    let hidden = path
+        .file_name()
+        .map(|n| n.to_string_lossy().starts_with('.'))
+        .unwrap_or(true);
+    hidden || PARTIAL_EXTENSIONS.contains(&extension(path).as_str()) || !path.is_file()
+}
+
+/// 等待文件写入完成：连续两次检查大小不变且不为空
+fn wait_until_stable(path: &Path) -> bool {
+    let mut last_size = None;
+    for _ in 0..20 {
+        std::thread::sleep(Duration::from_millis(500));
+        let Ok(size) = std::fs::metadata(path).map(|m| m.len()) else {
+            return false;
+        };
+        if size > 0 && last_size == Some(size) {
+            return true;
+        }
+        last_size = Some(size);
+    }
+    false
+}
+
+fn add_file(app: &tauri::AppHandle, path: &Path) {
+    let state = app.state::<AppState>();
+    if crate::utils::is_capture_suspended(&state) || crate::shutdown::is_shutting_down(app) {
+        return;
+    }
+    let max_size = state.config.lock().unwrap().max_history_size;
+
+    let item = if IMAGE_EXTENSIONS.contains(&extension(path).as_str()) {
+        // 复制到图片目录，避免历史被清理时删除用户的原文件
+        let Ok(app_data_dir) = app.path().app_data_dir() else {
+            return;
+        };
+        let filename = format!(
+            "{}.{}",
+            Local::now().timestamp_nanos_opt().unwrap_or(0),
+            extension(path)
+        );
+        let images_dir = app_data_dir.join("images");
+        if let Err(e) = std::fs::create_dir_all(&images_dir) {
+            log::error!("Failed to create images directory: {}", e);
+            return;
+        }
+        let image_path = images_dir.join(filename);
+        if let Err(e) = std::fs::copy(path, &image_path) {
+            log::error!("Failed to copy watched image {:?}: {}", path, e);
+            return;
+        }
+        ClipboardItem {
+            content: image_path.to_string_lossy().to_string(),
+            kind: "image".to_string(),
+            data_type: "image".to_string(),
+            ..Default::default()
+        }
+    } else {
+        let files = vec![path.to_string_lossy().to_string()];
+        ClipboardItem {
+            content: serde_json::to_string(&files).unwrap_or_default(),
+            kind: "file".to_string(),
+            data_type: "file-list".to_string(),
+            ..Default::default()
+        }
+    };
+    let item = ClipboardItem {
+        timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        source_app: Some("Folder Watch".to_string()),
+        ..item
+    };
+
+    match state.db.insert_item(&item, max_size) {
+        Ok((_, pruned_items)) => {
+            remove_pruned_images(&pruned_items);
+            log::info!("Added watched file {:?}", path);
+            let history = state
+                .db
+                .get_history(1, 20, &HistoryFilter::default())
+                .unwrap_or_default();
+            if let Err(e) = update_tray_menu(app, &history) {
+                log::error!("Failed to update tray: {}", e);
+            }
+            crate::utils::emit_clipboard_update(app);
+            check_storage_quota(app);
+        }
+        Err(e) => log::error!("Failed to insert watched file: {}", e),
+    }
+}
+
+/// 按配置的目录（重新）创建监听器，目录为空时停止监听
+pub fn restart(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let dirs: Vec<PathBuf> = state
+        .config
+        .lock()
+        .unwrap()
+        .watch_folders
+        .iter()
+        .filter_map(|d| expand_dir(d))
+        .collect();
+
+    let mut slot = state.folder_watcher.lock().unwrap();
+    // 先释放旧的监听器
+    *slot = None;
+    if dirs.is_empty() {
+        return;
+    }
+
+    // 同一个文件可能先后触发创建和重命名事件，只处理一次
+    let seen: Arc<Mutex<HashSet<PathBuf>>> = Arc::new(Mutex::new(HashSet::new()));
+    let handle = app.clone();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        let event = match res {
+            Ok(event) => event,
+            Err(e) => {
+                log::error!("Folder watch error: {}", e);
+                return;
+            }
+        };
+        let is_new = matches!(
+            event.kind,
+            EventKind::Create(_)
+                | EventKind::Modify(ModifyKind::Name(
+                    RenameMode::To | RenameMode::Both | RenameMode::Any
+                ))
+        );
+        if !is_new {
+            return;
+        }
+        // 重命名事件的最后一个路径是新文件名
+        let Some(path) = event.paths.last().cloned() else {
+            return;
+        };
+        if should_ignore(&path) || !seen.lock().unwrap().insert(path.clone()) {
+            return;
+        }
+
+        let handle = handle.clone();
+        let seen = seen.clone();
+        std::thread::spawn(move || {
+            if wait_until_stable(&path) {
+                add_file(&handle, &path);
+            }
+            // 稍后允许同名文件被再次添加（例如被覆盖）
+            std::thread::sleep(Duration::from_secs(5));
+            seen.lock().unwrap().remove(&path);
+        });
+    });
+
+    let mut watcher: RecommendedWatcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log::error!("Failed to create folder watcher: {}", e);
+            return;
+        }
+    };
+    for dir in &dirs {
+        match watcher.watch(dir, RecursiveMode::NonRecursive) {
+            Ok(()) => log::info!("Watching folder {:?}", dir),
+            Err(e) => log::error!("Failed to watch folder {:?}: {}", dir, e),
+        }
+    }
+    *slot = Some(watcher);
+}
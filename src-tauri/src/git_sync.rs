@@ -0,0 +1,536 @@
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::{Emitter, Manager};
+
+use crate::error::AppError;
+use crate::models::{ClipboardItem, GitSyncResult};
+use crate::state::AppState;
+use crate::utils::{classify_content, remove_pruned_images};
+
+// 通过系统安装的 git 命令行同步，而不是 libgit2 / gix：推送和拉取直接沿用用户已配置的
+// 凭据助手、SSH agent 和 ~/.ssh/config，不需要另外实现认证，也不必打包 libgit2 和 OpenSSL。
+// 代价是需要 PATH 中有 git，启动时和保存配置时检查，缺失时报 Unsupported
+const MANIFEST_FILE: &str = "manifest.json";
+const SNIPPETS_DIR: &str = "snippets";
+// 单条 git 命令的最长运行时间，网络卡住时不至于一直占着同步锁
+const GIT_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// 仓库中的 manifest.json，记录每个片段文件的元数据
+#[derive(Serialize, Deserialize, Default)]
+struct Manifest {
+    snippets: Vec<ManifestEntry>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct ManifestEntry {
+    file: String,
+    #[serde(default)]
+    note: Option<String>,
+    #[serde(default)]
+    data_type: Option<String>,
+    // 片段最初来自的设备
+    #[serde(default)]
+    device: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tags: Vec<String>,
+}
+
+/// 本地记录的上次同步结果：文件 -> (条目 id, 同步时的内容)，用于区分本地修改和远端修改
+#[derive(Serialize, Deserialize, Default, Clone)]
+struct SyncState {
+    repo: String,
+    files: BTreeMap<String, SyncedFile>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+struct SyncedFile {
+    item_id: i64,
+    content: String,
+}
+
+/// 在后台运行 git：禁止任何凭据提示（没有终端可以输入），超时后结束进程
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let name = args.first().copied().unwrap_or("");
+    let mut child = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .env("GIT_TERMINAL_PROMPT", "0")
+        .env("GIT_ASKPASS", "true")
+        .env("SSH_ASKPASS", "true")
+        .env("GCM_INTERACTIVE", "never")
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to run git (is it installed?): {}", e))?;
+    // 输出较多时管道会写满，必须边运行边读取
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        std::thread::spawn(move || {
+            let mut buf = Vec::new();
+            if let Some(mut pipe) = pipe {
+                let _ = pipe.read_to_end(&mut buf);
+            }
+            String::from_utf8_lossy(&buf).to_string()
+        })
+    };
+    let stdout = read(child.stdout.take().map(|p| Box::new(p) as _));
+    let stderr = read(child.stderr.take().map(|p| Box::new(p) as _));
+
+    let deadline = Instant::now() + GIT_TIMEOUT;
+    let status = loop {
+        match child.try_wait().map_err(|e| e.to_string())? {
+            Some(status) => break status,
+            None if Instant::now() >= deadline => {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(format!(
+                    "git {} timed out after {} seconds",
+                    name,
+                    GIT_TIMEOUT.as_secs()
+                ));
+            }
+            None => std::thread::sleep(Duration::from_millis(100)),
+        }
+    };
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+    if !status.success() {
+        return Err(format!("git {} failed: {}", name, stderr.trim()));
+    }
+    Ok(stdout)
+}
+
+fn sync_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("git_sync"))
+}
+
+/// 新片段的文件名：首行内容的简短 slug + 内容哈希，避免不同设备上的文件名冲突
+fn snippet_file_name(item: &ClipboardItem) -> String {
+    let title = item
+        .note
+        .as_deref()
+        .filter(|n| !n.trim().is_empty())
+        .unwrap_or_else(|| item.content.lines().next().unwrap_or(""));
+    let slug: String = title
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { '-' })
+        .collect::<String>()
+        .split('-')
+        .filter(|s| !s.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+        .chars()
+        .take(40)
+        .collect();
+    // 各设备上必须得到相同的文件名，不能用 DefaultHasher
+    let hash = format!("{:016x}", crate::similarity::fnv1a(item.content.bytes()));
+    if slug.is_empty() {
+        format!("{}/{}.txt", SNIPPETS_DIR, &hash[..8])
+    } else {
+        format!("{}/{}-{}.txt", SNIPPETS_DIR, slug, &hash[..8])
+    }
+}
+
+/// PATH 中是否有可用的 git，结果只检查一次
+fn git_available() -> bool {
+    static AVAILABLE: OnceLock<bool> = OnceLock::new();
+    *AVAILABLE.get_or_init(|| {
+        Command::new("git")
+            .arg("--version")
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    })
+}
+
+/// Git 同步依赖 git 命令行，找不到时返回 Unsupported
+pub fn check_git() -> Result<(), AppError> {
+    if git_available() {
+        Ok(())
+    } else {
+        Err(AppError::Unsupported(
+            "Git sync requires the git command line tool on PATH".to_string(),
+        ))
+    }
+}
+
+/// 仓库地址不能以 - 开头，否则会被 git 当作选项（如 --upload-pack）
+pub fn validate_repo(repo: &str) -> Result<(), String> {
+    if repo.trim_start().starts_with('-') {
+        return Err(format!("Invalid Git sync repository: {}", repo));
+    }
+    Ok(())
+}
+
+fn load_json<T: for<'de> Deserialize<'de> + Default>(path: &Path) -> T {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+fn save_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())
+}
+
+/// 把收藏夹中的本地修改和删除写入工作区并提交，返回写入的片段数
+fn export_local(
+    state: &AppState,
+    dir: &Path,
+    items: &HashMap<i64, ClipboardItem>,
+    sync_state: &mut SyncState,
+    published_device: Option<&str>,
+) -> Result<usize, String> {
+    // 重置到远端后 snippets/ 可能不存在
+    std::fs::create_dir_all(dir.join(SNIPPETS_DIR)).map_err(|e| e.to_string())?;
+    let mut pushed = 0;
+    let mut manifest: Manifest = load_json(&dir.join(MANIFEST_FILE));
+    let tracked: HashMap<i64, String> = sync_state
+        .files
+        .iter()
+        .map(|(file, synced)| (synced.item_id, file.clone()))
+        .collect();
+
+    for (id, item) in &items {
+        let file = match tracked.get(id) {
+            Some(file) => {
+                let unchanged = sync_state
+                    .files
+                    .get(file)
+                    .map(|s| s.content == item.content)
+                    .unwrap_or(false);
+                if unchanged {
+                    continue;
+                }
+                file.clone()
+            }
+            None => snippet_file_name(item),
+        };
+        std::fs::write(dir.join(&file), &item.content).map_err(|e| e.to_string())?;
+        manifest.snippets.retain(|e| e.file != file);
+        manifest.snippets.push(ManifestEntry {
+            file: file.clone(),
+            note: item.note.clone(),
+            data_type: Some(item.data_type.clone()),
+            device: item
+                .origin_device
+                .clone()
+                .or_else(|| published_device.map(str::to_string)),
+            tags: state.db.get_item_tags(*id).unwrap_or_default(),
+        });
+        sync_state.files.insert(
+            file,
+            SyncedFile {
+                item_id: *id,
+                content: item.content.clone(),
+            },
+        );
+        pushed += 1;
+    }
+
+    // 本地已移出收藏夹的片段，从仓库中删除
+    let removed: Vec<String> = sync_state
+        .files
+        .iter()
+        .filter(|(_, synced)| !items.contains_key(&synced.item_id))
+        .map(|(file, _)| file.clone())
+        .collect();
+    for file in removed {
+        let _ = std::fs::remove_file(dir.join(&file));
+        manifest.snippets.retain(|e| e.file != file);
+        sync_state.files.remove(&file);
+        pushed += 1;
+    }
+
+    if pushed > 0 {
+        manifest.snippets.sort_by(|a, b| a.file.cmp(&b.file));
+        save_json(&dir.join(MANIFEST_FILE), &manifest)?;
+    }
+    git(&dir, &["add", "-A"])?;
+    if !git(&dir, &["status", "--porcelain"])?.trim().is_empty() {
+        let message = format!(
+            "Update snippets ({})",
+            Local::now().format("%Y-%m-%d %H:%M:%S")
+        );
+        git(&dir, &["commit", "--quiet", "-m", &message])?;
+    }
+    Ok(pushed)
+}
+
+/// 同步一次：本地修改写入工作区并提交，拉取远端（rebase），再把仓库内容导入收藏夹并推送
+pub fn sync_once(app: &tauri::AppHandle) -> Result<GitSyncResult, String> {
+    let state = app.state::<AppState>();
+    let (repo, collection_id, max_size, device, published_device) = {
+        let config = state.config.lock().unwrap();
+        (
+            config.git_sync_repo.trim().to_string(),
+            config.git_sync_collection_id,
+            config.max_history_size,
+            crate::utils::device_name(&config),
+            // 共享仓库对团队可见，不默认公开主机名，只写入用户明确设置的设备名
+            Some(config.device_name.trim().to_string()).filter(|name| !name.is_empty()),
+        )
+    };
+    let collection_id = collection_id.ok_or("No collection selected for Git sync")?;
+    if repo.is_empty() {
+        return Err("Git sync repository is not configured".to_string());
+    }
+    validate_repo(&repo)?;
+
+    let dir = sync_dir(app)?;
+    let state_path = dir.with_extension("json");
+    let mut sync_state: SyncState = load_json(&state_path);
+
+    // 仓库地址变化时重新克隆
+    if sync_state.repo != repo || !dir.join(".git").exists() {
+        if dir.exists() {
+            std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+        }
+        let parent = dir.parent().ok_or("Invalid sync directory")?;
+        git(
+            parent,
+            &["clone", "--quiet", "--", &repo, &dir.to_string_lossy()],
+        )?;
+        sync_state = SyncState {
+            repo: repo.clone(),
+            files: BTreeMap::new(),
+        };
+    }
+
+    let mut result = GitSyncResult::default();
+
+    // 1. 本地修改写入工作区
+    let items: HashMap<i64, ClipboardItem> = state
+        .db
+        .get_collection_items(collection_id)
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .filter(|i| i.kind == "text" && !i.is_sensitive)
+        .filter_map(|i| Some((i.id?, i)))
+        .collect();
+    let before_export = sync_state.clone();
+    result.pushed = export_local(
+        &state,
+        &dir,
+        &items,
+        &mut sync_state,
+        published_device.as_deref(),
+    )?;
+
+    // 2. 拉取远端（空仓库没有分支可拉）；rebase 冲突时放弃本地提交，重置到远端后重新写入本地修改，
+    // 同一片段两边都改过时以本地为准
+    let remote_empty = git(&dir, &["ls-remote", "--heads", "origin"])?
+        .trim()
+        .is_empty();
+    if !remote_empty {
+        if let Err(e) = git(&dir, &["pull", "--quiet", "--rebase"]) {
+            log::warn!("Git sync pull failed, re-exporting local changes: {}", e);
+            let _ = git(&dir, &["rebase", "--abort"]);
+            git(&dir, &["reset", "--quiet", "--hard", "@{upstream}"])?;
+            sync_state = before_export;
+            result.pushed = export_local(
+                &state,
+                &dir,
+                &items,
+                &mut sync_state,
+                published_device.as_deref(),
+            )?;
+        }
+    }
+
+    // 3. 把仓库内容导入收藏夹
+    let manifest: Manifest = load_json(&dir.join(MANIFEST_FILE));
+    for entry in &manifest.snippets {
+        // 只接受 snippets/ 下的文件
+        if !entry.file.starts_with(&format!("{}/", SNIPPETS_DIR)) || entry.file.contains("..") {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(dir.join(&entry.file)) else {
+            continue;
+        };
+        // 其他设备可能用 GBK 等编码提交片段
+        let decoded = crate::encoding::decode(&bytes);
+        let content = decoded.text.clone();
+        let data_type = entry
+            .data_type
+            .clone()
+            .unwrap_or_else(|| classify_content(&content));
+
+        match sync_state.files.get(&entry.file).cloned() {
+            Some(synced) if synced.content == content => {}
+            // 锁定的条目不被远端修改覆盖
+            Some(synced) if items.get(&synced.item_id).is_some_and(|i| i.is_locked) => {}
+            Some(synced) if items.contains_key(&synced.item_id) => {
+                state
+                    .db
+                    .update_content(
+                        synced.item_id,
+                        content.clone(),
+                        data_type,
+                        entry.note.clone(),
+                        None,
+                    )
+                    .map_err(|e| e.to_string())?;
+                crate::encoding::record(&state.db, synced.item_id, &decoded);
+                state
+                    .content_cache
+                    .lock()
+                    .unwrap()
+                    .invalidate(synced.item_id);
+                sync_state.files.insert(
+                    entry.file.clone(),
+                    SyncedFile {
+                        item_id: synced.item_id,
+                        content,
+                    },
+                );
+                result.updated += 1;
+            }
+            _ => {
+                let item = ClipboardItem {
+                    content: content.clone(),
+                    kind: "text".to_string(),
+                    data_type,
+                    timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+                    source_app: Some("Git Sync".to_string()),
+                    note: entry.note.clone(),
+                    collection_id: Some(collection_id),
+                    origin_device: entry.device.clone().filter(|d| *d != device),
+                    ..Default::default()
+                };
+                // 本机已有相同内容时合并，不产生重复条目
+                let merged = state
+                    .db
+                    .merge_item(&item, &entry.tags, max_size)
+                    .map_err(|e| e.to_string())?;
+                remove_pruned_images(&merged.pruned);
+                let id = merged.id;
+                if !merged.merged {
+                    crate::encoding::record(&state.db, id, &decoded);
+                } else if merged.collection_id.is_none() {
+                    state
+                        .db
+                        .set_item_collection(id, Some(collection_id))
+                        .map_err(|e| e.to_string())?;
+                } else if merged.collection_id != Some(collection_id) {
+                    // 已在用户自己收藏夹中的条目不移动也不跟踪，否则下次同步会把它推送到共享仓库
+                    continue;
+                }
+                sync_state.files.insert(
+                    entry.file.clone(),
+                    SyncedFile {
+                        item_id: id,
+                        content,
+                    },
+                );
+                result.added += 1;
+            }
+        }
+    }
+
+    // 远端删除的片段移出收藏夹（保留在历史中）
+    let remote_removed: Vec<(String, i64)> = sync_state
+        .files
+        .iter()
+        .filter(|(file, _)| !manifest.snippets.iter().any(|e| &e.file == *file))
+        .map(|(file, synced)| (file.clone(), synced.item_id))
+        .collect();
+    for (file, item_id) in remote_removed {
+        let _ = state.db.set_item_collection(item_id, None);
+        sync_state.files.remove(&file);
+        result.removed += 1;
+    }
+
+    save_json(&state_path, &sync_state)?;
+
+    // 上次推送失败的提交也在这里补推；仓库还没有任何提交时跳过
+    if git(&dir, &["rev-parse", "--verify", "--quiet", "HEAD"]).is_ok() {
+        git(&dir, &["push", "--quiet", "origin", "HEAD"])?;
+    }
+    if result.added + result.updated + result.removed > 0 {
+        crate::utils::emit_clipboard_update(app);
+    }
+    Ok(result)
+}
+
+/// 同步并发出 git-sync-finished / git-sync-error 事件，同一时间只运行一次
+pub fn run(app: &tauri::AppHandle) -> Result<GitSyncResult, String> {
+    let state = app.state::<AppState>();
+    let _guard = state
+        .git_sync_lock
+        .try_lock()
+        .map_err(|_| "Git sync is already running".to_string())?;
+    let result = check_git()
+        .map_err(|e| e.to_string())
+        .and_then(|()| sync_once(app));
+    match &result {
+        Ok(summary) => {
+            log::info!(
+                "Git sync finished: pushed {}, added {}, updated {}, removed {}",
+                summary.pushed,
+                summary.added,
+                summary.updated,
+                summary.removed
+            );
+            let _ = app.emit("git-sync-finished", summary);
+        }
+        Err(e) => {
+            log::warn!("Git sync failed: {}", e);
+            let _ = app.emit("git-sync-error", e);
+        }
+    }
+    result
+}
+
+/// 后台循环：按配置的间隔同步
+pub async fn sync_loop(app: tauri::AppHandle) {
+    let configured = !app
+        .state::<AppState>()
+        .config
+        .lock()
+        .unwrap()
+        .git_sync_repo
+        .trim()
+        .is_empty();
+    if configured {
+        if let Err(e) = check_git() {
+            log::error!("{}", e);
+            let _ = app.emit("git-sync-error", e.to_string());
+        }
+    }
+
+    let mut last_sync: Option<Instant> = None;
+    loop {
+        let (enabled, interval) = {
+            let state = app.state::<AppState>();
+            let config = state.config.lock().unwrap();
+            (
+                !config.git_sync_repo.trim().is_empty() && config.git_sync_collection_id.is_some(),
+                Duration::from_secs(config.git_sync_interval_minutes.max(1) * 60),
+            )
+        };
+
+        if enabled && last_sync.map(|t| t.elapsed() >= interval).unwrap_or(true) {
+            last_sync = Some(Instant::now());
+            let handle = app.clone();
+            let _ = tauri::async_runtime::spawn_blocking(move || run(&handle)).await;
+        }
+
+        tokio::time::sleep(Duration::from_secs(60)).await;
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::{BTreeMap, HashMap};
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use tauri::{Emitter, Manager};
+
+use crate::models::JobInfo;
+use crate::state::AppState;
+
+/// 数值越小越先执行
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum JobPriority {
+    High,
+    Normal,
+    Low,
+}
+
+impl JobPriority {
+    fn as_str(&self) -> &'static str {
+        match self {
+            JobPriority::High => "high",
+            JobPriority::Normal => "normal",
+            JobPriority::Low => "low",
+        }
+    }
+}
+
+// 同类任务的并发上限，未列出的类型只受总并发数限制
+const KIND_LIMITS: [(&str, usize); 3] = [("ocr", 1), ("ocr-backfill", 1), ("prewarm", 1)];
+
+type JobFn = Box<dyn FnOnce(&JobContext) -> Result<(), String> + Send>;
+
+struct PendingJob {
+    kind: String,
+    run: JobFn,
+}
+
+struct RunningJob {
+    kind: String,
+    priority: JobPriority,
+    cancelled: Arc<AtomicBool>,
+    done: usize,
+    total: usize,
+}
+
+struct Inner {
+    // (优先级, 入队顺序) -> 任务，同优先级先进先出
+    pending: BTreeMap<(JobPriority, u64), PendingJob>,
+    running: HashMap<u64, RunningJob>,
+    next_id: u64,
+    workers: usize,
+    max_workers: usize,
+}
+
+/// 后台任务队列：按优先级调度，限制总并发和同类并发，支持取消并通过 job-progress 事件汇报状态
+pub struct JobQueue {
+    inner: Mutex<Inner>,
+}
+
+/// 传给任务的上下文，长任务应定期检查 is_cancelled
+pub struct JobContext {
+    app: tauri::AppHandle,
+    id: u64,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl JobContext {
+    pub fn app(&self) -> &tauri::AppHandle {
+        &self.app
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn progress(&self, done: usize, total: usize) {
+        let state = self.app.state::<AppState>();
+        let info = {
+            let mut inner = state.jobs.inner.lock().unwrap();
+            let Some(job) = inner.running.get_mut(&self.id) else {
+                return;
+            };
+            job.done = done;
+            job.total = total;
+            job_info(
+                self.id,
+                &job.kind,
+                job.priority,
+                "running",
+                done,
+                total,
+                None,
+            )
+        };
+        let _ = self.app.emit("job-progress", info);
+    }
+}
+
+fn job_info(
+    id: u64,
+    kind: &str,
+    priority: JobPriority,
+    status: &str,
+    done: usize,
+    total: usize,
+    error: Option<String>,
+) -> JobInfo {
+    JobInfo {
+        id,
+        kind: kind.to_string(),
+        priority: priority.as_str().to_string(),
+        status: status.to_string(),
+        done,
+        total,
+        error,
+    }
+}
+
+fn kind_limit(kind: &str) -> usize {
+    KIND_LIMITS
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map(|(_, limit)| *limit)
+        .unwrap_or(usize::MAX)
+}
+
+impl JobQueue {
+    pub fn new(max_workers: usize) -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                pending: BTreeMap::new(),
+                running: HashMap::new(),
+                next_id: 0,
+                workers: 0,
+                max_workers: max_workers.max(1),
+            }),
+        }
+    }
+
+    /// 加入队列，返回任务 id；有空闲名额时启动新的工作线程
+    pub fn schedule<F>(
+        &self,
+        app: &tauri::AppHandle,
+        kind: &str,
+        priority: JobPriority,
+        run: F,
+    ) -> u64
+    where
+        F: FnOnce(&JobContext) -> Result<(), String> + Send + 'static,
+    {
+        let (id, spawn) = {
+            let mut inner = self.inner.lock().unwrap();
+            inner.next_id += 1;
+            let id = inner.next_id;
+            inner.pending.insert(
+                (priority, id),
+                PendingJob {
+                    kind: kind.to_string(),
+                    run: Box::new(run),
+                },
+            );
+            let spawn = inner.workers < inner.max_workers;
+            if spawn {
+                inner.workers += 1;
+            }
+            (id, spawn)
+        };
+        let _ = app.emit(
+            "job-progress",
+            job_info(id, kind, priority, "queued", 0, 0, None),
+        );
+        if spawn {
+            let app = app.clone();
+            std::thread::spawn(move || worker(app));
+        }
+        id
+    }
+
+    /// 调整总并发数，多出的工作线程做完当前任务后退出
+    pub fn set_max_workers(&self, app: &tauri::AppHandle, max_workers: usize) {
+        self.inner.lock().unwrap().max_workers = max_workers.max(1);
+        self.wake(app);
+    }
+
+    /// 为排队中的任务补足工作线程，例如退出省电模式后继续被推迟的任务
+    pub fn wake(&self, app: &tauri::AppHandle) {
+        let spawn = {
+            let mut inner = self.inner.lock().unwrap();
+            let spawn = inner
+                .max_workers
+                .saturating_sub(inner.workers)
+                .min(inner.pending.len());
+            inner.workers += spawn;
+            spawn
+        };
+        for _ in 0..spawn {
+            let app = app.clone();
+            std::thread::spawn(move || worker(app));
+        }
+    }
+
+    /// 取消排队中的任务，或通知运行中的任务停止
+    pub fn cancel(&self, app: &tauri::AppHandle, id: u64) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(job) = inner.running.get(&id) {
+            job.cancelled.store(true, Ordering::Relaxed);
+            return true;
+        }
+        let Some(key) = inner.pending.keys().find(|(_, i)| *i == id).copied() else {
+            return false;
+        };
+        if let Some(job) = inner.pending.remove(&key) {
+            let _ = app.emit(
+                "job-progress",
+                job_info(id, &job.kind, key.0, "cancelled", 0, 0, None),
+            );
+        }
+        true
+    }
+
+    /// 取消某一类的全部任务
+    pub fn cancel_kind(&self, app: &tauri::AppHandle, kind: &str) {
+        let mut inner = self.inner.lock().unwrap();
+        for job in inner.running.values().filter(|job| job.kind == kind) {
+            job.cancelled.store(true, Ordering::Relaxed);
+        }
+        let keys: Vec<(JobPriority, u64)> = inner
+            .pending
+            .iter()
+            .filter(|(_, job)| job.kind == kind)
+            .map(|(key, _)| *key)
+            .collect();
+        for key in keys {
+            inner.pending.remove(&key);
+            let _ = app.emit(
+                "job-progress",
+                job_info(key.1, kind, key.0, "cancelled", 0, 0, None),
+            );
+        }
+    }
+
+    /// 退出时清空队列并通知运行中的任务停止
+    pub fn cancel_all(&self, app: &tauri::AppHandle) {
+        let mut inner = self.inner.lock().unwrap();
+        for job in inner.running.values() {
+            job.cancelled.store(true, Ordering::Relaxed);
+        }
+        for ((priority, id), job) in std::mem::take(&mut inner.pending) {
+            let _ = app.emit(
+                "job-progress",
+                job_info(id, &job.kind, priority, "cancelled", 0, 0, None),
+            );
+        }
+    }
+
+    /// 是否有该类任务在排队或运行
+    pub fn is_active(&self, kind: &str) -> bool {
+        let inner = self.inner.lock().unwrap();
+        inner.running.values().any(|job| job.kind == kind)
+            || inner.pending.values().any(|job| job.kind == kind)
+    }
+
+    pub fn list(&self) -> Vec<JobInfo> {
+        let inner = self.inner.lock().unwrap();
+        let mut jobs: Vec<JobInfo> = inner
+            .running
+            .iter()
+            .map(|(id, job)| {
+                job_info(
+                    *id,
+                    &job.kind,
+                    job.priority,
+                    "running",
+                    job.done,
+                    job.total,
+                    None,
+                )
+            })
+            .collect();
+        jobs.sort_by_key(|job| job.id);
+        jobs.extend(inner.pending.iter().map(|((priority, id), job)| {
+            job_info(*id, &job.kind, *priority, "queued", 0, 0, None)
+        }));
+        jobs
+    }
+}
+
+/// 取出优先级最高、且同类并发未满的任务；省电时跳过可推迟的任务
+fn next_job(
+    inner: &mut Inner,
+    power_saving: bool,
+) -> Option<(u64, JobPriority, String, JobFn, Arc<AtomicBool>)> {
+    let key = inner
+        .pending
+        .iter()
+        .filter(|(_, job)| {
+            !power_saving || !crate::power::DEFERRED_JOB_KINDS.contains(&job.kind.as_str())
+        })
+        .find(|(_, job)| {
+            let running = inner
+                .running
+                .values()
+                .filter(|r| r.kind == job.kind)
+                .count();
+            running < kind_limit(&job.kind)
+        })
+        .map(|(key, _)| *key)?;
+    let job = inner.pending.remove(&key)?;
+    let cancelled = Arc::new(AtomicBool::new(false));
+    inner.running.insert(
+        key.1,
+        RunningJob {
+            kind: job.kind.clone(),
+            priority: key.0,
+            cancelled: cancelled.clone(),
+            done: 0,
+            total: 0,
+        },
+    );
+    Some((key.1, key.0, job.kind, job.run, cancelled))
+}
+
+fn worker(app: tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    loop {
+        let power_saving = crate::power::is_power_saving(&app);
+        let (id, priority, kind, run, cancelled) = {
+            let mut inner = state.jobs.inner.lock().unwrap();
+            let job = if inner.workers > inner.max_workers {
+                None
+            } else {
+                next_job(&mut inner, power_saving)
+            };
+            match job {
+                Some(job) => job,
+                None => {
+                    inner.workers -= 1;
+                    return;
+                }
+            }
+        };
+        let _ = app.emit(
+            "job-progress",
+            job_info(id, &kind, priority, "running", 0, 0, None),
+        );
+
+        let ctx = JobContext {
+            app: app.clone(),
+            id,
+            cancelled,
+        };
+        let result = std::panic::catch_unwind(AssertUnwindSafe(|| run(&ctx)))
+            .unwrap_or_else(|_| Err("Job panicked".to_string()));
+
+        let (done, total) = state
+            .jobs
+            .inner
+            .lock()
+            .unwrap()
+            .running
+            .remove(&id)
+            .map(|job| (job.done, job.total))
+            .unwrap_or_default();
+        let (status, error) = match result {
+            Ok(()) if ctx.is_cancelled() => ("cancelled", None),
+            Ok(()) => ("finished", None),
+            Err(e) => {
+                log::error!("Background job {} ({}) failed: {}", id, kind, e);
+                ("failed", Some(e))
+            }
+        };
+        let _ = app.emit(
+            "job-progress",
+            job_info(id, &kind, priority, status, done, total, error),
+        );
+    }
+}
@@ -0,0 +1,338 @@
+#![allow(deprecated)]
+#![allow(unexpected_cfgs)]
+
+use tauri::Manager;
+
+use crate::models::ClipboardItem;
+use crate::state::AppState;
+
+// 跳转列表 / Dock 菜单显示的最近条目数量和标题长度
+const MAX_RECENT: usize = 5;
+const LABEL_CHARS: usize = 40;
+
+// 跳转列表启动程序时带的参数，由单实例插件转交给正在运行的实例
+const PASTE_ARG: &str = "--paste-item=";
+const PAUSE_ARG: &str = "--toggle-pause";
+const CAPTURE_ARG: &str = "--capture";
+
+/// 跳转列表和 Dock 菜单中的操作
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Action {
+    Paste(i64),
+    TogglePause,
+    Capture,
+}
+
+fn label(item: &ClipboardItem) -> String {
+    match item.kind.as_str() {
+        "text" => item
+            .content
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or_default()
+            .trim()
+            .chars()
+            .take(LABEL_CHARS)
+            .collect(),
+        kind => format!("[{}]", kind),
+    }
+}
+
+fn run(app: &tauri::AppHandle, action: Action) {
+    let palette_action = match action {
+        Action::Paste(id) => {
+            if let Err(e) = crate::quick_paste::paste(app, id) {
+                log::error!("Failed to paste item {} from jump list: {}", id, e);
+            }
+            return;
+        }
+        Action::TogglePause => "toggle_pause",
+        Action::Capture => "capture_screen",
+    };
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = crate::palette::run(&app, palette_action, None).await {
+            log::error!("Failed to run jump list task {}: {}", palette_action, e);
+        }
+    });
+}
+
+/// 处理启动参数（跳转列表中的条目和任务）；只粘贴当前跳转列表中发布的条目，
+/// 其他程序无法借参数粘贴任意历史
+pub fn handle_args(app: &tauri::AppHandle, args: &[String]) {
+    let published: Vec<i64> = app
+        .state::<AppState>()
+        .jump_list_items
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, _)| *id)
+        .collect();
+    for arg in args {
+        let action = if let Some(id) = arg.strip_prefix(PASTE_ARG) {
+            match id.parse() {
+                Ok(id) if published.contains(&id) => Some(Action::Paste(id)),
+                _ => {
+                    log::warn!("Ignoring jump list paste of unpublished item {}", id);
+                    None
+                }
+            }
+        } else if arg == PAUSE_ARG {
+            Some(Action::TogglePause)
+        } else if arg == CAPTURE_ARG {
+            Some(Action::Capture)
+        } else {
+            None
+        };
+        if let Some(action) = action {
+            run(app, action);
+        }
+    }
+}
+
+/// 用最近的条目和 暂停 / 截图 任务刷新 Windows 跳转列表和 macOS Dock 菜单，与托盘菜单一起更新。
+/// 跳转列表的标题由系统保存，所以不列出敏感条目，演示模式下不列出任何条目
+pub fn update(app: &tauri::AppHandle, history: &[ClipboardItem]) {
+    let state = app.state::<AppState>();
+    let presentation_mode = state.config.lock().unwrap().presentation_mode;
+    let recent: Vec<(i64, String)> = if presentation_mode {
+        Vec::new()
+    } else {
+        history
+            .iter()
+            .filter(|item| !item.is_sensitive)
+            .filter_map(|item| Some((item.id?, label(item))))
+            .take(MAX_RECENT)
+            .collect()
+    };
+    {
+        let mut shown = state.jump_list_items.lock().unwrap();
+        if *shown == recent {
+            return;
+        }
+        *shown = recent.clone();
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        windows_jump_list::schedule(recent);
+    }
+    #[cfg(target_os = "macos")]
+    {
+        dock_menu::update(app, recent);
+    }
+}
+
+#[cfg(target_os = "windows")]
+mod windows_jump_list {
+    use std::sync::mpsc::{self, Sender};
+    use std::sync::OnceLock;
+
+    use windows::core::{ComInterface, HSTRING, PWSTR};
+    use windows::Win32::Storage::EnhancedStorage::PKEY_Title;
+    use windows::Win32::System::Com::StructuredStorage::PROPVARIANT;
+    use windows::Win32::System::Com::{
+        CoCreateInstance, CoInitializeEx, CLSCTX_INPROC_SERVER, COINIT_APARTMENTTHREADED,
+    };
+    use windows::Win32::System::Variant::VT_LPWSTR;
+    use windows::Win32::UI::Shell::Common::{IObjectArray, IObjectCollection};
+    use windows::Win32::UI::Shell::PropertiesSystem::IPropertyStore;
+    use windows::Win32::UI::Shell::{
+        DestinationList, EnumerableObjectCollection, ICustomDestinationList, IShellLinkW, ShellLink,
+    };
+
+    use super::{CAPTURE_ARG, PASTE_ARG, PAUSE_ARG};
+
+    /// 指向本程序的快捷方式，标题写在 PKEY_Title 中
+    unsafe fn link(exe: &HSTRING, args: &str, title: &str) -> windows::core::Result<IShellLinkW> {
+        let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER)?;
+        link.SetPath(exe)?;
+        link.SetArguments(&HSTRING::from(args))?;
+        link.SetIconLocation(exe, 0)?;
+
+        // SetValue 会复制字符串，title 只需在调用期间有效
+        let title: Vec<u16> = title.encode_utf16().chain(std::iter::once(0)).collect();
+        let mut value = PROPVARIANT::default();
+        (*value.Anonymous.Anonymous).vt = VT_LPWSTR;
+        (*value.Anonymous.Anonymous).Anonymous.pwszVal = PWSTR(title.as_ptr() as *mut u16);
+        let store: IPropertyStore = link.cast()?;
+        store.SetValue(&PKEY_Title, &value)?;
+        store.Commit()?;
+        Ok(link)
+    }
+
+    unsafe fn collection(
+        exe: &HSTRING,
+        links: &[(String, String)],
+    ) -> windows::core::Result<IObjectArray> {
+        let collection: IObjectCollection =
+            CoCreateInstance(&EnumerableObjectCollection, None, CLSCTX_INPROC_SERVER)?;
+        for (args, title) in links {
+            collection.AddObject(&link(exe, args, title)?)?;
+        }
+        collection.cast()
+    }
+
+    /// 在专用的 COM 线程上更新跳转列表，不阻塞调用方；积压时只处理最新的一次
+    pub fn schedule(recent: Vec<(i64, String)>) {
+        static SENDER: OnceLock<Sender<Vec<(i64, String)>>> = OnceLock::new();
+        let sender = SENDER.get_or_init(|| {
+            let (sender, receiver) = mpsc::channel::<Vec<(i64, String)>>();
+            std::thread::spawn(move || {
+                unsafe {
+                    let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+                }
+                while let Ok(mut recent) = receiver.recv() {
+                    while let Ok(newer) = receiver.try_recv() {
+                        recent = newer;
+                    }
+                    if let Err(e) = update(&recent) {
+                        log::error!("Failed to update jump list: {}", e);
+                    }
+                }
+            });
+            sender
+        });
+        let _ = sender.send(recent);
+    }
+
+    fn update(recent: &[(i64, String)]) -> windows::core::Result<()> {
+        let exe = HSTRING::from(std::env::current_exe().unwrap_or_default().as_path());
+        let recent: Vec<(String, String)> = recent
+            .iter()
+            .map(|(id, title)| (format!("{}{}", PASTE_ARG, id), title.clone()))
+            .collect();
+        let tasks = [
+            (PAUSE_ARG.to_string(), "Pause / Resume".to_string()),
+            (CAPTURE_ARG.to_string(), "Capture Screen".to_string()),
+        ];
+        unsafe {
+            let list: ICustomDestinationList =
+                CoCreateInstance(&DestinationList, None, CLSCTX_INPROC_SERVER)?;
+            let mut slots = 0u32;
+            let _removed: IObjectArray = list.BeginList(&mut slots)?;
+            if !recent.is_empty() {
+                list.AppendCategory(&HSTRING::from("Recent"), &collection(&exe, &recent)?)?;
+            }
+            list.AddUserTasks(&collection(&exe, &tasks)?)?;
+            list.CommitList()
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod dock_menu {
+    use std::sync::{Mutex, Once, OnceLock};
+
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::NSString;
+    use objc::declare::ClassDecl;
+    use objc::runtime::{class_addMethod, object_getClass, Class, Object, Sel};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    use super::{run, Action};
+
+    // Dock 菜单中任务项的 tag，最近条目的 tag 为条目 id
+    const PAUSE_TAG: i64 = -1;
+    const CAPTURE_TAG: i64 = -2;
+
+    static APP: OnceLock<tauri::AppHandle> = OnceLock::new();
+    // 当前的 NSMenu，只在主线程读写
+    static MENU: Mutex<usize> = Mutex::new(0);
+    static INSTALL: Once = Once::new();
+
+    extern "C" fn application_dock_menu(_this: &Object, _sel: Sel, _sender: id) -> id {
+        *MENU.lock().unwrap() as id
+    }
+
+    extern "C" fn item_clicked(_this: &Object, _sel: Sel, item: id) {
+        let tag: i64 = unsafe { msg_send![item, tag] };
+        let action = match tag {
+            PAUSE_TAG => Action::TogglePause,
+            CAPTURE_TAG => Action::Capture,
+            id => Action::Paste(id),
+        };
+        if let Some(app) = APP.get() {
+            run(app, action);
+        }
+    }
+
+    /// 菜单项的点击目标
+    fn target() -> id {
+        static TARGET: OnceLock<usize> = OnceLock::new();
+        *TARGET.get_or_init(|| unsafe {
+            let mut decl = ClassDecl::new("ClipboardDockMenuTarget", class!(NSObject))
+                .expect("Dock menu target class already registered");
+            decl.add_method(
+                sel!(itemClicked:),
+                item_clicked as extern "C" fn(&Object, Sel, id),
+            );
+            let class = decl.register();
+            let target: id = msg_send![class, new];
+            target as usize
+        }) as id
+    }
+
+    /// 给应用代理添加 applicationDockMenu: 方法
+    unsafe fn install() {
+        let ns_app: id = msg_send![class!(NSApplication), sharedApplication];
+        let delegate: id = msg_send![ns_app, delegate];
+        if delegate == nil {
+            return;
+        }
+        let class = object_getClass(delegate) as *mut Class;
+        class_addMethod(
+            class,
+            sel!(applicationDockMenu:),
+            std::mem::transmute::<extern "C" fn(&Object, Sel, id) -> id, objc::runtime::Imp>(
+                application_dock_menu,
+            ),
+            c"@@:@".as_ptr(),
+        );
+    }
+
+    unsafe fn menu_item(title: &str, tag: i64) -> id {
+        let title = NSString::alloc(nil).init_str(title);
+        let key = NSString::alloc(nil).init_str("");
+        let item: id = msg_send![class!(NSMenuItem), alloc];
+        let item: id =
+            msg_send![item, initWithTitle: title action: sel!(itemClicked:) keyEquivalent: key];
+        let _: () = msg_send![item, setTarget: target()];
+        let _: () = msg_send![item, setTag: tag];
+        let _: () = msg_send![title, release];
+        let _: () = msg_send![key, release];
+        item
+    }
+
+    /// Dock 菜单只在 Dock 图标可见时显示
+    pub fn update(app: &tauri::AppHandle, recent: Vec<(i64, String)>) {
+        let _ = APP.set(app.clone());
+        let _ = app.run_on_main_thread(move || unsafe {
+            INSTALL.call_once(|| install());
+            let menu: id = msg_send![class!(NSMenu), new];
+            let _: () = msg_send![menu, setAutoenablesItems: false];
+            for (id, title) in &recent {
+                let item = menu_item(title, *id);
+                let _: () = msg_send![menu, addItem: item];
+                let _: () = msg_send![item, release];
+            }
+            if !recent.is_empty() {
+                let separator: id = msg_send![class!(NSMenuItem), separatorItem];
+                let _: () = msg_send![menu, addItem: separator];
+            }
+            for (title, tag) in [
+                ("Pause / Resume", PAUSE_TAG),
+                ("Capture Screen", CAPTURE_TAG),
+            ] {
+                let item = menu_item(title, tag);
+                let _: () = msg_send![menu, addItem: item];
+                let _: () = msg_send![item, release];
+            }
+            let mut current = MENU.lock().unwrap();
+            let old = std::mem::replace(&mut *current, menu as usize) as id;
+            if old != nil {
+                let _: () = msg_send![old, release];
+            }
+        });
+    }
+}
@@ -0,0 +1,114 @@
+use crate::error::AppError;
+
+/// 模拟按键，把剪贴板内容粘贴到前台应用；macOS 需要辅助功能权限，Linux 依赖 xdotool
+#[derive(Debug, Clone, Copy)]
+pub enum Key {
+    // 终端中的粘贴：macOS 为 Cmd+V，Linux 终端为 Ctrl+Shift+V，Windows 为 Ctrl+V
+    TerminalPaste,
+    Enter,
+    // 普通应用中的粘贴和撤销：macOS 为 Cmd+V / Cmd+Z，其他平台为 Ctrl+V / Ctrl+Z
+    Paste,
+    Undo,
+}
+
+#[cfg(target_os = "macos")]
+pub fn press(key: Key) -> Result<(), AppError> {
+    use core_graphics::event::{CGEvent, CGEventFlags, CGEventTapLocation};
+    use core_graphics::event_source::{CGEventSource, CGEventSourceStateID};
+
+    #[link(name = "ApplicationServices", kind = "framework")]
+    extern "C" {
+        fn AXIsProcessTrusted() -> u8;
+    }
+    // 没有辅助功能权限时系统会静默丢弃模拟的按键
+    if unsafe { AXIsProcessTrusted() } == 0 {
+        return Err(AppError::PermissionDenied(
+            "Accessibility permission is required to paste into other apps".to_string(),
+        ));
+    }
+
+    // kVK_ANSI_V、kVK_ANSI_Z 和 kVK_Return
+    let (keycode, flags) = match key {
+        Key::TerminalPaste | Key::Paste => (9, CGEventFlags::CGEventFlagCommand),
+        Key::Undo => (6, CGEventFlags::CGEventFlagCommand),
+        Key::Enter => (36, CGEventFlags::CGEventFlagNull),
+    };
+    for keydown in [true, false] {
+        let source = CGEventSource::new(CGEventSourceStateID::HIDSystemState)
+            .map_err(|_| AppError::Other("Failed to create event source".to_string()))?;
+        let event = CGEvent::new_keyboard_event(source, keycode, keydown)
+            .map_err(|_| AppError::Other("Failed to create keyboard event".to_string()))?;
+        event.set_flags(flags);
+        event.post(CGEventTapLocation::HID);
+    }
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+pub fn press(key: Key) -> Result<(), AppError> {
+    use windows::Win32::UI::Input::KeyboardAndMouse::{
+        SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYBD_EVENT_FLAGS, KEYEVENTF_KEYUP,
+        VIRTUAL_KEY, VK_CONTROL, VK_RETURN, VK_V, VK_Z,
+    };
+
+    let input = |vk: VIRTUAL_KEY, flags: KEYBD_EVENT_FLAGS| INPUT {
+        r#type: INPUT_KEYBOARD,
+        Anonymous: INPUT_0 {
+            ki: KEYBDINPUT {
+                wVk: vk,
+                wScan: 0,
+                dwFlags: flags,
+                time: 0,
+                dwExtraInfo: 0,
+            },
+        },
+    };
+    let down = KEYBD_EVENT_FLAGS(0);
+    let ctrl = |vk: VIRTUAL_KEY| {
+        vec![
+            input(VK_CONTROL, down),
+            input(vk, down),
+            input(vk, KEYEVENTF_KEYUP),
+            input(VK_CONTROL, KEYEVENTF_KEYUP),
+        ]
+    };
+    let inputs = match key {
+        Key::TerminalPaste | Key::Paste => ctrl(VK_V),
+        Key::Undo => ctrl(VK_Z),
+        Key::Enter => vec![input(VK_RETURN, down), input(VK_RETURN, KEYEVENTF_KEYUP)],
+    };
+    let sent = unsafe { SendInput(&inputs, std::mem::size_of::<INPUT>() as i32) };
+    if sent as usize != inputs.len() {
+        // 前台应用以管理员身份运行时，UIPI 会拒绝来自普通权限进程的输入
+        let error = std::io::Error::last_os_error();
+        if error.kind() == std::io::ErrorKind::PermissionDenied {
+            return Err(AppError::PermissionDenied(
+                "Windows blocked keyboard input to the foreground app, which may be running as administrator"
+                    .to_string(),
+            ));
+        }
+        return Err(AppError::Other(format!(
+            "Failed to send keyboard input: {}",
+            error
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn press(key: Key) -> Result<(), AppError> {
+    let keys = match key {
+        Key::TerminalPaste => "ctrl+shift+v",
+        Key::Enter => "Return",
+        Key::Paste => "ctrl+v",
+        Key::Undo => "ctrl+z",
+    };
+    let status = std::process::Command::new("xdotool")
+        .args(["key", "--clearmodifiers", keys])
+        .status()
+        .map_err(|e| AppError::Unsupported(format!("Failed to run xdotool: {}", e)))?;
+    if !status.success() {
+        return Err(AppError::Other(format!("xdotool exited with {}", status)));
+    }
+    Ok(())
+}
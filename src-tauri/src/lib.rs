@@ -1,13 +1,71 @@
+mod accessibility;
+mod ai;
+mod api_tokens;
+mod audio;
+mod bundle;
+mod cache;
+mod calc;
+mod calendar;
+mod clipboard_cursor;
+mod color;
+mod command_safety;
 mod commands;
+mod contact;
 mod crypto;
+mod cycle_paste;
 mod db;
+mod encoding;
+mod error;
+mod find_pasteboard;
+mod folder_watch;
+mod git_sync;
+mod jobs;
+mod jump_list;
+mod keystroke;
+mod listener;
+mod memory;
 mod models;
 mod monitor;
+mod mqtt;
 mod ocr;
+mod palette;
+mod paste_stack;
+mod pdf;
+mod plugins;
+mod popup;
+mod power;
+mod print;
+mod quick_paste;
+mod quicklook;
+mod raw_formats;
+mod rules;
 mod screenshot;
+mod scripting;
+mod self_write;
+mod settings;
+mod share_server;
+mod shortcut;
+mod shutdown;
+mod similarity;
 mod state;
+mod streamdeck;
+mod svg;
+mod table;
+mod test_data;
 mod tray;
+mod usage;
 mod utils;
+mod webhook;
+mod window_state;
+
+/// 供 benches/ 使用的数据库接口，不属于公开 API
+#[doc(hidden)]
+pub mod bench {
+    pub use crate::crypto::Crypto;
+    pub use crate::db::Database;
+    pub use crate::models::{ClipboardItem, HistoryFilter};
+    pub use crate::test_data::generate;
+}
 
 use clipboard_master::Master;
 use std::fs;
@@ -22,10 +80,12 @@ use window_vibrancy::{apply_vibrancy, NSVisualEffectMaterial};
 use crate::commands::*;
 use crate::crypto::Crypto;
 use crate::db::Database;
-use crate::models::{AppConfig, ClipboardItem};
-use crate::monitor::ClipboardMonitor;
+use crate::models::{AppConfig, ClipboardItem, HistoryFilter};
+use crate::monitor::{ClipboardMonitor, MonitorHandler};
 use crate::state::AppState;
-use crate::utils::write_to_clipboard;
+use crate::utils::{
+    apply_paste_rules, open_item_urls, write_to_clipboard, write_to_clipboard_with,
+};
 use tauri_plugin_updater::UpdaterExt;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -40,7 +100,7 @@ pub fn run() {
     }
 
     let config_path = app_data_dir.join("config.json");
-    let config = if let Ok(content) = fs::read_to_string(&config_path) {
+    let mut config = if let Ok(content) = fs::read_to_string(&config_path) {
         serde_json::from_str::<AppConfig>(&content).unwrap_or_default()
     } else {
         AppConfig::default()
@@ -49,37 +109,91 @@ pub fn run() {
     let db_path = app_data_dir.join("history.db");
     let key_path = app_data_dir.join("secret.key");
     let crypto = Arc::new(Crypto::new(&key_path));
-    let db = Arc::new(Database::new(&db_path, crypto).expect("Failed to initialize database"));
+    // 旧版本明文保存的密钥改为加密保存
+    if config.seal_secrets(&crypto) {
+        if let Ok(json) = serde_json::to_string_pretty(&config) {
+            let _ = fs::write(&config_path, json);
+        }
+    }
+    let db =
+        Arc::new(Database::new(&db_path, crypto.clone()).expect("Failed to initialize database"));
+    db.set_dedup_ignore_whitespace(config.dedup_ignore_whitespace);
 
-    let shortcut_key = config.shortcut.clone();
+    // 按物理按键注册，切换键盘布局后快捷键不变
+    let shortcut_key =
+        crate::shortcut::normalize(&config.shortcut).unwrap_or_else(|_| config.shortcut.clone());
     let config_arc = Arc::new(Mutex::new(config));
 
     let is_paused = Arc::new(Mutex::new(false));
     let is_paused_state = is_paused.clone();
-    let last_app_change = Arc::new(Mutex::new(None));
-    let last_app_change_state = last_app_change.clone();
-    let last_app_image_change = Arc::new(Mutex::new(None));
-    let last_app_image_change_state = last_app_image_change.clone();
-    let last_app_file_change = Arc::new(Mutex::new(None));
-    let last_app_file_change_state = last_app_file_change.clone();
     let paste_stack = Arc::new(Mutex::new(Vec::<ClipboardItem>::new()));
     let paste_stack_state = paste_stack.clone();
     let current_captures = Arc::new(Mutex::new(None));
     let current_captures_state = current_captures.clone();
 
     tauri::Builder::default()
+        // 跳转列表启动的新进程把参数转交给已运行的实例后退出
+        .plugin(tauri_plugin_single_instance::init(|app, args, _cwd| {
+            crate::jump_list::handle_args(app, &args);
+        }))
         .plugin(
             tauri_plugin_global_shortcut::Builder::new()
                 .with_shortcut(shortcut_key.as_str())
                 .expect("Failed to register shortcut")
-                .with_handler(|app, _shortcut, event| {
+                .with_handler(|app, shortcut, event| {
+                    // hold 模式下松开快捷键结束按住预览
+                    if event.state == tauri_plugin_global_shortcut::ShortcutState::Released {
+                        crate::popup::end_quick_peek(app);
+                        return;
+                    }
                     if event.state == tauri_plugin_global_shortcut::ShortcutState::Pressed {
+                        // 正在录制快捷键
+                        if crate::shortcut::finish_with_shortcut(app, shortcut) {
+                            return;
+                        }
+
+                        // 置顶条目的专属快捷键
+                        if let Some(id) = crate::quick_paste::item_for_shortcut(app, shortcut) {
+                            if let Err(e) = crate::quick_paste::paste(app, id) {
+                                log::error!("Failed to quick paste item {}: {}", id, e);
+                            }
+                            return;
+                        }
+
+                        // 循环粘贴
+                        if crate::cycle_paste::is_shortcut(app, shortcut) {
+                            crate::cycle_paste::trigger(app);
+                            return;
+                        }
+
+                        // 剪贴板在历史中后退 / 前进
+                        if let Some(direction) =
+                            crate::clipboard_cursor::direction_for_shortcut(app, shortcut)
+                        {
+                            if let Err(e) = crate::clipboard_cursor::step(app, direction) {
+                                log::error!("Failed to move clipboard through history: {}", e);
+                            }
+                            return;
+                        }
+
                         // Check Paste Stack
                         let state = app.state::<AppState>();
                         if let Ok(mut stack) = state.paste_stack.lock() {
                             if !stack.is_empty() {
-                                let item = stack.remove(0);
-                                let _ = write_to_clipboard(app, &item);
+                                let mut item = stack.remove(0);
+                                crate::paste_stack::save(app, &stack);
+                                // 粘贴栈直接粘贴到当前前台应用
+                                let include_raw_formats =
+                                    match active_win_pos_rs::get_active_window() {
+                                        Ok(active) => apply_paste_rules(
+                                            app,
+                                            &mut item,
+                                            &active.app_name,
+                                            &active.process_path.to_string_lossy(),
+                                        ),
+                                        Err(_) => true,
+                                    };
+                                let _ = write_to_clipboard_with(app, &item, include_raw_formats);
                                 return;
                             }
                         }
@@ -87,8 +201,27 @@ pub fn run() {
                         if let Some(window) = app.get_webview_window("popup") {
                             let is_visible = window.is_visible().unwrap_or(false);
                             if is_visible {
-                                let _ = window.hide();
+                                // 按住预览期间的按键重复
+                                if crate::popup::is_quick_peek_active(app) {
+                                    return;
+                                }
+                                crate::popup::hide(app, "shortcut", true);
                             } else {
+                                // Remember which app the user was in before the popup takes focus
+                                if let Ok(active) = active_win_pos_rs::get_active_window() {
+                                    if let Ok(mut target) = state.popup_focus_target.lock() {
+                                        *target =
+                                            Some(crate::popup::capture_focus(active.process_id));
+                                    }
+                                    if let Ok(mut last_path) = state.last_active_app_path.lock() {
+                                        *last_path =
+                                            Some(active.process_path.to_string_lossy().to_string());
+                                    }
+                                    if let Ok(mut last_app) = state.last_active_app.lock() {
+                                        *last_app = Some(active.app_name);
+                                    }
+                                }
+
                                 // Get mouse position
                                 use mouse_position::mouse_position::Mouse;
                                 let position = Mouse::get_mouse_position();
@@ -112,6 +245,7 @@ pub fn run() {
                                                 && y >= m_pos.y
                                                 && y < m_pos.y + m_size.height as i32
                                             {
+                                                crate::popup::fit_to_monitor(app, &window, &m);
                                                 if let Ok(w_size) = window.outer_size() {
                                                     let w = w_size.width as i32;
                                                     let h = w_size.height as i32;
@@ -143,6 +277,8 @@ pub fn run() {
 
                                 let _ = window.show();
                                 let _ = window.set_focus();
+                                crate::popup::emit_shown(app);
+                                crate::popup::start_quick_peek(app);
                             }
                         }
                     }
@@ -187,15 +323,55 @@ pub fn run() {
             // 将状态交给 Tauri 管理
             app.manage(AppState {
                 db: db.clone(),
+                crypto: crypto.clone(),
                 config_path: config_path.clone(),
                 config: config_arc.clone(),
                 is_paused: is_paused_state.clone(),
-                last_app_change: last_app_change_state.clone(),
-                last_app_image_change: last_app_image_change_state.clone(),
-                last_app_file_change: last_app_file_change_state.clone(),
+                self_write_token: Arc::new(Mutex::new(None)),
                 paste_stack: paste_stack_state.clone(),
                 current_captures: current_captures_state.clone(),
+                capture_frames: Arc::new(Mutex::new(Default::default())),
+                capture_source_app: Arc::new(Mutex::new(None)),
                 pause_item: Arc::new(Mutex::new(None)),
+                urls_menu: Arc::new(Mutex::new(None)),
+                quick_paste_menu: Arc::new(Mutex::new(None)),
+                quick_paste_shortcuts: Arc::new(Mutex::new(Default::default())),
+                exchange_rates: Arc::new(Mutex::new(None)),
+                last_active_app: Arc::new(Mutex::new(None)),
+                last_active_app_path: Arc::new(Mutex::new(None)),
+                last_find_pasteboard_write: Arc::new(Mutex::new(None)),
+                jobs: Arc::new(crate::jobs::JobQueue::new(
+                    config_arc.lock().unwrap().background_workers,
+                )),
+                shutting_down: Arc::new(Mutex::new(false)),
+                window_geometry: Arc::new(Mutex::new(Default::default())),
+                window_state_save_pending: Arc::new(Mutex::new(false)),
+                update_event_pending: Arc::new(Mutex::new(false)),
+                power_saving: Arc::new(Mutex::new(false)),
+                popup_pinned: Arc::new(Mutex::new(false)),
+                popup_focus_target: Arc::new(Mutex::new(None)),
+                folder_watcher: Arc::new(Mutex::new(None)),
+                scripts: Arc::new(Mutex::new(None)),
+                script_watcher: Arc::new(Mutex::new(None)),
+                share_server_generation: Arc::new(Mutex::new(0)),
+                plugin_classifiers: Arc::new(Mutex::new(Vec::new())),
+                streamdeck_generation: Arc::new(Mutex::new(0)),
+                mqtt_generation: Arc::new(Mutex::new(0)),
+                mqtt_client: Arc::new(Mutex::new(None)),
+                storage_warning_sent: Arc::new(Mutex::new(false)),
+                images_size: Arc::new(Mutex::new(None)),
+                git_sync_lock: Arc::new(Mutex::new(())),
+                content_cache: Arc::new(Mutex::new(crate::cache::ContentCache::new(
+                    config_arc.lock().unwrap().content_cache_mb,
+                ))),
+                audio_player: Arc::new(Mutex::new(None)),
+                shortcut_capture: Arc::new(Mutex::new(None)),
+                quick_peek: Arc::new(Mutex::new(None)),
+                jump_list_items: Arc::new(Mutex::new(Vec::new())),
+                clipboard_cursor: Arc::new(Mutex::new(0)),
+                clipboard_cursor_shortcuts: Arc::new(Mutex::new(Vec::new())),
+                cycle_paste_shortcut: Arc::new(Mutex::new(None)),
+                cycle_paste: Arc::new(Mutex::new(None)),
             });
 
             // 托盘设置
@@ -213,6 +389,26 @@ pub fn run() {
                         *pause_item = Some(item.clone());
                     };
                 }
+                if let Some(submenu) = items
+                    .iter()
+                    .find(|i| i.id() == "recent_urls")
+                    .and_then(|i| i.as_submenu())
+                {
+                    let state = app.state::<AppState>();
+                    if let Ok(mut urls_menu) = state.urls_menu.lock() {
+                        *urls_menu = Some(submenu.clone());
+                    };
+                }
+                if let Some(submenu) = items
+                    .iter()
+                    .find(|i| i.id() == "quick_paste")
+                    .and_then(|i| i.as_submenu())
+                {
+                    let state = app.state::<AppState>();
+                    if let Ok(mut quick_paste_menu) = state.quick_paste_menu.lock() {
+                        *quick_paste_menu = Some(submenu.clone());
+                    };
+                }
             }
 
             let _tray = TrayIconBuilder::with_id("tray")
@@ -222,6 +418,9 @@ pub fn run() {
                         .clone(),
                 )
                 .menu(&menu)
+                .on_tray_icon_event(|tray, event| {
+                    crate::tray::handle_icon_event(tray.app_handle(), event)
+                })
                 .on_menu_event(|app, event| match event.id.as_ref() {
                     "quit" => {
                         app.exit(0);
@@ -243,14 +442,12 @@ pub fn run() {
                         };
                     }
                     "clear" => {
-                        let state = app.state::<AppState>();
-                        let clear_pinned_on_clear =
-                            state.config.lock().unwrap().clear_pinned_on_clear;
-                        let clear_collected_on_clear =
-                            state.config.lock().unwrap().clear_collected_on_clear;
-                        if let Ok(_) = state
-                            .db
-                            .clear_history(clear_pinned_on_clear, clear_collected_on_clear)
+                        if crate::commands::clear_history(
+                            app.clone(),
+                            app.state::<AppState>(),
+                            None,
+                        )
+                        .is_ok()
                         {
                             log::info!("History cleared from tray");
                         }
@@ -284,17 +481,110 @@ pub fn run() {
                             }
                         });
                     }
+                    id if id.starts_with("open_url_") => {
+                        let state = app.state::<AppState>();
+                        if let Ok(item_id) = id.trim_start_matches("open_url_").parse::<i64>() {
+                            match state.db.get_item(item_id) {
+                                Ok(item) => {
+                                    if let Err(e) = open_item_urls(app, &item, false) {
+                                        log::error!("Failed to open URL from tray: {}", e);
+                                    }
+                                }
+                                Err(e) => log::error!("Failed to load item {}: {}", item_id, e),
+                            }
+                        }
+                    }
+                    id if id.starts_with("quick_paste_") => {
+                        if let Ok(item_id) = id.trim_start_matches("quick_paste_").parse::<i64>() {
+                            if let Err(e) = crate::quick_paste::paste(app, item_id) {
+                                log::error!("Failed to quick paste item {}: {}", item_id, e);
+                            }
+                        }
+                    }
                     _ => {}
                 })
                 .build(app)?;
 
+            let history = db
+                .get_history(1, 20, &HistoryFilter::default())
+                .unwrap_or_default();
+            if let Err(e) = crate::tray::update_tray_menu(app.handle(), &history) {
+                log::error!("Failed to populate tray menu: {}", e);
+            }
+
+            // 置顶条目的托盘菜单和快捷键
+            crate::quick_paste::sync(app.handle());
+            crate::clipboard_cursor::sync_shortcuts(app.handle());
+            crate::cycle_paste::sync_shortcut(app.handle());
+            // 应用未运行时从跳转列表启动
+            crate::jump_list::handle_args(app.handle(), &std::env::args().collect::<Vec<_>>());
+
+            // 恢复上次退出时的粘贴栈，以及窗口的位置和大小
+            crate::paste_stack::restore(app.handle());
+            crate::window_state::restore(app.handle());
+
+            // 重启后系统剪贴板为空，按配置恢复最近一条记录
+            let restore_on_startup = config_arc.lock().unwrap().restore_clipboard_on_startup;
+            if restore_on_startup {
+                match db.get_latest_restorable_item() {
+                    Ok(Some(item)) => {
+                        if let Err(e) = write_to_clipboard(app.handle(), &item) {
+                            log::error!("Failed to restore clipboard on startup: {}", e);
+                        } else {
+                            log::info!("Restored clipboard from history item {:?}", item.id);
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(e) => log::error!("Failed to load item to restore: {}", e),
+                }
+            }
+
+            // 汇率缓存，过期后在复制金额时才重新拉取
+            crate::calc::load_cached_rates(&handle);
+
+            // 收藏夹 Git 同步
+            tauri::async_runtime::spawn(crate::git_sync::sync_loop(handle.clone()));
+
+            // 按配置清理过期的截图缓存
+            let cleanup_handle = handle.clone();
+            tauri::async_runtime::spawn_blocking(move || {
+                crate::screenshot::cleanup_capture_cache(&cleanup_handle, false);
+            });
+
+            // 检测电池和空闲状态，按需进入省电模式
+            crate::power::start(&handle);
+
+            // 定期检查内存预算
+            crate::memory::start(&handle);
+
+            // 已启用插件提供的分类器
+            crate::plugins::reload(&handle);
+
+            // 监听配置的目录，新文件自动加入历史
+            crate::folder_watch::restart(&handle);
+
+            // 脚本文件变化后重新编译
+            crate::scripting::watch(&handle);
+
+            // 局域网只读共享页面
+            crate::share_server::restart(&handle);
+
+            // Stream Deck 插件的本机 WebSocket 服务
+            crate::streamdeck::restart(&handle);
+
+            // MQTT 事件发布和远程设置剪贴板
+            crate::mqtt::restart(&handle);
+
+            // macOS 查找剪贴板监听
+            crate::find_pasteboard::restart(&handle);
+
             // 剪切板监听线程
             let monitor_handle = handle.clone();
             thread::spawn(move || {
                 // Delay starting the monitor to avoid race conditions with startup tray menu
                 std::thread::sleep(std::time::Duration::from_secs(1));
 
-                let monitor = ClipboardMonitor::new(monitor_handle);
+                let monitor = MonitorHandler::new(ClipboardMonitor::new(monitor_handle));
                 match Master::new(monitor) {
                     Ok(mut master) => {
                         if let Err(e) = master.run() {
@@ -315,24 +605,126 @@ pub fn run() {
             delete_item,
             toggle_sensitive,
             toggle_pin,
+            toggle_lock,
+            set_item_meta,
+            get_item_meta,
+            set_item_accelerator,
             update_clipboard_item_content,
+            get_item_versions,
+            get_item_occurrences,
+            get_item_devices,
+            restore_version,
             clear_history,
+            get_cleanup_candidates,
+            apply_cleanup,
             get_config,
             save_config,
             set_paused,
             get_paused,
+            clipboard_back,
+            clipboard_forward,
+            set_presentation_mode,
+            set_popup_pinned,
+            hide_popup,
+            get_popup_pinned,
+            get_share_url,
+            regenerate_share_token,
             get_item_content,
+            get_thumbnail,
+            cache_stats,
+            get_memory_stats,
+            get_db_stats,
+            generate_test_data,
             get_history_count,
             create_collection,
             get_collections,
+            set_collection_retention_exempt,
+            get_collection_counts,
+            get_type_counts,
             delete_collection,
             set_item_collection,
+            export_collection,
+            export_images_zip,
+            print_item,
+            quicklook_item,
+            paste_and_run,
+            extract_pdf_text,
+            play_item,
+            stop_audio,
+            convert_color,
+            extract_palette,
+            convert_table,
+            call_item,
+            map_item,
+            normalize_phone,
+            compose_email,
+            create_event,
+            test_rule,
+            test_webhook,
+            regenerate_streamdeck_token,
+            create_api_token,
+            get_power_state,
+            get_accessibility_prefs,
+            get_item_descriptions,
+            capture_shortcut,
+            record_shortcut_key,
+            get_quick_peek_active,
+            cancel_quick_peek,
+            revoke_api_token,
+            list_scripts,
+            open_scripts_dir,
+            run_script_transform,
+            list_plugins,
+            set_plugin_enabled,
+            open_plugins_dir,
+            run_plugin_action,
+            share_to_plugin_target,
+            import_bundle,
+            export_settings,
+            import_settings,
+            git_sync_now,
             set_paste_stack,
+            get_paste_stack,
+            clear_paste_stack,
             ocr_image,
+            ocr_image_layout,
+            ocr_backfill,
+            cancel_ocr_backfill,
+            list_jobs,
+            cancel_job,
+            list_ocr_languages,
             start_capture,
+            start_capture_delayed,
             close_capture,
+            clear_capture_cache,
             get_capture_data,
-            save_captured_image
+            get_pixels_at,
+            get_window_rects,
+            save_captured_image,
+            save_captures_as_pdf,
+            finalize_capture,
+            open_item_path,
+            reveal_in_finder,
+            copy_file_paths,
+            copy_file_contents,
+            copy_file_object,
+            open_item_url,
+            extract_entities,
+            count_stats,
+            summarize_item,
+            rewrite_item,
+            explain_code,
+            find_similar,
+            get_activity_calendar,
+            get_devices,
+            get_storage_usage,
+            send_to_device,
+            get_usage_stats,
+            clear_usage_stats,
+            get_suggestions,
+            palette_actions,
+            run_action,
+            format_timestamp
         ])
         .on_window_event(|window, event| match event {
             tauri::WindowEvent::CloseRequested { api, .. } => {
@@ -343,11 +735,34 @@ pub fn run() {
             }
             tauri::WindowEvent::Focused(false) => {
                 if window.label() == "popup" {
-                    let _ = window.hide();
+                    let pinned = *window.state::<AppState>().popup_pinned.lock().unwrap();
+                    if !pinned {
+                        crate::popup::hide(window.app_handle(), "blur", false);
+                    }
+                }
+            }
+            tauri::WindowEvent::Moved(_) | tauri::WindowEvent::Resized(_) => {
+                crate::window_state::record(window);
+            }
+            // 截图窗口无论以何种方式关闭，都释放对应屏幕的冻结帧
+            tauri::WindowEvent::Destroyed => {
+                if let Some(screen_id) = window
+                    .label()
+                    .strip_prefix("screenshot_")
+                    .and_then(|id| id.parse::<u32>().ok())
+                {
+                    if let Ok(mut frames) = window.state::<AppState>().capture_frames.lock() {
+                        frames.remove(&screen_id);
+                    }
                 }
             }
             _ => {}
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            if let tauri::RunEvent::ExitRequested { .. } = event {
+                crate::shutdown::run(app);
+            }
+        });
 }
@@ -0,0 +1,70 @@
+use std::net::{TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// 递增代号并在后台线程监听 TCP 端口；代号变化后旧线程自行退出，每个连接交给 handler 在独立线程处理
+pub fn restart<H>(
+    generation: &Arc<Mutex<u64>>,
+    enabled: bool,
+    name: &'static str,
+    addr: (&'static str, u16),
+    handler: H,
+) where
+    H: Fn(TcpStream) + Clone + Send + 'static,
+{
+    let current = {
+        let mut generation = generation.lock().unwrap();
+        *generation += 1;
+        *generation
+    };
+    if !enabled {
+        return;
+    }
+
+    let generation = generation.clone();
+    std::thread::spawn(move || {
+        let is_current = || *generation.lock().unwrap() == current;
+        let port = addr.1;
+
+        // 旧线程释放端口前绑定可能失败，稍作重试
+        let mut listener = None;
+        for _ in 0..10 {
+            match TcpListener::bind(addr) {
+                Ok(l) => {
+                    listener = Some(l);
+                    break;
+                }
+                Err(e) => {
+                    log::warn!("Failed to bind {} on port {}: {}", name, port, e);
+                    std::thread::sleep(Duration::from_millis(300));
+                }
+            }
+        }
+        let Some(listener) = listener else {
+            log::error!("{} not started", name);
+            return;
+        };
+        if listener.set_nonblocking(true).is_err() {
+            return;
+        }
+        log::info!("{} listening on port {}", name, port);
+
+        while is_current() {
+            match listener.accept() {
+                Ok((stream, _)) => {
+                    let _ = stream.set_nonblocking(false);
+                    let handler = handler.clone();
+                    std::thread::spawn(move || handler(stream));
+                }
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                    std::thread::sleep(Duration::from_millis(200));
+                }
+                Err(e) => {
+                    log::error!("{} accept error: {}", name, e);
+                    std::thread::sleep(Duration::from_millis(200));
+                }
+            }
+        }
+        log::info!("{} stopped", name);
+    });
+}
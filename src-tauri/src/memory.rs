@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use tauri::Manager;
+
+use crate::models::MemoryStats;
+use crate::state::AppState;
+
+// 检查内存预算的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// 进程常驻内存（字节）；无法获取时返回 None
+#[cfg(target_os = "macos")]
+pub fn process_bytes() -> Option<u64> {
+    let output = std::process::Command::new("ps")
+        .args(["-o", "rss=", "-p", &std::process::id().to_string()])
+        .output()
+        .ok()?;
+    let kb: u64 = String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .ok()?;
+    Some(kb * 1024)
+}
+
+#[cfg(target_os = "windows")]
+pub fn process_bytes() -> Option<u64> {
+    use windows::Win32::System::ProcessStatus::{K32GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+    use windows::Win32::System::Threading::GetCurrentProcess;
+
+    let mut counters = PROCESS_MEMORY_COUNTERS::default();
+    let ok = unsafe {
+        K32GetProcessMemoryInfo(
+            GetCurrentProcess(),
+            &mut counters,
+            std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        )
+    };
+    ok.as_bool().then_some(counters.WorkingSetSize as u64)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn process_bytes() -> Option<u64> {
+    let statm = std::fs::read_to_string("/proc/self/statm").ok()?;
+    let pages: u64 = statm.split_whitespace().nth(1)?.parse().ok()?;
+    Some(pages * 4096)
+}
+
+/// 应用自己持有的大块内存：内容缓存、截图像素和粘贴栈
+pub fn stats(app: &tauri::AppHandle) -> MemoryStats {
+    let state = app.state::<AppState>();
+    let budget_bytes = state
+        .config
+        .lock()
+        .unwrap()
+        .memory_budget_mb
+        .saturating_mul(1024 * 1024);
+    let content_cache = state.content_cache.lock().unwrap().stats();
+    let capture_frame_bytes: u64 = state
+        .capture_frames
+        .lock()
+        .unwrap()
+        .values()
+        .map(|frame| frame.rgba.len() as u64)
+        .sum();
+    let (paste_stack_items, paste_stack_bytes) = {
+        let stack = state.paste_stack.lock().unwrap();
+        let bytes: usize = stack
+            .iter()
+            .map(|item| {
+                item.content.len() + item.html_content.as_ref().map_or(0, |html| html.len())
+            })
+            .sum();
+        (stack.len(), bytes as u64)
+    };
+    MemoryStats {
+        process_bytes: process_bytes(),
+        budget_bytes,
+        tracked_bytes: content_cache.bytes + capture_frame_bytes + paste_stack_bytes,
+        content_cache,
+        capture_frame_bytes,
+        paste_stack_items,
+        paste_stack_bytes,
+    }
+}
+
+/// 超出内存预算时收缩内容缓存，截图和粘贴栈由用户操作释放，不在这里丢弃
+pub fn enforce(app: &tauri::AppHandle) -> MemoryStats {
+    let stats = stats(app);
+    if stats.budget_bytes == 0 || stats.tracked_bytes <= stats.budget_bytes {
+        return stats;
+    }
+    let others = stats.tracked_bytes - stats.content_cache.bytes;
+    let limit = stats.budget_bytes.saturating_sub(others);
+    log::info!(
+        "Memory budget exceeded ({} > {} bytes), shrinking content cache to {} bytes",
+        stats.tracked_bytes,
+        stats.budget_bytes,
+        limit
+    );
+    app.state::<AppState>()
+        .content_cache
+        .lock()
+        .unwrap()
+        .shrink_to(limit as usize);
+    self::stats(app)
+}
+
+/// 启动定期检查内存预算的线程
+pub fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(CHECK_INTERVAL);
+        if *app.state::<AppState>().shutting_down.lock().unwrap() {
+            break;
+        }
+        enforce(&app);
+    });
+}
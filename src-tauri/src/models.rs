@@ -1,10 +1,11 @@
+use crate::crypto::Crypto;
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ClipboardItem {
     pub id: Option<i64>,
     pub content: String, // 文字内容或图片的Base64
-    pub kind: String,    // "text" or "image"
+    pub kind: String,    // "text", "image", "file", "audio" or "raw"
     pub timestamp: String,
     #[serde(default)]
     pub is_sensitive: bool,
@@ -13,24 +14,651 @@ pub struct ClipboardItem {
     #[serde(default)]
     pub source_app: Option<String>,
     #[serde(default = "default_data_type")]
-    pub data_type: String, // "text", "image", "url", "email", "code", "phone"
+    pub data_type: String, // "text", "image", "url", "email", "code", "phone" 或自定义分类器的名称
     #[serde(default)]
     pub collection_id: Option<i64>,
     #[serde(default)]
     pub note: Option<String>,
     #[serde(default)]
     pub html_content: Option<String>,
+    #[serde(default)]
+    pub byte_size: i64,
+    #[serde(default)]
+    pub char_count: i64,
+    #[serde(default)]
+    pub line_count: i64,
+    #[serde(default)]
+    pub image_width: Option<u32>,
+    #[serde(default)]
+    pub image_height: Option<u32>,
+    #[serde(default)]
+    pub suggested_actions: Vec<SuggestedAction>,
+    #[serde(default)]
+    pub parent_id: Option<i64>,
+    #[serde(default)]
+    pub paste_count: i64,
+    #[serde(default)]
+    pub timestamp_ms: i64, // 排序用的毫秒时间戳，timestamp 仅用于展示
+    #[serde(default)]
+    pub ocr_text: Option<String>,
+    #[serde(default)]
+    pub is_dangerous: bool, // 看起来像高风险的 shell 命令
+    #[serde(default)]
+    pub is_truncated: bool, // 超过长度上限被截断后记录
+    #[serde(default)]
+    pub accelerator: Option<String>, // 置顶后在托盘 Quick Paste 中注册的全局快捷键
+    #[serde(default)]
+    pub is_locked: bool, // 锁定后不能编辑、删除，也不会被清理
+    #[serde(default)]
+    pub copy_count: i64, // 相同内容被复制的次数
+    #[serde(default)]
+    pub origin_device: Option<String>, // 同步来的条目的来源设备，本机记录的为 None
+}
+
+/// 随条目返回的建议操作，例如表达式计算结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuggestedAction {
+    pub kind: String, // "calc", "unit", "currency", "color", "call", "e164", "map", "email", "event"
+    pub label: String,
+    pub value: String,
+}
+
+/// 条目的体积信息，在写入时计算，便于前端无需拉取全文即可展示大小
+#[derive(Debug, Clone, Default)]
+pub struct ItemMetrics {
+    pub byte_size: i64,
+    pub char_count: i64,
+    pub line_count: i64,
+    pub image_width: Option<u32>,
+    pub image_height: Option<u32>,
+}
+
+impl ItemMetrics {
+    pub fn measure(kind: &str, content: &str) -> Self {
+        match kind {
+            "image" => {
+                let byte_size = std::fs::metadata(content)
+                    .map(|m| m.len() as i64)
+                    .unwrap_or(0);
+                let (image_width, image_height) = match image::image_dimensions(content) {
+                    Ok((w, h)) => (Some(w), Some(h)),
+                    Err(_) => (None, None),
+                };
+                Self {
+                    byte_size,
+                    image_width,
+                    image_height,
+                    ..Default::default()
+                }
+            }
+            "file" => {
+                let files: Vec<String> = serde_json::from_str(content).unwrap_or_default();
+                let byte_size = files
+                    .iter()
+                    .filter_map(|f| std::fs::metadata(f).ok())
+                    .map(|m| m.len() as i64)
+                    .sum();
+                Self {
+                    byte_size,
+                    line_count: files.len() as i64,
+                    ..Default::default()
+                }
+            }
+            _ => Self {
+                byte_size: content.len() as i64,
+                char_count: content.chars().count() as i64,
+                line_count: content.lines().count().max(1) as i64,
+                ..Default::default()
+            },
+        }
+    }
 }
 
 fn default_data_type() -> String {
     "text".to_string()
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractedEntity {
+    pub kind: String, // "url", "email", "phone"
+    pub value: String,
+}
+
+/// 粘贴危险命令到终端前发给前端确认的内容
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DangerousPaste {
+    pub id: Option<i64>,
+    pub content: String,
+    pub target_app: Option<String>,
+    pub reasons: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TextStats {
+    pub words: usize,
+    pub characters: usize,
+    pub characters_no_spaces: usize,
+    pub lines: usize,
+    pub bytes: usize,
+    pub tokens: Option<usize>, // 粗略估算的 LLM token 数
+}
+
+/// 条目的一次复制记录
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemOccurrence {
+    pub copied_at: String,
+    pub source_app: Option<String>,
+}
+
+/// 条目编辑前的内容，version 从 1 开始递增
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemVersion {
+    pub version: i64,
+    pub content: String,
+    pub data_type: String,
+    pub note: Option<String>,
+    pub html_content: Option<String>,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SimilarItem {
+    pub item: ClipboardItem,
+    pub similarity: f64, // 0.0 - 1.0
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DayCount {
+    pub date: String, // YYYY-MM-DD
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ActivityCalendar {
+    pub year: i32,
+    pub total: usize,
+    pub days: Vec<DayCount>,
+    pub hours: Vec<usize>, // 24 个小时的分布
+}
+
+/// 某天某项功能的使用次数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageCount {
+    pub date: String, // YYYY-MM-DD
+    pub event: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageTotal {
+    pub event: String,
+    pub count: usize,
+}
+
+/// 本地使用统计：totals 为各功能在统计区间内的总次数，按次数降序
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageStats {
+    pub enabled: bool,
+    pub days: u32,
+    pub totals: Vec<UsageTotal>,
+    pub daily: Vec<UsageCount>,
+}
+
+/// 命令面板中的一个可执行操作，score 为模糊匹配得分
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaletteAction {
+    pub id: String,
+    pub title: String,
+    pub category: String,
+    pub score: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct GitSyncResult {
+    pub pushed: usize,
+    pub added: usize,
+    pub updated: usize,
+    pub removed: usize,
+}
+
+/// 某种数据类型的条目数，用于侧边栏角标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeCount {
+    pub data_type: String,
+    pub count: usize,
+}
+
+/// 收藏夹中的条目数，用于侧边栏角标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CollectionCount {
+    pub collection_id: i64,
+    pub name: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TypeUsage {
+    pub data_type: String,
+    pub count: usize,
+    pub bytes: i64,
+}
+
+/// 后台任务的状态，status 为 queued / running / finished / failed / cancelled
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobInfo {
+    pub id: u64,
+    pub kind: String,
+    pub priority: String,
+    pub status: String,
+    pub done: usize,
+    pub total: usize,
+    pub error: Option<String>,
+}
+
+/// 当前生效的无障碍设置，os_* 为系统设置，无法判断时为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessibilityPrefs {
+    pub reduced_motion: bool,
+    pub high_contrast: bool,
+    pub os_reduced_motion: Option<bool>,
+    pub os_high_contrast: Option<bool>,
+}
+
+/// 供读屏软件使用的条目描述
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemDescription {
+    pub id: i64,
+    pub label: String,
+}
+
+/// 电源状态：无法判断的项为 None；power_saving 为当前是否处于省电模式
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PowerState {
+    pub mode: String,
+    pub on_battery: Option<bool>,
+    pub idle_seconds: Option<u64>,
+    pub power_saving: bool,
+}
+
+/// 图片主色，share 为该颜色占像素的比例
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColorSwatch {
+    pub hex: String,
+    pub rgb: String,
+    pub share: f64,
+}
+
+/// 历史列表的搜索和筛选条件，未设置的条件不限制
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub query: Option<String>,
+    pub search_regex: bool,
+    pub search_case_sensitive: bool,
+    pub collection_id: Option<i64>,
+    pub data_type: Option<String>,
+    // 毫秒时间戳，from 含、to 不含
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    // Some(None) 为本机记录的条目
+    pub origin_device: Option<Option<String>>,
+}
+
+/// 清空历史、批量导出使用的条目筛选条件，未设置的条件不限制
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ItemFilter {
+    #[serde(default)]
+    pub older_than_days: Option<u32>,
+    #[serde(default)]
+    pub data_type: Option<String>,
+    #[serde(default)]
+    pub source_app: Option<String>,
+    // 未设置时按 clear_pinned_on_clear / clear_collected_on_clear 配置
+    #[serde(default)]
+    pub only_unpinned: Option<bool>,
+    #[serde(default)]
+    pub only_uncollected: Option<bool>,
+}
+
+/// 清空历史的结果：删除的条目数和剩余的条目数
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClearResult {
+    pub deleted: usize,
+    pub remaining: usize,
+}
+
+/// 清理候选条目，score 为占用空间（KB）与存放天数的乘积，越大越值得清理
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CleanupCandidate {
+    pub item: ClipboardItem,
+    pub reclaimable_bytes: i64,
+    pub age_days: u64,
+    pub score: f64,
+}
+
+/// 导入设置的结果：新建的收藏夹数，以及是否有未经确认而跳过的网络设置
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsImportResult {
+    pub collections_created: usize,
+    pub network_settings_skipped: bool,
+}
+
+/// 数据库操作的耗时统计，毫秒
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryStats {
+    pub name: String,
+    pub count: u64,
+    pub total_ms: f64,
+    pub max_ms: f64,
+}
+
+/// 内容 / 缩略图内存缓存的统计
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub bytes: u64,
+    pub budget_bytes: u64,
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+/// 内存占用诊断信息：tracked_bytes 为应用自己持有的缓存和缓冲区合计，
+/// process_bytes 为进程常驻内存，无法获取时为 None
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryStats {
+    pub process_bytes: Option<u64>,
+    pub budget_bytes: u64,
+    pub tracked_bytes: u64,
+    pub content_cache: CacheStats,
+    pub capture_frame_bytes: u64,
+    pub paste_stack_items: usize,
+    pub paste_stack_bytes: u64,
+}
+
+/// 存储占用，percent 为占配置配额的百分比，未设置配额时为 0
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub db_bytes: u64,
+    pub images_bytes: u64,
+    pub total_bytes: u64,
+    pub quota_bytes: u64,
+    pub percent: f64,
+    pub by_type: Vec<TypeUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrBackfillProgress {
+    pub done: usize,
+    pub total: usize,
+    pub item_id: Option<i64>,
+    pub finished: bool,
+    pub cancelled: bool,
+}
+
+/// OCR 区域，坐标按图片尺寸归一化到 0~1，原点在左上角
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct OcrBox {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl OcrBox {
+    pub fn union(boxes: impl IntoIterator<Item = OcrBox>) -> Option<OcrBox> {
+        boxes.into_iter().reduce(|a, b| {
+            let x = a.x.min(b.x);
+            let y = a.y.min(b.y);
+            OcrBox {
+                x,
+                y,
+                width: (a.x + a.width).max(b.x + b.width) - x,
+                height: (a.y + a.height).max(b.y + b.height) - y,
+            }
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrWord {
+    pub text: String,
+    pub bbox: OcrBox,
+    pub confidence: Option<f32>, // 0~1，引擎不提供时为空
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrLine {
+    pub text: String,
+    pub bbox: OcrBox,
+    pub confidence: Option<f32>,
+    pub words: Vec<OcrWord>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OcrResult {
+    pub text: String,
+    pub lines: Vec<OcrLine>,
+}
+
+impl OcrResult {
+    pub fn from_lines(lines: Vec<OcrLine>) -> Self {
+        let text = lines
+            .iter()
+            .map(|l| l.text.as_str())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string();
+        Self { text, lines }
+    }
+}
+
+/// 去掉末尾换行并统一换行符，line_endings 为 "lf" / "crlf"，其他值保持原样
+pub fn normalize_text(text: &str, trim_trailing_newlines: bool, line_endings: &str) -> String {
+    let text = if trim_trailing_newlines {
+        text.trim_end_matches(['\r', '\n'])
+    } else {
+        text
+    };
+    match line_endings {
+        "lf" => text.replace("\r\n", "\n"),
+        "crlf" => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+        _ => text.to_string(),
+    }
+}
+
+/// 复制或粘贴时对文本的统一处理
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TextNormalization {
+    #[serde(default)]
+    pub trim_trailing_newlines: bool,
+    #[serde(default)]
+    pub line_endings: String,
+}
+
+impl TextNormalization {
+    pub fn apply(&self, text: &str) -> String {
+        normalize_text(text, self.trim_trailing_newlines, &self.line_endings)
+    }
+}
+
+/// 用户自定义的正则分类器，匹配的普通文本条目 data_type 为 name，前端按 icon / color 显示标签
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct CustomClassifier {
+    pub name: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// 插件目录中的 plugin.json：插件可以提供条目操作（脚本函数）、分类器和分享目标
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginManifest {
+    pub name: String,
+    #[serde(default)]
+    pub version: String,
+    #[serde(default)]
+    pub description: String,
+    // 操作函数所在的 rhai 脚本，相对插件目录
+    #[serde(default = "default_plugin_script")]
+    pub script: String,
+    // 原生插件的动态库名（不含平台前缀和扩展名），设置后操作由动态库执行
+    #[serde(default)]
+    pub library: Option<String>,
+    // 脚本需要调用 fetch，只是声明；用户把 plugin:{id} 加入 script_http_permissions 才授权
+    #[serde(default)]
+    pub http: bool,
+    #[serde(default)]
+    pub actions: Vec<PluginAction>,
+    #[serde(default)]
+    pub classifiers: Vec<CustomClassifier>,
+    #[serde(default)]
+    pub share_targets: Vec<PluginShareTarget>,
+}
+
+fn default_plugin_script() -> String {
+    "main.rhai".to_string()
+}
+
+/// 插件提供的条目操作：以文本调用脚本中的 function(text)，返回的文本作为关联条目保存
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginAction {
+    pub id: String,
+    pub label: String,
+    pub function: String,
+    // 适用的 data_type，为空时适用于所有文本条目
+    #[serde(default)]
+    pub data_types: Vec<String>,
+}
+
+/// 插件提供的分享目标：url 中的 {text} 替换为编码后的条目文本后打开
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PluginShareTarget {
+    pub id: String,
+    pub label: String,
+    pub url: String,
+}
+
+/// 集成使用的 API 令牌：scope 为 read（只读）或 write（可粘贴、暂停等），
+/// no_sensitive 时看不到敏感条目
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ApiToken {
+    pub id: String,
+    pub name: String,
+    pub scope: String,
+    #[serde(default)]
+    pub no_sensitive: bool,
+    pub token_hash: String,
+    pub created_at: String,
+}
+
+/// 剪贴板事件的外发 Webhook
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    // 订阅的事件："capture"、"paste"、"clear"，为空表示全部
+    #[serde(default)]
+    pub events: Vec<String>,
+    // 非空时用它对请求体做 HMAC-SHA256 签名
+    #[serde(default)]
+    pub secret: String,
+    // 附带条目全文
+    #[serde(default)]
+    pub include_content: bool,
+    // 敏感条目也附带预览和全文
+    #[serde(default)]
+    pub include_sensitive: bool,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+/// 新条目的自动归类规则，按顺序使用第一条匹配的规则
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct RoutingRule {
+    #[serde(default)]
+    pub name: String,
+    // 匹配文本内容的正则
+    #[serde(default)]
+    pub pattern: Option<String>,
+    // 来源应用名包含该值（不区分大小写）
+    #[serde(default)]
+    pub source_app: Option<String>,
+    #[serde(default)]
+    pub collection_id: Option<i64>,
+    // 加入条目的标签列表（元数据 tags）
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub pin: bool,
+}
+
+/// 粘贴到特定应用时的处理规则，app 与应用名、bundle id（macOS）、可执行文件名或完整路径之一
+/// 相同时匹配（不区分大小写）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PasteRule {
+    pub app: String,
+    #[serde(default)]
+    pub plain_text: bool,
+    #[serde(default)]
+    pub trim_trailing_newlines: bool,
+    #[serde(default)]
+    pub line_endings: String,
+    // 把制表符替换为指定数量的空格，0 表示不替换
+    #[serde(default)]
+    pub tab_width: usize,
+}
+
+impl PasteRule {
+    pub fn matches(&self, app_name: &str, app_path: &str, bundle_id: Option<&str>) -> bool {
+        let pattern = self.app.trim().to_lowercase();
+        if pattern.is_empty() {
+            return false;
+        }
+        let path = std::path::Path::new(app_path);
+        let exe_name = path.file_name().and_then(|n| n.to_str());
+        let exe_stem = path.file_stem().and_then(|n| n.to_str());
+        [
+            Some(app_name),
+            bundle_id,
+            exe_name,
+            exe_stem,
+            Some(app_path),
+        ]
+        .into_iter()
+        .flatten()
+        .any(|candidate| candidate.to_lowercase() == pattern)
+    }
+
+    /// 按规则转换要写入剪贴板的文本条目；返回是否要求只粘贴纯文本
+    pub fn apply(&self, item: &mut ClipboardItem) -> bool {
+        if item.kind != "text" {
+            return false;
+        }
+        if self.plain_text {
+            item.html_content = None;
+        }
+        item.content = normalize_text(
+            &item.content,
+            self.trim_trailing_newlines,
+            &self.line_endings,
+        );
+        if self.tab_width > 0 {
+            item.content = item.content.replace('\t', &" ".repeat(self.tab_width));
+        }
+        self.plain_text
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Collection {
     pub id: i64,
     pub name: String,
     pub created_at: String,
+    // 免于自动清理和清空历史
+    #[serde(default)]
+    pub exempt_from_retention: bool,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,12 +673,402 @@ pub struct AppConfig {
     pub sensitive_apps: Vec<String>,
     #[serde(default)]
     pub compact_mode: bool,
+    // 演示模式：暂停记录、隐藏来源应用、始终模糊敏感内容，并在弹窗上显示水印
+    #[serde(default)]
+    pub presentation_mode: bool,
     // 清空历史时是否清空置顶内容
     #[serde(default)]
     pub clear_pinned_on_clear: bool,
     // 清空历史时是否删除收藏的内容
     #[serde(default)]
     pub clear_collected_on_clear: bool,
+    // 复制表达式/单位/金额时给出计算结果建议
+    #[serde(default = "default_true")]
+    pub calc_suggestions: bool,
+    #[serde(default = "default_target_currency")]
+    pub target_currency: String,
+    // OpenAI 兼容接口，留空则关闭 AI 操作
+    #[serde(default)]
+    pub ai_endpoint: String,
+    #[serde(default)]
+    pub ai_api_key: String,
+    #[serde(default = "default_ai_model")]
+    pub ai_model: String,
+    // macOS 查找剪贴板：记录查找词 / 把复制的文本同步为查找词
+    #[serde(default)]
+    pub monitor_find_pasteboard: bool,
+    #[serde(default)]
+    pub sync_find_pasteboard: bool,
+    // 启动时把最近一条非敏感记录写回系统剪贴板
+    #[serde(default)]
+    pub restore_clipboard_on_startup: bool,
+    // OCR 引擎：auto（系统引擎，失败时改用 Tesseract）/ native / tesseract；识别语言为空时使用引擎默认值
+    #[serde(default = "default_ocr_engine")]
+    pub ocr_engine: String,
+    #[serde(default)]
+    pub ocr_languages: Vec<String>,
+    // 临时截图缓存和默认目录中已保存截图的清理策略（自选保存目录不受影响），0 表示不限制
+    #[serde(default = "default_capture_cache_max_age_days")]
+    pub capture_cache_max_age_days: u64,
+    #[serde(default = "default_capture_cache_max_size_mb")]
+    pub capture_cache_max_size_mb: u64,
+    // 截图文件名模板，支持 {app}、{seq}、{clipboard} 和 {yyyy-MM-dd} 这类日期占位符
+    #[serde(default = "default_capture_filename_template")]
+    pub capture_filename_template: String,
+    // 截图默认保存目录，留空则保存到应用数据目录
+    #[serde(default)]
+    pub capture_save_dir: String,
+    // 监听的目录，其中新增的文件自动加入历史
+    #[serde(default)]
+    pub watch_folders: Vec<String>,
+    // 局域网只读共享页面，访问需要带上 token
+    #[serde(default)]
+    pub share_server_enabled: bool,
+    #[serde(default = "default_share_server_port")]
+    pub share_server_port: u16,
+    #[serde(default)]
+    pub share_token: String,
+    // 本机 Stream Deck 插件使用的 WebSocket 服务，每条消息都要带上 token
+    #[serde(default)]
+    pub streamdeck_enabled: bool,
+    #[serde(default = "default_streamdeck_port")]
+    pub streamdeck_port: u16,
+    #[serde(default)]
+    pub streamdeck_token: String,
+    // 各集成使用的 API 令牌（只保存哈希），通过 create_api_token / revoke_api_token 管理
+    #[serde(default)]
+    pub api_tokens: Vec<ApiToken>,
+    // macOS 通用剪贴板（其他设备复制）的内容是否记录，记录时来源标记为 Universal Clipboard
+    #[serde(default = "default_true")]
+    pub record_universal_clipboard: bool,
+    // 历史占用配额（数据库 + 图片），0 表示不限制；超过百分比时提醒清理
+    #[serde(default = "default_storage_quota_mb")]
+    pub storage_quota_mb: u64,
+    #[serde(default = "default_storage_warning_percent")]
+    pub storage_warning_percent: u8,
+    // 把一个收藏夹同步到 Git 仓库（使用本机的 git 和凭据），仓库地址为空则关闭
+    #[serde(default)]
+    pub git_sync_repo: String,
+    #[serde(default)]
+    pub git_sync_collection_id: Option<i64>,
+    #[serde(default = "default_git_sync_interval_minutes")]
+    pub git_sync_interval_minutes: u64,
+    // 高级：保存应用私有的剪贴板格式（如 Sketch 图层），粘贴时一并写回
+    #[serde(default)]
+    pub capture_raw_formats: bool,
+    // 按目标应用调整粘贴内容，例如终端只粘贴纯文本
+    #[serde(default)]
+    pub paste_rules: Vec<PasteRule>,
+    // 记录时 / 粘贴时对文本的统一处理，粘贴规则在此之后应用
+    #[serde(default)]
+    pub capture_normalization: TextNormalization,
+    #[serde(default)]
+    pub paste_normalization: TextNormalization,
+    // 粘贴危险命令到这些终端应用前需要确认
+    #[serde(default = "default_terminal_apps")]
+    pub terminal_apps: Vec<String>,
+    // 记录文本的长度限制：少于 min_text_length 个字符不记录；
+    // 超过 max_text_length_kb 时不记录，或开启 truncate_long_text 后截断记录，0 表示不限制
+    #[serde(default = "default_min_text_length")]
+    pub min_text_length: usize,
+    #[serde(default)]
+    pub max_text_length_kb: usize,
+    #[serde(default)]
+    pub truncate_long_text: bool,
+    // 只有首尾空白或换行符不同的文本视为重复
+    #[serde(default)]
+    pub dedup_ignore_whitespace: bool,
+    // 最近读取的条目全文和缩略图的内存缓存上限，0 表示不缓存
+    #[serde(default = "default_content_cache_mb")]
+    pub content_cache_mb: u64,
+    // 内容缓存、截图像素和粘贴栈合计的内存预算，超出时收缩内容缓存，0 表示不限制
+    #[serde(default = "default_memory_budget_mb")]
+    pub memory_budget_mb: u64,
+    // 后台任务（OCR、缩略图等）同时运行的最大数量
+    #[serde(default = "default_background_workers")]
+    pub background_workers: usize,
+    // 弹出窗口大小：remember 沿用上次调整的大小；fixed 使用基准逻辑大小；
+    // screen 再按所在屏幕的逻辑高度相对 1080 缩放
+    #[serde(default = "default_popup_scaling")]
+    pub popup_scaling: String,
+    #[serde(default = "default_popup_width")]
+    pub popup_width: u32,
+    #[serde(default = "default_popup_height")]
+    pub popup_height: u32,
+    // 号码没有国家码时用于转换 E.164 的默认国家码
+    #[serde(default = "default_country_code")]
+    pub default_country_code: String,
+    // 在内置类型之后按顺序匹配的自定义分类器
+    #[serde(default)]
+    pub custom_classifiers: Vec<CustomClassifier>,
+    // 新条目自动放入收藏夹、打标签或置顶的规则
+    #[serde(default)]
+    pub routing_rules: Vec<RoutingRule>,
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    // 运行 scripts 目录中脚本的 on_capture 钩子
+    #[serde(default)]
+    pub scripts_enabled: bool,
+    // 允许调用 fetch 的脚本名称（不含 .rhai），插件为 plugin:{文件夹名}
+    #[serde(default)]
+    pub script_http_permissions: Vec<String>,
+    // 已启用的插件（plugins 目录下的文件夹名）
+    #[serde(default)]
+    pub enabled_plugins: Vec<String>,
+    // MQTT：发布剪贴板事件到 {mqtt_topic}/event，接收 {mqtt_topic}/set/{发送者} 写入剪贴板
+    #[serde(default)]
+    pub mqtt_enabled: bool,
+    #[serde(default)]
+    pub mqtt_host: String,
+    #[serde(default = "default_mqtt_port")]
+    pub mqtt_port: u16,
+    #[serde(default)]
+    pub mqtt_username: String,
+    #[serde(default)]
+    pub mqtt_password: String,
+    #[serde(default = "default_mqtt_topic")]
+    pub mqtt_topic: String,
+    // 允许设置剪贴板的发送者，也是可以互相发送条目的已配对设备
+    #[serde(default)]
+    pub mqtt_allowlist: Vec<String>,
+    // 设置剪贴板的消息必须带此密钥的 HMAC 签名，设备之间发送的条目用它按通信双方派生的密钥加密；留空则不接受远程设置
+    #[serde(default)]
+    pub mqtt_shared_secret: String,
+    // 通过 TLS 连接服务器
+    #[serde(default)]
+    pub mqtt_tls: bool,
+    // 发布的事件中是否带非敏感文本的预览
+    #[serde(default)]
+    pub mqtt_publish_previews: bool,
+    // 同一应用的连续剪贴板变化在停止变化这段时间（毫秒）后只记录最后一次，0 表示立即记录
+    #[serde(default)]
+    pub capture_debounce_ms: u64,
+    // clipboard-update 事件合并的时间窗口（毫秒），0 表示立即发送
+    #[serde(default = "default_update_event_coalesce_ms")]
+    pub update_event_coalesce_ms: u64,
+    // 省电模式：auto 在使用电池或空闲超过 power_saving_idle_minutes 时开启，always / off 固定
+    #[serde(default = "default_power_saving_mode")]
+    pub power_saving_mode: String,
+    #[serde(default = "default_power_saving_idle_minutes")]
+    pub power_saving_idle_minutes: u64,
+    // 减弱动态效果和高对比度：system 跟随系统设置，on / off 固定
+    #[serde(default = "default_accessibility_setting")]
+    pub reduced_motion: String,
+    #[serde(default = "default_accessibility_setting")]
+    pub high_contrast: String,
+    // 弹出窗口的触发方式：toggle 按一次显示 / 隐藏，hold 按住显示、松开粘贴选中条目
+    #[serde(default = "default_popup_trigger_mode")]
+    pub popup_trigger_mode: String,
+    // 托盘图标中键单击、左键双击的操作：none、paste_latest 或命令面板中的操作 id
+    #[serde(default = "default_tray_middle_click_action")]
+    pub tray_middle_click_action: String,
+    #[serde(default = "default_tray_double_click_action")]
+    pub tray_double_click_action: String,
+    // 本地使用统计（弹窗、粘贴、记录、截图次数），只写入本机数据库，默认关闭
+    #[serde(default)]
+    pub usage_tracking: bool,
+    // 同步时标记条目来源的设备名，留空使用主机名；Git 同步的共享仓库只写入这里明确设置的名称
+    #[serde(default)]
+    pub device_name: String,
+    // 不打开窗口、直接把剪贴板换成历史中前一条 / 后一条的快捷键，留空不注册
+    #[serde(default)]
+    pub clipboard_back_shortcut: String,
+    #[serde(default)]
+    pub clipboard_forward_shortcut: String,
+    // 循环粘贴：连续按下时撤销上次粘贴并换成更早的一条，间隔超过窗口后从最新一条开始
+    #[serde(default)]
+    pub cycle_paste_shortcut: String,
+    #[serde(default = "default_cycle_paste_window_ms")]
+    pub cycle_paste_window_ms: u64,
+}
+
+fn default_cycle_paste_window_ms() -> u64 {
+    1500
+}
+
+fn default_tray_middle_click_action() -> String {
+    "none".to_string()
+}
+
+fn default_tray_double_click_action() -> String {
+    "none".to_string()
+}
+
+fn default_popup_trigger_mode() -> String {
+    "toggle".to_string()
+}
+
+fn default_accessibility_setting() -> String {
+    "system".to_string()
+}
+
+fn default_power_saving_mode() -> String {
+    "auto".to_string()
+}
+
+fn default_power_saving_idle_minutes() -> u64 {
+    10
+}
+
+fn default_update_event_coalesce_ms() -> u64 {
+    100
+}
+
+fn default_min_text_length() -> usize {
+    1
+}
+
+impl AppConfig {
+    /// 按长度限制处理要记录的文本，返回 None 表示不记录，bool 表示是否被截断
+    pub fn limit_text_length(&self, mut text: String) -> Option<(String, bool)> {
+        if text.chars().count() < self.min_text_length {
+            return None;
+        }
+        let max_bytes = self.max_text_length_kb * 1024;
+        if max_bytes == 0 || text.len() <= max_bytes {
+            return Some((text, false));
+        }
+        if !self.truncate_long_text {
+            return None;
+        }
+        let mut end = max_bytes;
+        while !text.is_char_boundary(end) {
+            end -= 1;
+        }
+        text.truncate(end);
+        Some((text, true))
+    }
+
+    /// 返回给前端的配置副本，去掉所有密钥；保存时不传这些字段即保留原值
+    pub fn redacted(&self) -> AppConfig {
+        AppConfig {
+            ai_api_key: String::new(),
+            mqtt_password: String::new(),
+            mqtt_shared_secret: String::new(),
+            ..self.clone()
+        }
+    }
+
+    /// 写入配置文件前加密其中的密钥，返回是否有明文被加密
+    pub fn seal_secrets(&mut self, crypto: &Crypto) -> bool {
+        let mut changed = false;
+        for secret in [
+            &mut self.ai_api_key,
+            &mut self.mqtt_password,
+            &mut self.mqtt_shared_secret,
+        ] {
+            let sealed = crypto.seal_secret(secret);
+            changed |= sealed != *secret;
+            *secret = sealed;
+        }
+        changed
+    }
+}
+
+fn default_terminal_apps() -> Vec<String> {
+    [
+        "Terminal",
+        "iTerm",
+        "Warp",
+        "Alacritty",
+        "kitty",
+        "WezTerm",
+        "Hyper",
+        "Ghostty",
+        "WindowsTerminal",
+        "cmd.exe",
+        "powershell",
+        "pwsh",
+        "Konsole",
+        "xterm",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_git_sync_interval_minutes() -> u64 {
+    15
+}
+
+fn default_popup_scaling() -> String {
+    "remember".to_string()
+}
+
+fn default_popup_width() -> u32 {
+    320
+}
+
+fn default_popup_height() -> u32 {
+    400
+}
+
+fn default_country_code() -> String {
+    "1".to_string()
+}
+
+fn default_background_workers() -> usize {
+    2
+}
+
+fn default_memory_budget_mb() -> u64 {
+    256
+}
+
+fn default_content_cache_mb() -> u64 {
+    64
+}
+
+fn default_storage_quota_mb() -> u64 {
+    1024
+}
+
+fn default_storage_warning_percent() -> u8 {
+    90
+}
+
+fn default_mqtt_port() -> u16 {
+    1883
+}
+
+fn default_mqtt_topic() -> String {
+    "clipboard".to_string()
+}
+
+fn default_streamdeck_port() -> u16 {
+    28196
+}
+
+fn default_share_server_port() -> u16 {
+    17890
+}
+
+fn default_capture_filename_template() -> String {
+    "capture_{yyyyMMdd_HHmmss}".to_string()
+}
+
+fn default_capture_cache_max_age_days() -> u64 {
+    7
+}
+
+fn default_capture_cache_max_size_mb() -> u64 {
+    512
+}
+
+fn default_ocr_engine() -> String {
+    "auto".to_string()
+}
+
+fn default_ai_model() -> String {
+    "gpt-4o-mini".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_target_currency() -> String {
+    "USD".to_string()
 }
 
 fn default_language() -> String {
@@ -82,12 +1100,120 @@ impl Default for AppConfig {
             theme: "auto".to_string(),
             sensitive_apps: default_sensitive_apps(),
             compact_mode: false,
+            presentation_mode: false,
             clear_pinned_on_clear: false,
             clear_collected_on_clear: false,
+            calc_suggestions: true,
+            target_currency: default_target_currency(),
+            ai_endpoint: String::new(),
+            ai_api_key: String::new(),
+            ai_model: default_ai_model(),
+            monitor_find_pasteboard: false,
+            sync_find_pasteboard: false,
+            restore_clipboard_on_startup: false,
+            ocr_engine: default_ocr_engine(),
+            ocr_languages: Vec::new(),
+            capture_cache_max_age_days: default_capture_cache_max_age_days(),
+            capture_cache_max_size_mb: default_capture_cache_max_size_mb(),
+            capture_filename_template: default_capture_filename_template(),
+            capture_save_dir: String::new(),
+            watch_folders: Vec::new(),
+            share_server_enabled: false,
+            share_server_port: default_share_server_port(),
+            share_token: String::new(),
+            streamdeck_enabled: false,
+            streamdeck_port: default_streamdeck_port(),
+            streamdeck_token: String::new(),
+            api_tokens: Vec::new(),
+            record_universal_clipboard: true,
+            storage_quota_mb: default_storage_quota_mb(),
+            storage_warning_percent: default_storage_warning_percent(),
+            git_sync_repo: String::new(),
+            git_sync_collection_id: None,
+            git_sync_interval_minutes: default_git_sync_interval_minutes(),
+            capture_raw_formats: false,
+            paste_rules: Vec::new(),
+            capture_normalization: TextNormalization::default(),
+            paste_normalization: TextNormalization::default(),
+            terminal_apps: default_terminal_apps(),
+            min_text_length: default_min_text_length(),
+            max_text_length_kb: 0,
+            truncate_long_text: false,
+            dedup_ignore_whitespace: false,
+            content_cache_mb: default_content_cache_mb(),
+            memory_budget_mb: default_memory_budget_mb(),
+            background_workers: default_background_workers(),
+            popup_scaling: default_popup_scaling(),
+            popup_width: default_popup_width(),
+            popup_height: default_popup_height(),
+            default_country_code: default_country_code(),
+            custom_classifiers: Vec::new(),
+            routing_rules: Vec::new(),
+            webhooks: Vec::new(),
+            scripts_enabled: false,
+            script_http_permissions: Vec::new(),
+            enabled_plugins: Vec::new(),
+            mqtt_enabled: false,
+            mqtt_host: String::new(),
+            mqtt_port: default_mqtt_port(),
+            mqtt_username: String::new(),
+            mqtt_password: String::new(),
+            mqtt_topic: default_mqtt_topic(),
+            mqtt_allowlist: Vec::new(),
+            mqtt_shared_secret: String::new(),
+            mqtt_tls: false,
+            mqtt_publish_previews: false,
+            capture_debounce_ms: 0,
+            update_event_coalesce_ms: default_update_event_coalesce_ms(),
+            power_saving_mode: default_power_saving_mode(),
+            power_saving_idle_minutes: default_power_saving_idle_minutes(),
+            reduced_motion: default_accessibility_setting(),
+            high_contrast: default_accessibility_setting(),
+            popup_trigger_mode: default_popup_trigger_mode(),
+            tray_middle_click_action: default_tray_middle_click_action(),
+            tray_double_click_action: default_tray_double_click_action(),
+            usage_tracking: false,
+            device_name: String::new(),
+            clipboard_back_shortcut: String::new(),
+            clipboard_forward_shortcut: String::new(),
+            cycle_paste_shortcut: String::new(),
+            cycle_paste_window_ms: default_cycle_paste_window_ms(),
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FinalizedCapture {
+    pub path: String,
+    pub item: Option<ClipboardItem>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CacheCleanup {
+    pub files_removed: usize,
+    pub bytes_freed: u64,
+}
+
+/// 截图屏幕上的窗口区域（截图像素坐标），用于选区吸附
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct WindowRect {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub title: String,
+    pub app: String,
+}
+
+/// 放大镜用的像素块，pixels 为逐行排列的 RGBA
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PixelGrid {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+    pub color: String,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct ScreenInfo {
     pub id: u32,
@@ -109,3 +1235,25 @@ pub struct CaptureResult {
     pub height: u32,
     pub scale_factor: f64,
 }
+
+/// scripts 目录下的脚本及其提供的钩子，供设置页展示
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptInfo {
+    pub name: String,
+    pub on_capture: bool,
+    pub transform: bool,
+    pub http: bool,
+    pub error: Option<String>,
+}
+
+/// plugins 目录下的插件，id 为文件夹名；清单无效时 manifest 为空并带上错误
+#[derive(Debug, Clone, Serialize)]
+pub struct PluginInfo {
+    pub id: String,
+    pub path: String,
+    pub enabled: bool,
+    // 用户已允许插件脚本调用 fetch
+    pub http_granted: bool,
+    pub manifest: Option<PluginManifest>,
+    pub error: Option<String>,
+}
@@ -2,97 +2,366 @@ use active_win_pos_rs::get_active_window;
 use chrono::Local;
 use clipboard_master::{CallbackResult, ClipboardHandler};
 use clipboard_rs::{Clipboard, ClipboardContext};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use tauri::{Emitter, Manager};
 use tauri_plugin_clipboard_manager::ClipboardExt;
 
-use crate::models::ClipboardItem;
+use crate::jobs::JobPriority;
+use crate::models::{ClipboardItem, HistoryFilter};
 use crate::state::AppState;
 use crate::tray::update_tray_menu;
-use crate::utils::classify_content;
+use crate::utils::{
+    check_storage_quota, classify_with_custom, emit_clipboard_update, remove_pruned_images,
+    resolve_existing_path_cached, suggested_actions,
+};
+
+// 通过 Handoff（通用剪贴板）从其他设备同步过来的内容带有这个类型标记
+#[cfg(target_os = "macos")]
+const REMOTE_CLIPBOARD_TYPE: &str = "com.apple.is-remote-clipboard";
+
+#[cfg(target_os = "macos")]
+#[allow(deprecated, unexpected_cfgs)]
+fn is_remote_clipboard() -> bool {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSAutoreleasePool, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+        let pasteboard: id = msg_send![class!(NSPasteboard), generalPasteboard];
+        let types: id = msg_send![pasteboard, types];
+        if types == nil {
+            return false;
+        }
+        let marker = NSString::alloc(nil).init_str(REMOTE_CLIPBOARD_TYPE);
+        let found: bool = msg_send![types, containsObject: marker];
+        found
+    }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn is_remote_clipboard() -> bool {
+    false
+}
+
+/// 在后台把剪贴板像素编码为 PNG 写入占位条目指向的文件，完成后更新体积信息、发送 item-updated 事件
+/// 并执行捕获后的处理；编码失败、任务取消或应用退出时删除占位条目和写了一半的文件
+fn encode_image(
+    app: &tauri::AppHandle,
+    id: i64,
+    path: std::path::PathBuf,
+    img: tauri::image::Image<'static>,
+) {
+    app.state::<AppState>()
+        .jobs
+        .schedule(app, "image-encode", JobPriority::High, move |ctx| {
+            let handle = ctx.app();
+            let state = handle.state::<AppState>();
+            let discard = || {
+                let _ = std::fs::remove_file(&path);
+                if let Err(e) = state.db.delete_items(&[id]) {
+                    log::error!("Failed to remove placeholder image item {}: {}", id, e);
+                }
+                refresh_history_views(handle);
+            };
+
+            let encoded = image::save_buffer(
+                &path,
+                img.rgba(),
+                img.width(),
+                img.height(),
+                image::ExtendedColorType::Rgba8,
+            );
+            drop(img);
+            if let Err(e) = encoded {
+                discard();
+                return Err(format!("Failed to encode image for item {}: {}", id, e));
+            }
+            if ctx.is_cancelled() || *state.shutting_down.lock().unwrap() {
+                discard();
+                return Ok(());
+            }
+
+            let exists = match state.db.refresh_image_metrics(id, &path.to_string_lossy()) {
+                Ok(exists) => exists,
+                Err(e) => {
+                    discard();
+                    return Err(format!("Failed to update image item {}: {}", id, e));
+                }
+            };
+            // 编码期间条目已被删除
+            if !exists {
+                let _ = std::fs::remove_file(&path);
+                return Ok(());
+            }
+            log::info!("New image captured and saved to {:?}", path);
+            let _ = handle.emit("item-updated", id);
+            after_capture(handle, id);
+            refresh_history_views(handle);
+            Ok(())
+        });
+}
+
+/// 分类、规范化并记录复制的文本，返回新条目的 id；超出长度限制或写入失败时返回 None
+fn store_text(
+    app: &tauri::AppHandle,
+    text: String,
+    html_content: Option<String>,
+    source_app: Option<String>,
+    max_size: usize,
+) -> Option<i64> {
+    let state = app.state::<AppState>();
+    let is_sensitive = false;
+
+    let data_type = classify_with_custom(&text, &crate::plugins::classifiers(app));
+
+    // Store copied paths in canonical form so they can be opened later
+    let text = if data_type == "path" {
+        resolve_existing_path_cached(&text).unwrap_or(text)
+    } else {
+        state
+            .config
+            .lock()
+            .unwrap()
+            .capture_normalization
+            .apply(&text)
+    };
+
+    let Some((text, is_truncated)) = state.config.lock().unwrap().limit_text_length(text) else {
+        log::info!("Ignored text outside the configured length limits");
+        return None;
+    };
+
+    let suggested_actions = suggested_actions(app, &text, &data_type);
+
+    let item = ClipboardItem {
+        id: None,
+        content: text,
+        kind: "text".to_string(),
+        timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+        is_sensitive,
+        is_pinned: false,
+        source_app,
+        data_type,
+        collection_id: None,
+        note: None,
+        html_content,
+        suggested_actions,
+        is_truncated,
+        ..Default::default()
+    };
+
+    match state.db.insert_item(&item, max_size) {
+        Ok((id, pruned_items)) => {
+            remove_pruned_images(&pruned_items);
+            if is_sensitive {
+                log::info!("New sensitive text captured");
+            } else {
+                log::info!("New text captured");
+                crate::find_pasteboard::propagate(app, &item.content);
+            }
+            Some(id)
+        }
+        Err(e) => {
+            log::error!("Failed to insert text item: {}", e);
+            None
+        }
+    }
+}
+
+/// 用户脚本的 on_capture 钩子可能调用 fetch，在后台任务中运行，钩子保留的文本再记录为条目
+fn run_capture_hooks(
+    app: &tauri::AppHandle,
+    text: String,
+    html_content: Option<String>,
+    source_app: Option<String>,
+    raw: Vec<(String, Vec<u8>)>,
+    max_size: usize,
+) {
+    app.state::<AppState>()
+        .jobs
+        .schedule(app, "capture-hooks", JobPriority::High, move |ctx| {
+            let handle = ctx.app();
+            let Some(text) = crate::scripting::on_capture(handle, text, source_app.as_deref())
+            else {
+                return Ok(());
+            };
+            let state = handle.state::<AppState>();
+            if ctx.is_cancelled() || *state.shutting_down.lock().unwrap() {
+                return Ok(());
+            }
+            let Some(id) = store_text(handle, text, html_content, source_app, max_size) else {
+                return Ok(());
+            };
+            if !raw.is_empty() {
+                if let Err(e) = state.db.set_raw_formats(id, &raw) {
+                    log::error!("Failed to store raw clipboard formats: {}", e);
+                }
+            }
+            after_capture(handle, id);
+            refresh_history_views(handle);
+            Ok(())
+        });
+}
+
+/// 新条目记录后：执行路由规则、发送 Webhook、计数并重置剪贴板后退位置
+fn after_capture(app: &tauri::AppHandle, id: i64) {
+    crate::rules::apply(app, id);
+    crate::webhook::notify_item(app, "capture", id, serde_json::json!({}));
+    crate::usage::record(app, crate::usage::CAPTURE);
+    crate::clipboard_cursor::reset(app);
+}
+
+/// 历史变化后刷新托盘菜单、通知前端并检查存储配额
+fn refresh_history_views(app: &tauri::AppHandle) {
+    let history = app
+        .state::<AppState>()
+        .db
+        .get_history(1, 20, &HistoryFilter::default())
+        .unwrap_or_default();
+    if let Err(e) = update_tray_menu(app, &history) {
+        log::error!("Failed to update tray: {}", e);
+    }
+    emit_clipboard_update(app);
+    check_storage_quota(app);
+}
+
+fn content_hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// 原始格式的稳定哈希，写入条目正文用于去重，不随进程或版本变化
+fn raw_hash(raw: &[(String, Vec<u8>)]) -> u64 {
+    crate::similarity::fnv1a(raw.iter().flat_map(|(format, data)| {
+        format
+            .bytes()
+            .chain(std::iter::once(0))
+            .chain(data.iter().copied())
+    }))
+}
 
 pub struct ClipboardMonitor {
     pub app_handle: tauri::AppHandle,
-    pub last_text: String,
-    pub last_image_hash: Vec<u8>,
+    // 只保存上次内容的哈希用于比较，不在内存中保留全文和图片像素
+    pub last_text_hash: u64,
+    pub last_image_hash: u64,
     pub last_files: Vec<String>,
+    pub last_audio_hash: u64,
+    // 剪贴板变化的序号；去抖等待结束时序号未变才记录，期间的任何变化都会让它作废
+    change_seq: u64,
 }
 
 impl ClipboardMonitor {
     pub fn new(app_handle: tauri::AppHandle) -> Self {
-        let mut last_text = String::new();
-        if let Ok(text) = app_handle.clipboard().read_text() {
-            last_text = text;
-        }
+        let last_text_hash = app_handle
+            .clipboard()
+            .read_text()
+            .map(|text| content_hash(&text))
+            .unwrap_or(0);
         Self {
             app_handle,
-            last_text,
-            last_image_hash: Vec::new(),
+            last_text_hash,
+            last_image_hash: 0,
             last_files: Vec::new(),
+            last_audio_hash: 0,
+            change_seq: 0,
         }
     }
 
+    /// 剪贴板中的音频保存为 audio/ 下的文件，记录为 audio 条目
+    fn insert_audio_item(
+        &self,
+        format: &str,
+        ext: &str,
+        data: &[u8],
+        source_app: Option<String>,
+        max_size: usize,
+    ) -> Result<i64, String> {
+        let state = self.app_handle.state::<AppState>();
+        let timestamp = Local::now().timestamp_nanos_opt().unwrap_or(0);
+        let path =
+            crate::audio::audio_dir(&self.app_handle)?.join(format!("{}.{}", timestamp, ext));
+        std::fs::write(&path, data).map_err(|e| e.to_string())?;
+
+        let item = ClipboardItem {
+            content: path.to_string_lossy().to_string(),
+            kind: "audio".to_string(),
+            data_type: "audio".to_string(),
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            source_app,
+            ..Default::default()
+        };
+        let (id, pruned_items) = state
+            .db
+            .insert_item(&item, max_size)
+            .map_err(|e| e.to_string())?;
+        remove_pruned_images(&pruned_items);
+        log::info!("New audio captured ({}) and saved to {:?}", format, path);
+        Ok(id)
+    }
+
+    /// 只有私有格式的内容：正文显示格式名和数据哈希，避免不同内容被当作重复项合并
+    fn insert_raw_item(
+        &self,
+        raw: &[(String, Vec<u8>)],
+        formats: Vec<String>,
+        source_app: Option<String>,
+        max_size: usize,
+    ) -> Result<(), String> {
+        let state = self.app_handle.state::<AppState>();
+        let item = ClipboardItem {
+            content: format!("{} [{:08x}]", formats.join(", "), raw_hash(raw) as u32),
+            kind: "raw".to_string(),
+            data_type: "raw".to_string(),
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            source_app,
+            ..Default::default()
+        };
+        let (id, pruned_items) = state
+            .db
+            .insert_item(&item, max_size)
+            .map_err(|e| e.to_string())?;
+        remove_pruned_images(&pruned_items);
+        state
+            .db
+            .set_raw_formats(id, raw)
+            .map_err(|e| e.to_string())?;
+        log::info!("New raw clipboard formats captured: {:?}", formats);
+        Ok(())
+    }
+
     fn is_password_manager(&self, app_name: &str) -> bool {
         let state = self.app_handle.state::<AppState>();
         let config = state.config.lock().unwrap();
-        config
-            .sensitive_apps
-            .iter()
-            .any(|app| app_name.contains(app) || app_name.eq_ignore_ascii_case(app))
+        crate::utils::is_sensitive_app(&config, app_name)
     }
 }
 
-impl ClipboardHandler for ClipboardMonitor {
-    fn on_clipboard_change(&mut self) -> CallbackResult {
+impl ClipboardMonitor {
+    /// 读取剪贴板并记录与上次不同的内容
+    fn capture(&mut self, source_app: Option<String>) {
         let state = self.app_handle.state::<AppState>();
-
-        // Check if paused
-        if let Ok(paused) = state.is_paused.lock() {
-            if *paused {
-                return CallbackResult::Next;
-            }
-        }
-
-        // Check active application
-        let mut source_app = None;
-        if let Ok(active_window) = get_active_window() {
-            log::info!("Active window app: {}", active_window.app_name);
-            if self.is_password_manager(&active_window.app_name) {
-                log::info!(
-                    "Ignored clipboard change from sensitive app: {}",
-                    active_window.app_name
-                );
-                return CallbackResult::Next;
-            }
-            source_app = Some(active_window.app_name);
-        } else {
-            log::warn!("Failed to get active window");
-        }
-
         let mut updated = false;
         let max_size = state.config.lock().unwrap().max_history_size;
 
         let mut captured_something = false;
+        let mut inserted_id = None;
+        // 图片编码或脚本钩子交给后台任务，捕获后的处理稍后才会执行
+        let mut pending = false;
 
         // Check files
         if let Ok(ctx) = ClipboardContext::new() {
             if let Ok(files) = ctx.get_files() {
                 if !files.is_empty() {
-                    // Check if this change was initiated by the app itself
-                    if let Ok(mut last_app_file_change) = state.last_app_file_change.lock() {
-                        if let Some(last_files) = last_app_file_change.as_ref() {
-                            if last_files == &files {
-                                log::info!("Ignoring clipboard file change initiated by app");
-                                self.last_files = files;
-                                *last_app_file_change = None;
-                                return CallbackResult::Next;
-                            }
-                        }
-                    }
-
                     if files != self.last_files {
                         self.last_files = files.clone();
-                        self.last_text = String::new();
-                        self.last_image_hash = Vec::new();
+                        self.last_text_hash = 0;
+                        self.last_image_hash = 0;
 
                         let content = serde_json::to_string(&files).unwrap_or_default();
 
@@ -108,18 +377,13 @@ impl ClipboardHandler for ClipboardMonitor {
                             collection_id: None,
                             note: None,
                             html_content: None,
+                            ..Default::default()
                         };
 
                         match state.db.insert_item(&item, max_size) {
-                            Ok(pruned_items) => {
-                                for pruned in pruned_items {
-                                    if pruned.kind == "image" {
-                                        let path = std::path::Path::new(&pruned.content);
-                                        if path.exists() {
-                                            let _ = std::fs::remove_file(path);
-                                        }
-                                    }
-                                }
+                            Ok((id, pruned_items)) => {
+                                inserted_id = Some(id);
+                                remove_pruned_images(&pruned_items);
                                 updated = true;
                                 log::info!("New files captured");
                             }
@@ -135,65 +399,48 @@ impl ClipboardHandler for ClipboardMonitor {
 
         // Check text
         if !captured_something {
-            if let Ok(text) = self.app_handle.clipboard().read_text() {
-                // Check if this change was initiated by the app itself
-                if let Ok(mut last_app_change) = state.last_app_change.lock() {
-                    if let Some(last_content) = last_app_change.as_ref() {
-                        if last_content == &text {
-                            log::info!("Ignoring clipboard change initiated by app");
-                            self.last_text = text;
-                            *last_app_change = None;
-                            return CallbackResult::Next;
-                        }
-                    }
-                }
-
-                if text != self.last_text && !text.is_empty() {
-                    self.last_text = text.clone();
-                    let is_sensitive = false;
-                    let data_type = classify_content(&text);
-
+            // 只提供 SVG 格式（如设计工具复制的图形）时，以 SVG 源码作为文本记录
+            let text = self
+                .app_handle
+                .clipboard()
+                .read_text()
+                .ok()
+                .filter(|text| !text.is_empty())
+                .or_else(crate::svg::read_clipboard);
+            if let Some(text) = text {
+                let text_hash = content_hash(&text);
+                if text_hash != self.last_text_hash && !text.is_empty() {
+                    self.last_text_hash = text_hash;
                     let html_content = if let Ok(ctx) = ClipboardContext::new() {
                         ctx.get_html().ok()
                     } else {
                         None
                     };
-
-                    let item = ClipboardItem {
-                        id: None,
-                        content: text,
-                        kind: "text".to_string(),
-                        timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
-                        is_sensitive,
-                        is_pinned: false,
-                        source_app: source_app.clone(),
-                        data_type,
-                        collection_id: None,
-                        note: None,
+                    if crate::scripting::has_capture_hooks(&self.app_handle) {
+                        // 剪贴板随后可能变化，原始格式现在读取，随条目一起记录
+                        let raw = if state.config.lock().unwrap().capture_raw_formats {
+                            crate::raw_formats::read().0
+                        } else {
+                            Vec::new()
+                        };
+                        run_capture_hooks(
+                            &self.app_handle,
+                            text,
+                            html_content,
+                            source_app.clone(),
+                            raw,
+                            max_size,
+                        );
+                        pending = true;
+                    } else if let Some(id) = store_text(
+                        &self.app_handle,
+                        text,
                         html_content,
-                    };
-
-                    match state.db.insert_item(&item, max_size) {
-                        Ok(pruned_items) => {
-                            // Delete pruned images
-                            for pruned in pruned_items {
-                                if pruned.kind == "image" {
-                                    let path = std::path::Path::new(&pruned.content);
-                                    if path.exists() {
-                                        let _ = std::fs::remove_file(path);
-                                    }
-                                }
-                            }
-                            updated = true;
-                            if is_sensitive {
-                                log::info!("New sensitive text captured");
-                            } else {
-                                log::info!("New text captured");
-                            }
-                        }
-                        Err(e) => {
-                            log::error!("Failed to insert text item: {}", e);
-                        }
+                        source_app.clone(),
+                        max_size,
+                    ) {
+                        inserted_id = Some(id);
+                        updated = true;
                     }
                 }
             }
@@ -202,89 +449,227 @@ impl ClipboardHandler for ClipboardMonitor {
         // Check image
         if !captured_something {
             if let Ok(img) = self.app_handle.clipboard().read_image() {
-                let rgba = img.rgba();
-
-                // Check if this change was initiated by the app itself
-                if let Ok(mut last_app_image_change) = state.last_app_image_change.lock() {
-                    if let Some(last_content) = last_app_image_change.as_ref() {
-                        if last_content == rgba {
-                            log::info!("Ignoring clipboard image change initiated by app");
-                            self.last_image_hash = rgba.to_vec();
-                            *last_app_image_change = None;
-                            return CallbackResult::Next;
-                        }
-                    }
-                }
+                let width = img.width();
+                let height = img.height();
+                let rgba_len = img.rgba().len();
+                let image_hash = content_hash(&(width, height, img.rgba()));
+                if rgba_len > 0 && image_hash != self.last_image_hash {
+                    self.last_image_hash = image_hash;
 
-                if !rgba.is_empty()
-                    && (rgba.len() != self.last_image_hash.len()
-                        || rgba != self.last_image_hash.as_slice())
-                {
-                    self.last_image_hash = rgba.to_vec();
-
-                    let width = img.width();
-                    let height = img.height();
-                    if let Some(buffer) = image::RgbaImage::from_raw(width, height, rgba.to_vec()) {
+                    if rgba_len == (width as usize) * (height as usize) * 4 {
+                        // 复制的是 GIF 时保存原始数据，保留动画
+                        let gif = crate::utils::read_clipboard_gif();
                         let timestamp = Local::now().timestamp_nanos_opt().unwrap_or(0);
-                        let filename = format!("{}.png", timestamp);
+                        let extension = if gif.is_some() { "gif" } else { "png" };
+                        let filename = format!("{}.{}", timestamp, extension);
                         let app_data_dir = self.app_handle.path().app_data_dir().unwrap();
-                        let image_path = app_data_dir.join("images").join(&filename);
+                        let images_dir = app_data_dir.join("images");
+                        if let Err(e) = std::fs::create_dir_all(&images_dir) {
+                            log::error!("Failed to create images directory: {}", e);
+                        }
+                        let image_path = images_dir.join(&filename);
 
-                        if let Err(e) = buffer.save(&image_path) {
-                            log::error!("Failed to save image to disk: {}", e);
-                        } else {
-                            let item = ClipboardItem {
-                                id: None,
-                                content: image_path.to_string_lossy().to_string(),
-                                kind: "image".to_string(),
-                                timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
-                                is_sensitive: false,
-                                is_pinned: false,
-                                source_app: source_app.clone(),
-                                data_type: "image".to_string(),
-                                collection_id: None,
-                                note: None,
-                                html_content: None,
-                            };
-
-                            match state.db.insert_item(&item, max_size) {
-                                Ok(pruned_items) => {
-                                    // Delete pruned images
-                                    for pruned in pruned_items {
-                                        if pruned.kind == "image" {
-                                            let path = std::path::Path::new(&pruned.content);
-                                            if path.exists() {
-                                                let _ = std::fs::remove_file(path);
+                        let item = ClipboardItem {
+                            id: None,
+                            content: image_path.to_string_lossy().to_string(),
+                            kind: "image".to_string(),
+                            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+                            is_sensitive: false,
+                            is_pinned: false,
+                            source_app: source_app.clone(),
+                            data_type: "image".to_string(),
+                            collection_id: None,
+                            note: None,
+                            html_content: None,
+                            ..Default::default()
+                        };
+
+                        // GIF 直接写入原始数据；PNG 编码较慢，先记录占位条目，再交给后台任务编码，
+                        // 编码完成后更新条目并执行捕获后的处理
+                        match gif {
+                            Some(bytes) => {
+                                if let Err(e) = std::fs::write(&image_path, bytes) {
+                                    log::error!("Failed to save image to disk: {}", e);
+                                } else {
+                                    match state.db.insert_item(&item, max_size) {
+                                        Ok((id, pruned_items)) => {
+                                            inserted_id = Some(id);
+                                            remove_pruned_images(&pruned_items);
+                                            updated = true;
+                                            log::info!(
+                                                "New image captured and saved to {:?}",
+                                                image_path
+                                            );
+                                        }
+                                        Err(e) => {
+                                            log::error!("Failed to insert image item: {}", e);
+                                        }
+                                    }
+                                }
+                            }
+                            None => match state.db.insert_item(&item, max_size) {
+                                Ok((id, pruned_items)) => {
+                                    remove_pruned_images(&pruned_items);
+                                    // 剪贴板随后可能变化，原始格式现在读取并记录
+                                    if state.config.lock().unwrap().capture_raw_formats {
+                                        let raw = crate::raw_formats::read().0;
+                                        if !raw.is_empty() {
+                                            if let Err(e) = state.db.set_raw_formats(id, &raw) {
+                                                log::error!(
+                                                    "Failed to store raw clipboard formats: {}",
+                                                    e
+                                                );
                                             }
                                         }
                                     }
+                                    encode_image(
+                                        &self.app_handle,
+                                        id,
+                                        image_path.clone(),
+                                        img.to_owned(),
+                                    );
                                     updated = true;
-                                    log::info!("New image captured and saved to {:?}", image_path);
+                                    pending = true;
                                 }
                                 Err(e) => {
                                     log::error!("Failed to insert image item: {}", e);
                                 }
-                            }
+                            },
                         }
                     }
                 }
             }
         }
 
-        if updated {
-            let history = state
-                .db
-                .get_history(1, 20, None, false, false, None)
-                .unwrap_or_default();
-            if let Err(e) = update_tray_menu(&self.app_handle, &history) {
-                log::error!("Failed to update tray: {}", e);
+        // Check audio
+        if !captured_something && inserted_id.is_none() && !pending {
+            if let Some((format, ext, data)) = crate::audio::read() {
+                let hash = content_hash(&data);
+
+                if hash != self.last_audio_hash {
+                    self.last_audio_hash = hash;
+                    match self.insert_audio_item(&format, ext, &data, source_app.clone(), max_size)
+                    {
+                        Ok(id) => {
+                            inserted_id = Some(id);
+                            updated = true;
+                        }
+                        Err(e) => log::error!("Failed to insert audio item: {}", e),
+                    }
+                }
             }
+        }
+
+        // 应用私有的剪贴板格式：附加到本次记录，或在只有这些格式时单独记录
+        if !pending && state.config.lock().unwrap().capture_raw_formats {
+            let (raw, only_raw) = crate::raw_formats::read();
+            if let Some(id) = inserted_id.filter(|_| !raw.is_empty()) {
+                if let Err(e) = state.db.set_raw_formats(id, &raw) {
+                    log::error!("Failed to store raw clipboard formats: {}", e);
+                }
+            } else if only_raw {
+                let formats: Vec<String> = raw.iter().map(|(format, _)| format.clone()).collect();
+                match self.insert_raw_item(&raw, formats, source_app.clone(), max_size) {
+                    Ok(()) => updated = true,
+                    Err(e) => log::error!("Failed to insert raw clipboard item: {}", e),
+                }
+            }
+        }
+
+        if let Some(id) = inserted_id {
+            after_capture(&self.app_handle, id);
+        }
+
+        if updated {
+            refresh_history_views(&self.app_handle);
+        }
+    }
+}
+
+/// 剪贴板监听回调；监听状态放在锁中，去抖等待结束后在计时线程中记录
+pub struct MonitorHandler(Arc<Mutex<ClipboardMonitor>>);
+
+impl MonitorHandler {
+    pub fn new(monitor: ClipboardMonitor) -> Self {
+        Self(Arc::new(Mutex::new(monitor)))
+    }
+}
+
+impl ClipboardHandler for MonitorHandler {
+    fn on_clipboard_change(&mut self) -> CallbackResult {
+        let mut monitor = self.0.lock().unwrap();
+        monitor.change_seq += 1;
+        let seq = monitor.change_seq;
+        let app = monitor.app_handle.clone();
+        let state = app.state::<AppState>();
+
+        if *state.shutting_down.lock().unwrap() {
+            return CallbackResult::Stop;
+        }
 
-            if let Err(e) = self.app_handle.emit("clipboard-update", ()) {
-                log::error!("Failed to emit clipboard-update event: {}", e);
+        // Check if paused
+        if crate::utils::is_capture_suspended(&state) {
+            return CallbackResult::Next;
+        }
+
+        // 应用自己写入的内容（粘贴、恢复等）带有写入令牌，任何类型都直接跳过；
+        // 清空上次记录的内容，之后再复制同样的内容仍会被记录
+        if crate::self_write::is_self_write(&app) {
+            log::info!("Ignoring clipboard change initiated by app");
+            monitor.last_text_hash = 0;
+            monitor.last_image_hash = 0;
+            monitor.last_files.clear();
+            monitor.last_audio_hash = 0;
+            return CallbackResult::Next;
+        }
+
+        // 来自其他设备的通用剪贴板内容，与当前前台应用无关
+        let remote = is_remote_clipboard();
+        if remote && !state.config.lock().unwrap().record_universal_clipboard {
+            log::info!("Ignored Universal Clipboard item");
+            return CallbackResult::Next;
+        }
+
+        // Check active application
+        let mut source_app = None;
+        if remote {
+            source_app = Some("Universal Clipboard".to_string());
+        } else if let Ok(active_window) = get_active_window() {
+            log::info!("Active window app: {}", active_window.app_name);
+            if monitor.is_password_manager(&active_window.app_name) {
+                log::info!(
+                    "Ignored clipboard change from sensitive app: {}",
+                    active_window.app_name
+                );
+                return CallbackResult::Next;
             }
+            source_app = Some(active_window.app_name);
+        } else {
+            log::warn!("Failed to get active window");
         }
 
+        // 有的应用（如部分 IDE）会在短时间内反复改写剪贴板：同一应用的连续变化只在停止变化
+        // 一段时间后记录最后一次。剪贴板只有一份内容，其他应用或应用自身的写入会让等待作废
+        let debounce = Duration::from_millis(state.config.lock().unwrap().capture_debounce_ms);
+        if debounce.is_zero() {
+            monitor.capture(source_app);
+            return CallbackResult::Next;
+        }
+        drop(monitor);
+        let shared = self.0.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(debounce);
+            let mut monitor = shared.lock().unwrap();
+            let state = app.state::<AppState>();
+            if monitor.change_seq != seq
+                || *state.shutting_down.lock().unwrap()
+                || crate::utils::is_capture_suspended(&state)
+            {
+                return;
+            }
+            monitor.capture(source_app);
+        });
+
         CallbackResult::Next
     }
 
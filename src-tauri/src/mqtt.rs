@@ -0,0 +1,512 @@
+use std::collections::HashMap;
+use std::time::Duration;
+
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use rumqttc::{Client, Event, MqttOptions, Packet, QoS, Transport};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::Sha256;
+use tauri::{Emitter, Manager};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+
+use crate::crypto::Crypto;
+use crate::error::AppError;
+use crate::models::ClipboardItem;
+use crate::state::AppState;
+
+// 通过 MQTT 写入剪贴板的文本长度上限
+const MAX_SET_BYTES: usize = 1024 * 1024;
+// 设置剪贴板消息的时间戳与本机时间相差超过此值（毫秒）时拒绝
+const MAX_SET_CLOCK_SKEW_MS: i64 = 5 * 60 * 1000;
+
+/// 设置剪贴板的消息体，signature 为 HMAC-SHA256(mqtt_shared_secret, "{sender}\n{timestamp}\n{text}")
+/// 的十六进制，可带 "sha256=" 前缀
+#[derive(Deserialize)]
+struct SetMessage {
+    text: String,
+    timestamp: i64,
+    signature: String,
+}
+
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    if text.len() % 2 != 0 {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(text.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// 校验消息签名，比较在常量时间内完成
+fn verify_signature(secret: &str, sender: &str, message: &SetMessage) -> bool {
+    let hex = message
+        .signature
+        .strip_prefix("sha256=")
+        .unwrap_or(&message.signature);
+    let Some(signature) = decode_hex(hex) else {
+        return false;
+    };
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(format!("{}\n{}\n{}", sender, message.timestamp, message.text).as_bytes());
+    mac.verify_slice(&signature).is_ok()
+}
+
+/// 设置剪贴板的主题：{topic}/set/{发送者}，发送者必须在白名单中
+fn set_topic(base: &str) -> String {
+    format!("{}/set/+", base)
+}
+
+fn event_topic(base: &str) -> String {
+    format!("{}/event", base)
+}
+
+/// 发送到设备的主题：{topic}/device/{接收设备}/{发送设备}
+fn transfer_topic(base: &str, target: &str, sender: &str) -> String {
+    format!("{}/device/{}/{}", base, target, sender)
+}
+
+/// 收到条目后的回执主题：{topic}/ack/{发送设备}/{接收设备}
+fn ack_topic(base: &str, target: &str, sender: &str) -> String {
+    format!("{}/ack/{}/{}", base, target, sender)
+}
+
+/// 设备名作为主题的一级，不能包含主题分隔符和通配符
+fn is_topic_level(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '+', '#'])
+}
+
+/// 设备之间传输的内容和回执，解密后 sender / target 必须与主题一致，防止被转发给其他设备
+#[derive(Serialize, Deserialize)]
+struct Transfer {
+    id: String,
+    sender: String,
+    target: String,
+    timestamp: i64,
+    // 回执不带内容
+    #[serde(default)]
+    text: Option<String>,
+}
+
+/// 实际发布的消息：随机 salt 和加密后的 Transfer
+#[derive(Serialize, Deserialize)]
+struct Envelope {
+    salt: String,
+    data: String,
+}
+
+/// 按发送方和接收方从共享密钥派生本条消息的密钥。共享密钥由所有已配对设备持有，
+/// 因此加密只防止 MQTT 服务器和未配对的客户端读取或伪造内容，不防已配对的设备
+fn transfer_cipher(secret: &str, salt: &[u8], sender: &str, target: &str) -> Crypto {
+    Crypto::derive(
+        secret.as_bytes(),
+        salt,
+        format!("clipboard send-to-device\n{}\n{}", sender, target).as_bytes(),
+    )
+}
+
+/// 加密 Transfer，密钥绑定其中的发送方和接收方
+fn seal_transfer(secret: &str, transfer: &Transfer) -> Result<String, AppError> {
+    let salt: [u8; 16] = rand::random();
+    let data = transfer_cipher(secret, &salt, &transfer.sender, &transfer.target)
+        .encrypt(&serde_json::to_string(transfer)?)?;
+    Ok(serde_json::to_string(&Envelope {
+        salt: general_purpose::STANDARD.encode(salt),
+        data,
+    })?)
+}
+
+/// 用主题中的发送方和本机名称派生密钥解密，失败时返回 None
+fn open_transfer(secret: &str, payload: &[u8], sender: &str, target: &str) -> Option<Transfer> {
+    let envelope: Envelope = serde_json::from_slice(payload).ok()?;
+    let salt = general_purpose::STANDARD.decode(envelope.salt).ok()?;
+    let json = transfer_cipher(secret, &salt, sender, target)
+        .decrypt(&envelope.data)
+        .ok()?;
+    serde_json::from_str(&json).ok()
+}
+
+/// 把一条文本条目加密后发送到白名单中的设备，对方收到后直接写入剪贴板并回执；
+/// 返回本次传输的 id，回执到达时发出 device-send-confirmed
+pub fn send_to_device(app: &tauri::AppHandle, id: i64, device: &str) -> Result<String, AppError> {
+    let state = app.state::<AppState>();
+    let Some(client) = state.mqtt_client.lock().unwrap().clone() else {
+        return Err(AppError::Unsupported("MQTT is not connected".to_string()));
+    };
+    let (base, own, allowed, secret) = {
+        let config = state.config.lock().unwrap();
+        (
+            config.mqtt_topic.trim_end_matches('/').to_string(),
+            crate::utils::device_name(&config),
+            config.mqtt_allowlist.iter().any(|a| a == device),
+            state.crypto.open_secret(&config.mqtt_shared_secret),
+        )
+    };
+    if !allowed || !is_topic_level(device) {
+        return Err(AppError::InvalidInput(format!(
+            "Device {} is not paired",
+            device
+        )));
+    }
+    if !is_topic_level(&own) {
+        return Err(AppError::InvalidInput(format!(
+            "Device name {} cannot be used as an MQTT topic level",
+            own
+        )));
+    }
+    if secret.is_empty() {
+        return Err(AppError::InvalidInput(
+            "No MQTT shared secret configured".to_string(),
+        ));
+    }
+
+    let item = state.db.get_item(id)?;
+    if item.kind != "text" {
+        return Err(AppError::Unsupported(
+            "Only text items can be sent to a device".to_string(),
+        ));
+    }
+    let transfer = Transfer {
+        id: crate::share_server::generate_token()[..16].to_string(),
+        sender: own,
+        target: device.to_string(),
+        timestamp: chrono::Utc::now().timestamp_millis(),
+        text: Some(item.content),
+    };
+    let payload = seal_transfer(&secret, &transfer)?;
+    if payload.len() > MAX_SET_BYTES {
+        return Err(AppError::InvalidInput(
+            "Item is too large to send to a device".to_string(),
+        ));
+    }
+    client
+        .try_publish(
+            transfer_topic(&base, device, &transfer.sender),
+            QoS::AtLeastOnce,
+            false,
+            payload,
+        )
+        .map_err(|e| AppError::Other(format!("Failed to publish MQTT transfer: {}", e)))?;
+    log::info!("Sent item {} to device {:?}", id, device);
+    Ok(transfer.id)
+}
+
+/// 处理 {topic}/device/{本机}/{sender} 和 {topic}/ack/{本机}/{sender} 消息：
+/// 发送设备在白名单中、能用派生的密钥解密且不是重放时，写入剪贴板并回执，或发出发送确认事件
+fn handle_transfer(
+    app: &tauri::AppHandle,
+    client: &Client,
+    base: &str,
+    topic: &str,
+    payload: &[u8],
+    last_seen: &mut HashMap<String, i64>,
+) {
+    let state = app.state::<AppState>();
+    let (own, allowlist, secret) = {
+        let config = state.config.lock().unwrap();
+        (
+            crate::utils::device_name(&config),
+            config.mqtt_allowlist.clone(),
+            state.crypto.open_secret(&config.mqtt_shared_secret),
+        )
+    };
+    let (is_ack, sender) =
+        if let Some(sender) = topic.strip_prefix(&format!("{}/device/{}/", base, own)) {
+            (false, sender)
+        } else if let Some(sender) = topic.strip_prefix(&format!("{}/ack/{}/", base, own)) {
+            (true, sender)
+        } else {
+            return;
+        };
+    if !allowlist.iter().any(|a| a == sender) {
+        log::warn!("Ignored MQTT transfer from {:?}: not in allowlist", sender);
+        return;
+    }
+    if secret.is_empty() || payload.len() > MAX_SET_BYTES {
+        log::warn!(
+            "Ignored MQTT transfer from {:?}: no shared secret or payload too large",
+            sender
+        );
+        return;
+    }
+    let transfer = open_transfer(&secret, payload, sender, &own);
+    let Some(transfer) = transfer.filter(|t| t.sender == sender && t.target == own) else {
+        log::warn!(
+            "Ignored MQTT transfer from {:?}: invalid or undecryptable message",
+            sender
+        );
+        return;
+    };
+    let now = chrono::Utc::now().timestamp_millis();
+    let key = format!("{}:{}", if is_ack { "ack" } else { "device" }, sender);
+    let replayed = last_seen
+        .get(&key)
+        .is_some_and(|&last| transfer.timestamp <= last);
+    if replayed || (now - transfer.timestamp).abs() > MAX_SET_CLOCK_SKEW_MS {
+        log::warn!(
+            "Ignored MQTT transfer from {:?}: stale or replayed message",
+            sender
+        );
+        return;
+    }
+    last_seen.insert(key, transfer.timestamp);
+
+    if is_ack {
+        log::info!("Device {:?} received transfer {}", sender, transfer.id);
+        let _ = app.emit(
+            "device-send-confirmed",
+            json!({ "id": transfer.id, "device": sender }),
+        );
+        return;
+    }
+
+    let Some(text) = transfer.text else {
+        return;
+    };
+    if let Err(e) = app.clipboard().write_text(text) {
+        log::error!("Failed to set clipboard from device transfer: {}", e);
+        return;
+    }
+    log::info!("Clipboard set from device {:?}", sender);
+    let _ = app.emit(
+        "device-item-received",
+        json!({ "id": transfer.id, "device": sender }),
+    );
+
+    let ack = Transfer {
+        id: transfer.id,
+        sender: own.clone(),
+        target: sender.to_string(),
+        timestamp: now,
+        text: None,
+    };
+    match seal_transfer(&secret, &ack) {
+        Ok(payload) => {
+            if let Err(e) = client.try_publish(
+                ack_topic(base, sender, &own),
+                QoS::AtLeastOnce,
+                false,
+                payload,
+            ) {
+                log::error!("Failed to publish MQTT transfer receipt: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to encrypt MQTT transfer receipt: {}", e),
+    }
+}
+
+/// 处理 {topic}/set/{sender} 消息：发送者在白名单中且签名有效时把文本写入剪贴板，随后由监听线程记录。
+/// 主题中的发送者任何客户端都能伪造，因此未配置共享密钥时拒绝所有消息；
+/// last_seen 记录各发送者最近一次接受的时间戳，防止重放
+fn handle_set(
+    app: &tauri::AppHandle,
+    base: &str,
+    topic: &str,
+    payload: &[u8],
+    last_seen: &mut HashMap<String, i64>,
+) {
+    let Some(sender) = topic.strip_prefix(&format!("{}/set/", base)) else {
+        return;
+    };
+    let state = app.state::<AppState>();
+    let (allowed, secret) = {
+        let config = state.config.lock().unwrap();
+        (
+            config.mqtt_allowlist.iter().any(|a| a == sender),
+            state.crypto.open_secret(&config.mqtt_shared_secret),
+        )
+    };
+    if !allowed {
+        log::warn!(
+            "Ignored MQTT set clipboard from {:?}: not in allowlist",
+            sender
+        );
+        return;
+    }
+    if secret.is_empty() {
+        log::warn!(
+            "Ignored MQTT set clipboard from {:?}: no shared secret configured",
+            sender
+        );
+        return;
+    }
+    if payload.len() > MAX_SET_BYTES {
+        log::warn!(
+            "Ignored MQTT set clipboard from {:?}: payload too large",
+            sender
+        );
+        return;
+    }
+    let Ok(message) = serde_json::from_slice::<SetMessage>(payload) else {
+        log::warn!(
+            "Ignored MQTT set clipboard from {:?}: invalid message",
+            sender
+        );
+        return;
+    };
+    if !verify_signature(&secret, sender, &message) {
+        log::warn!(
+            "Ignored MQTT set clipboard from {:?}: invalid signature",
+            sender
+        );
+        return;
+    }
+    let now = chrono::Utc::now().timestamp_millis();
+    let replayed = last_seen
+        .get(sender)
+        .is_some_and(|&last| message.timestamp <= last);
+    if replayed || (now - message.timestamp).abs() > MAX_SET_CLOCK_SKEW_MS {
+        log::warn!(
+            "Ignored MQTT set clipboard from {:?}: stale or replayed message",
+            sender
+        );
+        return;
+    }
+    last_seen.insert(sender.to_string(), message.timestamp);
+    match app.clipboard().write_text(message.text) {
+        Ok(()) => log::info!("Clipboard set from MQTT sender {:?}", sender),
+        Err(e) => log::error!("Failed to set clipboard from MQTT: {}", e),
+    }
+}
+
+/// 按配置（重新）连接 MQTT 服务器，关闭时断开旧连接并让旧线程退出
+pub fn restart(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let config = state.config.lock().unwrap().clone();
+    let generation = {
+        let mut generation = state.mqtt_generation.lock().unwrap();
+        *generation += 1;
+        *generation
+    };
+    if let Some(client) = state.mqtt_client.lock().unwrap().take() {
+        let _ = client.try_disconnect();
+    }
+    if !config.mqtt_enabled || config.mqtt_host.trim().is_empty() {
+        return;
+    }
+
+    let client_id = format!("clipboard-{}", &crate::share_server::generate_token()[..8]);
+    let mut options = MqttOptions::new(client_id, config.mqtt_host.trim(), config.mqtt_port);
+    options.set_keep_alive(Duration::from_secs(30));
+    if config.mqtt_tls {
+        options.set_transport(Transport::tls_with_default_config());
+    }
+    if !config.mqtt_username.is_empty() {
+        options.set_credentials(
+            config.mqtt_username,
+            state.crypto.open_secret(&config.mqtt_password),
+        );
+    }
+    let base = config.mqtt_topic.trim_end_matches('/').to_string();
+    let device = crate::utils::device_name(&config);
+
+    let app = app.clone();
+    std::thread::spawn(move || {
+        let is_current = || *app.state::<AppState>().mqtt_generation.lock().unwrap() == generation;
+        let (client, mut connection) = Client::new(options, 16);
+        if !is_current() {
+            return;
+        }
+        *app.state::<AppState>().mqtt_client.lock().unwrap() = Some(client.clone());
+        let mut last_seen = HashMap::new();
+
+        for notification in connection.iter() {
+            if !is_current() {
+                break;
+            }
+            match notification {
+                // 每次（重新）连接后都要重新订阅
+                Ok(Event::Incoming(Packet::ConnAck(_))) => {
+                    log::info!("MQTT connected");
+                    if let Err(e) = client.try_subscribe(set_topic(&base), QoS::AtLeastOnce) {
+                        log::error!("Failed to subscribe MQTT set topic: {}", e);
+                    }
+                    if is_topic_level(&device) {
+                        for topic in [
+                            transfer_topic(&base, &device, "+"),
+                            ack_topic(&base, &device, "+"),
+                        ] {
+                            if let Err(e) = client.try_subscribe(topic, QoS::AtLeastOnce) {
+                                log::error!("Failed to subscribe MQTT device topic: {}", e);
+                            }
+                        }
+                    }
+                }
+                Ok(Event::Incoming(Packet::Publish(publish))) => {
+                    if publish.topic.starts_with(&format!("{}/set/", base)) {
+                        handle_set(
+                            &app,
+                            &base,
+                            &publish.topic,
+                            &publish.payload,
+                            &mut last_seen,
+                        );
+                    } else {
+                        handle_transfer(
+                            &app,
+                            &client,
+                            &base,
+                            &publish.topic,
+                            &publish.payload,
+                            &mut last_seen,
+                        );
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    log::warn!("MQTT connection error: {}", e);
+                    std::thread::sleep(Duration::from_secs(5));
+                }
+            }
+        }
+        log::info!("MQTT client stopped");
+    });
+}
+
+/// 把剪贴板事件发布到 {topic}/event；只带类型和哈希，开启 mqtt_publish_previews 后
+/// 才带非敏感文本的预览
+pub fn publish(
+    app: &tauri::AppHandle,
+    event: &str,
+    item: Option<&ClipboardItem>,
+    data: &serde_json::Value,
+) {
+    let state = app.state::<AppState>();
+    let Some(client) = state.mqtt_client.lock().unwrap().clone() else {
+        return;
+    };
+    let (base, include_preview) = {
+        let config = state.config.lock().unwrap();
+        (
+            config.mqtt_topic.trim_end_matches('/').to_string(),
+            config.mqtt_publish_previews,
+        )
+    };
+    let payload = json!({
+        "event": event,
+        "sent_at": chrono::Local::now().to_rfc3339(),
+        "item": item.map(|item| crate::webhook::item_payload(item, include_preview, false, false)),
+        "data": data,
+    });
+    if let Err(e) = client.try_publish(
+        event_topic(&base),
+        QoS::AtLeastOnce,
+        false,
+        payload.to_string(),
+    ) {
+        log::error!("Failed to publish MQTT event: {}", e);
+    }
+}
+
+/// 退出时断开连接
+pub fn stop(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    if let Ok(mut generation) = state.mqtt_generation.lock() {
+        *generation += 1;
+    }
+    if let Some(client) = state.mqtt_client.lock().unwrap().take() {
+        let _ = client.try_disconnect();
+    }
+}
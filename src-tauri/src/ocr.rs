@@ -1,23 +1,361 @@
 #![allow(deprecated)]
 #![allow(unexpected_cfgs)]
 
+use crate::jobs::JobContext;
+use crate::models::{OcrBackfillProgress, OcrResult};
+#[cfg(any(target_os = "macos", target_os = "windows"))]
+use crate::models::{OcrBox, OcrLine, OcrWord};
+use crate::state::AppState;
+use tauri::{Emitter, Manager};
+
 #[cfg(target_os = "macos")]
 use cocoa::base::{id, nil};
 #[cfg(target_os = "macos")]
-use cocoa::foundation::{NSArray, NSAutoreleasePool, NSString};
+use cocoa::foundation::{NSArray, NSAutoreleasePool, NSRange, NSRect, NSString};
 #[cfg(target_os = "macos")]
 use objc::{class, msg_send, sel, sel_impl};
 
-#[cfg(target_os = "macos")]
-pub async fn recognize_text(image_path: &str) -> Result<String, String> {
+/// OCR 引擎与识别语言，languages 使用 BCP-47 标签（如 zh-Hans、en-US），为空时使用引擎默认值
+#[derive(Debug, Clone, Default)]
+pub struct OcrOptions {
+    pub engine: String,
+    pub languages: Vec<String>,
+}
+
+impl OcrOptions {
+    fn use_tesseract(&self) -> bool {
+        match self.engine.as_str() {
+            "tesseract" => true,
+            "native" => false,
+            _ => !cfg!(any(target_os = "macos", target_os = "windows")),
+        }
+    }
+}
+
+/// 按配置选择引擎识别图片中的文字，同时返回行/词的位置和置信度；
+/// auto 时系统引擎失败（如缺少语言包）再用 Tesseract 重试
+pub async fn recognize(image_path: &str, options: &OcrOptions) -> Result<OcrResult, String> {
     let path = image_path.to_string();
-    tauri::async_runtime::spawn_blocking(move || recognize_text_sync(&path))
+    let languages = options.languages.clone();
+    if options.use_tesseract() {
+        return recognize_tesseract(path, languages).await;
+    }
+    match recognize_native(path.clone(), languages.clone()).await {
+        Err(e) if options.engine != "native" => {
+            log::warn!("Native OCR failed, falling back to Tesseract: {}", e);
+            recognize_tesseract(path, languages)
+                .await
+                .map_err(|fallback| format!("{}; Tesseract fallback failed: {}", e, fallback))
+        }
+        result => result,
+    }
+}
+
+async fn recognize_tesseract(path: String, languages: Vec<String>) -> Result<OcrResult, String> {
+    tauri::async_runtime::spawn_blocking(move || tesseract::recognize(&path, &languages))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 只返回识别出的文本
+pub async fn recognize_text(image_path: &str, options: &OcrOptions) -> Result<String, String> {
+    recognize(image_path, options)
+        .await
+        .map(|result| result.text)
+}
+
+/// 列出引擎支持的识别语言
+pub async fn list_languages(options: &OcrOptions) -> Result<Vec<String>, String> {
+    let use_tesseract = options.use_tesseract();
+    tauri::async_runtime::spawn_blocking(move || {
+        if use_tesseract {
+            tesseract::list_languages()
+        } else {
+            list_languages_native()
+        }
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+// 两次识别之间的间隔，避免批量 OCR 占满 CPU
+const BACKFILL_INTERVAL_MS: u64 = 500;
+// 同一张图片最多尝试识别的次数，避免无法识别的图片每次补做都被重试
+pub const MAX_BACKFILL_ATTEMPTS: u32 = 3;
+
+/// 后台任务：为历史图片补做 OCR，通过 ocr-backfill-progress 事件汇报进度，可随时取消
+pub fn run_backfill(ctx: &JobContext, items: Vec<(i64, String)>) -> Result<(), String> {
+    let app = ctx.app();
+    let state = app.state::<AppState>();
+    let total = items.len();
+    let mut done = 0;
+
+    for (id, path) in items {
+        if ctx.is_cancelled() {
+            break;
+        }
+
+        let options = {
+            let config = state.config.lock().unwrap();
+            OcrOptions {
+                engine: config.ocr_engine.clone(),
+                languages: config.ocr_languages.clone(),
+            }
+        };
+        // 失败时只累计次数，ocr_text 保持 NULL，下次补做时重试
+        let stored = match tauri::async_runtime::block_on(recognize_text(&path, &options)) {
+            Ok(text) => state.db.set_ocr_text(id, &text),
+            Err(e) => {
+                log::warn!("OCR backfill failed for item {}: {}", id, e);
+                state.db.record_ocr_failure(id)
+            }
+        };
+        if let Err(e) = stored {
+            log::error!("Failed to store OCR result for item {}: {}", id, e);
+        }
+
+        done += 1;
+        ctx.progress(done, total);
+        let _ = app.emit(
+            "ocr-backfill-progress",
+            OcrBackfillProgress {
+                done,
+                total,
+                item_id: Some(id),
+                finished: false,
+                cancelled: false,
+            },
+        );
+        std::thread::sleep(crate::power::poll_interval(
+            app,
+            std::time::Duration::from_millis(BACKFILL_INTERVAL_MS),
+        ));
+    }
+
+    let cancelled = ctx.is_cancelled();
+    log::info!(
+        "OCR backfill finished: {}/{} items{}",
+        done,
+        total,
+        if cancelled { " (cancelled)" } else { "" }
+    );
+    let _ = app.emit(
+        "ocr-backfill-progress",
+        OcrBackfillProgress {
+            done,
+            total,
+            item_id: None,
+            finished: true,
+            cancelled,
+        },
+    );
+    Ok(())
+}
+
+mod tesseract {
+    use crate::models::{OcrBox, OcrLine, OcrResult, OcrWord};
+    use std::collections::BTreeMap;
+    use std::process::Command;
+
+    /// BCP-47 标签转换为 Tesseract 语言包名，无法识别的原样传入
+    fn language_code(tag: &str) -> String {
+        let lower = tag.to_lowercase();
+        let code = match lower.as_str() {
+            "zh-hans" | "zh-cn" | "zh-sg" | "zh" => "chi_sim",
+            "zh-hant" | "zh-tw" | "zh-hk" => "chi_tra",
+            _ => match lower.split(['-', '_']).next().unwrap_or("") {
+                "en" => "eng",
+                "ja" => "jpn",
+                "ko" => "kor",
+                "fr" => "fra",
+                "de" => "deu",
+                "es" => "spa",
+                "it" => "ita",
+                "pt" => "por",
+                "ru" => "rus",
+                "uk" => "ukr",
+                "ar" => "ara",
+                _ => return tag.to_string(),
+            },
+        };
+        code.to_string()
+    }
+
+    pub fn recognize(image_path: &str, languages: &[String]) -> Result<OcrResult, String> {
+        let (image_width, image_height) =
+            image::image_dimensions(image_path).map_err(|e| e.to_string())?;
+
+        let mut command = Command::new("tesseract");
+        command.arg(image_path).arg("stdout");
+        if !languages.is_empty() {
+            let langs: Vec<String> = languages.iter().map(|l| language_code(l)).collect();
+            command.arg("-l").arg(langs.join("+"));
+        }
+        command.arg("tsv");
+
+        let output = command
+            .output()
+            .map_err(|e| format!("Failed to run tesseract (is it installed?): {}", e))?;
+        if !output.status.success() {
+            return Err(format!(
+                "Tesseract failed: {}",
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        // TSV columns: level page block par line word left top width height conf text
+        let mut lines: BTreeMap<(u32, u32, u32, u32), Vec<OcrWord>> = BTreeMap::new();
+        for row in String::from_utf8_lossy(&output.stdout).lines().skip(1) {
+            let cols: Vec<&str> = row.splitn(12, '\t').collect();
+            if cols.len() < 12 || cols[0] != "5" || cols[11].trim().is_empty() {
+                continue;
+            }
+            let num = |i: usize| cols[i].parse::<f64>().unwrap_or(0.0);
+            let key = (num(1) as u32, num(2) as u32, num(3) as u32, num(4) as u32);
+            lines.entry(key).or_default().push(OcrWord {
+                text: cols[11].trim().to_string(),
+                bbox: OcrBox {
+                    x: num(6) / image_width as f64,
+                    y: num(7) / image_height as f64,
+                    width: num(8) / image_width as f64,
+                    height: num(9) / image_height as f64,
+                },
+                confidence: (num(10) >= 0.0).then(|| (num(10) / 100.0) as f32),
+            });
+        }
+
+        // 段落之间保留空行，与 tesseract 纯文本输出一致
+        let mut text = String::new();
+        let mut previous_paragraph = None;
+        let lines = lines
+            .into_iter()
+            .map(|((page, block, par, _), words)| {
+                let confidences: Vec<f32> = words.iter().filter_map(|w| w.confidence).collect();
+                let line_text = words
+                    .iter()
+                    .map(|w| w.text.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" ");
+                if let Some(previous) = previous_paragraph {
+                    text.push_str(if previous == (page, block, par) {
+                        "\n"
+                    } else {
+                        "\n\n"
+                    });
+                }
+                previous_paragraph = Some((page, block, par));
+                text.push_str(&line_text);
+                OcrLine {
+                    text: line_text,
+                    bbox: OcrBox::union(words.iter().map(|w| w.bbox)).unwrap_or_default(),
+                    confidence: (!confidences.is_empty())
+                        .then(|| confidences.iter().sum::<f32>() / confidences.len() as f32),
+                    words,
+                }
+            })
+            .collect();
+        Ok(OcrResult {
+            text: text.trim().to_string(),
+            lines,
+        })
+    }
+
+    pub fn list_languages() -> Result<Vec<String>, String> {
+        let output = Command::new("tesseract")
+            .arg("--list-langs")
+            .output()
+            .map_err(|e| format!("Failed to run tesseract (is it installed?): {}", e))?;
+        // First line is a header: List of available languages in "..." (N):
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .skip(1)
+            .map(|l| l.trim().to_string())
+            .filter(|l| !l.is_empty() && l != "osd")
+            .collect())
+    }
+}
+
+#[cfg(target_os = "macos")]
+async fn recognize_native(path: String, languages: Vec<String>) -> Result<OcrResult, String> {
+    tauri::async_runtime::spawn_blocking(move || recognize_sync(&path, &languages))
         .await
         .map_err(|e| e.to_string())?
 }
 
 #[cfg(target_os = "macos")]
-fn recognize_text_sync(image_path: &str) -> Result<String, String> {
+fn list_languages_native() -> Result<Vec<String>, String> {
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+        let request_class = class!(VNRecognizeTextRequest);
+        let request_alloc: id = msg_send![request_class, alloc];
+        let request: id = msg_send![request_alloc, init];
+        let _: () = msg_send![request, setRecognitionLevel:0];
+
+        let error: id = nil;
+        let languages: id = msg_send![request, supportedRecognitionLanguagesAndReturnError:&error];
+        if languages == nil {
+            return Err("Failed to query Vision OCR languages".to_string());
+        }
+        let count: usize = msg_send![languages, count];
+        let mut result = Vec::with_capacity(count);
+        for i in 0..count {
+            let language: id = msg_send![languages, objectAtIndex:i];
+            result.push(
+                std::ffi::CStr::from_ptr(language.UTF8String())
+                    .to_string_lossy()
+                    .to_string(),
+            );
+        }
+        Ok(result)
+    }
+}
+
+/// Vision 的 boundingBox 以左下角为原点，转换为左上角原点
+#[cfg(target_os = "macos")]
+fn vision_box(rect: NSRect) -> OcrBox {
+    OcrBox {
+        x: rect.origin.x,
+        y: 1.0 - rect.origin.y - rect.size.height,
+        width: rect.size.width,
+        height: rect.size.height,
+    }
+}
+
+/// 按空白切分候选文本，用 boundingBoxForRange 取得每个词的位置
+#[cfg(target_os = "macos")]
+unsafe fn vision_words(candidate: id, text: &str, confidence: f32) -> Vec<OcrWord> {
+    let mut words = Vec::new();
+    let mut offset = 0usize; // UTF-16 offset
+    let mut start: Option<(usize, usize)> = None; // (utf16 offset, byte offset)
+    let mut flush = |start: &mut Option<(usize, usize)>, end: usize, end_byte: usize| {
+        if let Some((begin, begin_byte)) = start.take() {
+            let range = NSRange::new(begin as u64, (end - begin) as u64);
+            let error: id = nil;
+            let observation: id = msg_send![candidate, boundingBoxForRange:range error:&error];
+            if observation != nil {
+                let rect: NSRect = msg_send![observation, boundingBox];
+                words.push(OcrWord {
+                    text: text[begin_byte..end_byte].to_string(),
+                    bbox: vision_box(rect),
+                    confidence: Some(confidence),
+                });
+            }
+        }
+    };
+    for (byte, c) in text.char_indices() {
+        if c.is_whitespace() {
+            flush(&mut start, offset, byte);
+        } else if start.is_none() {
+            start = Some((offset, byte));
+        }
+        offset += c.len_utf16();
+    }
+    flush(&mut start, offset, text.len());
+    words
+}
+
+#[cfg(target_os = "macos")]
+fn recognize_sync(image_path: &str, languages: &[String]) -> Result<OcrResult, String> {
     unsafe {
         let _pool = NSAutoreleasePool::new(nil);
 
@@ -39,14 +377,19 @@ fn recognize_text_sync(image_path: &str) -> Result<String, String> {
         let _: () = msg_send![request, setRecognitionLevel:0];
         // Set usesLanguageCorrection = YES
         let _: () = msg_send![request, setUsesLanguageCorrection:true];
-        // Set recognitionLanguages to ["zh-Hans", "en-US"]
-        let langs = NSArray::arrayWithObjects(
-            nil,
-            &[
+        // Set recognitionLanguages, defaulting to ["zh-Hans", "en-US"]
+        let language_tags: Vec<id> = if languages.is_empty() {
+            vec![
                 NSString::alloc(nil).init_str("zh-Hans"),
                 NSString::alloc(nil).init_str("en-US"),
-            ],
-        );
+            ]
+        } else {
+            languages
+                .iter()
+                .map(|l| NSString::alloc(nil).init_str(l))
+                .collect()
+        };
+        let langs = NSArray::arrayWithObjects(nil, &language_tags);
         let _: () = msg_send![request, setRecognitionLanguages:langs];
 
         // Perform request
@@ -62,7 +405,7 @@ fn recognize_text_sync(image_path: &str) -> Result<String, String> {
         let results: id = msg_send![request, results];
         let count: usize = msg_send![results, count];
 
-        let mut full_text = String::new();
+        let mut lines = Vec::with_capacity(count);
 
         for i in 0..count {
             let observation: id = msg_send![results, objectAtIndex:i];
@@ -72,13 +415,21 @@ fn recognize_text_sync(image_path: &str) -> Result<String, String> {
             if candidate_count > 0 {
                 let candidate: id = msg_send![candidates, objectAtIndex:0];
                 let string: id = msg_send![candidate, string];
-                let text = std::ffi::CStr::from_ptr(string.UTF8String()).to_string_lossy();
-                full_text.push_str(&text);
-                full_text.push('\n');
+                let text = std::ffi::CStr::from_ptr(string.UTF8String())
+                    .to_string_lossy()
+                    .to_string();
+                let confidence: f32 = msg_send![candidate, confidence];
+                let rect: NSRect = msg_send![observation, boundingBox];
+                lines.push(OcrLine {
+                    words: vision_words(candidate, &text, confidence),
+                    text,
+                    bbox: vision_box(rect),
+                    confidence: Some(confidence),
+                });
             }
         }
 
-        Ok(full_text.trim().to_string())
+        Ok(OcrResult::from_lines(lines))
     }
 }
 
@@ -88,14 +439,40 @@ use dunce;
 use windows::{
     core::HSTRING,
     Foundation,
+    Globalization::Language,
     Graphics::Imaging::{BitmapDecoder, BitmapPixelFormat, SoftwareBitmap},
     Media::Ocr::OcrEngine,
     Storage::{FileAccessMode, StorageFile},
 };
 
 #[cfg(target_os = "windows")]
-pub async fn recognize_text(image_path: &str) -> Result<String, String> {
-    let path = image_path.to_string();
+fn create_engine(languages: &[String]) -> Result<OcrEngine, String> {
+    // Windows.Media.Ocr recognizes a single language per engine
+    match languages.first() {
+        Some(tag) => {
+            let language = Language::CreateLanguage(&HSTRING::from(tag.as_str()))
+                .map_err(|e| format!("Invalid OCR language {}: {}", tag, e))?;
+            OcrEngine::TryCreateFromLanguage(&language)
+                .map_err(|e| format!("OCR language {} is not installed: {}", tag, e))
+        }
+        None => OcrEngine::TryCreateFromUserProfileLanguages()
+            .map_err(|e| format!("Failed to create OCR engine: {}", e)),
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn list_languages_native() -> Result<Vec<String>, String> {
+    let languages = OcrEngine::AvailableRecognizerLanguages().map_err(|e| e.to_string())?;
+    let count = languages.Size().map_err(|e| e.to_string())?;
+    Ok((0..count)
+        .filter_map(|i| languages.GetAt(i).ok())
+        .filter_map(|l| l.LanguageTag().ok())
+        .map(|tag| tag.to_string())
+        .collect())
+}
+
+#[cfg(target_os = "windows")]
+async fn recognize_native(path: String, languages: Vec<String>) -> Result<OcrResult, String> {
     tauri::async_runtime::spawn_blocking(move || {
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
@@ -189,9 +566,8 @@ pub async fn recognize_text(image_path: &str) -> Result<String, String> {
                     .map_err(|e| format!("Failed to convert bitmap format: {}", e))?;
             }
 
-            // 9. 创建 OCR 引擎（尝试指定中文）
-            let engine = OcrEngine::TryCreateFromUserProfileLanguages()
-                .map_err(|e| format!("Failed to create OCR engine: {}", e))?;
+            // 9. 创建 OCR 引擎（未指定语言时使用系统语言）
+            let engine = create_engine(&languages)?;
             log::info!("OcrEngine created successfully");
             // 10. 执行 OCR
             let result = engine
@@ -200,32 +576,55 @@ pub async fn recognize_text(image_path: &str) -> Result<String, String> {
                 .await
                 .map_err(|e| format!("Recognition failed: {}", e))?;
 
-            // 11. 提取文本
-            let lines = result
+            // 11. 提取文本和位置（Windows OCR 不提供置信度，坐标为像素）
+            let image_width = bitmap.PixelWidth().unwrap_or(1).max(1) as f64;
+            let image_height = bitmap.PixelHeight().unwrap_or(1).max(1) as f64;
+            let ocr_lines = result
                 .Lines()
                 .map_err(|e| format!("Failed to get lines: {}", e))?;
-            let mut full_text = String::new();
-            let line_count = lines.Size().map_err(|e| e.to_string())?;
+            let line_count = ocr_lines.Size().map_err(|e| e.to_string())?;
 
             log::info!("Found {} lines", line_count);
 
+            let mut lines = Vec::with_capacity(line_count as usize);
             for i in 0..line_count {
-                if let Ok(line) = lines.GetAt(i) {
-                    if let Ok(text) = line.Text() {
-                        full_text.push_str(&text.to_string());
-                        full_text.push('\n');
+                let Ok(line) = ocr_lines.GetAt(i) else {
+                    continue;
+                };
+                let Ok(text) = line.Text() else {
+                    continue;
+                };
+                let mut words = Vec::new();
+                if let Ok(ocr_words) = line.Words() {
+                    let word_count = ocr_words.Size().unwrap_or(0);
+                    for word in (0..word_count).filter_map(|j| ocr_words.GetAt(j).ok()) {
+                        let (Ok(word_text), Ok(rect)) = (word.Text(), word.BoundingRect()) else {
+                            continue;
+                        };
+                        words.push(OcrWord {
+                            text: word_text.to_string(),
+                            bbox: OcrBox {
+                                x: rect.X as f64 / image_width,
+                                y: rect.Y as f64 / image_height,
+                                width: rect.Width as f64 / image_width,
+                                height: rect.Height as f64 / image_height,
+                            },
+                            confidence: None,
+                        });
                     }
                 }
+                lines.push(OcrLine {
+                    text: text.to_string(),
+                    bbox: OcrBox::union(words.iter().map(|w| w.bbox)).unwrap_or_default(),
+                    confidence: None,
+                    words,
+                });
             }
 
-            let trimmed_text = full_text.trim().to_string();
-            log::info!(
-                "OCR Result ({} chars): {}",
-                trimmed_text.len(),
-                trimmed_text
-            );
+            let result = OcrResult::from_lines(lines);
+            log::info!("OCR Result ({} chars): {}", result.text.len(), result.text);
 
-            Ok(trimmed_text)
+            Ok(result)
         })
     })
     .await
@@ -233,6 +632,11 @@ pub async fn recognize_text(image_path: &str) -> Result<String, String> {
 }
 
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
-pub async fn recognize_text(_image_path: &str) -> Result<String, String> {
-    Err("OCR is only supported on macOS and Windows".to_string())
+async fn recognize_native(_path: String, _languages: Vec<String>) -> Result<OcrResult, String> {
+    Err("Native OCR is only supported on macOS and Windows, use the tesseract engine".to_string())
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn list_languages_native() -> Result<Vec<String>, String> {
+    Err("Native OCR is only supported on macOS and Windows, use the tesseract engine".to_string())
 }
@@ -0,0 +1,333 @@
+use chrono::Local;
+use tauri::{Emitter, Manager};
+
+use crate::models::{ClipboardItem, HistoryFilter, PaletteAction};
+use crate::state::AppState;
+use crate::tray::update_tray_menu;
+use crate::utils::{classify_with_custom, remove_pruned_images, write_to_clipboard};
+
+/// (id, 标题, 分类, 额外的搜索关键词)
+const ACTIONS: [(&str, &str, &str, &str); 20] = [
+    ("show_main", "Show Main Window", "window", "open history"),
+    (
+        "open_settings",
+        "Open Settings",
+        "window",
+        "preferences config",
+    ),
+    (
+        "toggle_pause",
+        "Pause / Resume Recording",
+        "history",
+        "stop start",
+    ),
+    (
+        "toggle_presentation_mode",
+        "Toggle Presentation Mode",
+        "history",
+        "demo screen share privacy",
+    ),
+    (
+        "clear_history",
+        "Clear History",
+        "history",
+        "delete remove all",
+    ),
+    (
+        "capture_screen",
+        "Capture Screen",
+        "capture",
+        "screenshot snip",
+    ),
+    ("git_sync", "Sync Collection with Git", "sync", "push pull"),
+    (
+        "transform:uppercase",
+        "Transform: UPPERCASE",
+        "transform",
+        "upper caps",
+    ),
+    (
+        "transform:lowercase",
+        "Transform: lowercase",
+        "transform",
+        "lower",
+    ),
+    (
+        "transform:title_case",
+        "Transform: Title Case",
+        "transform",
+        "capitalize",
+    ),
+    (
+        "transform:trim",
+        "Transform: Trim Whitespace",
+        "transform",
+        "strip spaces",
+    ),
+    (
+        "transform:sort_lines",
+        "Transform: Sort Lines",
+        "transform",
+        "order",
+    ),
+    (
+        "transform:unique_lines",
+        "Transform: Remove Duplicate Lines",
+        "transform",
+        "dedupe unique",
+    ),
+    (
+        "transform:remove_blank_lines",
+        "Transform: Remove Blank Lines",
+        "transform",
+        "empty",
+    ),
+    (
+        "transform:join_lines",
+        "Transform: Join Lines",
+        "transform",
+        "single line merge",
+    ),
+    (
+        "transform:json_pretty",
+        "Transform: Format JSON",
+        "transform",
+        "pretty beautify",
+    ),
+    (
+        "transform:json_minify",
+        "Transform: Minify JSON",
+        "transform",
+        "compact",
+    ),
+    (
+        "transform:table_csv",
+        "Transform: Table to CSV",
+        "transform",
+        "spreadsheet excel sheets",
+    ),
+    (
+        "transform:table_markdown",
+        "Transform: Table to Markdown",
+        "transform",
+        "spreadsheet excel sheets md",
+    ),
+    (
+        "transform:table_json",
+        "Transform: Table to JSON Rows",
+        "transform",
+        "spreadsheet excel sheets objects",
+    ),
+];
+
+/// 子序列模糊匹配打分：连续命中和词首命中加分，跳过的字符扣分；没有全部命中返回 None
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut prev: Option<usize> = None;
+    for q in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let idx = (pos..text.len()).find(|&i| text[i] == q)?;
+        score += 1;
+        if idx > 0 && prev == Some(idx - 1) {
+            score += 5;
+        }
+        if idx == 0 || !text[idx - 1].is_alphanumeric() {
+            score += 8;
+        }
+        score -= (idx - pos).min(5) as i64;
+        prev = Some(idx);
+        pos = idx + 1;
+    }
+    Some(score)
+}
+
+/// 是否为可执行的操作 id
+pub fn is_action(id: &str) -> bool {
+    ACTIONS.iter().any(|(action, ..)| *action == id)
+}
+
+/// 按查询模糊排序的可执行操作，空查询返回全部
+pub fn search(query: &str) -> Vec<PaletteAction> {
+    let query = query.trim();
+    let mut actions: Vec<PaletteAction> = ACTIONS
+        .iter()
+        .filter_map(|(id, title, category, keywords)| {
+            let score = if query.is_empty() {
+                0
+            } else {
+                // 标题优先，关键词命中打折
+                let title_score = fuzzy_score(query, title);
+                let keyword_score = fuzzy_score(query, keywords).map(|s| s / 2);
+                title_score.max(keyword_score)?
+            };
+            Some(PaletteAction {
+                id: id.to_string(),
+                title: title.to_string(),
+                category: category.to_string(),
+                score,
+            })
+        })
+        .collect();
+    actions.sort_by(|a, b| b.score.cmp(&a.score));
+    actions
+}
+
+fn title_case(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut word_start = true;
+    for c in text.chars() {
+        if word_start {
+            result.extend(c.to_uppercase());
+        } else {
+            result.extend(c.to_lowercase());
+        }
+        word_start = !c.is_alphanumeric() && c != '\'';
+    }
+    result
+}
+
+fn transform(op: &str, text: &str) -> Result<String, String> {
+    let lines = || text.lines();
+    Ok(match op {
+        "uppercase" => text.to_uppercase(),
+        "lowercase" => text.to_lowercase(),
+        "title_case" => title_case(text),
+        "trim" => lines()
+            .map(|l| l.trim())
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string(),
+        "sort_lines" => {
+            let mut sorted: Vec<&str> = lines().collect();
+            sorted.sort_unstable();
+            sorted.join("\n")
+        }
+        "unique_lines" => {
+            let mut seen = std::collections::HashSet::new();
+            lines()
+                .filter(|l| seen.insert(*l))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+        "remove_blank_lines" => lines()
+            .filter(|l| !l.trim().is_empty())
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "join_lines" => lines()
+            .map(|l| l.trim())
+            .filter(|l| !l.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+        "json_pretty" | "json_minify" => {
+            let value: serde_json::Value =
+                serde_json::from_str(text).map_err(|e| format!("Invalid JSON: {}", e))?;
+            if op == "json_pretty" {
+                serde_json::to_string_pretty(&value)
+            } else {
+                serde_json::to_string(&value)
+            }
+            .map_err(|e| e.to_string())?
+        }
+        "table_csv" | "table_markdown" | "table_json" => {
+            crate::table::convert(text, &op["table_".len()..])?
+        }
+        _ => return Err(format!("Unknown transform: {}", op)),
+    })
+}
+
+/// 对指定条目（默认最近一条文本）做转换，结果作为新条目记录并写入剪贴板
+fn run_transform(app: &tauri::AppHandle, op: &str, item_id: Option<i64>) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let source = match item_id {
+        Some(id) => state.db.get_item(id).map_err(|e| e.to_string())?,
+        None => state
+            .db
+            .get_history(1, 50, &HistoryFilter::default())
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .filter(|i| i.kind == "text")
+            .max_by_key(|i| i.timestamp_ms)
+            .ok_or("No text item to transform")?,
+    };
+    if source.kind != "text" {
+        return Err("Only text items can be transformed".to_string());
+    }
+
+    let content = transform(op, &source.content)?;
+    let item = ClipboardItem {
+        data_type: classify_with_custom(&content, &crate::plugins::classifiers(app)),
+        content,
+        kind: "text".to_string(),
+        timestamp: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        is_sensitive: source.is_sensitive,
+        parent_id: source.id,
+        ..Default::default()
+    };
+
+    write_to_clipboard(app, &item)?;
+
+    let max_size = state.config.lock().unwrap().max_history_size;
+    let (_, pruned_items) = state
+        .db
+        .insert_item(&item, max_size)
+        .map_err(|e| e.to_string())?;
+    remove_pruned_images(&pruned_items);
+
+    let history = state
+        .db
+        .get_history(1, 20, &HistoryFilter::default())
+        .unwrap_or_default();
+    if let Err(e) = update_tray_menu(app, &history) {
+        log::error!("Failed to update tray menu: {}", e);
+    }
+    crate::utils::emit_clipboard_update(app);
+    Ok(())
+}
+
+/// 命令面板的统一入口
+pub async fn run(app: &tauri::AppHandle, id: &str, item_id: Option<i64>) -> Result<(), String> {
+    log::info!("Running palette action {}", id);
+    match id {
+        "show_main" | "open_settings" => {
+            let window = app
+                .get_webview_window("main")
+                .ok_or("Main window not found")?;
+            let _ = window.show();
+            let _ = window.set_focus();
+            if id == "open_settings" {
+                let _ = window.emit("open-settings", ());
+            }
+            Ok(())
+        }
+        "toggle_pause" => {
+            let state = app.state::<AppState>();
+            let paused = !*state.is_paused.lock().unwrap();
+            crate::commands::set_paused(app.clone(), paused, state);
+            Ok(())
+        }
+        "toggle_presentation_mode" => {
+            let state = app.state::<AppState>();
+            let enabled = !state.config.lock().unwrap().presentation_mode;
+            crate::commands::set_presentation_mode(app.clone(), state, enabled)
+                .map_err(String::from)
+        }
+        "clear_history" => {
+            crate::commands::clear_history(app.clone(), app.state::<AppState>(), None)
+                .map(|_| ())
+                .map_err(String::from)
+        }
+        "capture_screen" => crate::commands::start_capture(app.clone(), app.state::<AppState>())
+            .await
+            .map_err(String::from),
+        "git_sync" => crate::commands::git_sync_now(app.clone())
+            .await
+            .map(|_| ())
+            .map_err(String::from),
+        _ => match id.strip_prefix("transform:") {
+            Some(op) => run_transform(app, op, item_id),
+            None => Err(format!("Unknown action: {}", id)),
+        },
+    }
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+use tauri::Manager;
+
+use crate::models::ClipboardItem;
+use crate::state::AppState;
+
+/// 旧版本退出时把粘贴栈写在这个文件里
+fn legacy_path(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join("paste_stack.json"))
+}
+
+/// 把粘贴栈写入数据库；每次改动后调用，应用被强制退出或更新后也能恢复
+pub fn save(app: &tauri::AppHandle, stack: &[ClipboardItem]) {
+    if let Err(e) = app.state::<AppState>().db.save_paste_stack(stack) {
+        log::error!("Failed to save paste stack: {}", e);
+    }
+}
+
+/// 启动时恢复未粘贴完的粘贴栈，并迁移旧版本留下的 paste_stack.json
+pub fn restore(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    if let Some(path) = legacy_path(app) {
+        if let Ok(content) = std::fs::read_to_string(&path) {
+            match serde_json::from_str::<Vec<ClipboardItem>>(&content) {
+                Ok(items) => save(app, &items),
+                Err(e) => log::error!("Failed to read legacy paste stack: {}", e),
+            }
+            let _ = std::fs::remove_file(&path);
+        }
+    }
+    match state.db.get_paste_stack() {
+        Ok(items) if !items.is_empty() => {
+            log::info!("Restored paste stack with {} items", items.len());
+            *state.paste_stack.lock().unwrap() = items;
+        }
+        Ok(_) => {}
+        Err(e) => log::error!("Failed to restore paste stack: {}", e),
+    }
+}
@@ -0,0 +1,293 @@
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// PDF 日期字符串（不含 D: 前缀）：本地时间加时区偏移，如 20261016143000+08'00'
+pub fn pdf_date(time: &chrono::DateTime<chrono::Local>) -> String {
+    let offset = time.offset().local_minus_utc();
+    let zone = if offset == 0 {
+        "Z".to_string()
+    } else {
+        let minutes = offset.abs() / 60;
+        format!(
+            "{}{:02}'{:02}'",
+            if offset < 0 { '-' } else { '+' },
+            minutes / 60,
+            minutes % 60
+        )
+    };
+    format!("{}{}", time.format("%Y%m%d%H%M%S"), zone)
+}
+
+/// 一页 PDF 对应一张 JPEG 图片，页面尺寸与图片像素一致
+pub struct PdfPage {
+    pub width: u32,
+    pub height: u32,
+    pub jpeg: Vec<u8>,
+}
+
+/// 生成只包含整页图片的 PDF（图片以 DCTDecode 直接嵌入）
+pub fn write_image_pdf(pages: &[PdfPage], creation_date: &str) -> Vec<u8> {
+    let mut out: Vec<u8> = Vec::new();
+    let mut offsets: Vec<usize> = Vec::new();
+    out.extend_from_slice(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n");
+
+    // Objects: 1 catalog, 2 pages, 3 info, then (page, content, image) per page
+    let page_id = |i: usize| 4 + i * 3;
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", page_id(i)))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut object = |out: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(out.len());
+        let _ = writeln!(out, "{} 0 obj", offsets.len());
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\nendobj\n");
+    };
+
+    object(&mut out, b"<< /Type /Catalog /Pages 2 0 R >>");
+    object(
+        &mut out,
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).as_bytes(),
+    );
+    object(
+        &mut out,
+        format!(
+            "<< /Producer (Clipboard) /CreationDate (D:{}) >>",
+            creation_date
+        )
+        .as_bytes(),
+    );
+
+    for (i, page) in pages.iter().enumerate() {
+        let id = page_id(i);
+        object(
+            &mut out,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /XObject << /Im0 {} 0 R >> >> /Contents {} 0 R >>",
+                page.width,
+                page.height,
+                id + 2,
+                id + 1
+            )
+            .as_bytes(),
+        );
+
+        let content = format!("q {} 0 0 {} 0 0 cm /Im0 Do Q", page.width, page.height);
+        object(
+            &mut out,
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            )
+            .as_bytes(),
+        );
+
+        let mut image = format!(
+            "<< /Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode /Length {} >>\nstream\n",
+            page.width,
+            page.height,
+            page.jpeg.len()
+        )
+        .into_bytes();
+        image.extend_from_slice(&page.jpeg);
+        image.extend_from_slice(b"\nendstream");
+        object(&mut out, &image);
+    }
+
+    write_trailer(&mut out, &offsets);
+    out
+}
+
+/// 写入交叉引用表和 trailer（1 号对象为 Catalog，3 号为 Info）
+fn write_trailer(out: &mut Vec<u8>, offsets: &[usize]) {
+    let xref_offset = out.len();
+    let _ = writeln!(out, "xref\n0 {}\n0000000000 65535 f ", offsets.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(out, "{:010} 00000 n ", offset);
+    }
+    let _ = writeln!(
+        out,
+        "trailer\n<< /Size {} /Root 1 0 R /Info 3 0 R >>\nstartxref\n{}\n%%EOF",
+        offsets.len() + 1,
+        xref_offset
+    );
+}
+
+// A4 纸，单位为 pt
+const PAGE_WIDTH: u32 = 595;
+const PAGE_HEIGHT: u32 = 842;
+const MARGIN: u32 = 50;
+const FONT_SIZE: u32 = 10;
+const LINE_HEIGHT: u32 = 12;
+// Courier 字宽为 0.6 em
+const CHARS_PER_LINE: usize = ((PAGE_WIDTH - 2 * MARGIN) * 10 / (FONT_SIZE * 6)) as usize;
+// 页眉占两行
+const LINES_PER_PAGE: usize = ((PAGE_HEIGHT - 2 * MARGIN) / LINE_HEIGHT) as usize - 2;
+
+/// 转为 PDF 字符串字面量；标准字体只支持 WinAnsi，超出 Latin-1 的字符显示为 ?
+fn pdf_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('(');
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => {
+                let _ = write!(out, "\\{:03o}", c as u32);
+            }
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+/// 按字符数折行，制表符展开为 4 个空格
+fn wrap_lines(text: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for line in text.replace('\t', "    ").lines() {
+        let chars: Vec<char> = line.trim_end().chars().collect();
+        if chars.is_empty() {
+            lines.push(String::new());
+        }
+        for chunk in chars.chunks(CHARS_PER_LINE) {
+            lines.push(chunk.iter().collect());
+        }
+    }
+    lines
+}
+
+/// 生成等宽排版的 A4 文本 PDF，每页带标题和页码页眉
+pub fn write_text_pdf(title: &str, text: &str, creation_date: &str) -> Vec<u8> {
+    let lines = wrap_lines(text);
+    let pages: Vec<&[String]> = if lines.is_empty() {
+        vec![&[]]
+    } else {
+        lines.chunks(LINES_PER_PAGE).collect()
+    };
+
+    let mut out: Vec<u8> = Vec::new();
+    let mut offsets: Vec<usize> = Vec::new();
+    out.extend_from_slice(b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n");
+
+    // Objects: 1 catalog, 2 pages, 3 info, 4 body font, 5 header font, then (page, content) per page
+    let page_id = |i: usize| 6 + i * 2;
+    let kids = (0..pages.len())
+        .map(|i| format!("{} 0 R", page_id(i)))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    let mut object = |out: &mut Vec<u8>, body: &[u8]| {
+        offsets.push(out.len());
+        let _ = writeln!(out, "{} 0 obj", offsets.len());
+        out.extend_from_slice(body);
+        out.extend_from_slice(b"\nendobj\n");
+    };
+
+    object(&mut out, b"<< /Type /Catalog /Pages 2 0 R >>");
+    object(
+        &mut out,
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, pages.len()).as_bytes(),
+    );
+    object(
+        &mut out,
+        format!(
+            "<< /Producer (Clipboard) /Title {} /CreationDate (D:{}) >>",
+            pdf_string(title),
+            creation_date
+        )
+        .as_bytes(),
+    );
+    object(
+        &mut out,
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Courier /Encoding /WinAnsiEncoding >>",
+    );
+    object(
+        &mut out,
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>",
+    );
+
+    for (i, page_lines) in pages.iter().enumerate() {
+        let id = page_id(i);
+        object(
+            &mut out,
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 4 0 R /F2 5 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                id + 1
+            )
+            .as_bytes(),
+        );
+
+        let top = PAGE_HEIGHT - MARGIN - FONT_SIZE;
+        let mut content = format!(
+            "BT /F2 {} Tf {} {} Td {} Tj ET\nBT /F2 {} Tf {} {} Td {} Tj ET\n",
+            FONT_SIZE,
+            MARGIN,
+            top,
+            pdf_string(title),
+            FONT_SIZE,
+            PAGE_WIDTH - MARGIN - 40,
+            top,
+            pdf_string(&format!("{} / {}", i + 1, pages.len()))
+        );
+        let _ = writeln!(
+            content,
+            "BT /F1 {} Tf {} TL {} {} Td",
+            FONT_SIZE,
+            LINE_HEIGHT,
+            MARGIN,
+            top - 2 * LINE_HEIGHT
+        );
+        for line in page_lines.iter() {
+            let _ = writeln!(content, "{} Tj T*", pdf_string(line));
+        }
+        content.push_str("ET");
+        object(
+            &mut out,
+            format!(
+                "<< /Length {} >>\nstream\n{}\nendstream",
+                content.len(),
+                content
+            )
+            .as_bytes(),
+        );
+    }
+
+    write_trailer(&mut out, &offsets);
+    out
+}
+
+/// 提取 PDF 文件中的文本，去掉行尾空白并合并连续空行
+pub fn extract_text(path: &std::path::Path) -> Result<String, String> {
+    // pdf-extract 遇到不支持的字体编码时可能 panic
+    let text = std::panic::catch_unwind(|| pdf_extract::extract_text(path))
+        .map_err(|_| "Failed to parse PDF".to_string())?
+        .map_err(|e| e.to_string())?;
+
+    let mut result = String::with_capacity(text.len());
+    let mut blank = false;
+    for line in text.lines().map(|l| l.trim_end()) {
+        if line.is_empty() {
+            blank = !result.is_empty();
+            continue;
+        }
+        if blank {
+            result.push('\n');
+            blank = false;
+        }
+        result.push_str(line);
+        result.push('\n');
+    }
+    let result = result.trim_end().to_string();
+    if result.is_empty() {
+        return Err("PDF contains no extractable text".to_string());
+    }
+    Ok(result)
+}
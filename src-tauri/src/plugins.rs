@@ -0,0 +1,267 @@
+use std::ffi::{c_char, CStr, CString};
+use std::path::{Path, PathBuf};
+
+use regex::Regex;
+use tauri::Manager;
+
+use crate::models::{CustomClassifier, PluginInfo, PluginManifest};
+use crate::scripting::Script;
+use crate::state::AppState;
+
+const MANIFEST_FILE: &str = "plugin.json";
+// 原生插件导出的 C 接口版本
+const NATIVE_ABI_VERSION: u32 = 1;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type CallFn = unsafe extern "C" fn(*const c_char, *const c_char) -> *mut c_char;
+type FreeFn = unsafe extern "C" fn(*mut c_char);
+
+/// 插件操作的执行方式：rhai 脚本或原生动态库
+pub trait PluginRuntime: Send {
+    /// 以文本调用插件函数 function(text)，返回结果文本
+    fn call(&self, function: &str, text: &str) -> Result<String, String>;
+}
+
+impl PluginRuntime for Script {
+    fn call(&self, function: &str, text: &str) -> Result<String, String> {
+        crate::scripting::run_text_fn(self, function, text)
+    }
+}
+
+/// 原生插件：动态库导出 clipboard_plugin_abi_version()、clipboard_plugin_call(function, text)
+/// 和 clipboard_plugin_free(result)，字符串均为 UTF-8 C 字符串，call 返回空指针表示失败
+struct NativePlugin {
+    name: String,
+    library: libloading::Library,
+}
+
+impl NativePlugin {
+    fn load(name: String, path: &Path) -> Result<Self, String> {
+        // 动态库由用户放入插件目录并启用，加载即执行其初始化代码
+        let library = unsafe { libloading::Library::new(path) }.map_err(|e| e.to_string())?;
+        let version = unsafe {
+            let abi_version: libloading::Symbol<AbiVersionFn> = library
+                .get(b"clipboard_plugin_abi_version\0")
+                .map_err(|e| e.to_string())?;
+            abi_version()
+        };
+        if version != NATIVE_ABI_VERSION {
+            return Err(format!(
+                "Plugin {} uses ABI version {}, expected {}",
+                name, version, NATIVE_ABI_VERSION
+            ));
+        }
+        Ok(Self { name, library })
+    }
+}
+
+impl PluginRuntime for NativePlugin {
+    fn call(&self, function: &str, text: &str) -> Result<String, String> {
+        let c_function = CString::new(function).map_err(|e| e.to_string())?;
+        let c_text = CString::new(text).map_err(|_| "Text contains a NUL character".to_string())?;
+        unsafe {
+            let call: libloading::Symbol<CallFn> = self
+                .library
+                .get(b"clipboard_plugin_call\0")
+                .map_err(|e| e.to_string())?;
+            let free: libloading::Symbol<FreeFn> = self
+                .library
+                .get(b"clipboard_plugin_free\0")
+                .map_err(|e| e.to_string())?;
+            let result = call(c_function.as_ptr(), c_text.as_ptr());
+            if result.is_null() {
+                return Err(format!("Plugin {}: {} failed", self.name, function));
+            }
+            let output = CStr::from_ptr(result)
+                .to_str()
+                .map(str::to_string)
+                .map_err(|_| format!("Plugin {}: {} returned invalid UTF-8", self.name, function));
+            free(result);
+            output
+        }
+    }
+}
+
+/// 插件目录（应用数据目录下的 plugins/），每个插件一个文件夹
+pub fn plugins_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("plugins");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// 读取并校验 plugin.json：名称不能为空，操作和分享目标 id 不能重复，分类器正则必须有效
+fn read_manifest(dir: &Path) -> Result<PluginManifest, String> {
+    let content = std::fs::read_to_string(dir.join(MANIFEST_FILE)).map_err(|e| e.to_string())?;
+    let manifest: PluginManifest = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+    if manifest.name.trim().is_empty() {
+        return Err("Plugin name is empty".to_string());
+    }
+    if let Some(library) = &manifest.library {
+        if library.is_empty() || library.contains(['/', '\\']) || library.contains("..") {
+            return Err(format!("Invalid plugin library name: {}", library));
+        }
+    }
+    for (i, action) in manifest.actions.iter().enumerate() {
+        if manifest.actions[..i].iter().any(|a| a.id == action.id) {
+            return Err(format!("Duplicate action id: {}", action.id));
+        }
+    }
+    for (i, target) in manifest.share_targets.iter().enumerate() {
+        if manifest.share_targets[..i]
+            .iter()
+            .any(|t| t.id == target.id)
+        {
+            return Err(format!("Duplicate share target id: {}", target.id));
+        }
+        if !target.url.starts_with("http://") && !target.url.starts_with("https://") {
+            return Err(format!(
+                "Share target {} must use an http(s) URL",
+                target.id
+            ));
+        }
+    }
+    for classifier in &manifest.classifiers {
+        Regex::new(&classifier.pattern)
+            .map_err(|e| format!("Invalid classifier {}: {}", classifier.name, e))?;
+    }
+    Ok(manifest)
+}
+
+/// 扫描插件目录，按文件夹名排序
+fn load(app: &tauri::AppHandle) -> Vec<(String, PathBuf, Result<PluginManifest, String>)> {
+    let Ok(dir) = plugins_dir(app) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<_> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.join(MANIFEST_FILE).is_file())
+                .filter_map(|path| {
+                    let id = path.file_name()?.to_string_lossy().to_string();
+                    let manifest = read_manifest(&path);
+                    Some((id, path, manifest))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    plugins.sort_by(|a, b| a.0.cmp(&b.0));
+    plugins
+}
+
+fn is_enabled(app: &tauri::AppHandle, id: &str) -> bool {
+    app.state::<AppState>()
+        .config
+        .lock()
+        .unwrap()
+        .enabled_plugins
+        .iter()
+        .any(|p| p == id)
+}
+
+/// 插件列表及启用状态和清单错误
+pub fn list(app: &tauri::AppHandle) -> Vec<PluginInfo> {
+    load(app)
+        .into_iter()
+        .map(|(id, path, manifest)| {
+            let (manifest, error) = match manifest {
+                Ok(manifest) => (Some(manifest), None),
+                Err(e) => (None, Some(e)),
+            };
+            PluginInfo {
+                enabled: is_enabled(app, &id),
+                http_granted: http_granted(app, &id),
+                path: path.to_string_lossy().to_string(),
+                id,
+                manifest,
+                error,
+            }
+        })
+        .collect()
+}
+
+/// 读取已启用的插件
+pub fn get(app: &tauri::AppHandle, id: &str) -> Result<(PathBuf, PluginManifest), String> {
+    if !is_enabled(app, id) {
+        return Err(format!("Plugin is not enabled: {}", id));
+    }
+    let (_, path, manifest) = load(app)
+        .into_iter()
+        .find(|(p, _, _)| p == id)
+        .ok_or_else(|| format!("Plugin not found: {}", id))?;
+    Ok((path, manifest?))
+}
+
+/// 用户是否允许插件脚本调用 fetch（script_http_permissions 中的 plugin:{id}）；
+/// 清单中的 http 只是声明，不授予权限
+pub fn http_granted(app: &tauri::AppHandle, id: &str) -> bool {
+    let grant = format!("plugin:{}", id);
+    app.state::<AppState>()
+        .config
+        .lock()
+        .unwrap()
+        .script_http_permissions
+        .contains(&grant)
+}
+
+/// 插件目录内的文件，不能通过符号链接或 .. 指向目录外
+fn plugin_file(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let path = dunce::canonicalize(dir.join(name)).map_err(|e| format!("{}: {}", name, e))?;
+    if !path.starts_with(dunce::canonicalize(dir).map_err(|e| e.to_string())?) {
+        return Err("Plugin files must be inside the plugin folder".to_string());
+    }
+    Ok(path)
+}
+
+/// 加载插件的运行时：设置了 library 时加载动态库，否则编译脚本，http 为用户授予的 fetch 权限
+pub fn runtime(
+    dir: &Path,
+    id: &str,
+    manifest: &PluginManifest,
+    http: bool,
+) -> Result<Box<dyn PluginRuntime>, String> {
+    let name = format!("plugin:{}", id);
+    if let Some(library) = &manifest.library {
+        let path = plugin_file(
+            dir,
+            &libloading::library_filename(library).to_string_lossy(),
+        )?;
+        return Ok(Box::new(NativePlugin::load(name, &path)?));
+    }
+    let source =
+        std::fs::read_to_string(plugin_file(dir, &manifest.script)?).map_err(|e| e.to_string())?;
+    Ok(Box::new(crate::scripting::compile(&name, &source, http)?))
+}
+
+/// 重新扫描插件目录，更新已启用插件提供的分类器
+pub fn reload(app: &tauri::AppHandle) {
+    let classifiers: Vec<CustomClassifier> = load(app)
+        .into_iter()
+        .filter(|(id, _, _)| is_enabled(app, id))
+        .filter_map(|(id, _, manifest)| {
+            manifest
+                .map_err(|e| log::error!("Failed to load plugin {}: {}", id, e))
+                .ok()
+        })
+        .flat_map(|manifest| manifest.classifiers)
+        .collect();
+    *app.state::<AppState>().plugin_classifiers.lock().unwrap() = classifiers;
+}
+
+/// 用户自定义分类器在前，随后是已启用插件的分类器
+pub fn classifiers(app: &tauri::AppHandle) -> Vec<CustomClassifier> {
+    let state = app.state::<AppState>();
+    let mut classifiers = state.config.lock().unwrap().custom_classifiers.clone();
+    classifiers.extend(state.plugin_classifiers.lock().unwrap().iter().cloned());
+    classifiers
+}
+
+/// 把 {text} 替换为编码后的文本
+pub fn share_url(template: &str, text: &str) -> String {
+    template.replace("{text}", &crate::contact::percent_encode(text))
+}
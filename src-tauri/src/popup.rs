@@ -0,0 +1,168 @@
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+use tauri::{Emitter, Manager};
+
+use crate::models::AppConfig;
+use crate::state::AppState;
+
+/// 弹出窗口显示前的前台应用，隐藏后把焦点还给它以便粘贴
+#[derive(Debug, Clone, Copy)]
+pub struct FocusTarget {
+    #[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+    pid: u64,
+    #[cfg(target_os = "windows")]
+    hwnd: isize,
+}
+
+// screen 缩放模式的参考屏幕高度（逻辑像素）
+const REFERENCE_HEIGHT: f64 = 1080.0;
+
+// hold 模式下按住快捷键少于该时间视为单击，弹出窗口保持打开
+const QUICK_PEEK_TAP: Duration = Duration::from_millis(300);
+
+#[derive(Clone, Serialize)]
+struct PopupHidden {
+    reason: String,
+}
+
+#[derive(Clone, Serialize)]
+struct QuickPeekEnded {
+    paste: bool,
+}
+
+/// 记录当前前台应用（在弹出窗口获得焦点之前调用）
+pub fn capture_focus(pid: u64) -> FocusTarget {
+    FocusTarget {
+        pid,
+        #[cfg(target_os = "windows")]
+        hwnd: unsafe { windows::Win32::UI::WindowsAndMessaging::GetForegroundWindow().0 },
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[allow(deprecated, unexpected_cfgs)]
+fn restore_focus(target: FocusTarget) {
+    use cocoa::base::{id, nil};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    // NSApplicationActivateIgnoringOtherApps
+    const ACTIVATE_IGNORING_OTHER_APPS: u64 = 1 << 1;
+    unsafe {
+        let app: id = msg_send![
+            class!(NSRunningApplication),
+            runningApplicationWithProcessIdentifier: target.pid as i32
+        ];
+        if app != nil {
+            let _: bool = msg_send![app, activateWithOptions: ACTIVATE_IGNORING_OTHER_APPS];
+        }
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn restore_focus(target: FocusTarget) {
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::UI::WindowsAndMessaging::SetForegroundWindow;
+
+    if target.hwnd != 0 {
+        unsafe {
+            let _ = SetForegroundWindow(HWND(target.hwnd));
+        }
+    }
+}
+
+// 其他平台上窗口管理器会在窗口隐藏后自行切回焦点
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn restore_focus(_target: FocusTarget) {}
+
+/// 按配置的缩放方式计算弹出窗口在该屏幕上的物理大小，remember 模式返回 None
+fn size_on_monitor(config: &AppConfig, monitor: &tauri::Monitor) -> Option<(u32, u32)> {
+    let scale = monitor.scale_factor();
+    let factor = match config.popup_scaling.as_str() {
+        "fixed" => 1.0,
+        "screen" => {
+            let logical_height = monitor.size().height as f64 / scale;
+            (logical_height / REFERENCE_HEIGHT).clamp(0.75, 2.0)
+        }
+        _ => return None,
+    };
+    // 用目标屏幕的缩放比例换算，窗口还在另一块屏幕上时也能得到正确的大小
+    Some((
+        (config.popup_width as f64 * factor * scale).round() as u32,
+        (config.popup_height as f64 * factor * scale).round() as u32,
+    ))
+}
+
+/// 显示前按鼠标所在屏幕调整弹出窗口大小
+pub fn fit_to_monitor(
+    app: &tauri::AppHandle,
+    window: &tauri::WebviewWindow,
+    monitor: &tauri::Monitor,
+) {
+    let size = {
+        let state = app.state::<AppState>();
+        let config = state.config.lock().unwrap();
+        size_on_monitor(&config, monitor)
+    };
+    if let Some((width, height)) = size {
+        let _ = window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }));
+    }
+}
+
+pub fn emit_shown(app: &tauri::AppHandle) {
+    let _ = app.emit("popup-shown", ());
+    crate::usage::record(app, crate::usage::POPUP_OPEN);
+}
+
+/// 隐藏弹出窗口并发出 popup-hidden 事件；restore 为 true 时把焦点还给之前的应用
+pub fn hide(app: &tauri::AppHandle, reason: &str, restore: bool) {
+    // 隐藏后松开快捷键不再粘贴
+    app.state::<AppState>().quick_peek.lock().unwrap().take();
+    let Some(window) = app.get_webview_window("popup") else {
+        return;
+    };
+    if !window.is_visible().unwrap_or(false) {
+        return;
+    }
+    let _ = window.hide();
+    let _ = app.emit(
+        "popup-hidden",
+        PopupHidden {
+            reason: reason.to_string(),
+        },
+    );
+    if restore {
+        let target = *app.state::<AppState>().popup_focus_target.lock().unwrap();
+        if let Some(target) = target {
+            restore_focus(target);
+        }
+    }
+}
+
+/// 快捷键按下并显示弹出窗口后调用：hold 模式下进入按住预览，发出 quick-peek-started
+pub fn start_quick_peek(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    if state.config.lock().unwrap().popup_trigger_mode != "hold" {
+        return;
+    }
+    *state.quick_peek.lock().unwrap() = Some(Instant::now());
+    let _ = app.emit("quick-peek-started", ());
+}
+
+pub fn is_quick_peek_active(app: &tauri::AppHandle) -> bool {
+    app.state::<AppState>().quick_peek.lock().unwrap().is_some()
+}
+
+/// 快捷键松开：结束按住预览并发出 quick-peek-ended，按住足够久时弹出窗口粘贴选中条目
+pub fn end_quick_peek(app: &tauri::AppHandle) {
+    let Some(started) = app.state::<AppState>().quick_peek.lock().unwrap().take() else {
+        return;
+    };
+    let paste = started.elapsed() >= QUICK_PEEK_TAP;
+    let _ = app.emit("quick-peek-ended", QuickPeekEnded { paste });
+}
+
+/// 按住期间取消（如按下 Esc）：隐藏弹出窗口，松开时不再粘贴
+pub fn cancel_quick_peek(app: &tauri::AppHandle) {
+    hide(app, "quick-peek-cancel", true);
+}
@@ -0,0 +1,169 @@
+use std::time::Duration;
+
+use tauri::{Emitter, Manager};
+
+use crate::models::PowerState;
+use crate::state::AppState;
+
+// 检查电源和空闲状态的间隔
+const CHECK_INTERVAL: Duration = Duration::from_secs(30);
+// 省电时轮询间隔放大的倍数
+const POLL_SLOWDOWN: u32 = 4;
+// 省电时推迟执行的后台任务类型
+pub const DEFERRED_JOB_KINDS: [&str; 3] = ["ocr", "ocr-backfill", "prewarm"];
+
+/// 是否使用电池供电；无法判断时返回 None
+#[cfg(target_os = "macos")]
+pub fn on_battery() -> Option<bool> {
+    let output = std::process::Command::new("pmset")
+        .args(["-g", "batt"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let first = text.lines().next()?;
+    Some(first.contains("'Battery Power'"))
+}
+
+#[cfg(target_os = "windows")]
+pub fn on_battery() -> Option<bool> {
+    use windows::Win32::System::Power::{GetSystemPowerStatus, SYSTEM_POWER_STATUS};
+
+    let mut status = SYSTEM_POWER_STATUS::default();
+    unsafe { GetSystemPowerStatus(&mut status) }.ok()?;
+    match status.ACLineStatus {
+        0 => Some(true),
+        1 => Some(false),
+        _ => None,
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn on_battery() -> Option<bool> {
+    let mut has_mains = false;
+    for entry in std::fs::read_dir("/sys/class/power_supply").ok()?.flatten() {
+        let path = entry.path();
+        let kind = std::fs::read_to_string(path.join("type")).unwrap_or_default();
+        if kind.trim() != "Mains" {
+            continue;
+        }
+        has_mains = true;
+        let online = std::fs::read_to_string(path.join("online")).unwrap_or_default();
+        if online.trim() == "1" {
+            return Some(false);
+        }
+    }
+    has_mains.then_some(true)
+}
+
+/// 距离用户最后一次键盘鼠标输入的秒数；无法获取时返回 None
+#[cfg(target_os = "macos")]
+pub fn idle_seconds() -> Option<u64> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-c", "IOHIDSystem", "-d", "4"])
+        .output()
+        .ok()?;
+    let text = String::from_utf8_lossy(&output.stdout);
+    let line = text.lines().find(|l| l.contains("\"HIDIdleTime\""))?;
+    let nanos: u64 = line.rsplit('=').next()?.trim().parse().ok()?;
+    Some(nanos / 1_000_000_000)
+}
+
+#[cfg(target_os = "windows")]
+pub fn idle_seconds() -> Option<u64> {
+    use windows::Win32::System::SystemInformation::GetTickCount;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetLastInputInfo, LASTINPUTINFO};
+
+    let mut info = LASTINPUTINFO {
+        cbSize: std::mem::size_of::<LASTINPUTINFO>() as u32,
+        dwTime: 0,
+    };
+    if !unsafe { GetLastInputInfo(&mut info) }.as_bool() {
+        return None;
+    }
+    let now = unsafe { GetTickCount() };
+    Some(u64::from(now.wrapping_sub(info.dwTime)) / 1000)
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn idle_seconds() -> Option<u64> {
+    None
+}
+
+/// 按配置判断当前是否应省电：auto 时使用电池或空闲超过设定时间即省电
+fn evaluate(app: &tauri::AppHandle) -> PowerState {
+    let (mode, idle_minutes) = {
+        let state = app.state::<AppState>();
+        let config = state.config.lock().unwrap();
+        (
+            config.power_saving_mode.clone(),
+            config.power_saving_idle_minutes,
+        )
+    };
+    let on_battery = on_battery();
+    let idle_seconds = idle_seconds();
+    let power_saving = match mode.as_str() {
+        "always" => true,
+        "off" => false,
+        _ => {
+            on_battery == Some(true)
+                || (idle_minutes > 0 && idle_seconds.is_some_and(|s| s >= idle_minutes * 60))
+        }
+    };
+    PowerState {
+        mode,
+        on_battery,
+        idle_seconds,
+        power_saving,
+    }
+}
+
+pub fn is_power_saving(app: &tauri::AppHandle) -> bool {
+    *app.state::<AppState>().power_saving.lock().unwrap()
+}
+
+/// 省电时放大轮询间隔
+pub fn poll_interval(app: &tauri::AppHandle, base: Duration) -> Duration {
+    if is_power_saving(app) {
+        base * POLL_SLOWDOWN
+    } else {
+        base
+    }
+}
+
+/// 重新检查电源状态；省电状态变化时更新托盘、通知前端，退出省电后继续推迟的任务
+pub fn refresh(app: &tauri::AppHandle) -> PowerState {
+    let power = evaluate(app);
+    let state = app.state::<AppState>();
+    let changed = {
+        let mut current = state.power_saving.lock().unwrap();
+        let changed = *current != power.power_saving;
+        *current = power.power_saving;
+        changed
+    };
+    if changed {
+        log::info!(
+            "Power saving {}",
+            if power.power_saving { "on" } else { "off" }
+        );
+        if let Err(e) = crate::tray::update_power_indicator(app, power.power_saving) {
+            log::error!("Failed to update tray power indicator: {}", e);
+        }
+        if !power.power_saving {
+            state.jobs.wake(app);
+        }
+        let _ = app.emit("power-state-changed", &power);
+    }
+    power
+}
+
+/// 启动后台检查线程
+pub fn start(app: &tauri::AppHandle) {
+    let app = app.clone();
+    std::thread::spawn(move || loop {
+        if *app.state::<AppState>().shutting_down.lock().unwrap() {
+            break;
+        }
+        refresh(&app);
+        std::thread::sleep(CHECK_INTERVAL);
+    });
+}
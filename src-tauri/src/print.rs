@@ -0,0 +1,94 @@
+use std::path::{Path, PathBuf};
+
+use chrono::Local;
+use tauri::Manager;
+
+use crate::pdf::{write_image_pdf, write_text_pdf, PdfPage};
+use crate::state::AppState;
+
+fn print_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join("print");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// 调起系统打印对话框；Linux 没有统一的打印对话框，用默认查看器打开
+fn open_print_dialog(app: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = app;
+        let script = format!(
+            "tell application \"Preview\"\nactivate\nprint POSIX file \"{}\" with print dialog\nend tell",
+            path.to_string_lossy().replace('\\', "\\\\").replace('"', "\\\"")
+        );
+        std::process::Command::new("osascript")
+            .arg("-e")
+            .arg(script)
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+    #[cfg(target_os = "windows")]
+    {
+        let _ = app;
+        let command = format!(
+            "Start-Process -FilePath '{}' -Verb Print",
+            path.to_string_lossy().replace('\'', "''")
+        );
+        std::process::Command::new("powershell")
+            .args(["-NoProfile", "-Command", &command])
+            .spawn()
+            .map_err(|e| e.to_string())?;
+        Ok(())
+    }
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    {
+        use tauri_plugin_opener::OpenerExt;
+        app.opener()
+            .open_path(path.to_string_lossy(), None::<&str>)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// 把文本（等宽排版，带页眉）或图片条目渲染为临时 PDF 并发送到系统打印；
+/// 临时 PDF 会留在缓存目录，所以敏感条目不能打印
+pub fn print_item(app: &tauri::AppHandle, id: i64) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let item =
+        crate::utils::present_item(&state, state.db.get_item(id).map_err(|e| e.to_string())?);
+    if item.is_sensitive {
+        return Err("Sensitive items cannot be printed".to_string());
+    }
+    let now = Local::now();
+    let creation_date = crate::pdf::pdf_date(&now);
+
+    let pdf = match item.kind.as_str() {
+        "text" => {
+            let content = state.db.get_item_content(id).map_err(|e| e.to_string())?;
+            let title = match &item.source_app {
+                Some(source_app) => format!("{} - {}", item.timestamp, source_app),
+                None => item.timestamp.clone(),
+            };
+            write_text_pdf(&title, &content, &creation_date)
+        }
+        "image" => {
+            let img = image::open(&item.content).map_err(|e| e.to_string())?;
+            let page = PdfPage {
+                width: img.width(),
+                height: img.height(),
+                jpeg: crate::screenshot::encode_capture(&img, "jpeg", 95, Vec::new())?,
+            };
+            write_image_pdf(&[page], &creation_date)
+        }
+        _ => return Err("Only text and image items can be printed".to_string()),
+    };
+
+    let path = print_dir(app)?.join(format!("item_{}.pdf", id));
+    std::fs::write(&path, pdf).map_err(|e| e.to_string())?;
+    log::info!("Printing item {} via {:?}", id, path);
+    open_print_dialog(app, &path)
+}
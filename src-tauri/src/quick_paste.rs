@@ -0,0 +1,115 @@
+use tauri::Manager;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut};
+
+use crate::state::AppState;
+use crate::tray::update_quick_paste_menu;
+use crate::utils::{apply_paste_rules, write_to_clipboard_with};
+
+const MAX_ITEMS: usize = 20;
+
+/// 按当前置顶条目重新注册快捷键，并刷新托盘 Quick Paste 子菜单
+pub fn sync(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let pinned = match state.db.get_quick_paste_items(MAX_ITEMS) {
+        Ok(items) => items,
+        Err(e) => {
+            log::error!("Failed to load quick paste items: {}", e);
+            return;
+        }
+    };
+    let main_shortcut = crate::shortcut::parse(&state.config.lock().unwrap().shortcut).ok();
+
+    let manager = app.global_shortcut();
+    if let Ok(mut shortcuts) = state.quick_paste_shortcuts.lock() {
+        for shortcut in shortcuts.keys() {
+            let _ = manager.unregister(*shortcut);
+        }
+        shortcuts.clear();
+
+        for item in &pinned {
+            let (Some(id), Some(accelerator)) = (item.id, item.accelerator.as_deref()) else {
+                continue;
+            };
+            let shortcut = match crate::shortcut::parse(accelerator) {
+                Ok(shortcut) => shortcut,
+                Err(e) => {
+                    log::warn!("Invalid quick paste shortcut {}: {}", accelerator, e);
+                    continue;
+                }
+            };
+            // 不抢占弹出窗口的快捷键
+            if Some(shortcut) == main_shortcut || shortcuts.contains_key(&shortcut) {
+                log::warn!("Quick paste shortcut {} already in use", accelerator);
+                continue;
+            }
+            match manager.register(shortcut) {
+                Ok(()) => {
+                    shortcuts.insert(shortcut, id);
+                }
+                Err(e) => {
+                    log::warn!(
+                        "Failed to register quick paste shortcut {}: {}",
+                        accelerator,
+                        e
+                    );
+                }
+            }
+        }
+    }
+
+    if let Err(e) = update_quick_paste_menu(app, &pinned) {
+        log::error!("Failed to update quick paste menu: {}", e);
+    }
+}
+
+/// 快捷键对应的条目 id
+pub fn item_for_shortcut(app: &tauri::AppHandle, shortcut: &Shortcut) -> Option<i64> {
+    let state = app.state::<AppState>();
+    let shortcuts = state.quick_paste_shortcuts.lock().ok()?;
+    shortcuts.get(shortcut).copied()
+}
+
+/// 把条目写入剪贴板，按当前前台应用的粘贴规则处理；托盘、跳转列表和 Stream Deck 也经由这里粘贴，
+/// 危险命令粘贴到终端前同样需要确认
+pub fn paste(app: &tauri::AppHandle, id: i64) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut item = state.db.get_item(id).map_err(|e| e.to_string())?;
+    let target_app = active_win_pos_rs::get_active_window().ok();
+    let include_raw_formats = match &target_app {
+        Some(active) => apply_paste_rules(
+            app,
+            &mut item,
+            &active.app_name,
+            &active.process_path.to_string_lossy(),
+        ),
+        None => true,
+    };
+    if item.kind == "text" {
+        crate::commands::confirm_dangerous_paste(
+            app,
+            Some(id),
+            &item.content,
+            target_app.as_ref().map(|active| active.app_name.clone()),
+            &target_app
+                .as_ref()
+                .map(|active| active.process_path.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        )
+        .map_err(String::from)?;
+    }
+
+    write_to_clipboard_with(app, &item, include_raw_formats)?;
+
+    let target_app = target_app.map(|active| active.app_name);
+    if let Err(e) = state.db.record_paste(id, target_app.as_deref()) {
+        log::error!("Failed to record paste: {}", e);
+    }
+    crate::usage::record(app, crate::usage::PASTE);
+    crate::webhook::notify(
+        app,
+        "paste",
+        Some(&item),
+        serde_json::json!({ "target_app": target_app }),
+    );
+    Ok(())
+}
@@ -0,0 +1,156 @@
+#![allow(deprecated)]
+#![allow(unexpected_cfgs)]
+
+use std::path::PathBuf;
+
+use tauri::Manager;
+
+use crate::state::AppState;
+
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn quicklook_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join("quicklook");
+    // 只保留当前预览的临时文件
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+/// 把条目落地为可预览的文件：文本写入临时文件，图片、音频和文件列表直接使用原路径
+#[cfg_attr(not(target_os = "macos"), allow(dead_code))]
+fn materialize(app: &tauri::AppHandle, id: i64) -> Result<Vec<PathBuf>, String> {
+    let state = app.state::<AppState>();
+    let item = state.db.get_item(id).map_err(|e| e.to_string())?;
+    if item.is_sensitive {
+        return Err("Sensitive items cannot be previewed with Quick Look".to_string());
+    }
+    let paths = match item.kind.as_str() {
+        "text" => {
+            let (content, extension) = match (&item.html_content, item.data_type.as_str()) {
+                (Some(html), _) => (html.clone(), "html"),
+                (None, "svg") => (item.content.clone(), "svg"),
+                (None, "json") => (item.content.clone(), "json"),
+                _ => (
+                    state.db.get_item_content(id).map_err(|e| e.to_string())?,
+                    "txt",
+                ),
+            };
+            let path = quicklook_dir(app)?.join(format!("item_{}.{}", id, extension));
+            std::fs::write(&path, content).map_err(|e| e.to_string())?;
+            vec![path]
+        }
+        "image" | "audio" => vec![PathBuf::from(&item.content)],
+        "file" => serde_json::from_str::<Vec<String>>(&item.content)
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
+        kind => return Err(format!("Cannot preview {} items", kind)),
+    };
+    let paths: Vec<PathBuf> = paths.into_iter().filter(|p| p.exists()).collect();
+    if paths.is_empty() {
+        return Err(format!("Item {} has no file to preview", id));
+    }
+    Ok(paths)
+}
+
+/// 用系统 Quick Look 面板预览条目（仅 macOS）
+pub fn quicklook_item(app: &tauri::AppHandle, id: i64) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    {
+        let paths = materialize(app, id)?;
+        log::info!("Quick Look item {} via {:?}", id, paths);
+        panel::show(app, paths)
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        let _ = (app, id);
+        Err("Quick Look is only available on macOS".to_string())
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod panel {
+    use std::path::PathBuf;
+    use std::sync::{Mutex, OnceLock};
+
+    use cocoa::base::{id, nil, NO, YES};
+    use cocoa::foundation::{NSInteger, NSString};
+    use objc::declare::ClassDecl;
+    use objc::runtime::{Class, Object, Sel};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    #[link(name = "Quartz", kind = "framework")]
+    extern "C" {}
+
+    // 当前预览的 NSURL（已 retain），只在主线程读写
+    static ITEMS: Mutex<Vec<usize>> = Mutex::new(Vec::new());
+
+    extern "C" fn number_of_items(_this: &Object, _sel: Sel, _panel: id) -> NSInteger {
+        ITEMS.lock().unwrap().len() as NSInteger
+    }
+
+    extern "C" fn item_at(_this: &Object, _sel: Sel, _panel: id, index: NSInteger) -> id {
+        ITEMS
+            .lock()
+            .unwrap()
+            .get(index as usize)
+            .map_or(nil, |url| *url as id)
+    }
+
+    /// QLPreviewPanel 的数据源
+    fn data_source() -> id {
+        static SOURCE: OnceLock<usize> = OnceLock::new();
+        *SOURCE.get_or_init(|| unsafe {
+            let mut decl = ClassDecl::new("ClipboardQuickLookDataSource", class!(NSObject))
+                .expect("Quick Look data source class already registered");
+            decl.add_method(
+                sel!(numberOfPreviewItemsInPreviewPanel:),
+                number_of_items as extern "C" fn(&Object, Sel, id) -> NSInteger,
+            );
+            decl.add_method(
+                sel!(previewPanel:previewItemAtIndex:),
+                item_at as extern "C" fn(&Object, Sel, id, NSInteger) -> id,
+            );
+            let class = decl.register();
+            let source: id = msg_send![class, new];
+            source as usize
+        }) as id
+    }
+
+    pub fn show(app: &tauri::AppHandle, paths: Vec<PathBuf>) -> Result<(), String> {
+        app.run_on_main_thread(move || unsafe {
+            let Some(panel_class) = Class::get("QLPreviewPanel") else {
+                log::error!("QLPreviewPanel is not available");
+                return;
+            };
+            {
+                let mut items = ITEMS.lock().unwrap();
+                for url in items.drain(..) {
+                    let _: () = msg_send![url as id, release];
+                }
+                for path in &paths {
+                    let path = NSString::alloc(nil).init_str(&path.to_string_lossy());
+                    let url: id = msg_send![class!(NSURL), fileURLWithPath: path isDirectory: NO];
+                    let _: id = msg_send![url, retain];
+                    let _: () = msg_send![path, release];
+                    items.push(url as usize);
+                }
+            }
+
+            // 托盘应用需要先激活，面板才会显示在最前
+            let ns_app: id = msg_send![class!(NSApplication), sharedApplication];
+            let _: () = msg_send![ns_app, activateIgnoringOtherApps: YES];
+            let panel: id = msg_send![panel_class, sharedPreviewPanel];
+            let _: () = msg_send![panel, setDataSource: data_source()];
+            let _: () = msg_send![panel, reloadData];
+            let _: () = msg_send![panel, setCurrentPreviewItemIndex: 0 as NSInteger];
+            let _: () = msg_send![panel, makeKeyAndOrderFront: nil];
+        })
+        .map_err(|e| e.to_string())
+    }
+}
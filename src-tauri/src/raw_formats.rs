@@ -0,0 +1,106 @@
+use clipboard_rs::{Clipboard, ClipboardContent, ClipboardContext};
+
+// 单个格式 / 单条记录保存的原始数据上限
+const MAX_FORMAT_BYTES: usize = 16 * 1024 * 1024;
+const MAX_TOTAL_BYTES: usize = 32 * 1024 * 1024;
+
+/// 文本、HTML、RTF、图片、文件等已经单独处理的格式，以及系统内部的标记类型
+fn is_standard_format(format: &str) -> bool {
+    const STANDARD: [&str; 37] = [
+        // macOS
+        "public.utf8-plain-text",
+        "public.utf16-plain-text",
+        "public.utf16-external-plain-text",
+        "NSStringPboardType",
+        "public.html",
+        "Apple HTML pasteboard type",
+        "public.rtf",
+        "NeXT Rich Text Format v1.0 pasteboard type",
+        "public.png",
+        "public.tiff",
+        "public.jpeg",
+        "NeXT TIFF v4.0 pasteboard type",
+        "com.compuserve.gif",
+        "public.svg-image",
+        "public.file-url",
+        "NSFilenamesPboardType",
+        "public.url",
+        "public.url-name",
+        "Apple URL pasteboard type",
+        "com.apple.is-remote-clipboard",
+        // Windows
+        "HTML Format",
+        "Rich Text Format",
+        "PNG",
+        "GIF",
+        "FileName",
+        "FileNameW",
+        "Shell IDList Array",
+        "DataObject",
+        "Ole Private Data",
+        // Linux
+        "TARGETS",
+        "TIMESTAMP",
+        "MULTIPLE",
+        "SAVE_TARGETS",
+        "UTF8_STRING",
+        "STRING",
+        "TEXT",
+        "text/uri-list",
+    ];
+    STANDARD.contains(&format)
+        || format.starts_with("CF_")
+        || format.starts_with("dyn.")
+        || format.starts_with("org.nspasteboard.")
+        || format.starts_with("text/plain")
+        || format.starts_with("text/html")
+        || format.starts_with("image/")
+        || format.starts_with("x-special/")
+        || format == crate::self_write::MARKER_FORMAT
+        || crate::audio::is_audio_format(format)
+}
+
+/// 读取剪贴板中的非标准格式；第二个返回值表示剪贴板里是否只有这些格式
+pub fn read() -> (Vec<(String, Vec<u8>)>, bool) {
+    let Ok(ctx) = ClipboardContext::new() else {
+        return (Vec::new(), false);
+    };
+    let formats = ctx.available_formats().unwrap_or_default();
+    let mut raw = Vec::new();
+    let mut total = 0;
+    let mut only_raw = !formats.is_empty();
+    for format in formats {
+        if is_standard_format(&format) {
+            only_raw = false;
+            continue;
+        }
+        let Ok(data) = ctx.get_buffer(&format) else {
+            continue;
+        };
+        if data.is_empty() || data.len() > MAX_FORMAT_BYTES || total + data.len() > MAX_TOTAL_BYTES
+        {
+            log::info!(
+                "Skipping raw clipboard format {} ({} bytes)",
+                format,
+                data.len()
+            );
+            continue;
+        }
+        total += data.len();
+        raw.push((format, data));
+    }
+    (raw, only_raw && !raw.is_empty())
+}
+
+/// 和条目本身的内容一起写回剪贴板
+pub fn write(
+    mut contents: Vec<ClipboardContent>,
+    raw: Vec<(String, Vec<u8>)>,
+) -> Result<(), String> {
+    contents.extend(
+        raw.into_iter()
+            .map(|(format, data)| ClipboardContent::Other(format, data)),
+    );
+    let ctx = ClipboardContext::new().map_err(|e| e.to_string())?;
+    ctx.set(contents).map_err(|e| e.to_string())
+}
@@ -0,0 +1,81 @@
+use crate::models::{ClipboardItem, RoutingRule};
+use crate::state::AppState;
+use regex::Regex;
+use tauri::Manager;
+
+/// 规则是否匹配：pattern 匹配文本内容、source_app 包含在来源应用名中（不区分大小写），
+/// 两者都设置时需同时满足；没有任何条件的规则不匹配
+pub fn matches(
+    rule: &RoutingRule,
+    content: &str,
+    source_app: Option<&str>,
+) -> Result<bool, String> {
+    let pattern = rule.pattern.as_deref().filter(|p| !p.is_empty());
+    let app = rule
+        .source_app
+        .as_deref()
+        .map(str::trim)
+        .filter(|a| !a.is_empty());
+    if pattern.is_none() && app.is_none() {
+        return Ok(false);
+    }
+    if let Some(app) = app {
+        let source = source_app.unwrap_or_default().to_lowercase();
+        if !source.contains(&app.to_lowercase()) {
+            return Ok(false);
+        }
+    }
+    if let Some(pattern) = pattern {
+        let re = Regex::new(pattern).map_err(|e| e.to_string())?;
+        if !re.is_match(content) {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+fn item_matches(rule: &RoutingRule, item: &ClipboardItem) -> bool {
+    // 正则只对文本条目生效，其他条目只能按来源应用匹配
+    if rule.pattern.as_deref().is_some_and(|p| !p.is_empty()) && item.kind != "text" {
+        return false;
+    }
+    matches(rule, &item.content, item.source_app.as_deref()).unwrap_or(false)
+}
+
+/// 对新记录的条目按顺序应用第一条匹配的规则：放入收藏夹、添加标签、置顶
+pub fn apply(app: &tauri::AppHandle, id: i64) {
+    let state = app.state::<AppState>();
+    let rules = state.config.lock().unwrap().routing_rules.clone();
+    if rules.is_empty() {
+        return;
+    }
+    let Ok(item) = state.db.get_item(id) else {
+        return;
+    };
+    let Some(rule) = rules.iter().find(|rule| item_matches(rule, &item)) else {
+        return;
+    };
+
+    // 收藏夹已被删除时不再放入
+    let collection_id = rule.collection_id.filter(|cid| {
+        state
+            .db
+            .get_collections()
+            .is_ok_and(|collections| collections.iter().any(|c| c.id == *cid))
+    });
+    let tag = rule.tag.as_deref().map(str::trim).filter(|t| !t.is_empty());
+    if let Err(e) = state.db.route_item(id, collection_id, tag, rule.pin) {
+        log::error!("Failed to apply routing rule {:?}: {}", rule.name, e);
+        return;
+    }
+    if let Err(e) = state
+        .db
+        .set_item_meta(id, "routing_rule", Some(&serde_json::json!(rule.name)))
+    {
+        log::error!("Failed to record routing rule on item {}: {}", id, e);
+    }
+    log::info!("Applied routing rule {:?} to item {}", rule.name, id);
+    if rule.pin && !item.is_pinned {
+        crate::quick_paste::sync(app);
+    }
+}
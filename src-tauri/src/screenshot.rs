@@ -1,13 +1,623 @@
-use crate::models::{CaptureResult, ScreenInfo};
+use crate::error::AppError;
+use crate::models::{CacheCleanup, CaptureResult, PixelGrid, ScreenInfo, WindowRect};
+use crate::state::AppState;
 use image::ImageEncoder;
 use screenshots::Screen;
-use std::time::Instant;
-use tauri::Runtime; // Import ImageEncoder trait
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tauri::{Manager, Runtime}; // Import ImageEncoder trait
 
-pub fn capture_all_screens(cache_dir: std::path::PathBuf) -> Result<Vec<CaptureResult>, String> {
+/// 截图过程中的临时整屏截图目录
+pub fn screenshot_cache_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?
+        .join("screenshots"))
+}
+
+/// 用户确认后保存的截图目录
+pub fn captures_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    Ok(app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("captures"))
+}
+
+/// 文件名中不允许出现的字符替换为下划线
+fn sanitize_filename(name: &str) -> String {
+    name.chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect::<String>()
+        .trim()
+        .trim_matches('.')
+        .to_string()
+}
+
+/// 把 yyyy-MM-dd HH:mm:ss 风格的日期格式转换为 chrono 格式，其余字符原样保留
+fn date_pattern(pattern: &str) -> String {
+    const TOKENS: [(&str, &str); 8] = [
+        ("yyyy", "%Y"),
+        ("SSS", "%3f"),
+        ("yy", "%y"),
+        ("MM", "%m"),
+        ("dd", "%d"),
+        ("HH", "%H"),
+        ("mm", "%M"),
+        ("ss", "%S"),
+    ];
+    let mut format = String::new();
+    let mut rest = pattern;
+    'outer: while let Some(c) = rest.chars().next() {
+        for (token, spec) in TOKENS {
+            if let Some(tail) = rest.strip_prefix(token) {
+                format.push_str(spec);
+                rest = tail;
+                continue 'outer;
+            }
+        }
+        if c == '%' {
+            format.push_str("%%");
+        } else {
+            format.push(c);
+        }
+        rest = &rest[c.len_utf8()..];
+    }
+    format
+}
+
+/// 按模板生成截图文件名（不含扩展名）
+pub fn render_filename_template(
+    template: &str,
+    now: &chrono::DateTime<chrono::Local>,
+    app_name: Option<&str>,
+    clipboard: Option<&str>,
+    seq: u32,
+) -> String {
+    let mut name = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        name.push_str(&rest[..start]);
+        let token = &rest[start + 1..start + len];
+        match token {
+            "app" => name.push_str(app_name.unwrap_or("Screen")),
+            "seq" => name.push_str(&format!("{:03}", seq)),
+            "clipboard" => {
+                let text = clipboard.and_then(|t| t.lines().find(|l| !l.trim().is_empty()));
+                name.extend(text.unwrap_or("").trim().chars().take(50));
+            }
+            pattern => {
+                use std::fmt::Write;
+                let _ = write!(name, "{}", now.format(&date_pattern(pattern)));
+            }
+        }
+        rest = &rest[start + len + 1..];
+    }
+    name.push_str(rest);
+
+    let name = sanitize_filename(&name);
+    if name.is_empty() {
+        format!("capture_{}", now.format("%Y%m%d_%H%M%S"))
+    } else {
+        name
+    }
+}
+
+/// 最小的 EXIF（TIFF）块：Software、DateTime 和 DateTimeOriginal
+pub fn exif_metadata(time: &chrono::DateTime<chrono::Local>) -> Vec<u8> {
+    let software = b"Clipboard\0";
+    let datetime = format!("{}\0", time.format("%Y:%m:%d %H:%M:%S")).into_bytes();
+
+    // Header (8) + IFD0 with 3 entries (2 + 36 + 4) + Exif IFD with 1 entry (2 + 12 + 4)
+    let exif_ifd_offset: u32 = 8 + 42;
+    let software_offset: u32 = exif_ifd_offset + 18;
+    let datetime_offset: u32 = software_offset + software.len() as u32;
+
+    let mut exif = b"MM\0\x2A\0\0\0\x08".to_vec();
+    let entry = |exif: &mut Vec<u8>, tag: u16, kind: u16, count: u32, value: u32| {
+        exif.extend_from_slice(&tag.to_be_bytes());
+        exif.extend_from_slice(&kind.to_be_bytes());
+        exif.extend_from_slice(&count.to_be_bytes());
+        exif.extend_from_slice(&value.to_be_bytes());
+    };
+    const ASCII: u16 = 2;
+    const LONG: u16 = 4;
+
+    exif.extend_from_slice(&3u16.to_be_bytes());
+    entry(
+        &mut exif,
+        0x0131,
+        ASCII,
+        software.len() as u32,
+        software_offset,
+    );
+    entry(
+        &mut exif,
+        0x0132,
+        ASCII,
+        datetime.len() as u32,
+        datetime_offset,
+    );
+    entry(&mut exif, 0x8769, LONG, 1, exif_ifd_offset);
+    exif.extend_from_slice(&0u32.to_be_bytes());
+
+    exif.extend_from_slice(&1u16.to_be_bytes());
+    entry(
+        &mut exif,
+        0x9003,
+        ASCII,
+        datetime.len() as u32,
+        datetime_offset,
+    );
+    exif.extend_from_slice(&0u32.to_be_bytes());
+
+    exif.extend_from_slice(software);
+    exif.extend_from_slice(&datetime);
+    exif
+}
+
+/// 给 WebP 文件加上 EXIF 块：简单格式先转换为带 VP8X 头的扩展格式，再设置 EXIF 标志位
+fn webp_with_exif(data: &[u8], width: u32, height: u32, alpha: bool, exif: &[u8]) -> Vec<u8> {
+    if exif.is_empty() || data.len() < 12 || &data[0..4] != b"RIFF" || &data[8..12] != b"WEBP" {
+        return data.to_vec();
+    }
+    let mut chunks: Vec<([u8; 4], Vec<u8>)> = Vec::new();
+    let mut rest = &data[12..];
+    while rest.len() >= 8 {
+        let fourcc = [rest[0], rest[1], rest[2], rest[3]];
+        let size = u32::from_le_bytes([rest[4], rest[5], rest[6], rest[7]]) as usize;
+        let Some(payload) = rest.get(8..8 + size) else {
+            return data.to_vec();
+        };
+        chunks.push((fourcc, payload.to_vec()));
+        rest = rest.get(8 + size + size % 2..).unwrap_or_default();
+    }
+
+    const EXIF_FLAG: u8 = 0x08;
+    const ALPHA_FLAG: u8 = 0x10;
+    match chunks.first_mut() {
+        Some((fourcc, payload)) if fourcc == b"VP8X" && !payload.is_empty() => {
+            payload[0] |= EXIF_FLAG;
+        }
+        _ => {
+            let mut vp8x = vec![EXIF_FLAG | if alpha { ALPHA_FLAG } else { 0 }, 0, 0, 0];
+            vp8x.extend_from_slice(&width.saturating_sub(1).to_le_bytes()[..3]);
+            vp8x.extend_from_slice(&height.saturating_sub(1).to_le_bytes()[..3]);
+            chunks.insert(0, (*b"VP8X", vp8x));
+        }
+    }
+    chunks.push((*b"EXIF", exif.to_vec()));
+
+    let mut body = b"WEBP".to_vec();
+    for (fourcc, payload) in &chunks {
+        body.extend_from_slice(fourcc);
+        body.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+        body.extend_from_slice(payload);
+        if payload.len() % 2 == 1 {
+            body.push(0);
+        }
+    }
+    let mut out = b"RIFF".to_vec();
+    out.extend_from_slice(&(body.len() as u32).to_le_bytes());
+    out.extend_from_slice(&body);
+    out
+}
+
+/// 按格式编码截图：png / jpeg / webp；JPEG 和 WebP 使用 quality，WebP 在 quality 为 100 时无损
+pub fn encode_capture(
+    img: &image::DynamicImage,
+    format: &str,
+    quality: u8,
+    exif: Vec<u8>,
+) -> Result<Vec<u8>, String> {
+    let mut data = Vec::new();
+    let result = match format {
+        "png" => {
+            let mut encoder = image::codecs::png::PngEncoder::new(&mut data);
+            let _ = encoder.set_exif_metadata(exif);
+            img.write_with_encoder(encoder)
+        }
+        "jpeg" | "jpg" => {
+            // JPEG has no alpha channel
+            let rgb = image::DynamicImage::ImageRgb8(img.to_rgb8());
+            let mut encoder = image::codecs::jpeg::JpegEncoder::new_with_quality(
+                &mut data,
+                quality.clamp(1, 100),
+            );
+            let _ = encoder.set_exif_metadata(exif);
+            rgb.write_with_encoder(encoder)
+        }
+        "webp" => {
+            let rgba = img.to_rgba8();
+            let encoder = webp::Encoder::from_rgba(&rgba, rgba.width(), rgba.height());
+            let encoded = if quality >= 100 {
+                encoder.encode_lossless()
+            } else {
+                encoder.encode(quality.max(1) as f32)
+            };
+            return Ok(webp_with_exif(
+                &encoded,
+                rgba.width(),
+                rgba.height(),
+                img.color().has_alpha(),
+                &exif,
+            ));
+        }
+        other => return Err(format!("Unsupported image format: {}", other)),
+    };
+    result.map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+/// 删除目录中超过 max_age 的文件，再按从旧到新删除直到总大小不超过 max_bytes
+fn cleanup_dir(
+    dir: &Path,
+    max_age: Option<Duration>,
+    max_bytes: Option<u64>,
+    keep: &dyn Fn(&Path) -> bool,
+) -> CacheCleanup {
+    let mut cleanup = CacheCleanup::default();
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return cleanup;
+    };
+
+    let mut files: Vec<(PathBuf, SystemTime, u64)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let meta = entry.metadata().ok()?;
+            meta.is_file().then(|| {
+                (
+                    entry.path(),
+                    meta.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    meta.len(),
+                )
+            })
+        })
+        .collect();
+    files.sort_by_key(|(_, modified, _)| *modified);
+
+    let mut remove = |path: &Path, size: u64| {
+        if keep(path) {
+            return false;
+        }
+        match std::fs::remove_file(path) {
+            Ok(_) => {
+                cleanup.files_removed += 1;
+                cleanup.bytes_freed += size;
+                true
+            }
+            Err(e) => {
+                log::warn!("Failed to remove cached capture {:?}: {}", path, e);
+                false
+            }
+        }
+    };
+
+    let now = SystemTime::now();
+    let mut total: u64 = files.iter().map(|(_, _, size)| size).sum();
+    for (path, modified, size) in &files {
+        let expired = max_age
+            .map(|max_age| now.duration_since(*modified).unwrap_or_default() > max_age)
+            .unwrap_or(false);
+        let over_size = max_bytes.map(|max| total > max).unwrap_or(false);
+        if (expired || over_size) && remove(path, *size) {
+            total -= size;
+        }
+    }
+    cleanup
+}
+
+/// 按配置清理临时截图缓存和默认目录中已保存的截图；all 为 true 时清空临时缓存。
+/// 用户自选的保存目录从不清理，正在进行的截图会话用到的文件也跳过
+pub fn cleanup_capture_cache(app: &tauri::AppHandle, all: bool) -> CacheCleanup {
+    let state = app.state::<AppState>();
+    let (max_age_days, max_size_mb, custom_save_dir) = {
+        let config = state.config.lock().unwrap();
+        (
+            config.capture_cache_max_age_days,
+            config.capture_cache_max_size_mb,
+            !config.capture_save_dir.trim().is_empty(),
+        )
+    };
+    let policy_age = (max_age_days > 0).then(|| Duration::from_secs(max_age_days * 24 * 60 * 60));
+    let policy_size = (max_size_mb > 0).then(|| max_size_mb * 1024 * 1024);
+    // 截图窗口还开着时，本次会话的整屏截图仍在使用，选区也可能正在写入保存目录
+    let capturing = !state.capture_frames.lock().unwrap().is_empty();
+    let active: Vec<PathBuf> = if capturing {
+        state
+            .current_captures
+            .lock()
+            .unwrap()
+            .iter()
+            .flatten()
+            .map(|c| PathBuf::from(&c.path))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    // Files still referenced by history items are never removed
+    let keep = |path: &Path| {
+        active.iter().any(|p| p == path)
+            || state
+                .db
+                .is_content_referenced(&path.to_string_lossy())
+                .unwrap_or(true)
+    };
+
+    let mut total = CacheCleanup::default();
+    if let Ok(dir) = screenshot_cache_dir(app) {
+        total = if all {
+            cleanup_dir(&dir, Some(Duration::ZERO), None, &keep)
+        } else {
+            cleanup_dir(&dir, policy_age, policy_size, &keep)
+        };
+    }
+    if !capturing && !custom_save_dir {
+        if let Ok(dir) = captures_dir(app) {
+            let saved = cleanup_dir(&dir, policy_age, policy_size, &keep);
+            total.files_removed += saved.files_removed;
+            total.bytes_freed += saved.bytes_freed;
+        }
+    }
+    if total.files_removed > 0 {
+        log::info!(
+            "Capture cache cleanup removed {} files ({} bytes)",
+            total.files_removed,
+            total.bytes_freed
+        );
+    }
+    total
+}
+
+/// 截图时冻结的屏幕像素和窗口位置，供放大镜和窗口吸附使用
+pub struct CaptureFrame {
+    pub width: u32,
+    pub height: u32,
+    pub rgba: Vec<u8>,
+    pub windows: Vec<WindowRect>,
+}
+
+impl CaptureFrame {
+    /// 以 (x, y) 为中心取 (2r+1)x(2r+1) 的像素，超出屏幕的部分为透明
+    pub fn pixels_at(&self, x: i32, y: i32, radius: u32) -> PixelGrid {
+        let size = radius.saturating_mul(2).saturating_add(1);
+        let r = radius as i64;
+        let mut pixels = Vec::with_capacity((size as usize).pow(2) * 4);
+        // 坐标来自前端，用 i64 计算，避免 x ± radius 溢出
+        for py in y as i64 - r..=y as i64 + r {
+            for px in x as i64 - r..=x as i64 + r {
+                let inside =
+                    (0..self.width as i64).contains(&px) && (0..self.height as i64).contains(&py);
+                let pixel = inside
+                    .then(|| (py as usize * self.width as usize + px as usize) * 4)
+                    .and_then(|offset| self.rgba.get(offset..offset + 4));
+                pixels.extend_from_slice(pixel.unwrap_or(&[0, 0, 0, 0]));
+            }
+        }
+        let center = (size as usize * radius as usize + radius as usize) * 4;
+        PixelGrid {
+            width: size,
+            height: size,
+            color: format!(
+                "#{:02X}{:02X}{:02X}",
+                pixels[center],
+                pixels[center + 1],
+                pixels[center + 2]
+            ),
+            pixels,
+        }
+    }
+}
+
+/// 当前屏幕上的可见窗口（从前到后），坐标为全局坐标，不含本应用窗口
+#[cfg(target_os = "macos")]
+fn list_windows() -> Vec<WindowRect> {
+    use core_foundation::base::{CFType, TCFType};
+    use core_foundation::dictionary::CFDictionary;
+    use core_foundation::number::CFNumber;
+    use core_foundation::string::{CFString, CFStringRef};
+    use core_graphics::window::{
+        copy_window_info, kCGNullWindowID, kCGWindowBounds, kCGWindowLayer,
+        kCGWindowListExcludeDesktopElements, kCGWindowListOptionOnScreenOnly, kCGWindowName,
+        kCGWindowOwnerName, kCGWindowOwnerPID,
+    };
+
+    let Some(windows) = copy_window_info(
+        kCGWindowListOptionOnScreenOnly | kCGWindowListExcludeDesktopElements,
+        kCGNullWindowID,
+    ) else {
+        return Vec::new();
+    };
+
+    let find = |dict: &CFDictionary, key: CFStringRef| {
+        dict.find(key as *const std::ffi::c_void)
+            .map(|v| unsafe { CFType::wrap_under_get_rule(*v) })
+    };
+    let number = |dict: &CFDictionary, key: CFStringRef| {
+        find(dict, key)
+            .and_then(|v| v.downcast::<CFNumber>())
+            .and_then(|n| n.to_f64())
+    };
+    let string = |dict: &CFDictionary, key: CFStringRef| {
+        find(dict, key)
+            .and_then(|v| v.downcast::<CFString>())
+            .map(|s| s.to_string())
+            .unwrap_or_default()
+    };
+
+    let own_pid = std::process::id() as f64;
+    let mut rects = Vec::new();
+    for window in windows.iter() {
+        let info: CFDictionary = unsafe { CFDictionary::wrap_under_get_rule(*window as _) };
+        unsafe {
+            // Layer 0 holds normal application windows
+            if number(&info, kCGWindowLayer) != Some(0.0)
+                || number(&info, kCGWindowOwnerPID) == Some(own_pid)
+            {
+                continue;
+            }
+            let Some(bounds) =
+                find(&info, kCGWindowBounds).and_then(|v| v.downcast::<CFDictionary>())
+            else {
+                continue;
+            };
+            let field = |name: &'static str| {
+                let key = CFString::from_static_string(name);
+                number(&bounds, key.as_concrete_TypeRef()).unwrap_or(0.0)
+            };
+            let (width, height) = (field("Width"), field("Height"));
+            if width < 1.0 || height < 1.0 {
+                continue;
+            }
+            rects.push(WindowRect {
+                x: field("X") as i32,
+                y: field("Y") as i32,
+                width: width as u32,
+                height: height as u32,
+                title: string(&info, kCGWindowName),
+                app: string(&info, kCGWindowOwnerName),
+            });
+        }
+    }
+    rects
+}
+
+#[cfg(target_os = "windows")]
+fn list_windows() -> Vec<WindowRect> {
+    use windows::Win32::Foundation::{BOOL, HWND, LPARAM, RECT};
+    use windows::Win32::Graphics::Dwm::{
+        DwmGetWindowAttribute, DWMWA_CLOAKED, DWMWA_EXTENDED_FRAME_BOUNDS,
+    };
+    use windows::Win32::UI::WindowsAndMessaging::{
+        EnumWindows, GetWindowRect, GetWindowTextW, GetWindowThreadProcessId, IsIconic,
+        IsWindowVisible,
+    };
+
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let rects = &mut *(lparam.0 as *mut Vec<WindowRect>);
+        if !IsWindowVisible(hwnd).as_bool() || IsIconic(hwnd).as_bool() {
+            return true.into();
+        }
+        let mut pid = 0u32;
+        GetWindowThreadProcessId(hwnd, Some(&mut pid as *mut u32));
+        if pid == std::process::id() {
+            return true.into();
+        }
+        // Suspended UWP windows are "visible" but cloaked
+        let mut cloaked = 0u32;
+        let _ = DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_CLOAKED,
+            &mut cloaked as *mut _ as *mut _,
+            std::mem::size_of::<u32>() as u32,
+        );
+        if cloaked != 0 {
+            return true.into();
+        }
+        // Extended frame bounds exclude the invisible resize borders
+        let mut rect = RECT::default();
+        if DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            &mut rect as *mut _ as *mut _,
+            std::mem::size_of::<RECT>() as u32,
+        )
+        .is_err()
+            && GetWindowRect(hwnd, &mut rect).is_err()
+        {
+            return true.into();
+        }
+        let mut title = [0u16; 256];
+        let len = GetWindowTextW(hwnd, &mut title);
+        if rect.right > rect.left && rect.bottom > rect.top && len > 0 {
+            rects.push(WindowRect {
+                x: rect.left,
+                y: rect.top,
+                width: (rect.right - rect.left) as u32,
+                height: (rect.bottom - rect.top) as u32,
+                title: String::from_utf16_lossy(&title[..len as usize]),
+                app: String::new(),
+            });
+        }
+        true.into()
+    }
+
+    let mut rects: Vec<WindowRect> = Vec::new();
+    unsafe {
+        let _ = EnumWindows(Some(collect), LPARAM(&mut rects as *mut _ as isize));
+    }
+    rects
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn list_windows() -> Vec<WindowRect> {
+    Vec::new()
+}
+
+/// 把全局窗口坐标转换到截图的像素坐标系，并裁剪到该屏幕范围内
+fn windows_on_screen(windows: &[WindowRect], capture: &CaptureResult) -> Vec<WindowRect> {
+    // macOS reports window bounds in points, Windows in physical pixels
+    let scale = if cfg!(target_os = "macos") {
+        capture.scale_factor
+    } else {
+        1.0
+    };
+    windows
+        .iter()
+        .filter_map(|w| {
+            let left = (((w.x - capture.x) as f64) * scale).max(0.0);
+            let top = (((w.y - capture.y) as f64) * scale).max(0.0);
+            let right =
+                (((w.x - capture.x) as f64 + w.width as f64) * scale).min(capture.width as f64);
+            let bottom =
+                (((w.y - capture.y) as f64 + w.height as f64) * scale).min(capture.height as f64);
+            (right > left && bottom > top).then(|| WindowRect {
+                x: left as i32,
+                y: top as i32,
+                width: (right - left) as u32,
+                height: (bottom - top) as u32,
+                title: w.title.clone(),
+                app: w.app.clone(),
+            })
+        })
+        .collect()
+}
+
+/// 是否有屏幕录制权限；没有时 macOS 只返回桌面背景
+#[cfg(target_os = "macos")]
+fn has_screen_capture_access() -> bool {
+    #[link(name = "CoreGraphics", kind = "framework")]
+    extern "C" {
+        fn CGPreflightScreenCaptureAccess() -> bool;
+    }
+    unsafe { CGPreflightScreenCaptureAccess() }
+}
+
+#[cfg(not(target_os = "macos"))]
+fn has_screen_capture_access() -> bool {
+    true
+}
+
+pub fn capture_all_screens(
+    cache_dir: std::path::PathBuf,
+) -> Result<Vec<(CaptureResult, CaptureFrame)>, AppError> {
+    if !has_screen_capture_access() {
+        return Err(AppError::PermissionDenied(
+            "Screen recording permission is required to capture the screen".to_string(),
+        ));
+    }
     let start = Instant::now();
-    let screens = Screen::all().map_err(|e| e.to_string())?;
+    let screens = Screen::all().map_err(|e| AppError::Other(e.to_string()))?;
     log::info!("Found {} screens", screens.len());
+    let windows = list_windows();
 
     // Use thread scope for parallel capture and encoding
     let results = std::thread::scope(|s| {
@@ -15,54 +625,64 @@ pub fn capture_all_screens(cache_dir: std::path::PathBuf) -> Result<Vec<CaptureR
 
         for screen in screens {
             let dir = cache_dir.clone();
-            handles.push(s.spawn(move || -> Result<CaptureResult, String> {
-                let capture_start = Instant::now();
-                let image = screen.capture().map_err(|e| e.to_string())?;
-
-                // Convert to raw bytes and encode
-                // Parallel encoding helps performance
-                let width = image.width();
-                let height = image.height();
-
-                let filename = format!(
-                    "screenshot_{}_{}.png",
-                    screen.display_info.id,
-                    chrono::Local::now().timestamp_millis()
-                );
-                let path = dir.join(filename);
-
-                // Use std::fs::File for buffered writing
-                let file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
-                let mut writer = std::io::BufWriter::new(file);
-
-                let encoder = image::codecs::png::PngEncoder::new(&mut writer);
-
-                // Using as_raw() to get the underlying Vec<u8>
-                encoder
-                    .write_image(
-                        image.as_raw(),
+            let windows = &windows;
+            handles.push(
+                s.spawn(move || -> Result<(CaptureResult, CaptureFrame), String> {
+                    let capture_start = Instant::now();
+                    let image = screen.capture().map_err(|e| e.to_string())?;
+
+                    // Convert to raw bytes and encode
+                    // Parallel encoding helps performance
+                    let width = image.width();
+                    let height = image.height();
+
+                    let filename = format!(
+                        "screenshot_{}_{}.png",
+                        screen.display_info.id,
+                        chrono::Local::now().timestamp_millis()
+                    );
+                    let path = dir.join(filename);
+
+                    // Use std::fs::File for buffered writing
+                    let file = std::fs::File::create(&path).map_err(|e| e.to_string())?;
+                    let mut writer = std::io::BufWriter::new(file);
+
+                    let encoder = image::codecs::png::PngEncoder::new(&mut writer);
+
+                    // Using as_raw() to get the underlying Vec<u8>
+                    encoder
+                        .write_image(
+                            image.as_raw(),
+                            width,
+                            height,
+                            image::ExtendedColorType::Rgba8,
+                        )
+                        .map_err(|e| e.to_string())?;
+
+                    log::info!(
+                        "Screen {} capture+save took {:?}",
+                        screen.display_info.id,
+                        capture_start.elapsed()
+                    );
+
+                    let capture = CaptureResult {
+                        id: screen.display_info.id,
+                        path: path.to_string_lossy().to_string(),
+                        x: screen.display_info.x,
+                        y: screen.display_info.y,
+                        width: width,
+                        height: height,
+                        scale_factor: screen.display_info.scale_factor as f64,
+                    };
+                    let frame = CaptureFrame {
                         width,
                         height,
-                        image::ExtendedColorType::Rgba8,
-                    )
-                    .map_err(|e| e.to_string())?;
-
-                log::info!(
-                    "Screen {} capture+save took {:?}",
-                    screen.display_info.id,
-                    capture_start.elapsed()
-                );
-
-                Ok(CaptureResult {
-                    id: screen.display_info.id,
-                    path: path.to_string_lossy().to_string(),
-                    x: screen.display_info.x,
-                    y: screen.display_info.y,
-                    width: width,
-                    height: height,
-                    scale_factor: screen.display_info.scale_factor as f64,
-                })
-            }));
+                        rgba: image.into_raw(),
+                        windows: windows_on_screen(windows, &capture),
+                    };
+                    Ok((capture, frame))
+                }),
+            );
         }
 
         let mut results = Vec::new();
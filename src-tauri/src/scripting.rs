@@ -0,0 +1,317 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use tauri::Manager;
+
+use crate::models::ScriptInfo;
+use crate::state::AppState;
+
+// 单次调用脚本的运行时间和资源上限
+const MAX_RUN_TIME: Duration = Duration::from_secs(2);
+const MAX_OPERATIONS: u64 = 5_000_000;
+const MAX_STRING_SIZE: usize = 16 * 1024 * 1024;
+const FETCH_TIMEOUT_SECS: u64 = 10;
+
+/// 已编译的脚本，http 表示用户允许它调用 fetch
+#[derive(Clone)]
+pub struct Script {
+    pub name: String,
+    pub ast: AST,
+    pub http: bool,
+}
+
+impl Script {
+    fn has_fn(&self, name: &str, params: usize) -> bool {
+        self.ast
+            .iter_functions()
+            .any(|f| f.name == name && f.params.len() == params)
+    }
+}
+
+/// 受限的脚本引擎：没有文件访问，限制运行时间和资源；fetch 只对授权脚本可用
+fn engine(script: &str, http: bool) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    engine.set_max_call_levels(32);
+    engine.set_max_string_size(MAX_STRING_SIZE);
+    engine.set_max_array_size(100_000);
+    engine.set_max_map_size(100_000);
+    let start = Instant::now();
+    engine.on_progress(move |_| (start.elapsed() > MAX_RUN_TIME).then_some(Dynamic::UNIT));
+
+    let name = script.to_string();
+    engine.on_print(move |text| log::info!("[script {}] {}", name, text));
+    let name = script.to_string();
+    engine.on_debug(move |text, _, _| log::debug!("[script {}] {}", name, text));
+
+    let name = script.to_string();
+    engine.register_fn(
+        "fetch",
+        move |url: &str| -> Result<String, Box<rhai::EvalAltResult>> {
+            if !http {
+                return Err(format!("Script {} is not allowed to use fetch", name).into());
+            }
+            fetch(url).map_err(|e| e.into())
+        },
+    );
+    engine
+}
+
+/// 脚本中的 fetch(url)：GET 请求并返回响应文本
+fn fetch(url: &str) -> Result<String, String> {
+    if !url.starts_with("http://") && !url.starts_with("https://") {
+        return Err("Only http(s) URLs can be fetched".to_string());
+    }
+    let url = url.to_string();
+    tauri::async_runtime::block_on(async move {
+        reqwest::Client::new()
+            .get(&url)
+            .timeout(Duration::from_secs(FETCH_TIMEOUT_SECS))
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .error_for_status()
+            .map_err(|e| e.to_string())?
+            .text()
+            .await
+            .map_err(|e| e.to_string())
+    })
+}
+
+pub fn compile(name: &str, source: &str, http: bool) -> Result<Script, String> {
+    let ast = engine(name, http)
+        .compile(source)
+        .map_err(|e| e.to_string())?;
+    Ok(Script {
+        name: name.to_string(),
+        ast,
+        http,
+    })
+}
+
+fn call(script: &Script, func: &str, args: impl rhai::FuncArgs) -> Result<Dynamic, String> {
+    engine(&script.name, script.http)
+        .call_fn::<Dynamic>(&mut Scope::new(), &script.ast, func, args)
+        .map_err(|e| format!("Script {}: {}", script.name, e))
+}
+
+/// 调用脚本的 func(text)，必须返回字符串
+pub fn run_text_fn(script: &Script, func: &str, text: &str) -> Result<String, String> {
+    if !script.has_fn(func, 1) {
+        return Err(format!(
+            "Script {} has no {}(text) function",
+            script.name, func
+        ));
+    }
+    call(script, func, (text.to_string(),))?
+        .into_string()
+        .map_err(|_| format!("Script {}: {} must return a string", script.name, func))
+}
+
+/// 调用脚本的 transform(text)
+pub fn run_transform(script: &Script, text: &str) -> Result<String, String> {
+    run_text_fn(script, "transform", text)
+}
+
+/// 依次调用各脚本的 on_capture(item)：返回字符串替换内容，返回 false 丢弃该条目，
+/// 其他返回值保持不变。出错的脚本只记录日志
+pub fn run_on_capture<'a>(
+    scripts: impl IntoIterator<Item = &'a Script>,
+    text: String,
+    source_app: Option<&str>,
+) -> Option<String> {
+    let mut text = text;
+    for script in scripts.into_iter().filter(|s| s.has_fn("on_capture", 1)) {
+        let mut item = Map::new();
+        item.insert("content".into(), text.clone().into());
+        item.insert(
+            "source_app".into(),
+            source_app.map_or(Dynamic::UNIT, |app| app.to_string().into()),
+        );
+        match call(script, "on_capture", (item,)) {
+            Ok(result) if result.as_bool() == Ok(false) => {
+                log::info!("Script {} dropped captured text", script.name);
+                return None;
+            }
+            Ok(result) if result.is_string() => {
+                text = result.into_string().unwrap_or(text);
+            }
+            Ok(_) => {}
+            Err(e) => log::error!("{}", e),
+        }
+    }
+    Some(text)
+}
+
+/// 用户脚本目录（应用数据目录下的 scripts/，*.rhai）
+pub fn scripts_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("scripts");
+    std::fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(dir)
+}
+
+fn script_files(dir: &Path) -> Vec<(String, PathBuf)> {
+    let mut files: Vec<(String, PathBuf)> = std::fs::read_dir(dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+                .filter_map(|path| {
+                    let name = path.file_stem()?.to_string_lossy().to_string();
+                    Some((name, path))
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+/// 全部脚本的编译结果（按文件名排序），以及编译时的 fetch 授权
+pub struct CompiledScripts {
+    http_allowed: Vec<String>,
+    scripts: Arc<Vec<(String, Result<Script, String>)>>,
+}
+
+/// 读取并编译全部脚本；结果缓存到脚本目录变化或 fetch 授权变化为止
+fn load(app: &tauri::AppHandle) -> Arc<Vec<(String, Result<Script, String>)>> {
+    let state = app.state::<AppState>();
+    let http_allowed = state.config.lock().unwrap().script_http_permissions.clone();
+    let mut cache = state.scripts.lock().unwrap();
+    if let Some(cached) = cache.as_ref().filter(|c| c.http_allowed == http_allowed) {
+        return cached.scripts.clone();
+    }
+
+    let Ok(dir) = scripts_dir(app) else {
+        return Arc::new(Vec::new());
+    };
+    let scripts: Vec<_> = script_files(&dir)
+        .into_iter()
+        .map(|(name, path)| {
+            let http = http_allowed.contains(&name);
+            let script = std::fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| compile(&name, &source, http));
+            if let Err(e) = &script {
+                log::error!("Failed to compile script {}: {}", name, e);
+            }
+            (name, script)
+        })
+        .collect();
+    let scripts = Arc::new(scripts);
+    *cache = Some(CompiledScripts {
+        http_allowed,
+        scripts: scripts.clone(),
+    });
+    scripts
+}
+
+/// 监听脚本目录，文件变化后丢弃已编译的脚本，下次使用时重新编译
+pub fn watch(app: &tauri::AppHandle) {
+    let Ok(dir) = scripts_dir(app) else {
+        return;
+    };
+    let handle = app.clone();
+    let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+        match res {
+            // 编译时读取文件也会产生访问事件
+            Ok(event) if matches!(event.kind, notify::EventKind::Access(_)) => return,
+            Ok(_) => {}
+            Err(e) => log::error!("Script watch error: {}", e),
+        }
+        *handle.state::<AppState>().scripts.lock().unwrap() = None;
+    });
+    let mut watcher: RecommendedWatcher = match watcher {
+        Ok(watcher) => watcher,
+        Err(e) => {
+            log::error!("Failed to create script watcher: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = watcher.watch(&dir, RecursiveMode::NonRecursive) {
+        log::error!("Failed to watch scripts folder {:?}: {}", dir, e);
+        return;
+    }
+    *app.state::<AppState>().script_watcher.lock().unwrap() = Some(watcher);
+}
+
+/// 脚本列表及各自的钩子、权限和编译错误
+pub fn list(app: &tauri::AppHandle) -> Vec<ScriptInfo> {
+    load(app)
+        .iter()
+        .map(|(name, script)| match script {
+            Ok(script) => ScriptInfo {
+                on_capture: script.has_fn("on_capture", 1),
+                transform: script.has_fn("transform", 1),
+                http: script.http,
+                name: name.clone(),
+                error: None,
+            },
+            Err(e) => ScriptInfo {
+                name: name.clone(),
+                on_capture: false,
+                transform: false,
+                http: false,
+                error: Some(e.clone()),
+            },
+        })
+        .collect()
+}
+
+/// 按名称加载单个脚本
+pub fn get(app: &tauri::AppHandle, name: &str) -> Result<Script, String> {
+    load(app)
+        .iter()
+        .find(|(n, _)| n == name)
+        .map(|(_, script)| script.clone())
+        .ok_or_else(|| format!("Script not found: {}", name))?
+}
+
+/// 是否开启了脚本且有脚本提供 on_capture 钩子；有则监听线程把文本交给后台任务处理
+pub fn has_capture_hooks(app: &tauri::AppHandle) -> bool {
+    if !app
+        .state::<AppState>()
+        .config
+        .lock()
+        .unwrap()
+        .scripts_enabled
+    {
+        return false;
+    }
+    load(app)
+        .iter()
+        .any(|(_, script)| script.as_ref().is_ok_and(|s| s.has_fn("on_capture", 1)))
+}
+
+/// 记录文本前调用脚本钩子，在后台任务中运行；未开启脚本时原样返回
+pub fn on_capture(
+    app: &tauri::AppHandle,
+    text: String,
+    source_app: Option<&str>,
+) -> Option<String> {
+    if !app
+        .state::<AppState>()
+        .config
+        .lock()
+        .unwrap()
+        .scripts_enabled
+    {
+        return Some(text);
+    }
+    let scripts = load(app);
+    run_on_capture(
+        scripts
+            .iter()
+            .filter_map(|(_, script)| script.as_ref().ok()),
+        text,
+        source_app,
+    )
+}
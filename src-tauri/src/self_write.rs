@@ -0,0 +1,34 @@
+use clipboard_rs::{Clipboard, ClipboardContent, ClipboardContext};
+use tauri::Manager;
+
+use crate::state::AppState;
+
+/// 应用自己写入剪贴板时附带的私有类型，内容为这次写入的令牌
+pub const MARKER_FORMAT: &str = "com.tipsxbase.clipboard.self-write";
+
+/// 给要写入的内容加上新的写入令牌；需在写入剪贴板之前调用，避免监听线程先看到变化
+pub fn stamp(app: &tauri::AppHandle, contents: &mut Vec<ClipboardContent>) {
+    let token = crate::share_server::generate_token();
+    contents.push(ClipboardContent::Other(
+        MARKER_FORMAT.to_string(),
+        token.clone().into_bytes(),
+    ));
+    *app.state::<AppState>().self_write_token.lock().unwrap() = Some(token);
+}
+
+/// 剪贴板当前内容是否带有最近一次写入的令牌，即由应用自己写入；适用于所有类型的内容
+pub fn is_self_write(app: &tauri::AppHandle) -> bool {
+    let Some(expected) = app
+        .state::<AppState>()
+        .self_write_token
+        .lock()
+        .unwrap()
+        .clone()
+    else {
+        return false;
+    };
+    ClipboardContext::new()
+        .ok()
+        .and_then(|ctx| ctx.get_buffer(MARKER_FORMAT).ok())
+        .is_some_and(|data| data == expected.as_bytes())
+}
@@ -0,0 +1,238 @@
+use std::path::Path;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+use crate::models::{AppConfig, SettingsImportResult};
+use crate::state::AppState;
+
+const SETTINGS_FORMAT: &str = "clipboard-settings";
+const SETTINGS_VERSION: u32 = 1;
+
+/// 导出的收藏夹，只有名称和设置，不含条目
+#[derive(Serialize, Deserialize)]
+struct SettingsCollection {
+    name: String,
+    #[serde(default)]
+    exempt_from_retention: bool,
+}
+
+/// 设置文件：配置（含规则和快捷键）和收藏夹列表
+#[derive(Serialize, Deserialize)]
+struct SettingsFile {
+    format: String,
+    version: u32,
+    exported_at: String,
+    config: AppConfig,
+    #[serde(default)]
+    collections: Vec<SettingsCollection>,
+    // Git 同步的收藏夹按名称记录，导入时映射到本机的收藏夹 id
+    #[serde(default)]
+    git_sync_collection: Option<String>,
+}
+
+/// 用 local 的值覆盖 config 中会把剪贴板数据发往外部、放宽隐私保护或改变文件读写位置的设置，
+/// 返回是否有不同
+fn keep_protected_settings(config: &mut AppConfig, local: &AppConfig) -> bool {
+    fn keep<T: PartialEq + Clone>(field: &mut T, local: &T) -> bool {
+        if field == local {
+            return false;
+        }
+        *field = local.clone();
+        true
+    }
+
+    let mut changed = false;
+    changed |= keep(&mut config.ai_endpoint, &local.ai_endpoint);
+    changed |= keep(&mut config.ai_model, &local.ai_model);
+    changed |= keep(&mut config.git_sync_repo, &local.git_sync_repo);
+    changed |= keep(
+        &mut config.share_server_enabled,
+        &local.share_server_enabled,
+    );
+    changed |= keep(&mut config.share_server_port, &local.share_server_port);
+    changed |= keep(&mut config.streamdeck_enabled, &local.streamdeck_enabled);
+    changed |= keep(&mut config.streamdeck_port, &local.streamdeck_port);
+    changed |= keep(&mut config.mqtt_enabled, &local.mqtt_enabled);
+    changed |= keep(&mut config.mqtt_host, &local.mqtt_host);
+    changed |= keep(&mut config.mqtt_port, &local.mqtt_port);
+    changed |= keep(&mut config.mqtt_username, &local.mqtt_username);
+    changed |= keep(&mut config.mqtt_topic, &local.mqtt_topic);
+    changed |= keep(&mut config.mqtt_allowlist, &local.mqtt_allowlist);
+    changed |= keep(&mut config.mqtt_tls, &local.mqtt_tls);
+    changed |= keep(
+        &mut config.mqtt_publish_previews,
+        &local.mqtt_publish_previews,
+    );
+    changed |= keep(&mut config.scripts_enabled, &local.scripts_enabled);
+    changed |= keep(
+        &mut config.script_http_permissions,
+        &local.script_http_permissions,
+    );
+    changed |= keep(&mut config.sensitive_apps, &local.sensitive_apps);
+    changed |= keep(&mut config.terminal_apps, &local.terminal_apps);
+    changed |= keep(&mut config.routing_rules, &local.routing_rules);
+    changed |= keep(&mut config.watch_folders, &local.watch_folders);
+    changed |= keep(&mut config.capture_save_dir, &local.capture_save_dir);
+    changed
+}
+
+/// 去掉 URL 中的用户信息（user:token@）；scp 形式的 git@host:path 没有密钥，保持不变
+fn strip_userinfo(url: &str) -> String {
+    let Some((scheme, rest)) = url.split_once("://") else {
+        return url.to_string();
+    };
+    let authority = &rest[..rest.find('/').unwrap_or(rest.len())];
+    let Some(at) = authority.rfind('@') else {
+        return url.to_string();
+    };
+    let is_http = scheme.eq_ignore_ascii_case("http") || scheme.eq_ignore_ascii_case("https");
+    if !is_http && !authority[..at].contains(':') {
+        return url.to_string();
+    }
+    format!("{}://{}", scheme, &rest[at + 1..])
+}
+
+/// 导出设置为 JSON；密钥、令牌、Webhook 和本机插件不会导出，便于分享给他人
+pub fn export_settings(app: &tauri::AppHandle, path: &Path) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    let mut config = state.config.lock().unwrap().clone();
+    config.ai_api_key.clear();
+    config.mqtt_password.clear();
+    config.mqtt_shared_secret.clear();
+    config.share_token.clear();
+    config.streamdeck_token.clear();
+    config.api_tokens.clear();
+    config.enabled_plugins.clear();
+    // Webhook URLs often act as bearer secrets
+    config.webhooks.clear();
+    config.git_sync_repo = strip_userinfo(&config.git_sync_repo);
+
+    let collections = state.db.get_collections().map_err(|e| e.to_string())?;
+    let git_sync_collection = config.git_sync_collection_id.and_then(|id| {
+        collections
+            .iter()
+            .find(|c| c.id == id)
+            .map(|c| c.name.clone())
+    });
+    config.git_sync_collection_id = None;
+
+    let file = SettingsFile {
+        format: SETTINGS_FORMAT.to_string(),
+        version: SETTINGS_VERSION,
+        exported_at: Local::now().to_rfc3339(),
+        config,
+        collections: collections
+            .into_iter()
+            .map(|c| SettingsCollection {
+                name: c.name,
+                exempt_from_retention: c.exempt_from_retention,
+            })
+            .collect(),
+        git_sync_collection,
+    };
+    let json = serde_json::to_string_pretty(&file).map_err(|e| e.to_string())?;
+    std::fs::write(path, json).map_err(|e| e.to_string())?;
+    log::info!("Exported settings to {:?}", path);
+    Ok(())
+}
+
+/// 导入设置：替换当前配置，按名称创建缺少的收藏夹；文件中为空的密钥沿用本机的值。
+/// Webhook、令牌等始终保留本机的值；AI、同步、MQTT 等网络设置，以及敏感应用、终端应用、
+/// 路由规则、监听目录和截图目录只在用户确认（allow_network）后导入
+pub fn import_settings(
+    app: &tauri::AppHandle,
+    path: &Path,
+    allow_network: bool,
+) -> Result<SettingsImportResult, String> {
+    let content = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let file: SettingsFile =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid settings file: {}", e))?;
+    if file.format != SETTINGS_FORMAT {
+        return Err(format!("Not a settings file: {}", file.format));
+    }
+    if file.version > SETTINGS_VERSION {
+        return Err(format!(
+            "Settings file version {} is newer than supported ({})",
+            file.version, SETTINGS_VERSION
+        ));
+    }
+
+    let state = app.state::<AppState>();
+    let current = state.config.lock().unwrap().clone();
+    let mut config = file.config;
+    config.shortcut = crate::shortcut::normalize(&config.shortcut)?;
+    config.clipboard_back_shortcut =
+        crate::shortcut::normalize_optional(&config.clipboard_back_shortcut)?;
+    config.clipboard_forward_shortcut =
+        crate::shortcut::normalize_optional(&config.clipboard_forward_shortcut)?;
+    config.cycle_paste_shortcut =
+        crate::shortcut::normalize_optional(&config.cycle_paste_shortcut)?;
+    crate::commands::validate_config(&config)?;
+    let network_settings_skipped = !allow_network && keep_protected_settings(&mut config, &current);
+    if network_settings_skipped {
+        log::warn!(
+            "Settings import left network and privacy settings unchanged, confirmation required"
+        );
+    }
+    // 本机的密钥只在服务端不变时沿用，避免发给文件指定的其他地址
+    if config.ai_api_key.is_empty() && config.ai_endpoint == current.ai_endpoint {
+        config.ai_api_key = current.ai_api_key.clone();
+    }
+    if config.mqtt_password.is_empty() && config.mqtt_host == current.mqtt_host {
+        config.mqtt_password = current.mqtt_password.clone();
+    }
+    if !allow_network || config.mqtt_shared_secret.is_empty() {
+        config.mqtt_shared_secret = current.mqtt_shared_secret.clone();
+    }
+    config.webhooks = current.webhooks;
+    config.share_token = current.share_token;
+    config.streamdeck_token = current.streamdeck_token;
+    config.api_tokens = current.api_tokens;
+    config.enabled_plugins = current.enabled_plugins;
+    config.presentation_mode = current.presentation_mode;
+    config.device_name = current.device_name;
+
+    let mut collections = state.db.get_collections().map_err(|e| e.to_string())?;
+    let mut created = 0;
+    for imported in &file.collections {
+        let id = match collections.iter().find(|c| c.name == imported.name) {
+            Some(existing) => existing.id,
+            None => {
+                let collection = state
+                    .db
+                    .create_collection(imported.name.clone())
+                    .map_err(|e| e.to_string())?;
+                created += 1;
+                let id = collection.id;
+                collections.push(collection);
+                id
+            }
+        };
+        if imported.exempt_from_retention {
+            state
+                .db
+                .set_collection_retention_exempt(id, true)
+                .map_err(|e| e.to_string())?;
+        }
+    }
+    if allow_network {
+        config.git_sync_collection_id = file
+            .git_sync_collection
+            .and_then(|name| collections.iter().find(|c| c.name == name).map(|c| c.id));
+    } else {
+        config.git_sync_collection_id = current.git_sync_collection_id;
+    }
+
+    crate::commands::apply_config(app, &state, config)?;
+    log::info!(
+        "Imported settings from {:?}, created {} collections",
+        path,
+        created
+    );
+    Ok(SettingsImportResult {
+        collections_created: created,
+        network_settings_skipped,
+    })
+}
@@ -0,0 +1,140 @@
+use std::io::{Read, Write};
+use std::net::{TcpStream, UdpSocket};
+use std::time::Duration;
+
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::OsRng;
+use tauri::Manager;
+
+use crate::models::{ClipboardItem, HistoryFilter};
+use crate::state::AppState;
+
+const MAX_ITEMS: usize = 50;
+
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 本机在局域网中的地址（通过 UDP connect 选出默认出口网卡，不会真正发包）
+pub fn lan_ip() -> Option<String> {
+    let socket = UdpSocket::bind("0.0.0.0:0").ok()?;
+    socket.connect("8.8.8.8:80").ok()?;
+    Some(socket.local_addr().ok()?.ip().to_string())
+}
+
+pub fn share_url(port: u16, token: &str) -> String {
+    let host = lan_ip().unwrap_or_else(|| "127.0.0.1".to_string());
+    format!("http://{}:{}/?token={}", host, port, token)
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// 逐字节比较，耗时不随匹配长度变化
+pub fn token_matches(given: &str, expected: &str) -> bool {
+    given.len() == expected.len()
+        && given
+            .bytes()
+            .zip(expected.bytes())
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn render_page(items: &[ClipboardItem]) -> String {
+    let mut list = String::new();
+    for item in items {
+        let content = escape_html(&item.content);
+        let body = if item.data_type == "url" {
+            format!("<a href=\"{0}\">{0}</a>", content)
+        } else {
+            format!("<pre>{}</pre>", content)
+        };
+        list.push_str(&format!(
+            "<li>{}<small>{} · {}</small></li>",
+            body,
+            escape_html(&item.timestamp),
+            escape_html(item.source_app.as_deref().unwrap_or("")),
+        ));
+    }
+    if items.is_empty() {
+        list.push_str("<li>No items</li>");
+    }
+    format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta name=\"viewport\" content=\"width=device-width, initial-scale=1\"><title>Clipboard</title><style>body{{font-family:-apple-system,sans-serif;margin:0;padding:12px;background:#f5f5f5}}ul{{list-style:none;padding:0;margin:0}}li{{background:#fff;border-radius:8px;padding:10px 12px;margin-bottom:8px;word-break:break-all}}pre{{white-space:pre-wrap;margin:0;font-family:inherit;user-select:all;-webkit-user-select:all}}small{{display:block;color:#888;margin-top:6px}}</style></head><body><ul>{}</ul></body></html>",
+        list
+    )
+}
+
+fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nCache-Control: no-store\r\nReferrer-Policy: no-referrer\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes());
+}
+
+fn handle(app: &tauri::AppHandle, mut stream: TcpStream) {
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+    let mut buf = [0u8; 4096];
+    let Ok(n) = stream.read(&mut buf) else {
+        return;
+    };
+    let request = String::from_utf8_lossy(&buf[..n]);
+    let mut parts = request.lines().next().unwrap_or("").split_whitespace();
+    let (method, target) = (parts.next().unwrap_or(""), parts.next().unwrap_or(""));
+    if method != "GET" {
+        return respond(&mut stream, "405 Method Not Allowed", "");
+    }
+
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if path != "/" {
+        return respond(&mut stream, "404 Not Found", "");
+    }
+    let token = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("token="))
+        .unwrap_or("");
+
+    let state = app.state::<AppState>();
+    let expected = state.config.lock().unwrap().share_token.clone();
+    let authorized = (!expected.is_empty() && token_matches(token, &expected))
+        || crate::api_tokens::authorize(app, token).is_some();
+    if !authorized {
+        return respond(&mut stream, "403 Forbidden", "");
+    }
+
+    let items: Vec<ClipboardItem> = state
+        .db
+        .get_history(1, MAX_ITEMS, &HistoryFilter::default())
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|item| item.kind == "text" && !item.is_sensitive)
+        .collect();
+    let items = crate::utils::present_items(&state, items);
+    respond(&mut stream, "200 OK", &render_page(&items));
+}
+
+/// 按配置（重新）启动共享页面服务，关闭时只让旧线程退出
+pub fn restart(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let (enabled, port) = {
+        let config = state.config.lock().unwrap();
+        (config.share_server_enabled, config.share_server_port)
+    };
+    let app = app.clone();
+    crate::listener::restart(
+        &state.share_server_generation,
+        enabled,
+        "Share server",
+        ("0.0.0.0", port),
+        move |stream| handle(&app, stream),
+    );
+}
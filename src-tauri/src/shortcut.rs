@@ -0,0 +1,267 @@
+use std::time::Duration;
+
+use tauri::{Emitter, Manager};
+use tauri_plugin_global_shortcut::{Modifiers, Shortcut};
+
+use crate::state::AppState;
+
+// 等待用户按下快捷键的时间
+const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 修饰键的规范名称，接受常见别名
+fn modifier_name(token: &str) -> Option<&'static str> {
+    match token.to_ascii_lowercase().as_str() {
+        "commandorcontrol" | "cmdorctrl" | "commandorctrl" | "cmdorcontrol" => {
+            Some("CommandOrControl")
+        }
+        "control" | "ctrl" => Some("Control"),
+        "alt" | "option" => Some("Alt"),
+        "shift" => Some("Shift"),
+        "super" | "command" | "cmd" | "meta" | "win" => Some("Super"),
+        _ => None,
+    }
+}
+
+/// ЙЦУКЕН（俄语、乌克兰语）布局下字母对应的物理按键
+fn cyrillic_code(c: char) -> Option<&'static str> {
+    Some(match c {
+        'й' => "KeyQ",
+        'ц' => "KeyW",
+        'у' => "KeyE",
+        'к' => "KeyR",
+        'е' => "KeyT",
+        'н' => "KeyY",
+        'г' => "KeyU",
+        'ш' => "KeyI",
+        'щ' => "KeyO",
+        'з' => "KeyP",
+        'х' => "BracketLeft",
+        'ъ' | 'ї' => "BracketRight",
+        'ф' => "KeyA",
+        'ы' | 'і' => "KeyS",
+        'в' => "KeyD",
+        'а' => "KeyF",
+        'п' => "KeyG",
+        'р' => "KeyH",
+        'о' => "KeyJ",
+        'л' => "KeyK",
+        'д' => "KeyL",
+        'ж' => "Semicolon",
+        'э' | 'є' => "Quote",
+        'я' => "KeyZ",
+        'ч' => "KeyX",
+        'с' => "KeyC",
+        'м' => "KeyV",
+        'и' => "KeyB",
+        'т' => "KeyN",
+        'ь' => "KeyM",
+        'б' => "Comma",
+        'ю' => "Period",
+        'ё' => "Backquote",
+        'ґ' => "Backslash",
+        _ => return None,
+    })
+}
+
+/// 把按键名称转换为与布局无关的物理按键代码（W3C KeyboardEvent.code）
+fn key_code(token: &str) -> Result<String, String> {
+    let mut chars = token.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        let code = match lower {
+            'a'..='z' => format!("Key{}", lower.to_ascii_uppercase()),
+            '0'..='9' => format!("Digit{}", lower),
+            '-' => "Minus".to_string(),
+            '=' => "Equal".to_string(),
+            '[' => "BracketLeft".to_string(),
+            ']' => "BracketRight".to_string(),
+            '\\' => "Backslash".to_string(),
+            ';' => "Semicolon".to_string(),
+            '\'' => "Quote".to_string(),
+            ',' => "Comma".to_string(),
+            '.' => "Period".to_string(),
+            '/' => "Slash".to_string(),
+            '`' => "Backquote".to_string(),
+            ' ' => "Space".to_string(),
+            _ => cyrillic_code(lower)
+                .map(str::to_string)
+                .ok_or_else(|| format!("Unsupported key: {}", token))?,
+        };
+        return Ok(code);
+    }
+    let lower = token.to_ascii_lowercase();
+    let code = match lower.as_str() {
+        "up" | "arrowup" => "ArrowUp".to_string(),
+        "down" | "arrowdown" => "ArrowDown".to_string(),
+        "left" | "arrowleft" => "ArrowLeft".to_string(),
+        "right" | "arrowright" => "ArrowRight".to_string(),
+        "return" | "enter" => "Enter".to_string(),
+        "esc" | "escape" => "Escape".to_string(),
+        "space" => "Space".to_string(),
+        "tab" => "Tab".to_string(),
+        "backspace" => "Backspace".to_string(),
+        "delete" | "del" => "Delete".to_string(),
+        "home" => "Home".to_string(),
+        "end" => "End".to_string(),
+        "pageup" => "PageUp".to_string(),
+        "pagedown" => "PageDown".to_string(),
+        "insert" => "Insert".to_string(),
+        _ if lower.starts_with("key") && token.len() == 4 => key_code(&token[3..])?,
+        _ if lower.starts_with("digit") && token.len() == 6 => key_code(&token[5..])?,
+        _ if lower.starts_with('f') && lower[1..].parse::<u8>().is_ok_and(|n| n <= 24) => {
+            lower.to_ascii_uppercase()
+        }
+        // 其他已是代码形式的名称（Minus、Numpad1 等）交给解析校验
+        _ => token.to_string(),
+    };
+    Ok(code)
+}
+
+/// 可以留空的快捷键，空字符串表示不注册
+pub fn normalize_optional(accelerator: &str) -> Result<String, String> {
+    if accelerator.trim().is_empty() {
+        Ok(String::new())
+    } else {
+        normalize(accelerator)
+    }
+}
+
+/// 把加速键规范为 "修饰键+物理按键代码" 的形式，同一个组合在不同键盘布局下得到相同结果
+pub fn normalize(accelerator: &str) -> Result<String, String> {
+    let tokens: Vec<&str> = accelerator
+        .split('+')
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect();
+    let (key, modifiers) = match tokens.split_last() {
+        Some((key, modifiers)) if modifier_name(key).is_none() => (key_code(key)?, modifiers),
+        _ => return Err(format!("Shortcut has no key: {}", accelerator)),
+    };
+
+    let mut names: Vec<&str> = Vec::new();
+    for token in modifiers {
+        let name = modifier_name(token).ok_or_else(|| format!("Unknown modifier: {}", token))?;
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+    // 固定修饰键顺序
+    let order = ["CommandOrControl", "Control", "Alt", "Shift", "Super"];
+    names.sort_by_key(|name| order.iter().position(|o| o == name));
+
+    let normalized = names
+        .into_iter()
+        .chain(std::iter::once(key.as_str()))
+        .collect::<Vec<_>>()
+        .join("+");
+    normalized
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut {}: {}", accelerator, e))?;
+    Ok(normalized)
+}
+
+/// 解析加速键，先规范为物理按键代码
+pub fn parse(accelerator: &str) -> Result<Shortcut, String> {
+    normalize(accelerator)?
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid shortcut {}: {}", accelerator, e))
+}
+
+/// 已注册快捷键的规范表示
+fn describe(shortcut: &Shortcut) -> String {
+    let mut parts = Vec::new();
+    for (modifier, name) in [
+        (Modifiers::CONTROL, "Control"),
+        (Modifiers::ALT, "Alt"),
+        (Modifiers::SHIFT, "Shift"),
+        (Modifiers::SUPER, "Super"),
+    ] {
+        if shortcut.mods.contains(modifier) {
+            parts.push(name.to_string());
+        }
+    }
+    parts.push(shortcut.key.to_string());
+    parts.join("+")
+}
+
+/// 等待用户按下下一个组合键，返回规范后的加速键；超时或被新的录制取代时返回错误
+pub async fn capture(app: &tauri::AppHandle) -> Result<String, String> {
+    let (tx, rx) = tokio::sync::oneshot::channel();
+    *app.state::<AppState>().shortcut_capture.lock().unwrap() = Some(tx);
+    let _ = app.emit("shortcut-capture-started", ());
+    let result = tokio::time::timeout(CAPTURE_TIMEOUT, rx).await;
+    let _ = app.emit("shortcut-capture-finished", ());
+    match result {
+        Ok(Ok(accelerator)) => Ok(accelerator),
+        Ok(Err(_)) => Err("Shortcut capture cancelled".to_string()),
+        Err(_) => {
+            app.state::<AppState>()
+                .shortcut_capture
+                .lock()
+                .unwrap()
+                .take();
+            Err("Shortcut capture timed out".to_string())
+        }
+    }
+}
+
+/// 前端按键事件：code 为 KeyboardEvent.code，只按下修饰键时继续等待
+pub fn record_key(
+    app: &tauri::AppHandle,
+    code: &str,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+    meta: bool,
+) -> Result<(), String> {
+    if code.is_empty()
+        || ["Control", "Alt", "Shift", "Meta", "OS"]
+            .iter()
+            .any(|m| code.starts_with(m))
+    {
+        return Ok(());
+    }
+    let mut parts = Vec::new();
+    if meta {
+        parts.push("CommandOrControl");
+    }
+    if ctrl {
+        parts.push("Control");
+    }
+    if alt {
+        parts.push("Alt");
+    }
+    if shift {
+        parts.push("Shift");
+    }
+    parts.push(code);
+    let accelerator = normalize(&parts.join("+"))?;
+    finish(app, accelerator);
+    Ok(())
+}
+
+/// 录制期间按下已注册的全局快捷键时，系统不会把按键交给窗口，由全局快捷键回调完成录制
+pub fn finish_with_shortcut(app: &tauri::AppHandle, shortcut: &Shortcut) -> bool {
+    let pending = app
+        .state::<AppState>()
+        .shortcut_capture
+        .lock()
+        .unwrap()
+        .is_some();
+    if pending {
+        finish(app, describe(shortcut));
+    }
+    pending
+}
+
+fn finish(app: &tauri::AppHandle, accelerator: String) {
+    if let Some(tx) = app
+        .state::<AppState>()
+        .shortcut_capture
+        .lock()
+        .unwrap()
+        .take()
+    {
+        let _ = tx.send(accelerator);
+    }
+}
@@ -0,0 +1,41 @@
+use tauri::Manager;
+
+use crate::state::AppState;
+
+/// 退出前停止后台工作、保存窗口位置，并把数据库写入落盘；粘贴栈在每次改动时已写入数据库
+pub fn run(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    {
+        let mut shutting_down = state.shutting_down.lock().unwrap();
+        if *shutting_down {
+            return;
+        }
+        *shutting_down = true;
+    }
+    log::info!("Shutting down");
+
+    // 停止后台任务、目录监听、共享服务、MQTT 连接和音频播放
+    state.jobs.cancel_all(app);
+    crate::audio::stop(app);
+    if let Ok(mut watcher) = state.folder_watcher.lock() {
+        *watcher = None;
+    }
+    if let Ok(mut generation) = state.share_server_generation.lock() {
+        *generation += 1;
+    }
+    if let Ok(mut generation) = state.streamdeck_generation.lock() {
+        *generation += 1;
+    }
+    crate::mqtt::stop(app);
+
+    crate::window_state::save(app);
+
+    // 拿到连接锁即说明监听线程没有写到一半
+    if let Err(e) = state.db.flush() {
+        log::error!("Failed to flush database: {}", e);
+    }
+}
+
+pub fn is_shutting_down(app: &tauri::AppHandle) -> bool {
+    *app.state::<AppState>().shutting_down.lock().unwrap()
+}
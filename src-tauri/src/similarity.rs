@@ -0,0 +1,97 @@
+const SHINGLE_SIZE: usize = 3;
+
+/// FNV-1a 哈希，跨版本稳定，可持久化
+pub fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+    bytes.into_iter().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+fn hash_shingle(shingle: &[char]) -> u64 {
+    fnv1a(shingle.iter().collect::<String>().into_bytes())
+}
+
+/// 文本的 64 位 SimHash，基于字符 3-gram，对大小写和空白不敏感
+pub fn simhash(text: &str) -> Option<u64> {
+    let normalized: Vec<char> = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+        .chars()
+        .collect();
+    if normalized.is_empty() {
+        return None;
+    }
+
+    let mut weights = [0i64; 64];
+    let shingles: Vec<&[char]> = if normalized.len() < SHINGLE_SIZE {
+        vec![normalized.as_slice()]
+    } else {
+        normalized.windows(SHINGLE_SIZE).collect()
+    };
+    for shingle in shingles {
+        let hash = hash_shingle(shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    let mut fingerprint = 0u64;
+    for (bit, weight) in weights.iter().enumerate() {
+        if *weight > 0 {
+            fingerprint |= 1 << bit;
+        }
+    }
+    Some(fingerprint)
+}
+
+/// 图片的差值感知哈希（dHash）：缩放到 9x8 灰度图后比较相邻像素
+pub fn dhash(image_path: &str) -> Option<u64> {
+    let img = image::open(image_path).ok()?;
+    let small = img
+        .grayscale()
+        .resize_exact(9, 8, image::imageops::FilterType::Triangle)
+        .to_luma8();
+
+    let mut fingerprint = 0u64;
+    for y in 0..8 {
+        for x in 0..8 {
+            let left = small.get_pixel(x, y)[0];
+            let right = small.get_pixel(x + 1, y)[0];
+            fingerprint <<= 1;
+            if left > right {
+                fingerprint |= 1;
+            }
+        }
+    }
+    Some(fingerprint)
+}
+
+/// 忽略首尾空白、行尾空白和换行符风格后的文本哈希
+pub fn whitespace_hash(text: &str) -> u64 {
+    let normalized = text
+        .trim()
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<_>>()
+        .join("\n");
+    fnv1a(normalized.bytes())
+}
+
+pub fn fingerprint(kind: &str, content: &str) -> Option<u64> {
+    match kind {
+        "text" => simhash(content),
+        "image" => dhash(content),
+        _ => None,
+    }
+}
+
+/// 两个指纹的相似度，1.0 表示完全一致
+pub fn similarity(a: u64, b: u64) -> f64 {
+    1.0 - (a ^ b).count_ones() as f64 / 64.0
+}
@@ -1,19 +1,97 @@
+use crate::cache::ContentCache;
+use crate::calc::ExchangeRates;
+use crate::crypto::Crypto;
 use crate::db::Database;
-use crate::models::{AppConfig, CaptureResult, ClipboardItem};
+use crate::jobs::JobQueue;
+use crate::models::{AppConfig, CaptureResult, ClipboardItem, CustomClassifier};
+use crate::popup::FocusTarget;
+use crate::screenshot::CaptureFrame;
+use crate::scripting::CompiledScripts;
+use crate::window_state::WindowGeometry;
+use notify::RecommendedWatcher;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use tauri::menu::MenuItem;
+use std::time::Instant;
+use tauri::menu::{MenuItem, Submenu};
 use tauri::Wry;
+use tauri_plugin_global_shortcut::Shortcut;
 
 pub struct AppState {
     pub db: Arc<Database>,
+    // 加密配置文件中的密钥，与数据库共用同一把密钥
+    pub crypto: Arc<Crypto>,
     pub config_path: PathBuf,
     pub config: Arc<Mutex<AppConfig>>,
     pub is_paused: Arc<Mutex<bool>>,
-    pub last_app_change: Arc<Mutex<Option<String>>>,
-    pub last_app_image_change: Arc<Mutex<Option<Vec<u8>>>>,
-    pub last_app_file_change: Arc<Mutex<Option<Vec<String>>>>,
+    // 应用最近一次写入剪贴板时附带的令牌，监听线程据此忽略随之而来的变化
+    pub self_write_token: Arc<Mutex<Option<String>>>,
     pub paste_stack: Arc<Mutex<Vec<ClipboardItem>>>,
     pub current_captures: Arc<Mutex<Option<Vec<CaptureResult>>>>,
+    // 按屏幕 id 保存本次截图的像素和窗口位置
+    pub capture_frames: Arc<Mutex<HashMap<u32, CaptureFrame>>>,
+    // 开始截图时处于前台的应用，用于文件名模板
+    pub capture_source_app: Arc<Mutex<Option<String>>>,
     pub pause_item: Arc<Mutex<Option<MenuItem<Wry>>>>,
+    pub urls_menu: Arc<Mutex<Option<Submenu<Wry>>>>,
+    pub quick_paste_menu: Arc<Mutex<Option<Submenu<Wry>>>>,
+    // 已注册的 Quick Paste 快捷键及对应的条目 id
+    pub quick_paste_shortcuts: Arc<Mutex<HashMap<Shortcut, i64>>>,
+    pub exchange_rates: Arc<Mutex<Option<ExchangeRates>>>,
+    // 弹出窗口显示前处于前台的应用
+    pub last_active_app: Arc<Mutex<Option<String>>>,
+    pub last_active_app_path: Arc<Mutex<Option<String>>>,
+    pub last_find_pasteboard_write: Arc<Mutex<Option<i64>>>,
+    // OCR、缩略图预热等后台任务共用的队列
+    pub jobs: Arc<JobQueue>,
+    // 正在退出，监听线程不再写入
+    pub shutting_down: Arc<Mutex<bool>>,
+    // 各窗口最近的位置和大小，以及是否已安排写入文件
+    pub window_geometry: Arc<Mutex<HashMap<String, WindowGeometry>>>,
+    pub window_state_save_pending: Arc<Mutex<bool>>,
+    // 是否处于省电模式，由 power 模块定期更新
+    pub power_saving: Arc<Mutex<bool>>,
+    // 已安排发送 clipboard-update 事件，窗口内的其他更新合并到这一次
+    pub update_event_pending: Arc<Mutex<bool>>,
+    // 固定后弹出窗口失去焦点时不再自动隐藏
+    pub popup_pinned: Arc<Mutex<bool>>,
+    // 弹出窗口显示前的前台应用，隐藏时把焦点还给它
+    pub popup_focus_target: Arc<Mutex<Option<FocusTarget>>>,
+    // 监听目录的文件监听器，替换或置空即停止旧的监听
+    pub folder_watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    // 已编译的用户脚本，脚本目录变化时置空；以及脚本目录的监听器
+    pub scripts: Arc<Mutex<Option<CompiledScripts>>>,
+    pub script_watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
+    // 局域网共享页面服务的代数，变化后旧的服务线程退出
+    pub share_server_generation: Arc<Mutex<u64>>,
+    // 已启用插件提供的分类器，插件启用状态变化或重新扫描时更新
+    pub plugin_classifiers: Arc<Mutex<Vec<CustomClassifier>>>,
+    // Stream Deck WebSocket 服务的代数
+    pub streamdeck_generation: Arc<Mutex<u64>>,
+    // 是否已经发出过存储配额提醒
+    pub storage_warning_sent: Arc<Mutex<bool>>,
+    // 最近一次统计的图片目录大小及统计时间
+    pub images_size: Arc<Mutex<Option<(Instant, u64)>>>,
+    // Git 同步进行中时持有
+    pub git_sync_lock: Arc<Mutex<()>>,
+    // 最近读取的条目全文和缩略图
+    pub content_cache: Arc<Mutex<ContentCache>>,
+    // MQTT 连接的代数和用于发布的客户端，代数变化后旧连接线程退出
+    pub mqtt_generation: Arc<Mutex<u64>>,
+    pub mqtt_client: Arc<Mutex<Option<rumqttc::Client>>>,
+    // 正在播放音频条目的进程
+    pub audio_player: Arc<Mutex<Option<std::process::Child>>>,
+    // 正在录制快捷键时，用于返回录到的组合键
+    pub shortcut_capture: Arc<Mutex<Option<tokio::sync::oneshot::Sender<String>>>>,
+    // 按住快捷键显示弹出窗口的开始时间，松开或取消后清空
+    pub quick_peek: Arc<Mutex<Option<std::time::Instant>>>,
+    // 跳转列表 / Dock 菜单当前显示的条目（id 和标题），没有变化时不重建
+    pub jump_list_items: Arc<Mutex<Vec<(i64, String)>>>,
+    // 剪贴板后退的步数，0 为最新一条；复制新内容后归零
+    pub clipboard_cursor: Arc<Mutex<usize>>,
+    // 已注册的剪贴板后退 / 前进快捷键
+    pub clipboard_cursor_shortcuts: Arc<Mutex<Vec<(Shortcut, crate::clipboard_cursor::Direction)>>>,
+    // 已注册的循环粘贴快捷键，以及上次循环粘贴的条目序号和时间
+    pub cycle_paste_shortcut: Arc<Mutex<Option<Shortcut>>>,
+    pub cycle_paste: Arc<Mutex<Option<(usize, std::time::Instant)>>>,
 }
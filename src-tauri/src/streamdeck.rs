@@ -0,0 +1,181 @@
+use std::net::TcpStream;
+
+use serde::Deserialize;
+use serde_json::json;
+use tauri::Manager;
+use tungstenite::Message;
+
+use crate::api_tokens::Grant;
+use crate::models::ClipboardItem;
+use crate::state::AppState;
+
+// Stream Deck 按键上显示的置顶条目数量上限和图标尺寸
+const MAX_SLOTS: usize = 32;
+const ICON_SIZE: u32 = 144;
+const LABEL_CHARS: usize = 24;
+
+/// 客户端请求：每条消息都带 token，无需建立会话
+#[derive(Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(default)]
+    token: String,
+    action: String,
+    #[serde(default)]
+    slot: Option<usize>,
+}
+
+/// 置顶条目按 id 排序得到稳定的槽位，槽位从 1 开始；令牌不能访问敏感条目时跳过它们
+fn pinned_slots(app: &tauri::AppHandle, grant: Grant) -> Result<Vec<ClipboardItem>, String> {
+    let mut items = app
+        .state::<AppState>()
+        .db
+        .get_quick_paste_items(MAX_SLOTS)
+        .map_err(|e| e.to_string())?;
+    items.retain(|item| grant.sensitive || !item.is_sensitive);
+    items.sort_by_key(|item| item.id);
+    Ok(items)
+}
+
+fn label(item: &ClipboardItem) -> String {
+    if let Some(note) = item.note.as_deref().filter(|n| !n.trim().is_empty()) {
+        return note.trim().to_string();
+    }
+    if item.is_sensitive {
+        return "••••••".to_string();
+    }
+    match item.kind.as_str() {
+        "text" => item
+            .content
+            .lines()
+            .find(|l| !l.trim().is_empty())
+            .unwrap_or_default()
+            .trim()
+            .chars()
+            .take(LABEL_CHARS)
+            .collect(),
+        kind => kind.to_string(),
+    }
+}
+
+fn list_pinned(app: &tauri::AppHandle, grant: Grant) -> Result<serde_json::Value, String> {
+    let slots: Vec<serde_json::Value> = pinned_slots(app, grant)?
+        .iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let icon = item
+                .id
+                .filter(|_| {
+                    matches!(item.kind.as_str(), "image" | "audio") || item.data_type == "svg"
+                })
+                .and_then(|id| crate::utils::cached_thumbnail(app, id, ICON_SIZE).ok());
+            json!({
+                "slot": i + 1,
+                "id": item.id,
+                "label": label(item),
+                "kind": item.kind,
+                "data_type": item.data_type,
+                "icon": icon,
+            })
+        })
+        .collect();
+    let paused = *app.state::<AppState>().is_paused.lock().unwrap();
+    Ok(json!({ "items": slots, "paused": paused }))
+}
+
+fn handle_request(
+    app: &tauri::AppHandle,
+    request: &Request,
+    grant: Grant,
+) -> Result<serde_json::Value, String> {
+    if request.action != "list_pinned" && !grant.write {
+        return Err("Token is read-only".to_string());
+    }
+    match request.action.as_str() {
+        "list_pinned" => list_pinned(app, grant),
+        "paste" => {
+            let slot = request.slot.ok_or("Missing slot")?;
+            let item = slot
+                .checked_sub(1)
+                .and_then(|i| pinned_slots(app, grant).ok()?.into_iter().nth(i))
+                .ok_or_else(|| format!("No pinned item in slot {}", slot))?;
+            crate::quick_paste::paste(app, item.id.ok_or("Item has no id")?)?;
+            Ok(json!({ "slot": slot }))
+        }
+        "toggle_pause" => {
+            tauri::async_runtime::block_on(crate::palette::run(app, "toggle_pause", None))?;
+            let paused = *app.state::<AppState>().is_paused.lock().unwrap();
+            Ok(json!({ "paused": paused }))
+        }
+        "capture" => {
+            tauri::async_runtime::block_on(crate::palette::run(app, "capture_screen", None))?;
+            Ok(json!({}))
+        }
+        action => Err(format!("Unknown action: {}", action)),
+    }
+}
+
+fn respond(app: &tauri::AppHandle, text: &str) -> serde_json::Value {
+    let request: Request = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => return json!({ "ok": false, "error": format!("Invalid request: {}", e) }),
+    };
+    let (enabled, expected) = {
+        let state = app.state::<AppState>();
+        let config = state.config.lock().unwrap();
+        (config.streamdeck_enabled, config.streamdeck_token.clone())
+    };
+    let grant =
+        if !expected.is_empty() && crate::share_server::token_matches(&request.token, &expected) {
+            Some(Grant::FULL)
+        } else {
+            crate::api_tokens::authorize(app, &request.token)
+        };
+    let Some(grant) = grant.filter(|_| enabled) else {
+        return json!({ "id": request.id, "ok": false, "error": "Invalid token" });
+    };
+    match handle_request(app, &request, grant) {
+        Ok(result) => json!({ "id": request.id, "ok": true, "result": result }),
+        Err(e) => json!({ "id": request.id, "ok": false, "error": e }),
+    }
+}
+
+fn handle(app: &tauri::AppHandle, stream: TcpStream) {
+    let mut socket = match tungstenite::accept(stream) {
+        Ok(socket) => socket,
+        Err(e) => {
+            log::warn!("Stream Deck handshake failed: {}", e);
+            return;
+        }
+    };
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let response = respond(app, text.as_str());
+                if socket.send(Message::text(response.to_string())).is_err() {
+                    break;
+                }
+            }
+            Ok(Message::Close(_)) | Err(_) => break,
+            Ok(_) => {}
+        }
+    }
+}
+
+/// 按配置（重新）启动 Stream Deck WebSocket 服务，只监听本机
+pub fn restart(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let (enabled, port) = {
+        let config = state.config.lock().unwrap();
+        (config.streamdeck_enabled, config.streamdeck_port)
+    };
+    let app = app.clone();
+    crate::listener::restart(
+        &state.streamdeck_generation,
+        enabled,
+        "Stream Deck server",
+        ("127.0.0.1", port),
+        move |stream| handle(&app, stream),
+    );
+}
@@ -0,0 +1,91 @@
+use std::sync::{Arc, OnceLock};
+
+use clipboard_rs::{Clipboard, ClipboardContext};
+use resvg::{tiny_skia, usvg};
+
+// 各平台剪贴板上 SVG 的格式名
+const SVG_FORMATS: [&str; 2] = ["public.svg-image", "image/svg+xml"];
+
+// 按自身尺寸渲染时的最长边上限
+const MAX_RENDER_SIZE: f32 = 2048.0;
+
+/// 文本是否为 SVG 源码（允许前置 XML 声明、注释和 DOCTYPE）
+pub fn is_svg(content: &str) -> bool {
+    let trimmed = content.trim_start();
+    if !trimmed.starts_with('<') || !trimmed.trim_end().ends_with("</svg>") {
+        return false;
+    }
+    let head = &trimmed[..trimmed.len().min(1024)];
+    head.find("<svg").is_some_and(|pos| {
+        head[..pos].split('>').all(|part| {
+            part.trim().is_empty()
+                || part.trim_start().starts_with("<?")
+                || part.trim_start().starts_with("<!")
+        })
+    })
+}
+
+/// 剪贴板中没有纯文本、只有 SVG 格式时读取其源码
+pub fn read_clipboard() -> Option<String> {
+    let ctx = ClipboardContext::new().ok()?;
+    let formats = ctx.available_formats().ok()?;
+    SVG_FORMATS
+        .iter()
+        .filter(|format| formats.iter().any(|f| f == *format))
+        .find_map(|format| {
+            let data = ctx.get_buffer(format).ok()?;
+            String::from_utf8(data).ok().filter(|svg| is_svg(svg))
+        })
+}
+
+/// 写回 SVG 时使用的当前平台格式名
+pub fn clipboard_format() -> &'static str {
+    if cfg!(target_os = "macos") {
+        SVG_FORMATS[0]
+    } else {
+        SVG_FORMATS[1]
+    }
+}
+
+/// 系统字体只加载一次，渲染 SVG 中的文字时使用
+fn fonts() -> Arc<usvg::fontdb::Database> {
+    static FONTS: OnceLock<Arc<usvg::fontdb::Database>> = OnceLock::new();
+    FONTS
+        .get_or_init(|| {
+            let mut fonts = usvg::fontdb::Database::new();
+            fonts.load_system_fonts();
+            Arc::new(fonts)
+        })
+        .clone()
+}
+
+/// 把 SVG 渲染为 PNG：指定 size 时缩放到最长边为 size，否则按自身尺寸（过大时缩小）
+pub fn render_png(svg: &str, size: Option<u32>) -> Result<Vec<u8>, String> {
+    let options = usvg::Options {
+        fontdb: fonts(),
+        ..Default::default()
+    };
+    let tree = usvg::Tree::from_str(svg, &options).map_err(|e| e.to_string())?;
+
+    let tree_size = tree.size();
+    let longest = tree_size.width().max(tree_size.height());
+    let scale = match size {
+        Some(size) => size as f32 / longest,
+        None => (MAX_RENDER_SIZE / longest).min(1.0),
+    };
+    let width = ((tree_size.width() * scale).round() as u32).max(1);
+    let height = ((tree_size.height() * scale).round() as u32).max(1);
+    let mut pixmap = tiny_skia::Pixmap::new(width, height).ok_or("Invalid SVG size")?;
+    resvg::render(
+        &tree,
+        tiny_skia::Transform::from_scale(scale, scale),
+        &mut pixmap.as_mut(),
+    );
+    pixmap.encode_png().map_err(|e| e.to_string())
+}
+
+/// SVG 条目的 PNG 预览（data URL）
+pub fn thumbnail(svg: &str, size: u32) -> Result<String, String> {
+    let png = render_png(svg, Some(size))?;
+    Ok(crate::utils::png_data_url(&png))
+}
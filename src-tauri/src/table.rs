@@ -0,0 +1,149 @@
+/// 从 Excel / Sheets 复制的制表符分隔数据：至少两行两列，且每行列数一致
+fn parse_tsv(text: &str) -> Option<Vec<Vec<String>>> {
+    let rows: Vec<Vec<String>> = text
+        .trim_end_matches(['\r', '\n'])
+        .lines()
+        .map(|line| {
+            line.trim_end_matches('\r')
+                .split('\t')
+                .map(|cell| cell.trim().to_string())
+                .collect()
+        })
+        .collect();
+    let columns = rows.first()?.len();
+    if rows.len() < 2 || columns < 2 || rows.iter().any(|row| row.len() != columns) {
+        return None;
+    }
+    Some(rows)
+}
+
+/// Markdown 表格：| a | b | 形式，第二行为 |---|---| 分隔行
+fn parse_markdown(text: &str) -> Option<Vec<Vec<String>>> {
+    let lines: Vec<&str> = text.trim().lines().map(|l| l.trim()).collect();
+    if lines.len() < 2 || !lines.iter().all(|l| l.starts_with('|')) {
+        return None;
+    }
+    let cells = |line: &str| -> Vec<String> {
+        line.trim_matches('|')
+            .split('|')
+            .map(|cell| cell.trim().replace("\\|", "|"))
+            .collect()
+    };
+    let separator = cells(lines[1]);
+    if !separator
+        .iter()
+        .all(|c| !c.is_empty() && c.chars().all(|ch| matches!(ch, '-' | ':')))
+    {
+        return None;
+    }
+    let rows: Vec<Vec<String>> = std::iter::once(lines[0])
+        .chain(lines[2..].iter().copied())
+        .map(cells)
+        .collect();
+    Some(rows)
+}
+
+/// CSV（支持双引号转义），至少两行两列
+fn parse_csv(text: &str) -> Option<Vec<Vec<String>>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.trim_end_matches(['\r', '\n']).chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            ('"', _) => quoted = !quoted,
+            (',', false) => row.push(std::mem::take(&mut cell)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut cell));
+                rows.push(std::mem::take(&mut row));
+            }
+            _ => cell.push(c),
+        }
+    }
+    row.push(cell);
+    rows.push(row);
+
+    let columns = rows.first()?.len();
+    if quoted || rows.len() < 2 || columns < 2 || rows.iter().any(|row| row.len() != columns) {
+        return None;
+    }
+    Some(rows)
+}
+
+/// 是否为从电子表格复制的表格数据
+pub fn is_table(text: &str) -> bool {
+    text.contains('\t') && parse_tsv(text).is_some()
+}
+
+/// 解析制表符分隔、Markdown 或 CSV 表格
+pub fn parse(text: &str) -> Option<Vec<Vec<String>>> {
+    parse_tsv(text)
+        .or_else(|| parse_markdown(text))
+        .or_else(|| parse_csv(text))
+}
+
+fn csv_cell(cell: &str) -> String {
+    if cell.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell.to_string()
+    }
+}
+
+fn to_markdown(rows: &[Vec<String>]) -> String {
+    let line = |row: &[String]| {
+        format!(
+            "| {} |",
+            row.iter()
+                .map(|cell| cell.replace('|', "\\|").replace('\n', " "))
+                .collect::<Vec<_>>()
+                .join(" | ")
+        )
+    };
+    let mut lines = vec![line(&rows[0])];
+    lines.push(format!("|{}|", vec![" --- "; rows[0].len()].join("|")));
+    lines.extend(rows[1..].iter().map(|row| line(row)));
+    lines.join("\n")
+}
+
+/// 第一行作为字段名，其余每行转为一个对象
+fn to_json(rows: &[Vec<String>]) -> Result<String, String> {
+    let headers = &rows[0];
+    let objects: Vec<serde_json::Map<String, serde_json::Value>> = rows[1..]
+        .iter()
+        .map(|row| {
+            headers
+                .iter()
+                .zip(row)
+                .map(|(key, value)| (key.clone(), serde_json::Value::String(value.clone())))
+                .collect()
+        })
+        .collect();
+    serde_json::to_string_pretty(&objects).map_err(|e| e.to_string())
+}
+
+/// 把表格文本转换为 csv / markdown / json
+pub fn convert(text: &str, format: &str) -> Result<String, String> {
+    let rows = parse(text).ok_or("Content is not a table")?;
+    match format {
+        "csv" => Ok(rows
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|c| csv_cell(c))
+                    .collect::<Vec<_>>()
+                    .join(",")
+            })
+            .collect::<Vec<_>>()
+            .join("\n")),
+        "markdown" => Ok(to_markdown(&rows)),
+        "json" => to_json(&rows),
+        _ => Err(format!("Unknown table format: {}", format)),
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{Duration, Local};
+
+use crate::models::ClipboardItem;
+
+// 生成的条目按这个间隔依次往前排，10 万条约覆盖两个月
+const STEP_SECONDS: i64 = 60;
+
+const WORDS: [&str; 24] = [
+    "clipboard",
+    "history",
+    "search",
+    "paste",
+    "image",
+    "note",
+    "rust",
+    "tauri",
+    "sqlite",
+    "window",
+    "shortcut",
+    "collection",
+    "pinned",
+    "config",
+    "report",
+    "meeting",
+    "invoice",
+    "draft",
+    "release",
+    "review",
+    "feature",
+    "bug",
+    "deploy",
+    "build",
+];
+const APPS: [&str; 5] = ["Safari", "Code", "Terminal", "Slack", "Notes"];
+
+/// 可复现的伪随机数，避免测试数据依赖随机种子
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[(self.next() % items.len() as u64) as usize]
+    }
+}
+
+fn sentence(rng: &mut Rng, words: usize) -> String {
+    (0..words)
+        .map(|_| rng.pick(&WORDS))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// 生成 n 条文本条目（普通文本、链接、代码混合），内容各不相同，时间戳从现在往前递减
+pub fn generate(n: usize, seed: u64) -> Vec<ClipboardItem> {
+    let mut rng = Rng(seed.max(1));
+    let now = Local::now();
+    (0..n)
+        .map(|i| {
+            let (content, data_type) = match rng.next() % 4 {
+                0 => (
+                    format!("https://example.com/{}/{}", rng.pick(&WORDS), i),
+                    "url",
+                ),
+                1 => (
+                    format!(
+                        "fn {}_{}() {{\n    println!(\"{}\");\n}}",
+                        rng.pick(&WORDS),
+                        i,
+                        sentence(&mut rng, 3)
+                    ),
+                    "code",
+                ),
+                _ => {
+                    let words = 4 + (rng.next() % 40) as usize;
+                    (format!("{} #{}", sentence(&mut rng, words), i), "text")
+                }
+            };
+            let timestamp = now - Duration::seconds(i as i64 * STEP_SECONDS);
+            ClipboardItem {
+                content,
+                kind: "text".to_string(),
+                timestamp: timestamp.format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+                source_app: Some(rng.pick(&APPS).to_string()),
+                data_type: data_type.to_string(),
+                ..Default::default()
+            }
+        })
+        .collect()
+}
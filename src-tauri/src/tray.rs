@@ -1,7 +1,12 @@
 use crate::models::ClipboardItem;
-use tauri::menu::{Menu, MenuItem, PredefinedMenuItem};
+use crate::state::AppState;
+use crate::utils::normalize_url;
+use tauri::menu::{Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIconEvent};
 use tauri::{Manager, Wry};
 
+const MAX_RECENT_URLS: usize = 10;
+
 pub fn create_tray_menu(app: &tauri::AppHandle) -> Result<Menu<Wry>, String> {
     let menu = Menu::new(app).map_err(|e| e.to_string())?;
 
@@ -10,6 +15,16 @@ pub fn create_tray_menu(app: &tauri::AppHandle) -> Result<Menu<Wry>, String> {
         .map_err(|e| e.to_string())?;
     menu.append(&show_item).map_err(|e| e.to_string())?;
 
+    // Recent links, filled by update_tray_menu
+    let urls_menu = Submenu::with_id(app, "recent_urls", "Open Recent Link", false)
+        .map_err(|e| e.to_string())?;
+    menu.append(&urls_menu).map_err(|e| e.to_string())?;
+
+    // Pinned items with their shortcuts, filled by update_quick_paste_menu
+    let quick_paste_menu =
+        Submenu::with_id(app, "quick_paste", "Quick Paste", false).map_err(|e| e.to_string())?;
+    menu.append(&quick_paste_menu).map_err(|e| e.to_string())?;
+
     menu.append(&PredefinedMenuItem::separator(app).map_err(|e| e.to_string())?)
         .map_err(|e| e.to_string())?;
 
@@ -55,8 +70,107 @@ pub fn create_tray_menu(app: &tauri::AppHandle) -> Result<Menu<Wry>, String> {
     Ok(menu)
 }
 
-pub fn update_tray_menu(_app: &tauri::AppHandle, _history: &[ClipboardItem]) -> Result<(), String> {
-    // No longer updating tray menu with history
+pub fn update_tray_menu(app: &tauri::AppHandle, history: &[ClipboardItem]) -> Result<(), String> {
+    crate::jump_list::update(app, history);
+
+    // Only the recent links submenu reflects history
+    let state = app.state::<crate::state::AppState>();
+    let urls_menu = state.urls_menu.lock().map_err(|e| e.to_string())?;
+    let Some(urls_menu) = urls_menu.as_ref() else {
+        return Ok(());
+    };
+
+    while let Ok(Some(_)) = urls_menu.remove_at(0) {}
+
+    // 演示模式下托盘菜单不列出最近的链接
+    let presentation_mode = state.config.lock().unwrap().presentation_mode;
+    let mut count = 0;
+    for item in history
+        .iter()
+        .filter(|i| !presentation_mode && i.data_type == "url")
+    {
+        let (Some(id), Some(url)) = (item.id, normalize_url(&item.content)) else {
+            continue;
+        };
+        let label: String = url.chars().take(60).collect();
+        let entry = MenuItem::with_id(app, format!("open_url_{}", id), label, true, None::<&str>)
+            .map_err(|e| e.to_string())?;
+        urls_menu.append(&entry).map_err(|e| e.to_string())?;
+        count += 1;
+        if count >= MAX_RECENT_URLS {
+            break;
+        }
+    }
+
+    urls_menu
+        .set_enabled(count > 0)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+pub fn update_quick_paste_menu(
+    app: &tauri::AppHandle,
+    pinned: &[ClipboardItem],
+) -> Result<(), String> {
+    let state = app.state::<crate::state::AppState>();
+    let quick_paste_menu = state.quick_paste_menu.lock().map_err(|e| e.to_string())?;
+    let Some(quick_paste_menu) = quick_paste_menu.as_ref() else {
+        return Ok(());
+    };
+
+    while let Ok(Some(_)) = quick_paste_menu.remove_at(0) {}
+
+    let mut count = 0;
+    for item in pinned {
+        let Some(id) = item.id else {
+            continue;
+        };
+        let label = if item.is_sensitive {
+            "••••••".to_string()
+        } else if item.kind == "text" {
+            let line = item
+                .content
+                .lines()
+                .find(|l| !l.trim().is_empty())
+                .unwrap_or("");
+            line.trim().chars().take(40).collect()
+        } else {
+            format!("[{}]", item.kind)
+        };
+        // 只显示已成功注册的快捷键
+        let accelerator = item.accelerator.as_deref().filter(|_| {
+            state
+                .quick_paste_shortcuts
+                .lock()
+                .map(|shortcuts| shortcuts.values().any(|v| *v == id))
+                .unwrap_or(false)
+        });
+        let entry = MenuItem::with_id(app, format!("quick_paste_{}", id), label, true, accelerator)
+            .map_err(|e| e.to_string())?;
+        quick_paste_menu.append(&entry).map_err(|e| e.to_string())?;
+        count += 1;
+    }
+
+    quick_paste_menu
+        .set_enabled(count > 0)
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 省电模式时在托盘提示（macOS 菜单栏同时显示标记）
+pub fn update_power_indicator(app: &tauri::AppHandle, power_saving: bool) -> Result<(), String> {
+    let Some(tray) = app.tray_by_id("tray") else {
+        return Ok(());
+    };
+    let tooltip = if power_saving {
+        "Clipboard (Power Saving)"
+    } else {
+        "Clipboard"
+    };
+    tray.set_tooltip(Some(tooltip)).map_err(|e| e.to_string())?;
+    #[cfg(target_os = "macos")]
+    tray.set_title(power_saving.then_some("⚡"))
+        .map_err(|e| e.to_string())?;
     Ok(())
 }
 
@@ -74,3 +188,51 @@ pub fn update_pause_menu_item(app: &tauri::AppHandle, is_paused: bool) -> Result
     }
     Ok(())
 }
+
+/// 托盘图标手势：中键单击、左键双击按配置执行操作（Linux 上托盘不产生这些事件）
+pub fn handle_icon_event(app: &tauri::AppHandle, event: TrayIconEvent) {
+    let state = app.state::<AppState>();
+    let action = {
+        let config = state.config.lock().unwrap();
+        match event {
+            TrayIconEvent::Click {
+                button: MouseButton::Middle,
+                button_state: MouseButtonState::Up,
+                ..
+            } => config.tray_middle_click_action.clone(),
+            TrayIconEvent::DoubleClick {
+                button: MouseButton::Left,
+                ..
+            } => config.tray_double_click_action.clone(),
+            _ => return,
+        }
+    };
+    match action.as_str() {
+        "none" => {}
+        "paste_latest" => {
+            if let Err(e) = paste_latest(app) {
+                log::error!("Failed to paste latest item from tray: {}", e);
+            }
+        }
+        // 其他取值为命令面板中的操作
+        _ => {
+            let app = app.clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = crate::palette::run(&app, &action, None).await {
+                    log::error!("Failed to run tray action {}: {}", action, e);
+                }
+            });
+        }
+    }
+}
+
+/// 把最近一条记录写入剪贴板并记为粘贴；敏感条目不会被托盘手势粘贴
+fn paste_latest(app: &tauri::AppHandle) -> Result<(), String> {
+    let item = app
+        .state::<AppState>()
+        .db
+        .get_latest_restorable_item()
+        .map_err(|e| e.to_string())?
+        .ok_or("History is empty")?;
+    crate::quick_paste::paste(app, item.id.ok_or("Item has no id")?)
+}
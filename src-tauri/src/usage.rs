@@ -0,0 +1,50 @@
+use std::collections::HashMap;
+
+use tauri::Manager;
+
+use crate::models::{UsageStats, UsageTotal};
+use crate::state::AppState;
+
+// 统计的功能
+pub const POPUP_OPEN: &str = "popup_open";
+pub const PASTE: &str = "paste";
+pub const CAPTURE: &str = "capture";
+pub const SCREENSHOT: &str = "screenshot";
+
+/// 记录一次功能使用；未开启本地统计时忽略。数据只写入本机数据库，不会上传
+pub fn record(app: &tauri::AppHandle, event: &str) {
+    let state = app.state::<AppState>();
+    if !state.config.lock().unwrap().usage_tracking {
+        return;
+    }
+    if let Err(e) = state.db.record_usage(event) {
+        log::error!("Failed to record usage {}: {}", event, e);
+    }
+}
+
+/// 最近 days 天的使用统计
+pub fn stats(app: &tauri::AppHandle, days: u32) -> Result<UsageStats, String> {
+    let state = app.state::<AppState>();
+    let enabled = state.config.lock().unwrap().usage_tracking;
+    let daily = state.db.get_usage_counts(days).map_err(|e| e.to_string())?;
+
+    let mut totals: HashMap<&str, usize> = HashMap::new();
+    for count in &daily {
+        *totals.entry(count.event.as_str()).or_default() += count.count;
+    }
+    let mut totals: Vec<UsageTotal> = totals
+        .into_iter()
+        .map(|(event, count)| UsageTotal {
+            event: event.to_string(),
+            count,
+        })
+        .collect();
+    totals.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.event.cmp(&b.event)));
+
+    Ok(UsageStats {
+        enabled,
+        days,
+        totals,
+        daily,
+    })
+}
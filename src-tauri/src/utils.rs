@@ -1,11 +1,465 @@
-use crate::models::ClipboardItem;
+use crate::cache::CacheKey;
+use crate::models::{
+    AppConfig, ClipboardItem, CustomClassifier, ExtractedEntity, StorageUsage, SuggestedAction,
+    TextStats,
+};
 use crate::state::AppState;
 use base64::{engine::general_purpose, Engine as _};
+use chrono::{Datelike, Local, SecondsFormat, TimeZone};
+use clipboard_rs::common::{RustImage, RustImageData};
 use clipboard_rs::{Clipboard, ClipboardContent, ClipboardContext};
 use regex::Regex;
+use std::collections::HashMap;
 use std::fs;
-use tauri::Manager;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tauri::{Emitter, Manager};
 use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_opener::OpenerExt;
+
+/// 如果文本是一个存在的文件系统路径，返回其规范化后的绝对路径
+pub fn resolve_existing_path(content: &str) -> Option<String> {
+    let path = path_candidate(content)?;
+    if !path.exists() {
+        return None;
+    }
+
+    dunce::canonicalize(&path)
+        .ok()
+        .map(|p| p.to_string_lossy().to_string())
+}
+
+/// 路径查询结果的缓存时间；复制时分类和规范化会对同一段文本重复查询
+const PATH_CACHE_TTL: Duration = Duration::from_secs(30);
+const PATH_CACHE_CAPACITY: usize = 256;
+
+/// 带缓存的 resolve_existing_path，供剪贴板捕获使用，避免每次复制都访问文件系统
+pub fn resolve_existing_path_cached(content: &str) -> Option<String> {
+    static CACHE: OnceLock<Mutex<HashMap<PathBuf, (Instant, Option<String>)>>> = OnceLock::new();
+
+    // 不像绝对路径的文本不需要访问文件系统
+    let path = path_candidate(content)?;
+    let cache = CACHE.get_or_init(|| Mutex::new(HashMap::new()));
+    if let Some((checked_at, resolved)) = cache.lock().unwrap().get(&path) {
+        if checked_at.elapsed() < PATH_CACHE_TTL {
+            return resolved.clone();
+        }
+    }
+
+    let resolved = if path.exists() {
+        dunce::canonicalize(&path)
+            .ok()
+            .map(|p| p.to_string_lossy().to_string())
+    } else {
+        None
+    };
+
+    let mut cache = cache.lock().unwrap();
+    if cache.len() >= PATH_CACHE_CAPACITY {
+        cache.retain(|_, (checked_at, _)| checked_at.elapsed() < PATH_CACHE_TTL);
+        if cache.len() >= PATH_CACHE_CAPACITY {
+            cache.clear();
+        }
+    }
+    cache.insert(path, (Instant::now(), resolved.clone()));
+    resolved
+}
+
+/// 从文本中取出可能的绝对路径（去掉引号、file:// 前缀并展开 ~），不访问文件系统
+fn path_candidate(content: &str) -> Option<PathBuf> {
+    let trimmed = content.trim();
+    if trimmed.is_empty() || trimmed.contains('\n') {
+        return None;
+    }
+
+    // Strip surrounding quotes added by "Copy as path" in Explorer / shells
+    let unquoted = trimmed
+        .strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| {
+            trimmed
+                .strip_prefix('\'')
+                .and_then(|s| s.strip_suffix('\''))
+        })
+        .unwrap_or(trimmed);
+    let without_scheme = unquoted.strip_prefix("file://").unwrap_or(unquoted);
+
+    let path = if let Some(rest) = without_scheme.strip_prefix("~/") {
+        home::home_dir()?.join(rest)
+    } else {
+        Path::new(without_scheme).to_path_buf()
+    };
+
+    // Only absolute paths, so short words never resolve against the cwd
+    path.is_absolute().then_some(path)
+}
+
+/// 校验并规范化 URL，只接受带主机名的 http/https 链接
+pub fn normalize_url(candidate: &str) -> Option<String> {
+    let trimmed = candidate
+        .trim()
+        .trim_end_matches(|c| matches!(c, '.' | ',' | ';' | ':' | ')' | ']' | '}' | '\'' | '"'));
+    let with_scheme = if trimmed.starts_with("www.") {
+        format!("https://{}", trimmed)
+    } else {
+        trimmed.to_string()
+    };
+
+    let url = tauri::Url::parse(&with_scheme).ok()?;
+    if !matches!(url.scheme(), "http" | "https") || url.host_str().is_none() {
+        return None;
+    }
+    Some(url.to_string())
+}
+
+/// 提取文本中出现的所有 URL（去重并保持出现顺序）
+pub fn extract_urls(content: &str) -> Vec<String> {
+    let url_regex = Regex::new(r#"(?i)\b(?:https?://|www\.)[^\s<>"'`]+"#).unwrap();
+    let mut urls: Vec<String> = Vec::new();
+    for m in url_regex.find_iter(content) {
+        if let Some(url) = normalize_url(m.as_str()) {
+            if !urls.contains(&url) {
+                urls.push(url);
+            }
+        }
+    }
+    urls
+}
+
+/// 提取文本中的 URL、邮箱和电话号码
+pub fn extract_entities(content: &str) -> Vec<ExtractedEntity> {
+    let mut entities: Vec<ExtractedEntity> = extract_urls(content)
+        .into_iter()
+        .map(|value| ExtractedEntity {
+            kind: "url".to_string(),
+            value,
+        })
+        .collect();
+
+    let email_regex = Regex::new(r"[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}").unwrap();
+    let mut emails: Vec<String> = Vec::new();
+    for m in email_regex.find_iter(content) {
+        let email = m.as_str().trim_end_matches('.').to_string();
+        // Skip credentials embedded in URLs such as https://user@host
+        if entities.iter().any(|e| e.value.contains(&email)) || emails.contains(&email) {
+            continue;
+        }
+        emails.push(email);
+    }
+    entities.extend(emails.into_iter().map(|value| ExtractedEntity {
+        kind: "email".to_string(),
+        value,
+    }));
+
+    let phone_regex = Regex::new(r"(?:\+\d{1,3}[- ]?)?\(?\d{3}\)?[- ]?\d{3}[- ]?\d{4}\b").unwrap();
+    let mut phones: Vec<String> = Vec::new();
+    for m in phone_regex.find_iter(content) {
+        let phone = m.as_str().trim().to_string();
+        if entities.iter().any(|e| e.value.contains(&phone)) || phones.contains(&phone) {
+            continue;
+        }
+        phones.push(phone);
+    }
+    entities.extend(phones.into_iter().map(|value| ExtractedEntity {
+        kind: "phone".to_string(),
+        value,
+    }));
+
+    entities
+}
+
+fn is_cjk(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF // Hiragana / Katakana
+        | 0x3400..=0x4DBF
+        | 0x4E00..=0x9FFF
+        | 0xAC00..=0xD7AF // Hangul
+        | 0xF900..=0xFAFF)
+}
+
+/// 统计文本的字数、字符数和行数；CJK 字符按单字计词
+pub fn text_stats(content: &str, include_tokens: bool) -> TextStats {
+    let cjk_chars = content.chars().filter(|c| is_cjk(*c)).count();
+    let words = content
+        .split(|c: char| c.is_whitespace() || is_cjk(c))
+        .filter(|w| !w.is_empty())
+        .count()
+        + cjk_chars;
+    let characters = content.chars().count();
+    let characters_no_spaces = content.chars().filter(|c| !c.is_whitespace()).count();
+
+    // Rough BPE estimate: ~4 chars per token for Latin text, ~1 token per CJK char
+    let tokens = include_tokens.then(|| cjk_chars + (characters - cjk_chars).div_ceil(4));
+
+    TextStats {
+        words,
+        characters,
+        characters_no_spaces,
+        lines: if content.is_empty() {
+            0
+        } else {
+            content.lines().count()
+        },
+        bytes: content.len(),
+        tokens,
+    }
+}
+
+/// 解析界面语言，"auto" 时根据系统环境变量判断
+pub fn resolve_language(language: &str) -> &'static str {
+    let language = if language == "auto" {
+        std::env::var("LC_ALL")
+            .or_else(|_| std::env::var("LANG"))
+            .unwrap_or_default()
+    } else {
+        language.to_string()
+    };
+    if language.to_lowercase().starts_with("zh") {
+        "zh"
+    } else {
+        "en"
+    }
+}
+
+/// 按本地时区和界面语言格式化时间戳，style 为 relative / short / long / iso
+pub fn format_timestamp(timestamp_ms: i64, style: &str, language: &str) -> String {
+    let Some(time) = Local.timestamp_millis_opt(timestamp_ms).single() else {
+        return String::new();
+    };
+    let zh = resolve_language(language) == "zh";
+
+    match style {
+        "iso" => time.to_rfc3339_opts(SecondsFormat::Millis, false),
+        "short" => {
+            let now = Local::now();
+            if time.date_naive() == now.date_naive() {
+                time.format("%H:%M").to_string()
+            } else if time.year() == now.year() {
+                if zh {
+                    time.format("%-m月%-d日 %H:%M").to_string()
+                } else {
+                    time.format("%b %-d, %H:%M").to_string()
+                }
+            } else {
+                time.format("%Y-%m-%d").to_string()
+            }
+        }
+        "long" => {
+            if zh {
+                time.format("%Y年%-m月%-d日 %H:%M:%S").to_string()
+            } else {
+                time.format("%a, %b %-d %Y %H:%M:%S").to_string()
+            }
+        }
+        _ => {
+            let seconds = (Local::now().timestamp_millis() - timestamp_ms).max(0) / 1000;
+            let (n, unit_en, unit_zh) = match seconds {
+                s if s < 60 => return if zh { "刚刚" } else { "just now" }.to_string(),
+                s if s < 3600 => (s / 60, "minute", "分钟"),
+                s if s < 86400 => (s / 3600, "hour", "小时"),
+                s if s < 86400 * 30 => (s / 86400, "day", "天"),
+                s if s < 86400 * 365 => (s / (86400 * 30), "month", "个月"),
+                s => (s / (86400 * 365), "year", "年"),
+            };
+            if zh {
+                format!("{}{}前", n, unit_zh)
+            } else {
+                format!("{} {}{} ago", n, unit_en, if n == 1 { "" } else { "s" })
+            }
+        }
+    }
+}
+
+/// 本地时区某天开始的毫秒时间戳；夏令时在午夜切换、当天没有 00:00 时取之后第一个有效的时刻
+fn local_midnight_millis(date: chrono::NaiveDate) -> i64 {
+    let midnight = date.and_time(chrono::NaiveTime::MIN);
+    (0..24 * 60)
+        .find_map(|minutes| {
+            (midnight + chrono::Duration::minutes(minutes))
+                .and_local_timezone(Local)
+                .earliest()
+        })
+        .map(|time| time.timestamp_millis())
+        .unwrap_or_else(|| midnight.and_utc().timestamp_millis())
+}
+
+/// 日期预设对应的毫秒时间范围 [from, to)，按本地时区：
+/// today / yesterday / this_week（周一开始）/ last_7_days / this_month / last_30_days
+pub fn date_preset_range(preset: &str, now: chrono::DateTime<Local>) -> Result<(i64, i64), String> {
+    let today = now.date_naive();
+    let tomorrow = local_midnight_millis(today + chrono::Days::new(1));
+    let range = match preset {
+        "today" => (local_midnight_millis(today), tomorrow),
+        "yesterday" => (
+            local_midnight_millis(today - chrono::Days::new(1)),
+            local_midnight_millis(today),
+        ),
+        "this_week" => {
+            let days = today.weekday().num_days_from_monday() as u64;
+            (
+                local_midnight_millis(today - chrono::Days::new(days)),
+                tomorrow,
+            )
+        }
+        "last_7_days" => (
+            local_midnight_millis(today - chrono::Days::new(6)),
+            tomorrow,
+        ),
+        "this_month" => (
+            local_midnight_millis(today.with_day(1).unwrap_or(today)),
+            tomorrow,
+        ),
+        "last_30_days" => (
+            local_midnight_millis(today - chrono::Days::new(29)),
+            tomorrow,
+        ),
+        _ => return Err(format!("Unknown date preset: {}", preset)),
+    };
+    Ok(range)
+}
+
+/// 暂停记录或处于演示模式时不记录新内容
+pub fn is_capture_suspended(state: &AppState) -> bool {
+    state.is_paused.lock().map(|p| *p).unwrap_or(false)
+        || state
+            .config
+            .lock()
+            .map(|c| c.presentation_mode)
+            .unwrap_or(false)
+}
+
+/// 应用是否在敏感应用列表中（如密码管理器），其中的复制不记录
+pub fn is_sensitive_app(config: &AppConfig, app_name: &str) -> bool {
+    config
+        .sensitive_apps
+        .iter()
+        .any(|app| app_name.contains(app) || app_name.eq_ignore_ascii_case(app))
+}
+
+/// 本机的设备名：配置中的名称，留空时使用主机名
+pub fn device_name(config: &AppConfig) -> String {
+    let name = config.device_name.trim();
+    if !name.is_empty() {
+        return name.to_string();
+    }
+    static HOSTNAME: std::sync::OnceLock<String> = std::sync::OnceLock::new();
+    HOSTNAME
+        .get_or_init(|| {
+            std::env::var("COMPUTERNAME")
+                .or_else(|_| std::env::var("HOSTNAME"))
+                .ok()
+                .filter(|name| !name.trim().is_empty())
+                .or_else(|| {
+                    std::process::Command::new("hostname")
+                        .output()
+                        .ok()
+                        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+                        .filter(|name| !name.is_empty())
+                })
+                .unwrap_or_else(|| "Unknown device".to_string())
+        })
+        .clone()
+}
+
+/// 演示模式下返回给界面的条目不带来源应用
+pub fn hide_source_apps(items: Vec<ClipboardItem>) -> Vec<ClipboardItem> {
+    items
+        .into_iter()
+        .map(|item| ClipboardItem {
+            source_app: None,
+            ..item
+        })
+        .collect()
+}
+
+/// 对外提供（界面、共享页面、Webhook 等）的条目：演示模式下去掉来源应用
+pub fn present_items(state: &AppState, items: Vec<ClipboardItem>) -> Vec<ClipboardItem> {
+    if state.config.lock().unwrap().presentation_mode {
+        hide_source_apps(items)
+    } else {
+        items
+    }
+}
+
+/// 同 present_items，用于单个条目
+pub fn present_item(state: &AppState, item: ClipboardItem) -> ClipboardItem {
+    present_items(state, vec![item]).pop().unwrap_or_default()
+}
+
+/// 在默认浏览器中打开条目中的链接，返回打开的数量
+pub fn open_item_urls(
+    app: &tauri::AppHandle,
+    item: &ClipboardItem,
+    open_all: bool,
+) -> Result<usize, String> {
+    if item.kind != "text" {
+        return Err("Only text items can contain URLs".to_string());
+    }
+
+    let mut urls = extract_urls(&item.content);
+    if urls.is_empty() {
+        return Err("No valid URL found in item".to_string());
+    }
+    if !open_all {
+        urls.truncate(1);
+    }
+
+    for url in &urls {
+        log::info!("Opening URL: {}", url);
+        app.opener()
+            .open_url(url.as_str(), None::<&str>)
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(urls.len())
+}
+
+/// 先按内置类型分类，仍为普通文本时使用第一个匹配的自定义分类器名称
+/// 文本条目的建议操作：计算、换算，以及按类型附带的颜色转换、日程和联系方式操作
+pub fn suggested_actions(
+    app: &tauri::AppHandle,
+    text: &str,
+    data_type: &str,
+) -> Vec<SuggestedAction> {
+    let mut actions = crate::calc::suggestions(app, text);
+    // 颜色值附带其他写法的转换
+    if data_type == "color" {
+        actions.extend(crate::color::conversions(text));
+    }
+    // 普通文本中的日期时间附带创建日程操作
+    if data_type == "text" {
+        actions.extend(crate::calendar::suggestion(
+            text,
+            chrono::Local::now().naive_local(),
+        ));
+    }
+    // 电话、地址和邮箱附带拨打、E.164、地图、写邮件操作
+    if matches!(data_type, "phone" | "address" | "email") {
+        let country_code = app
+            .state::<AppState>()
+            .config
+            .lock()
+            .unwrap()
+            .default_country_code
+            .clone();
+        actions.extend(crate::contact::suggestions(text, data_type, &country_code));
+    }
+    actions
+}
+
+pub fn classify_with_custom(content: &str, classifiers: &[CustomClassifier]) -> String {
+    let data_type = classify_content(content);
+    if data_type != "text" {
+        return data_type;
+    }
+    classifiers
+        .iter()
+        .find(|c| {
+            !c.name.trim().is_empty() && Regex::new(&c.pattern).is_ok_and(|re| re.is_match(content))
+        })
+        .map(|c| c.name.trim().to_string())
+        .unwrap_or(data_type)
+}
 
 pub fn classify_content(content: &str) -> String {
     // URL
@@ -14,6 +468,11 @@ pub fn classify_content(content: &str) -> String {
         return "url".to_string();
     }
 
+    // Filesystem path
+    if resolve_existing_path_cached(content).is_some() {
+        return "path".to_string();
+    }
+
     // Email
     let email_regex = Regex::new(r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$").unwrap();
     if email_regex.is_match(content) {
@@ -21,11 +480,30 @@ pub fn classify_content(content: &str) -> String {
     }
 
     // Phone (Simple)
-    let phone_regex = Regex::new(r"^(\+\d{1,3}[- ]?)?\(?\d{3}\)?[- ]?\d{3}[- ]?\d{4}$").unwrap();
-    if phone_regex.is_match(content) {
+    if crate::contact::is_phone(content) {
         return "phone".to_string();
     }
 
+    // SVG 源码
+    if crate::svg::is_svg(content) {
+        return "svg".to_string();
+    }
+
+    // 从电子表格复制的制表符分隔数据
+    if crate::table::is_table(content) {
+        return "table".to_string();
+    }
+
+    // 颜色值（#hex、rgb()、hsl()、oklch()）
+    if crate::color::is_color(content) {
+        return "color".to_string();
+    }
+
+    // 街道地址
+    if crate::contact::is_address(content) {
+        return "address".to_string();
+    }
+
     // Code (Heuristic)
     let code_indicators = [
         "function", "class", "def", "import", "const", "let", "var", "public", "private", "return",
@@ -49,69 +527,409 @@ pub fn classify_content(content: &str) -> String {
     "text".to_string()
 }
 
-pub fn write_to_clipboard(app: &tauri::AppHandle, item: &ClipboardItem) -> Result<(), String> {
-    if item.kind == "text" {
-        // Try to use clipboard-rs for dual storage (Text + HTML)
-        if let Some(html) = &item.html_content {
-            if let Ok(ctx) = ClipboardContext::new() {
-                let contents = vec![
-                    ClipboardContent::Text(item.content.clone()),
-                    ClipboardContent::Html(html.clone()),
-                ];
-                if let Err(e) = ctx.set(contents) {
-                    log::error!("Failed to set rich text via clipboard-rs: {}", e);
-                    // Fallback to standard text via tauri plugin if rich text fails
+/// 通知前端历史已变化；按配置把时间窗口内的多次更新合并为一次事件
+pub fn emit_clipboard_update(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let delay = state.config.lock().unwrap().update_event_coalesce_ms;
+    if delay == 0 {
+        if let Err(e) = app.emit("clipboard-update", ()) {
+            log::error!("Failed to emit clipboard-update event: {}", e);
+        }
+        return;
+    }
+    {
+        let mut pending = state.update_event_pending.lock().unwrap();
+        if *pending {
+            return;
+        }
+        *pending = true;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(delay));
+        *app.state::<AppState>().update_event_pending.lock().unwrap() = false;
+        if let Err(e) = app.emit("clipboard-update", ()) {
+            log::error!("Failed to emit clipboard-update event: {}", e);
+        }
+    });
+}
+
+/// 删除被清理条目对应的图片 / 音频文件
+pub fn remove_pruned_images(pruned_items: &[ClipboardItem]) {
+    for pruned in pruned_items {
+        if pruned.kind == "image" || pruned.kind == "audio" {
+            let path = Path::new(&pruned.content);
+            if path.exists() {
+                if let Err(e) = fs::remove_file(path) {
+                    log::error!("Failed to delete pruned image file: {}", e);
                 } else {
-                    return Ok(());
+                    log::info!("Deleted pruned image file: {:?}", path);
                 }
             }
         }
+    }
+}
 
-        app.clipboard()
-            .write_text(item.content.clone())
-            .map_err(|e| e.to_string())?;
-    } else if item.kind == "image" {
-        let bytes = if item.content.starts_with('/') || item.content.chars().nth(1) == Some(':') {
-            // It's a file path
-            fs::read(&item.content).map_err(|e| e.to_string())?
-        } else {
-            // It's base64 (legacy support)
-            general_purpose::STANDARD
-                .decode(&item.content)
-                .map_err(|e| e.to_string())?
+// 各平台剪贴板上 GIF 数据的格式名
+const GIF_FORMATS: [&str; 3] = ["com.compuserve.gif", "GIF", "image/gif"];
+
+/// 剪贴板中原始的 GIF 数据，保存后可保留动画
+pub fn read_clipboard_gif() -> Option<Vec<u8>> {
+    let ctx = ClipboardContext::new().ok()?;
+    let formats = ctx.available_formats().ok()?;
+    GIF_FORMATS
+        .iter()
+        .filter(|format| formats.iter().any(|f| f == *format))
+        .find_map(|format| {
+            ctx.get_buffer(format)
+                .ok()
+                .filter(|data| data.starts_with(b"GIF8"))
+        })
+}
+
+fn is_gif_item(item: &ClipboardItem) -> bool {
+    item.kind == "image"
+        && Path::new(&item.content)
+            .extension()
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"))
+}
+
+/// 写回 GIF 时使用的当前平台格式名
+fn gif_clipboard_format() -> &'static str {
+    if cfg!(target_os = "macos") {
+        GIF_FORMATS[0]
+    } else if cfg!(target_os = "windows") {
+        GIF_FORMATS[1]
+    } else {
+        GIF_FORMATS[2]
+    }
+}
+
+// 列表中图片缩略图的默认边长
+pub const THUMBNAIL_SIZE: u32 = 256;
+
+/// 图片条目（文件路径或旧版的 base64）缩放后的 PNG data URL，GIF 取第一帧作为预览
+fn image_thumbnail(content: &str, size: u32) -> Result<String, String> {
+    let img = image::load_from_memory(&image_bytes(content)?).map_err(|e| e.to_string())?;
+    let mut png = Vec::new();
+    img.thumbnail(size, size)
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(png_data_url(&png))
+}
+
+/// PNG 的 data URL，直接编码到预留好容量的字符串中，避免中间副本
+pub fn png_data_url(png: &[u8]) -> String {
+    const PREFIX: &str = "data:image/png;base64,";
+    let mut url = String::with_capacity(PREFIX.len() + png.len().div_ceil(3) * 4);
+    url.push_str(PREFIX);
+    general_purpose::STANDARD.encode_string(png, &mut url);
+    url
+}
+
+/// 条目全文，优先读内存缓存；敏感条目不进缓存
+pub fn cached_item_content(app: &tauri::AppHandle, id: i64) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    if let Some(content) = state
+        .content_cache
+        .lock()
+        .unwrap()
+        .get(CacheKey::Content(id))
+    {
+        return Ok(content);
+    }
+    let item = state.db.get_item(id).map_err(|e| e.to_string())?;
+    if !item.is_sensitive {
+        state
+            .content_cache
+            .lock()
+            .unwrap()
+            .insert(CacheKey::Content(id), item.content.clone());
+    }
+    Ok(item.content)
+}
+
+/// 图片条目的缩略图、SVG 的渲染预览或音频条目的波形图，优先读内存缓存
+pub fn cached_thumbnail(app: &tauri::AppHandle, id: i64, size: u32) -> Result<String, String> {
+    let state = app.state::<AppState>();
+    let key = CacheKey::Thumbnail(id, size);
+    if let Some(thumbnail) = state.content_cache.lock().unwrap().get(key) {
+        return Ok(thumbnail);
+    }
+    let item = state.db.get_item(id).map_err(|e| e.to_string())?;
+    let thumbnail = match item.kind.as_str() {
+        "image" => image_thumbnail(&item.content, size)?,
+        "audio" => crate::audio::waveform_thumbnail(&item.content, size)?,
+        "text" if item.data_type == "svg" => crate::svg::thumbnail(&item.content, size)?,
+        _ => return Err("Item is not an image".to_string()),
+    };
+    if !item.is_sensitive {
+        state
+            .content_cache
+            .lock()
+            .unwrap()
+            .insert(key, thumbnail.clone());
+    }
+    Ok(thumbnail)
+}
+
+/// 预先缓存列表条目的全文和默认尺寸缩略图（在后台任务中调用）
+pub fn prewarm_cache(
+    app: &tauri::AppHandle,
+    items: impl IntoIterator<Item = ClipboardItem>,
+    is_cancelled: impl Fn() -> bool,
+) {
+    let state = app.state::<AppState>();
+    for item in items {
+        if is_cancelled() {
+            return;
+        }
+        let Some(id) = item.id else {
+            continue;
         };
+        if item.is_sensitive {
+            continue;
+        }
+        if item.kind == "image" {
+            let key = CacheKey::Thumbnail(id, THUMBNAIL_SIZE);
+            if state.content_cache.lock().unwrap().contains(key) {
+                continue;
+            }
+            match image_thumbnail(&item.content, THUMBNAIL_SIZE) {
+                Ok(thumbnail) => state.content_cache.lock().unwrap().insert(key, thumbnail),
+                Err(e) => log::warn!("Failed to prewarm thumbnail for item {}: {}", id, e),
+            }
+        } else {
+            let key = CacheKey::Content(id);
+            let mut cache = state.content_cache.lock().unwrap();
+            if !cache.contains(key) {
+                cache.insert(key, item.content);
+            }
+        }
+    }
+}
 
-        let img = image::load_from_memory(&bytes).map_err(|e| e.to_string())?;
-        let rgba = img.to_rgba8();
-        let width = img.width();
-        let height = img.height();
-        let rgba_bytes = rgba.into_raw();
+/// 复制时检查配额所用的图片目录大小缓存时间，避免每次复制都遍历图片目录
+const IMAGES_SIZE_MAX_AGE: Duration = Duration::from_secs(60);
 
-        // Update last_app_image_change
-        let state = app.state::<AppState>();
-        if let Ok(mut last_change) = state.last_app_image_change.lock() {
-            *last_change = Some(rgba_bytes.clone());
+/// 图片目录占用的字节数；缓存的结果早于 max_age 时重新遍历目录
+fn images_dir_size(app: &tauri::AppHandle, max_age: Duration) -> Result<u64, String> {
+    let state = app.state::<AppState>();
+    if let Some((checked_at, bytes)) = *state.images_size.lock().unwrap() {
+        if checked_at.elapsed() < max_age {
+            return Ok(bytes);
         }
+    }
 
-        let tauri_img = tauri::image::Image::new(&rgba_bytes, width, height);
-        app.clipboard()
-            .write_image(&tauri_img)
-            .map_err(|e| e.to_string())?;
-    } else if item.kind == "file" {
-        let files: Vec<String> = serde_json::from_str(&item.content).map_err(|e| e.to_string())?;
+    let images_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| e.to_string())?
+        .join("images");
+    let bytes = fs::read_dir(images_dir)
+        .map(|entries| {
+            entries
+                .flatten()
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|m| m.is_file())
+                .map(|m| m.len())
+                .sum()
+        })
+        .unwrap_or(0);
+    *state.images_size.lock().unwrap() = Some((Instant::now(), bytes));
+    Ok(bytes)
+}
 
-        // Update last_app_file_change
-        let state = app.state::<AppState>();
-        if let Ok(mut last_change) = state.last_app_file_change.lock() {
-            *last_change = Some(files.clone());
+/// 存储占用；images_max_age 为可以接受的图片目录大小缓存时间，传 Duration::ZERO 时重新统计
+pub fn storage_usage(
+    app: &tauri::AppHandle,
+    images_max_age: Duration,
+) -> Result<StorageUsage, String> {
+    let state = app.state::<AppState>();
+    let db_bytes = state.db.database_size().map_err(|e| e.to_string())?;
+    let by_type = state.db.usage_by_type().map_err(|e| e.to_string())?;
+    let images_bytes = images_dir_size(app, images_max_age)?;
+
+    let quota_bytes = state.config.lock().unwrap().storage_quota_mb * 1024 * 1024;
+    let total_bytes = db_bytes + images_bytes;
+    let percent = if quota_bytes > 0 {
+        total_bytes as f64 * 100.0 / quota_bytes as f64
+    } else {
+        0.0
+    };
+    Ok(StorageUsage {
+        db_bytes,
+        images_bytes,
+        total_bytes,
+        quota_bytes,
+        percent,
+        by_type,
+    })
+}
+
+/// 占用超过提醒阈值时发出一次 storage-quota-warning，回落到阈值以下后才会再次提醒
+pub fn check_storage_quota(app: &tauri::AppHandle) {
+    let state = app.state::<AppState>();
+    let threshold = state.config.lock().unwrap().storage_warning_percent as f64;
+    let Ok(usage) = storage_usage(app, IMAGES_SIZE_MAX_AGE) else {
+        return;
+    };
+    let over = usage.quota_bytes > 0 && usage.percent >= threshold;
+
+    let mut warned = state.storage_warning_sent.lock().unwrap();
+    if over && !*warned {
+        log::warn!(
+            "Storage usage {:.1}% exceeds warning threshold {}%",
+            usage.percent,
+            threshold
+        );
+        let _ = app.emit("storage-quota-warning", &usage);
+    }
+    *warned = over;
+}
+
+/// 可执行文件所在 .app 的 bundle id（macOS）
+#[cfg(target_os = "macos")]
+#[allow(deprecated, unexpected_cfgs)]
+pub fn bundle_identifier(app_path: &str) -> Option<String> {
+    use cocoa::base::{id, nil};
+    use cocoa::foundation::{NSAutoreleasePool, NSString};
+    use objc::{class, msg_send, sel, sel_impl};
+
+    let bundle_path = Path::new(app_path)
+        .ancestors()
+        .find(|p| p.extension().is_some_and(|ext| ext == "app"))?;
+    unsafe {
+        let _pool = NSAutoreleasePool::new(nil);
+        let path = NSString::alloc(nil).init_str(&bundle_path.to_string_lossy());
+        let bundle: id = msg_send![class!(NSBundle), bundleWithPath: path];
+        if bundle == nil {
+            return None;
+        }
+        let identifier: id = msg_send![bundle, bundleIdentifier];
+        if identifier == nil {
+            return None;
         }
+        Some(
+            std::ffi::CStr::from_ptr(identifier.UTF8String())
+                .to_string_lossy()
+                .to_string(),
+        )
+    }
+}
 
-        if let Ok(ctx) = ClipboardContext::new() {
-            let contents = vec![ClipboardContent::Files(files)];
-            ctx.set(contents).map_err(|e| e.to_string())?;
-        } else {
-            return Err("Failed to access clipboard context".to_string());
+#[cfg(not(target_os = "macos"))]
+pub fn bundle_identifier(_app_path: &str) -> Option<String> {
+    None
+}
+
+/// 先做全局的粘贴文本处理，再应用第一条匹配目标应用的粘贴规则。
+/// 返回是否还能写入条目保存的原始格式：纯文本规则命中或文本被转换后，原始格式仍是原文，不再写入
+pub fn apply_paste_rules(
+    app: &tauri::AppHandle,
+    item: &mut ClipboardItem,
+    app_name: &str,
+    app_path: &str,
+) -> bool {
+    if item.kind != "text" {
+        return true;
+    }
+    let bundle_id = bundle_identifier(app_path);
+    let state = app.state::<AppState>();
+    let config = state.config.lock().unwrap();
+    let original = item.content.clone();
+    item.content = config.paste_normalization.apply(&item.content);
+    let plain_text = config
+        .paste_rules
+        .iter()
+        .find(|r| r.matches(app_name, app_path, bundle_id.as_deref()))
+        .is_some_and(|rule| rule.apply(item));
+    !plain_text && item.content == original
+}
+
+/// 读取图片条目的原始字节：文件路径，或旧版本保存的 base64
+fn image_bytes(content: &str) -> Result<Vec<u8>, String> {
+    if content.starts_with('/') || content.chars().nth(1) == Some(':') {
+        fs::read(content).map_err(|e| e.to_string())
+    } else {
+        general_purpose::STANDARD
+            .decode(content)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// 把条目的正文和原始格式通过 clipboard-rs 一次写入，并带上自写标记，监听线程据此跳过这次变化
+pub fn write_to_clipboard(app: &tauri::AppHandle, item: &ClipboardItem) -> Result<(), String> {
+    write_to_clipboard_with(app, item, true)
+}
+
+/// 同 write_to_clipboard；include_raw_formats 为 false 时只写入正文，用于按粘贴规则转换过的条目
+pub fn write_to_clipboard_with(
+    app: &tauri::AppHandle,
+    item: &ClipboardItem,
+    include_raw_formats: bool,
+) -> Result<(), String> {
+    let raw = item
+        .id
+        .filter(|_| include_raw_formats)
+        .and_then(|id| app.state::<AppState>().db.get_raw_formats(id).ok())
+        .unwrap_or_default();
+    let mut contents = Vec::new();
+    match item.kind.as_str() {
+        "text" => {
+            contents.push(ClipboardContent::Text(item.content.clone()));
+            if let Some(html) = &item.html_content {
+                contents.push(ClipboardContent::Html(html.clone()));
+            }
+            // SVG 同时提供源码和 PNG，设计工具和聊天应用都能粘贴
+            if item.data_type == "svg" {
+                contents.push(ClipboardContent::Other(
+                    crate::svg::clipboard_format().to_string(),
+                    item.content.clone().into_bytes(),
+                ));
+                match crate::svg::render_png(&item.content, None)
+                    .and_then(|png| RustImageData::from_bytes(&png).map_err(|e| e.to_string()))
+                {
+                    Ok(image) => contents.push(ClipboardContent::Image(image)),
+                    Err(e) => log::warn!("Failed to render SVG for paste: {}", e),
+                }
+            }
+        }
+        "image" => {
+            let bytes = image_bytes(&item.content)?;
+            let image = RustImageData::from_bytes(&bytes).map_err(|e| e.to_string())?;
+            contents.push(ClipboardContent::Image(image));
+            // 同时写入原始 GIF，支持的应用粘贴后仍是动图
+            if is_gif_item(item) {
+                contents.push(ClipboardContent::Other(
+                    gif_clipboard_format().to_string(),
+                    bytes,
+                ));
+            }
+        }
+        "file" => {
+            let files: Vec<String> =
+                serde_json::from_str(&item.content).map_err(|e| e.to_string())?;
+            contents.push(ClipboardContent::Files(files));
+        }
+        "audio" => {
+            let format = crate::audio::clipboard_format(&item.content)
+                .ok_or("Audio format is not supported on this platform")?;
+            let bytes = fs::read(&item.content).map_err(|e| e.to_string())?;
+            contents.push(ClipboardContent::Other(format.to_string(), bytes));
         }
+        _ => {}
+    }
+    crate::self_write::stamp(app, &mut contents);
+    if let Err(e) = crate::raw_formats::write(contents, raw) {
+        // 纯文本写入失败时退回到剪贴板插件，这次变化没有自写标记，由数据库去重
+        if item.kind != "text" {
+            return Err(e);
+        }
+        log::error!("Failed to write clipboard via clipboard-rs: {}", e);
+        app.clipboard()
+            .write_text(item.content.clone())
+            .map_err(|e| e.to_string())?;
     }
     Ok(())
 }
@@ -0,0 +1,166 @@
+use crate::models::{ClipboardItem, Webhook};
+use crate::state::AppState;
+use hmac::{Hmac, Mac};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tauri::Manager;
+
+// 发送失败（网络错误、429 或 5xx）时的最大尝试次数，间隔依次翻倍
+const MAX_ATTEMPTS: u32 = 3;
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+const PREVIEW_CHARS: usize = 200;
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// 请求体的 HMAC-SHA256 签名，放在 X-Clipboard-Signature 头中
+fn sign(secret: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(body.as_bytes());
+    format!("sha256={}", hex(&mac.finalize().into_bytes()))
+}
+
+/// 事件中的条目信息：默认只有哈希和类型；预览和全文分别需开启 include_preview 和
+/// include_content，敏感条目的哈希、预览和全文还需开启 include_sensitive（短密码的哈希可被穷举还原）
+pub fn item_payload(
+    item: &ClipboardItem,
+    include_preview: bool,
+    include_content: bool,
+    include_sensitive: bool,
+) -> serde_json::Value {
+    let content_visible = !item.is_sensitive || include_sensitive;
+    let preview = (include_preview && item.kind == "text" && content_visible)
+        .then(|| item.content.chars().take(PREVIEW_CHARS).collect::<String>());
+    let content = (include_content && content_visible).then(|| item.content.clone());
+    json!({
+        "id": item.id,
+        "kind": item.kind,
+        "data_type": item.data_type,
+        "source_app": item.source_app,
+        "timestamp": item.timestamp,
+        "is_sensitive": item.is_sensitive,
+        "content_hash": content_visible.then(|| hex(&Sha256::digest(item.content.as_bytes()))),
+        "preview": preview,
+        "content": content,
+    })
+}
+
+fn build_body(
+    event: &str,
+    item: Option<&ClipboardItem>,
+    data: &serde_json::Value,
+    hook: &Webhook,
+) -> String {
+    json!({
+        "event": event,
+        "sent_at": chrono::Local::now().to_rfc3339(),
+        "item": item.map(|item| item_payload(item, true, hook.include_content, hook.include_sensitive)),
+        "data": data,
+    })
+    .to_string()
+}
+
+/// 发送一次请求，返回 HTTP 状态码
+async fn send(hook: &Webhook, event: &str, body: &str) -> Result<u16, String> {
+    let mut request = reqwest::Client::new()
+        .post(&hook.url)
+        .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+        .header("Content-Type", "application/json")
+        .header("X-Clipboard-Event", event)
+        .body(body.to_string());
+    if !hook.secret.is_empty() {
+        request = request.header("X-Clipboard-Signature", sign(&hook.secret, body));
+    }
+    let response = request.send().await.map_err(|e| e.to_string())?;
+    Ok(response.status().as_u16())
+}
+
+/// 发送并按需重试，客户端错误（429 除外）不重试
+async fn deliver(hook: Webhook, event: String, body: String) {
+    let mut delay = Duration::from_secs(1);
+    for attempt in 1..=MAX_ATTEMPTS {
+        let error = match send(&hook, &event, &body).await {
+            Ok(status) if (200..300).contains(&status) => return,
+            Ok(status) if status != 429 && status < 500 => {
+                log::warn!(
+                    "Webhook {} rejected {} event: HTTP {}",
+                    hook.url,
+                    event,
+                    status
+                );
+                return;
+            }
+            Ok(status) => format!("HTTP {}", status),
+            Err(e) => e,
+        };
+        log::warn!(
+            "Webhook {} failed for {} event (attempt {}/{}): {}",
+            hook.url,
+            event,
+            attempt,
+            MAX_ATTEMPTS,
+            error
+        );
+        if attempt < MAX_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+}
+
+/// 把事件（capture / paste / clear）发送到所有订阅了它的 Webhook，后台进行不阻塞调用方；
+/// 同时发布到 MQTT
+pub fn notify(
+    app: &tauri::AppHandle,
+    event: &str,
+    item: Option<&ClipboardItem>,
+    data: serde_json::Value,
+) {
+    // 演示模式下事件中的条目同样不带来源应用
+    let item = item.map(|item| crate::utils::present_item(&app.state::<AppState>(), item.clone()));
+    let item = item.as_ref();
+    crate::mqtt::publish(app, event, item, &data);
+    let hooks: Vec<Webhook> = {
+        let state = app.state::<AppState>();
+        let config = state.config.lock().unwrap();
+        config
+            .webhooks
+            .iter()
+            .filter(|hook| {
+                hook.enabled
+                    && !hook.url.trim().is_empty()
+                    && (hook.events.is_empty() || hook.events.iter().any(|e| e == event))
+            })
+            .cloned()
+            .collect()
+    };
+    for hook in hooks {
+        let body = build_body(event, item, &data, &hook);
+        let event = event.to_string();
+        tauri::async_runtime::spawn(deliver(hook, event, body));
+    }
+}
+
+/// 按 id 读取条目后发送事件
+pub fn notify_item(app: &tauri::AppHandle, event: &str, id: i64, data: serde_json::Value) {
+    let state = app.state::<AppState>();
+    {
+        let config = state.config.lock().unwrap();
+        if config.webhooks.is_empty() && !config.mqtt_enabled {
+            return;
+        }
+    }
+    match state.db.get_item(id) {
+        Ok(item) => notify(app, event, Some(&item), data),
+        Err(e) => log::error!("Failed to load item {} for webhook: {}", id, e),
+    }
+}
+
+/// 发送一条 test 事件（不重试），返回 HTTP 状态码，供设置页检查配置
+pub async fn test(hook: Webhook) -> Result<u16, String> {
+    let body = build_body("test", None, &json!({}), &hook);
+    send(&hook, "test", &body).await
+}
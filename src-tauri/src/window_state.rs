@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tauri::Manager;
+
+// 需要记住的窗口以及是否记住位置；弹出窗口每次跟随鼠标显示，只记大小
+const WINDOWS: [(&str, bool); 2] = [("main", true), ("popup", false)];
+
+// 移动 / 缩放停止后多久写入文件
+const SAVE_DELAY_MS: u64 = 1000;
+
+/// 窗口的物理像素位置和内部大小
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    #[serde(default)]
+    pub x: Option<i32>,
+    #[serde(default)]
+    pub y: Option<i32>,
+    #[serde(default)]
+    pub width: Option<u32>,
+    #[serde(default)]
+    pub height: Option<u32>,
+}
+
+fn state_path(app: &tauri::AppHandle) -> Option<PathBuf> {
+    app.path()
+        .app_data_dir()
+        .ok()
+        .map(|dir| dir.join("window_state.json"))
+}
+
+fn remembers_position(label: &str) -> Option<bool> {
+    WINDOWS
+        .iter()
+        .find(|(l, _)| *l == label)
+        .map(|(_, position)| *position)
+}
+
+fn current_geometry(window: &tauri::WebviewWindow, position: bool) -> Option<WindowGeometry> {
+    // 隐藏或最小化时的位置没有意义（Windows 上最小化窗口在 -32000）
+    if !window.is_visible().unwrap_or(false) || window.is_minimized().unwrap_or(false) {
+        return None;
+    }
+    let size = window.inner_size().ok()?;
+    let mut geometry = WindowGeometry {
+        width: Some(size.width),
+        height: Some(size.height),
+        ..Default::default()
+    };
+    if position {
+        let pos = window.outer_position().ok()?;
+        geometry.x = Some(pos.x);
+        geometry.y = Some(pos.y);
+    }
+    Some(geometry)
+}
+
+fn write(app: &tauri::AppHandle) {
+    let Some(path) = state_path(app) else {
+        return;
+    };
+    let geometry = app
+        .state::<crate::state::AppState>()
+        .window_geometry
+        .lock()
+        .unwrap()
+        .clone();
+    match serde_json::to_string_pretty(&geometry) {
+        Ok(json) => {
+            if let Err(e) = std::fs::write(&path, json) {
+                log::error!("Failed to save window state: {}", e);
+            }
+        }
+        Err(e) => log::error!("Failed to serialize window state: {}", e),
+    }
+}
+
+/// 窗口移动或缩放后记录新的位置和大小，稍后写入 window_state.json
+pub fn record(window: &tauri::Window) {
+    let label = window.label();
+    let Some(position) = remembers_position(label) else {
+        return;
+    };
+    let app = window.app_handle();
+    let Some(geometry) = app
+        .get_webview_window(label)
+        .and_then(|w| current_geometry(&w, position))
+    else {
+        return;
+    };
+    let state = app.state::<crate::state::AppState>();
+    state
+        .window_geometry
+        .lock()
+        .unwrap()
+        .insert(label.to_string(), geometry);
+
+    // 拖动时事件很密集，同一时间只安排一次写入
+    {
+        let mut pending = state.window_state_save_pending.lock().unwrap();
+        if *pending {
+            return;
+        }
+        *pending = true;
+    }
+    let app = app.clone();
+    std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(SAVE_DELAY_MS));
+        *app.state::<crate::state::AppState>()
+            .window_state_save_pending
+            .lock()
+            .unwrap() = false;
+        write(&app);
+    });
+}
+
+/// 退出时记录当前的窗口位置和大小并写入文件
+pub fn save(app: &tauri::AppHandle) {
+    for (label, position) in WINDOWS {
+        if let Some(geometry) = app
+            .get_webview_window(label)
+            .and_then(|w| current_geometry(&w, position))
+        {
+            app.state::<crate::state::AppState>()
+                .window_geometry
+                .lock()
+                .unwrap()
+                .insert(label.to_string(), geometry);
+        }
+    }
+    write(app);
+}
+
+/// 在显示窗口前按上次保存的大小和位置摆放，位置已不在任何屏幕内时保持默认
+pub fn restore(app: &tauri::AppHandle) {
+    let Some(path) = state_path(app) else {
+        return;
+    };
+    let Ok(content) = std::fs::read_to_string(&path) else {
+        return;
+    };
+    let geometry: HashMap<String, WindowGeometry> =
+        serde_json::from_str(&content).unwrap_or_default();
+
+    for (label, geometry) in &geometry {
+        let (Some(window), Some(position)) =
+            (app.get_webview_window(label), remembers_position(label))
+        else {
+            continue;
+        };
+        if let (Some(width), Some(height)) = (geometry.width, geometry.height) {
+            if width > 0 && height > 0 {
+                let _ =
+                    window.set_size(tauri::Size::Physical(tauri::PhysicalSize { width, height }));
+            }
+        }
+        let (Some(x), Some(y)) = (geometry.x, geometry.y) else {
+            continue;
+        };
+        let on_screen = position
+            && window
+                .available_monitors()
+                .unwrap_or_default()
+                .iter()
+                .any(|m| {
+                    let (pos, size) = (m.position(), m.size());
+                    x >= pos.x
+                        && x < pos.x + size.width as i32
+                        && y >= pos.y
+                        && y < pos.y + size.height as i32
+                });
+        if on_screen {
+            let _ =
+                window.set_position(tauri::Position::Physical(tauri::PhysicalPosition { x, y }));
+        }
+    }
+
+    *app.state::<crate::state::AppState>()
+        .window_geometry
+        .lock()
+        .unwrap() = geometry;
+}
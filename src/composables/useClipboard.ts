@@ -6,6 +6,7 @@ import { useI18n } from "vue-i18n";
 import { useToast } from "./useToast";
 import type { ClipboardItem, Collection } from "../types";
 import { confirm } from "@/composables/useConfirm";
+import { errorMessage } from "@/lib/utils";
 
 export function useClipboard() {
   const { t } = useI18n();
@@ -198,7 +199,13 @@ export function useClipboard() {
       previewItem.value = null;
 
       if (hideWindow) {
-        await getCurrentWindow().hide();
+        const currentWindow = getCurrentWindow();
+        if (currentWindow.label === "popup") {
+          // Hands focus back to the previous app so the paste lands there
+          await invoke("hide_popup", { reason: "paste" });
+        } else {
+          await currentWindow.hide();
+        }
       }
 
       // Fetch full content if it's text and might be truncated
@@ -400,7 +407,7 @@ export function useClipboard() {
       showToast(t("collections.itemUpdated"));
     } catch (e) {
       console.error("Failed to set item collection:", e);
-      showToast(`${t("collections.updateFailed")}: ${e}`);
+      showToast(`${t("collections.updateFailed")}: ${errorMessage(e)}`);
     }
   }
 
@@ -151,10 +151,20 @@ export function useSettings() {
     }
   }
 
-  function startRecording(e: MouseEvent) {
+  async function startRecording(e: MouseEvent) {
+    const previous = tempShortcut.value;
     isRecording.value = true;
     tempShortcut.value = t("settings.recordShortcut");
     (e.target as HTMLInputElement).focus();
+    try {
+      // 后端返回按物理按键规范后的组合键，与当前键盘布局无关
+      tempShortcut.value = await invoke<string>("capture_shortcut");
+    } catch (err) {
+      console.error("Failed to capture shortcut:", err);
+      tempShortcut.value = previous;
+    } finally {
+      isRecording.value = false;
+    }
   }
 
   function handleShortcutKeydown(e: KeyboardEvent) {
@@ -162,37 +172,13 @@ export function useSettings() {
     e.preventDefault();
     e.stopPropagation();
 
-    const modifiers = [];
-    if (e.metaKey) modifiers.push("CommandOrControl");
-    if (e.ctrlKey) modifiers.push("Control");
-    if (e.altKey) modifiers.push("Alt");
-    if (e.shiftKey) modifiers.push("Shift");
-
-    let key = e.key.toUpperCase();
-
-    const keyMap: Record<string, string> = {
-      " ": "Space",
-      ARROWUP: "Up",
-      ARROWDOWN: "Down",
-      ARROWLEFT: "Left",
-      ARROWRIGHT: "Right",
-      ENTER: "Return",
-      ESCAPE: "Escape",
-      BACKSPACE: "Backspace",
-      TAB: "Tab",
-    };
-
-    if (keyMap[key]) {
-      key = keyMap[key];
-    }
-
-    if (["META", "CONTROL", "ALT", "SHIFT"].includes(key)) {
-      return;
-    }
-
-    const shortcut = [...modifiers, key].join("+");
-    tempShortcut.value = shortcut;
-    isRecording.value = false;
+    invoke("record_shortcut_key", {
+      code: e.code,
+      ctrl: e.ctrlKey,
+      alt: e.altKey,
+      shift: e.shiftKey,
+      meta: e.metaKey,
+    }).catch((err) => console.error("Failed to record shortcut:", err));
   }
 
   async function setupConfigListeners() {
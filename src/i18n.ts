@@ -74,6 +74,9 @@ const messages = {
       title: "No items found",
       subtitle: "Try copying something!",
     },
+    presentation: {
+      watermark: "Presentation Mode",
+    },
     settings: {
       title: "Settings",
       globalShortcut: "Global Shortcut",
@@ -199,6 +202,9 @@ const messages = {
       title: "没有找到记录",
       subtitle: "试着复制一些内容！",
     },
+    presentation: {
+      watermark: "演示模式",
+    },
     settings: {
       title: "设置",
       globalShortcut: "全局快捷键",
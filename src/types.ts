@@ -10,12 +10,117 @@ export interface ClipboardItem {
   collection_id?: number;
   note?: string;
   html_content?: string;
+  byte_size?: number;
+  char_count?: number;
+  line_count?: number;
+  image_width?: number;
+  image_height?: number;
+  suggested_actions?: SuggestedAction[];
+  parent_id?: number;
+  paste_count?: number;
+  timestamp_ms?: number;
+  ocr_text?: string | null;
+  is_dangerous?: boolean;
+  is_truncated?: boolean;
+  is_locked?: boolean;
+  accelerator?: string | null;
+  copy_count?: number;
+  origin_device?: string | null;
+}
+
+export interface SuggestedAction {
+  kind: string;
+  label: string;
+  value: string;
 }
 
 export interface Collection {
   id: number;
   name: string;
   created_at: string;
+  exempt_from_retention?: boolean;
+}
+
+// 后端命令返回的错误
+export interface AppError {
+  code: string;
+  kind: "user" | "permission" | "storage" | "internal";
+  message: string;
+  details?: string | null;
+}
+
+export interface CollectionCount {
+  collection_id: number;
+  name: string;
+  count: number;
+}
+
+export interface TypeCount {
+  data_type: string;
+  count: number;
+}
+
+export interface DangerousPaste {
+  id: number | null;
+  content: string;
+  target_app: string | null;
+  reasons: string[];
+}
+
+export type LineEndings = "keep" | "lf" | "crlf";
+
+export interface TextNormalization {
+  trim_trailing_newlines?: boolean;
+  line_endings?: LineEndings;
+}
+
+export interface PasteRule {
+  app: string;
+  plain_text?: boolean;
+  trim_trailing_newlines?: boolean;
+  line_endings?: LineEndings;
+  tab_width?: number;
+}
+
+export interface Webhook {
+  url: string;
+  events?: ("capture" | "paste" | "clear")[];
+  secret?: string;
+  include_content?: boolean;
+  include_sensitive?: boolean;
+  enabled?: boolean;
+}
+
+export interface RoutingRule {
+  name?: string;
+  pattern?: string;
+  source_app?: string;
+  collection_id?: number;
+  tag?: string;
+  pin?: boolean;
+}
+
+export interface PowerState {
+  mode: string;
+  on_battery?: boolean | null;
+  idle_seconds?: number | null;
+  power_saving: boolean;
+}
+
+export interface ApiToken {
+  id: string;
+  name: string;
+  scope: "read" | "write";
+  no_sensitive: boolean;
+  token_hash: string;
+  created_at: string;
+}
+
+export interface CustomClassifier {
+  name: string;
+  pattern: string;
+  icon?: string;
+  color?: string;
 }
 
 export interface AppConfig {
@@ -25,8 +130,96 @@ export interface AppConfig {
   theme: string;
   sensitive_apps: string[];
   compact_mode?: boolean;
+  presentation_mode?: boolean;
   clear_pinned_on_clear?: boolean;
   clear_collected_on_clear?: boolean;
+  calc_suggestions?: boolean;
+  target_currency?: string;
+  ai_endpoint?: string;
+  ai_api_key?: string;
+  ai_model?: string;
+  monitor_find_pasteboard?: boolean;
+  sync_find_pasteboard?: boolean;
+  restore_clipboard_on_startup?: boolean;
+  ocr_engine?: "auto" | "native" | "tesseract";
+  ocr_languages?: string[];
+  capture_cache_max_age_days?: number;
+  capture_cache_max_size_mb?: number;
+  capture_filename_template?: string;
+  capture_save_dir?: string;
+  watch_folders?: string[];
+  share_server_enabled?: boolean;
+  share_server_port?: number;
+  share_token?: string;
+  record_universal_clipboard?: boolean;
+  storage_quota_mb?: number;
+  storage_warning_percent?: number;
+  git_sync_repo?: string;
+  git_sync_collection_id?: number | null;
+  git_sync_interval_minutes?: number;
+  capture_raw_formats?: boolean;
+  paste_rules?: PasteRule[];
+  capture_normalization?: TextNormalization;
+  paste_normalization?: TextNormalization;
+  terminal_apps?: string[];
+  min_text_length?: number;
+  max_text_length_kb?: number;
+  truncate_long_text?: boolean;
+  dedup_ignore_whitespace?: boolean;
+  content_cache_mb?: number;
+  memory_budget_mb?: number;
+  background_workers?: number;
+  popup_scaling?: "remember" | "fixed" | "screen";
+  popup_width?: number;
+  popup_height?: number;
+  default_country_code?: string;
+  custom_classifiers?: CustomClassifier[];
+  routing_rules?: RoutingRule[];
+  webhooks?: Webhook[];
+  streamdeck_enabled?: boolean;
+  streamdeck_port?: number;
+  streamdeck_token?: string;
+  api_tokens?: ApiToken[];
+  scripts_enabled?: boolean;
+  script_http_permissions?: string[];
+  enabled_plugins?: string[];
+  mqtt_enabled?: boolean;
+  mqtt_host?: string;
+  mqtt_port?: number;
+  mqtt_username?: string;
+  mqtt_password?: string;
+  mqtt_topic?: string;
+  mqtt_allowlist?: string[];
+  mqtt_shared_secret?: string;
+  mqtt_tls?: boolean;
+  mqtt_publish_previews?: boolean;
+  capture_debounce_ms?: number;
+  update_event_coalesce_ms?: number;
+  power_saving_mode?: "auto" | "always" | "off";
+  power_saving_idle_minutes?: number;
+  reduced_motion?: "system" | "on" | "off";
+  high_contrast?: "system" | "on" | "off";
+  popup_trigger_mode?: "toggle" | "hold";
+  tray_middle_click_action?: string;
+  tray_double_click_action?: string;
+  usage_tracking?: boolean;
+  device_name?: string;
+  clipboard_back_shortcut?: string;
+  clipboard_forward_shortcut?: string;
+  cycle_paste_shortcut?: string;
+  cycle_paste_window_ms?: number;
+}
+
+export interface AccessibilityPrefs {
+  reduced_motion: boolean;
+  high_contrast: boolean;
+  os_reduced_motion: boolean | null;
+  os_high_contrast: boolean | null;
+}
+
+export interface ItemDescription {
+  id: number;
+  label: string;
 }
 
 export interface CaptureResult {
@@ -38,3 +231,227 @@ export interface CaptureResult {
   height: number;
   scale_factor: number;
 }
+
+export interface OcrBox {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface OcrWord {
+  text: string;
+  bbox: OcrBox;
+  confidence: number | null;
+}
+
+export interface OcrLine {
+  text: string;
+  bbox: OcrBox;
+  confidence: number | null;
+  words: OcrWord[];
+}
+
+export interface OcrResult {
+  text: string;
+  lines: OcrLine[];
+}
+
+export interface OcrBackfillProgress {
+  done: number;
+  total: number;
+  item_id: number | null;
+  finished: boolean;
+  cancelled: boolean;
+}
+
+export interface WindowRect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+  title: string;
+  app: string;
+}
+
+export interface PixelGrid {
+  width: number;
+  height: number;
+  pixels: number[];
+  color: string;
+}
+
+export interface JobInfo {
+  id: number;
+  kind: string;
+  priority: "high" | "normal" | "low";
+  status: "queued" | "running" | "finished" | "failed" | "cancelled";
+  done: number;
+  total: number;
+  error?: string | null;
+}
+
+export interface ColorSwatch {
+  hex: string;
+  rgb: string;
+  share: number;
+}
+
+export interface ItemFilter {
+  older_than_days?: number | null;
+  data_type?: string | null;
+  source_app?: string | null;
+  only_unpinned?: boolean | null;
+  only_uncollected?: boolean | null;
+}
+
+export interface PluginAction {
+  id: string;
+  label: string;
+  function: string;
+  data_types: string[];
+}
+
+export interface PluginShareTarget {
+  id: string;
+  label: string;
+  url: string;
+}
+
+export interface PluginManifest {
+  name: string;
+  version: string;
+  description: string;
+  script: string;
+  library?: string | null;
+  http: boolean;
+  actions: PluginAction[];
+  classifiers: CustomClassifier[];
+  share_targets: PluginShareTarget[];
+}
+
+export interface PluginInfo {
+  id: string;
+  path: string;
+  enabled: boolean;
+  http_granted: boolean;
+  manifest?: PluginManifest | null;
+  error?: string | null;
+}
+
+export interface ScriptInfo {
+  name: string;
+  on_capture: boolean;
+  transform: boolean;
+  http: boolean;
+  error?: string | null;
+}
+
+export interface ClearResult {
+  deleted: number;
+  remaining: number;
+}
+
+export interface CleanupCandidate {
+  item: ClipboardItem;
+  reclaimable_bytes: number;
+  age_days: number;
+  score: number;
+}
+
+export interface SettingsImportResult {
+  collections_created: number;
+  network_settings_skipped: boolean;
+}
+
+export interface CacheStats {
+  entries: number;
+  bytes: number;
+  budget_bytes: number;
+  hits: number;
+  misses: number;
+  evictions: number;
+}
+
+export interface ItemOccurrence {
+  copied_at: string;
+  source_app: string | null;
+}
+
+export interface ItemVersion {
+  version: number;
+  content: string;
+  data_type: string;
+  note: string | null;
+  html_content: string | null;
+  created_at: string;
+}
+
+export interface QueryStats {
+  name: string;
+  count: number;
+  total_ms: number;
+  max_ms: number;
+}
+
+export interface MemoryStats {
+  process_bytes: number | null;
+  budget_bytes: number;
+  tracked_bytes: number;
+  content_cache: CacheStats;
+  capture_frame_bytes: number;
+  paste_stack_items: number;
+  paste_stack_bytes: number;
+}
+
+export interface PaletteAction {
+  id: string;
+  title: string;
+  category: string;
+  score: number;
+}
+
+export interface GitSyncResult {
+  pushed: number;
+  added: number;
+  updated: number;
+  removed: number;
+}
+
+export interface TypeUsage {
+  data_type: string;
+  count: number;
+  bytes: number;
+}
+
+export interface UsageCount {
+  date: string;
+  event: "popup_open" | "paste" | "capture" | "screenshot";
+  count: number;
+}
+
+export interface UsageStats {
+  enabled: boolean;
+  days: number;
+  totals: { event: UsageCount["event"]; count: number }[];
+  daily: UsageCount[];
+}
+
+export interface StorageUsage {
+  db_bytes: number;
+  images_bytes: number;
+  total_bytes: number;
+  quota_bytes: number;
+  percent: number;
+  by_type: TypeUsage[];
+}
+
+export interface CacheCleanup {
+  files_removed: number;
+  bytes_freed: number;
+}
+
+export interface FinalizedCapture {
+  path: string;
+  item: ClipboardItem | null;
+}